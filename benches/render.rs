@@ -0,0 +1,142 @@
+//! Rendering benchmarks for large filter sets
+//!
+//! Run with `cargo bench --bench render`. `write_sql` renders every condition into
+//! one pre-sized buffer; `to_sql_joined` renders each condition into its own
+//! `String` and joins them, as rendering worked before, to show the difference.
+//!
+//! `add_condition` builds a large filter one condition at a time, appending to the
+//! root AND group; `clone_and_wrap` clones the root and wraps it in a new group
+//! for every condition, as `add_expression` used to.
+
+use clickhouse_filters::filtering::{
+    ColumnTypeInfo, FilterBuilder, FilterCondition, FilterExpression, FilterOperator,
+    LogicalOperator,
+};
+use clickhouse_filters::sorting::SortedColumn;
+use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry, PaginationOptions};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// `count` conditions cycling through the common condition kinds
+fn conditions(count: usize) -> Vec<FilterCondition> {
+    (0..count)
+        .map(|i| match i % 5 {
+            0 => FilterCondition::string("name", FilterOperator::Like, Some("%o'brien%")),
+            1 => FilterCondition::uint32("age", FilterOperator::GreaterThan, Some(i as u32)),
+            2 => FilterCondition::date("created_at", FilterOperator::LessThan, Some("2024-06-01")),
+            3 => FilterCondition::boolean("active", FilterOperator::Equal, Some(true)),
+            _ => FilterCondition::in_values(
+                "status",
+                FilterOperator::In,
+                vec![
+                    "open".to_string(),
+                    "pending".to_string(),
+                    "closed".to_string(),
+                ],
+                Some(ColumnTypeInfo::String),
+            ),
+        })
+        .collect()
+}
+
+fn builder(conditions: &[FilterCondition]) -> FilterBuilder {
+    conditions
+        .iter()
+        .cloned()
+        .fold(FilterBuilder::new(), |builder, condition| {
+            builder.add_condition(condition)
+        })
+}
+
+fn bench_render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    for count in [100, 250, 1000] {
+        let conditions = conditions(count);
+        let builder = builder(&conditions);
+
+        group.bench_with_input(
+            BenchmarkId::new("write_sql", count),
+            &builder,
+            |b, builder| b.iter(|| black_box(builder.build().unwrap())),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("to_sql_joined", count),
+            &conditions,
+            |b, conditions| {
+                b.iter(|| {
+                    let parts: Vec<String> = conditions
+                        .iter()
+                        .map(|condition| condition.to_sql(false).unwrap())
+                        .collect();
+                    black_box(format!(" WHERE ({})", parts.join(" AND ")))
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    for count in [100, 500] {
+        let conditions = conditions(count);
+
+        group.bench_with_input(
+            BenchmarkId::new("add_condition", count),
+            &conditions,
+            |b, conditions| b.iter(|| black_box(builder(conditions))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("clone_and_wrap", count),
+            &conditions,
+            |b, conditions| {
+                b.iter(|| {
+                    let mut root: Option<FilterExpression> = None;
+                    for condition in conditions {
+                        let expression = FilterExpression::Condition(condition.clone());
+                        root = Some(match &root {
+                            None => expression,
+                            Some(existing) => FilterExpression::Group {
+                                operator: LogicalOperator::And,
+                                expressions: vec![existing.clone(), expression],
+                            },
+                        });
+                    }
+                    black_box(root)
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_query_sql(c: &mut Criterion) {
+    let columns: ColumnRegistry = column_registry! {
+        name: String,
+        age: UInt32,
+        created_at: Date,
+        active: Boolean,
+        status: String,
+    };
+    let expressions = conditions(100)
+        .into_iter()
+        .map(FilterExpression::Condition)
+        .collect();
+    let filtering = clickhouse_filters::FilteringOptions::case_sensitive(expressions, &columns);
+
+    c.bench_function("query_sql/100", |b| {
+        b.iter(|| {
+            // A fresh instance per request, so the WHERE clause cache doesn't help
+            let filters = ClickHouseFilters::new(
+                Some(PaginationOptions::new(3, 50, 100, 10_000)),
+                vec![SortedColumn::new("created_at", "desc")],
+                Some(filtering.clone()),
+                &columns,
+            )
+            .unwrap();
+            black_box(filters.query_sql("db.users", &["name", "age"]).unwrap())
+        })
+    });
+}
+
+criterion_group!(benches, bench_render, bench_build, bench_query_sql);
+criterion_main!(benches);
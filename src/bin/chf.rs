@@ -0,0 +1,162 @@
+//! chf - convert JSON filter payloads to ClickHouse SQL
+//!
+//! Reads a column definition file and a JSON filter payload and prints the SQL that
+//! clickhouse-filters generates for them. Useful for reproducing filter issues
+//! without writing a Rust harness.
+//!
+//! ```text
+//! chf --columns columns.json --filters filters.json [--sort name:asc] [--page 1 --per-page 10]
+//! ```
+//!
+//! The column file maps column names to ClickHouse types, either as a JSON object,
+//! e.g. `{"name": "String", "tags": "Array(String)"}`, or, for files ending in
+//! `.toml`, as TOML keys, e.g. `tags = "Array(String)"`. The filter payload is a JSON array
+//! of `{"n", "f", "v", "c"}` filters; pass `-` to read it from stdin.
+
+use clickhouse_filters::filtering::JsonFilter;
+use clickhouse_filters::sorting::SortedColumn;
+use clickhouse_filters::{
+    ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions, PaginationOptions,
+};
+use eyre::Result;
+use std::collections::HashMap;
+use std::io::Read;
+
+const USAGE: &str = "\
+Usage: chf --columns <file> [--filters <file|->] [options]
+
+Options:
+  --columns <file>     JSON object or .toml file mapping column names to
+                       ClickHouse types
+  --filters <file|->   JSON array of filters, or - to read from stdin
+  --sort <col:dir>     Sort column and direction (asc/desc); may be repeated
+  --page <n>           Current page (enables pagination)
+  --per-page <n>       Rows per page (default 10)
+  --table <db.table>   Print a full SELECT and COUNT query for this table
+  --case-sensitive     Disable case-insensitive string matching
+  -h, --help           Show this help";
+
+#[derive(Default)]
+struct Args {
+    columns: Option<String>,
+    filters: Option<String>,
+    sort: Vec<String>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+    table: Option<String>,
+    case_sensitive: bool,
+}
+
+fn parse_args() -> Result<Option<Args>> {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .ok_or_else(|| eyre::eyre!("Missing value for {}", name))
+        };
+        match arg.as_str() {
+            "--columns" => args.columns = Some(value("--columns")?),
+            "--filters" => args.filters = Some(value("--filters")?),
+            "--sort" => args.sort.push(value("--sort")?),
+            "--page" => args.page = Some(value("--page")?.parse()?),
+            "--per-page" => args.per_page = Some(value("--per-page")?.parse()?),
+            "--table" => args.table = Some(value("--table")?),
+            "--case-sensitive" => args.case_sensitive = true,
+            "-h" | "--help" => return Ok(None),
+            _ => return Err(eyre::eyre!("Unknown argument: {}\n\n{}", arg, USAGE)),
+        }
+    }
+
+    Ok(Some(args))
+}
+
+fn read_input(path: &str) -> Result<String> {
+    if path == "-" {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        Ok(input)
+    } else {
+        std::fs::read_to_string(path).map_err(|e| eyre::eyre!("Failed to read {}: {}", path, e))
+    }
+}
+
+fn load_columns(path: &str) -> Result<ColumnRegistry> {
+    let input = read_input(path)?;
+    let types: HashMap<String, String> = if path.ends_with(".toml") {
+        toml::from_str(&input).map_err(|e| eyre::eyre!("Invalid TOML in {}: {}", path, e))?
+    } else {
+        serde_json::from_str(&input)?
+    };
+
+    types
+        .into_iter()
+        .map(|(name, type_name)| {
+            // The process is short-lived, so leaking names for ColumnDef is fine
+            let name: &'static str = Box::leak(name.into_boxed_str());
+            ColumnDef::from_clickhouse_type(name, &type_name)
+                .map(|def| (name, def))
+                .ok_or_else(|| eyre::eyre!("Unsupported type for {}: {}", name, type_name))
+        })
+        .collect::<Result<HashMap<_, _>>>()
+        .map(ColumnRegistry::new)
+}
+
+fn main() -> Result<()> {
+    let Some(args) = parse_args()? else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+
+    let columns_path = args
+        .columns
+        .ok_or_else(|| eyre::eyre!("--columns is required\n\n{}", USAGE))?;
+    let columns = load_columns(&columns_path)?;
+
+    // Build filtering options from the JSON payload
+    let filtering = match &args.filters {
+        Some(path) => {
+            let json_filters: Vec<JsonFilter> = serde_json::from_str(&read_input(path)?)?;
+            FilteringOptions::from_json_filters(&json_filters, &columns)?.map(|mut opts| {
+                opts.case_insensitive = !args.case_sensitive;
+                opts
+            })
+        }
+        None => None,
+    };
+
+    // Sorting is given as column:direction
+    let sorting = args
+        .sort
+        .iter()
+        .map(|spec| {
+            let (column, order) = spec.split_once(':').unwrap_or((spec, "asc"));
+            if columns.contains(column) {
+                Ok(SortedColumn::new(column, order))
+            } else {
+                Err(eyre::eyre!("Column not found: {}", column))
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let pagination = args.page.map(|page| {
+        let per_page = args.per_page.unwrap_or(10);
+        PaginationOptions::new(page, per_page, per_page, i64::MAX)
+    });
+
+    let filters = ClickHouseFilters::new(pagination, sorting, filtering, &columns)?;
+
+    match &args.table {
+        Some(table) => {
+            let (schema, table) = table
+                .split_once('.')
+                .ok_or_else(|| eyre::eyre!("--table must be in db.table form"))?;
+            println!("{}", filters.query_sql((schema, table), &[])?);
+            println!("{}", filters.count_sql((schema, table))?);
+        }
+        None => println!("{}", filters.sql()?.trim_start()),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,236 @@
+//! Inspecting the structure of filter trees
+//!
+//! `FilterExpression` and `FilterBuilder` report their depth, how many
+//! conditions and groups they contain and which operators they use, and
+//! `to_ast()` converts a tree into `AstNode`s. Unlike the expression types, `AstNode` only holds plain data (names,
+//! operators and the rendered SQL of each condition), so with the `serde` feature it
+//! can be serialized for tools that analyze, diff or display generated queries.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::ast::AstNode;
+//! use clickhouse_filters::filtering::{FilterBuilder, FilterCondition, FilterExpression, FilterOperator, LogicalOperator};
+//!
+//! let builder = FilterBuilder::new()
+//!     .case_insensitive(false)
+//!     .add_condition(FilterCondition::uint32("age", FilterOperator::GreaterThan, Some(25)))
+//!     .group(
+//!         LogicalOperator::Or,
+//!         vec![
+//!             FilterExpression::Condition(FilterCondition::string("status", FilterOperator::Equal, Some("open"))),
+//!             FilterExpression::Condition(FilterCondition::string("status", FilterOperator::Equal, Some("new"))),
+//!         ],
+//!     );
+//!
+//! assert_eq!(builder.depth(), 3);
+//! assert_eq!(builder.condition_count(), 3);
+//! assert_eq!(builder.group_count(), 2);
+//! assert_eq!(builder.operator_histogram().get("="), Some(&2));
+//!
+//! let Some(AstNode::Group { operator, children }) = builder.to_ast().unwrap() else {
+//!     panic!("expected a group");
+//! };
+//! assert_eq!(operator, "AND");
+//! assert_eq!(
+//!     children[0],
+//!     AstNode::Condition {
+//!         kind: "UInt32Value".to_string(),
+//!         column: "age".to_string(),
+//!         operator: Some(">".to_string()),
+//!         sql: "age > 25".to_string(),
+//!     }
+//! );
+//! ```
+
+use crate::filtering::{FilterBuilder, FilterExpression, FilterOperator};
+use eyre::Result;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Plain-data view of a filter expression
+///
+/// Serialized with a `type` tag of `condition`, `group` or `custom`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum AstNode {
+    /// A single condition
+    Condition {
+        /// Condition kind, as returned by `FilterCondition::kind()`
+        kind: String,
+        column: String,
+        /// SQL operator, absent for conditions that have none
+        operator: Option<String>,
+        /// The condition rendered on its own
+        sql: String,
+    },
+    /// An AND/OR group
+    Group {
+        operator: String,
+        children: Vec<AstNode>,
+    },
+    /// A `FilterExpression::Custom` condition
+    Custom {
+        /// Columns reported by the condition
+        columns: Vec<String>,
+        sql: String,
+    },
+}
+
+impl FilterExpression {
+    /// Nesting depth: 1 for a condition, plus one for each enclosing group
+    pub fn depth(&self) -> usize {
+        match self {
+            FilterExpression::Condition(_) | FilterExpression::Custom(_) => 1,
+            FilterExpression::Group { expressions, .. } => {
+                1 + expressions
+                    .iter()
+                    .map(FilterExpression::depth)
+                    .max()
+                    .unwrap_or(0)
+            }
+        }
+    }
+
+    /// Number of conditions, including custom ones
+    pub fn condition_count(&self) -> usize {
+        match self {
+            FilterExpression::Condition(_) | FilterExpression::Custom(_) => 1,
+            FilterExpression::Group { expressions, .. } => expressions
+                .iter()
+                .map(FilterExpression::condition_count)
+                .sum(),
+        }
+    }
+
+    /// Number of AND/OR groups
+    pub fn group_count(&self) -> usize {
+        match self {
+            FilterExpression::Condition(_) | FilterExpression::Custom(_) => 0,
+            FilterExpression::Group { expressions, .. } => {
+                1 + expressions
+                    .iter()
+                    .map(FilterExpression::group_count)
+                    .sum::<usize>()
+            }
+        }
+    }
+
+    /// Convert the tree to `AstNode`s, rendering conditions as `to_sql()` would
+    pub fn to_ast(&self, case_insensitive: bool) -> Result<AstNode> {
+        to_ast(self, case_insensitive, &HashMap::new())
+    }
+
+    /// Number of conditions per SQL operator, e.g. `{"=": 2, "LIKE": 1}`
+    ///
+    /// Conditions without an operator are counted under their kind, e.g.
+    /// `DateRange::Range`, and custom conditions under `Custom`.
+    pub fn operator_histogram(&self) -> BTreeMap<&'static str, usize> {
+        let mut histogram = BTreeMap::new();
+        count_operators(self, &mut histogram);
+        histogram
+    }
+}
+
+impl FilterBuilder {
+    /// The root expression, if any filters were added
+    pub fn root(&self) -> Option<&FilterExpression> {
+        self.root.as_ref()
+    }
+
+    /// Nesting depth of the root expression, 0 when there are no filters
+    pub fn depth(&self) -> usize {
+        self.root.as_ref().map_or(0, FilterExpression::depth)
+    }
+
+    /// Number of conditions, including custom ones
+    pub fn condition_count(&self) -> usize {
+        self.root
+            .as_ref()
+            .map_or(0, FilterExpression::condition_count)
+    }
+
+    /// Number of AND/OR groups
+    pub fn group_count(&self) -> usize {
+        self.root.as_ref().map_or(0, FilterExpression::group_count)
+    }
+
+    /// Number of conditions per SQL operator, empty when there are no filters
+    pub fn operator_histogram(&self) -> BTreeMap<&'static str, usize> {
+        self.root
+            .as_ref()
+            .map_or_else(BTreeMap::new, FilterExpression::operator_histogram)
+    }
+
+    /// Convert the root expression to `AstNode`s
+    ///
+    /// Conditions are rendered with the builder's case sensitivity. Hooks are not
+    /// run, so this is the tree as it was built.
+    pub fn to_ast(&self) -> Result<Option<AstNode>> {
+        self.root
+            .as_ref()
+            .map(|root| to_ast(root, self.case_insensitive, &self.case_overrides))
+            .transpose()
+    }
+}
+
+fn count_operators(expression: &FilterExpression, histogram: &mut BTreeMap<&'static str, usize>) {
+    let key = match expression {
+        FilterExpression::Condition(condition) => condition
+            .operator()
+            .map_or_else(|| condition.kind(), FilterOperator::as_sql),
+        FilterExpression::Custom(_) => "Custom",
+        FilterExpression::Group { expressions, .. } => {
+            for expression in expressions {
+                count_operators(expression, histogram);
+            }
+            return;
+        }
+    };
+    *histogram.entry(key).or_insert(0) += 1;
+}
+
+fn to_ast(
+    expression: &FilterExpression,
+    case_insensitive: bool,
+    case_overrides: &HashMap<&'static str, bool>,
+) -> Result<AstNode> {
+    Ok(match expression {
+        FilterExpression::Condition(condition) => {
+            let case_insensitive = case_overrides
+                .get(condition.column())
+                .copied()
+                .unwrap_or(case_insensitive);
+            AstNode::Condition {
+                kind: condition.kind().to_string(),
+                column: condition.column().to_string(),
+                operator: condition.operator().map(ToString::to_string),
+                sql: condition.to_sql(case_insensitive)?,
+            }
+        }
+        FilterExpression::Group {
+            operator,
+            expressions,
+        } => AstNode::Group {
+            operator: operator.to_string(),
+            children: expressions
+                .iter()
+                .map(|expression| to_ast(expression, case_insensitive, case_overrides))
+                .collect::<Result<_>>()?,
+        },
+        FilterExpression::Custom(condition) => {
+            let mut sql = String::new();
+            condition.write_sql(&mut sql, case_insensitive)?;
+            AstNode::Custom {
+                columns: condition
+                    .columns()
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+                sql,
+            }
+        }
+    })
+}
@@ -0,0 +1,200 @@
+//! Canonical SQL output
+//!
+//! Semantically identical filters can be written in many ways: IN lists in a different
+//! order, AND/OR siblings swapped, stray whitespace in pass-through expressions. The
+//! canonicalization pass rewrites a filter tree into one stable form so that caching
+//! layers keyed on SQL text see byte-identical queries.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterBuilder, FilterCondition, FilterExpression, FilterOperator};
+//!
+//! let a = FilterBuilder::new()
+//!     .add_condition(FilterCondition::string("status", FilterOperator::In, Some("b, a")))
+//!     .add_condition(FilterCondition::uint32("age", FilterOperator::GreaterThan, Some(25)));
+//! let b = FilterBuilder::new()
+//!     .add_condition(FilterCondition::uint32("age", FilterOperator::GreaterThan, Some(25)))
+//!     .add_condition(FilterCondition::string("status", FilterOperator::In, Some("a,b")));
+//!
+//! assert_eq!(a.build_canonical().unwrap(), b.build_canonical().unwrap());
+//! assert_eq!(
+//!     a.build_canonical().unwrap(),
+//!     "WHERE (age > 25 AND status IN ('a', 'b'))"
+//! );
+//! ```
+
+use crate::filtering::{FilterBuilder, FilterCondition, FilterExpression, FilterOperator};
+use crate::hooks::BuildHooks;
+use crate::ClickHouseFilters;
+use eyre::Result;
+
+impl FilterExpression {
+    /// Rewrite this expression into its canonical form
+    ///
+    /// IN lists and `hasAll` value lists are sorted and deduplicated, and the children of every
+    /// AND/OR group are ordered by their rendered SQL.
+    pub fn canonicalize(&self) -> FilterExpression {
+        match self {
+            FilterExpression::Condition(condition) => {
+                FilterExpression::Condition(condition.canonicalize())
+            }
+            FilterExpression::Custom(_) => self.clone(),
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } => {
+                let mut expressions: Vec<_> = expressions
+                    .iter()
+                    .map(FilterExpression::canonicalize)
+                    .collect();
+                expressions.sort_by_cached_key(|expr| {
+                    expr.to_sql(false).unwrap_or_else(|_| format!("{:?}", expr))
+                });
+                FilterExpression::Group {
+                    operator: *operator,
+                    expressions,
+                }
+            }
+        }
+    }
+}
+
+impl FilterCondition {
+    /// Rewrite this condition into its canonical form
+    ///
+    /// Only set-like values (IN lists, `hasAll` lists and multi-pattern lists) are
+    /// affected; all other conditions are returned unchanged.
+    pub fn canonicalize(&self) -> FilterCondition {
+        let mut condition = self.clone();
+        match &mut condition {
+            FilterCondition::InValues { values, .. }
+            | FilterCondition::ArrayJoinIn { values, .. } => {
+                values.sort_unstable();
+                values.dedup();
+            }
+            FilterCondition::StringValue {
+                operator: FilterOperator::In | FilterOperator::NotIn,
+                value: Some(value),
+                ..
+            }
+            | FilterCondition::FixedStringValue {
+                operator: FilterOperator::In | FilterOperator::NotIn,
+                value: Some(value),
+                ..
+            }
+            | FilterCondition::UUIDValue {
+                operator: FilterOperator::In | FilterOperator::NotIn,
+                value: Some(value),
+                ..
+            }
+            | FilterCondition::StringValue {
+                operator: FilterOperator::MultiMatchAny | FilterOperator::MultiSearchAny,
+                value: Some(value),
+                ..
+            }
+            | FilterCondition::FixedStringValue {
+                operator: FilterOperator::MultiMatchAny | FilterOperator::MultiSearchAny,
+                value: Some(value),
+                ..
+            } => {
+                *value = sorted_list(value);
+            }
+            FilterCondition::ArrayContains { value, .. } => {
+                *value = sorted_list(value);
+            }
+            FilterCondition::ArrayValues {
+                operator:
+                    FilterOperator::ArrayContains
+                    | FilterOperator::ArrayAll
+                    | FilterOperator::ArrayAny
+                    | FilterOperator::ArrayNotContains
+                    | FilterOperator::ArrayNotAny,
+                values,
+                ..
+            }
+            | FilterCondition::ArrayOverlap { values, .. } => {
+                values.sort_unstable();
+                values.dedup();
+            }
+            _ => {}
+        }
+        condition
+    }
+}
+
+impl FilterBuilder {
+    /// Build the canonical WHERE clause, without a leading space
+    ///
+    /// Hooks are applied before canonicalization.
+    pub fn build_canonical(&self) -> Result<String> {
+        let builder = FilterBuilder {
+            root: self
+                .resolved_root()?
+                .as_ref()
+                .map(FilterExpression::canonicalize),
+            case_insensitive: self.case_insensitive,
+            case_overrides: self.case_overrides.clone(),
+            hooks: BuildHooks::default(),
+        };
+        Ok(normalize_whitespace(&builder.build()?))
+    }
+}
+
+impl ClickHouseFilters {
+    /// Generate the canonical SQL for this filter
+    ///
+    /// Semantically identical filters produce byte-identical output. The result
+    /// has no leading space and all whitespace outside string literals is
+    /// collapsed to single spaces.
+    pub fn sql_canonical(&self) -> Result<String> {
+        let mut sql = String::new();
+
+        if let Some(filters) = self.effective_filters()? {
+            sql.push_str(&filters.build_canonical()?);
+        }
+
+        if let Some(sorting) = &self.sorting {
+            sorting.write_sql(&mut sql);
+        }
+
+        if let Some(pagination) = &self.pagination {
+            pagination.write_sql(&mut sql);
+        }
+
+        Ok(normalize_whitespace(&sql))
+    }
+}
+
+// Sort and deduplicate a comma-separated value list, trimming each item
+fn sorted_list(value: &str) -> String {
+    let mut items: Vec<_> = value.split(',').map(str::trim).collect();
+    items.sort_unstable();
+    items.dedup();
+    items.join(",")
+}
+
+/// Collapse whitespace outside single-quoted literals and trim the ends
+pub fn normalize_whitespace(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut in_literal = false;
+    let mut pending_space = false;
+
+    for ch in sql.chars() {
+        if !in_literal && ch.is_whitespace() {
+            pending_space = !out.is_empty();
+            continue;
+        }
+        if pending_space {
+            out.push(' ');
+            pending_space = false;
+        }
+        if ch == '\'' {
+            // An escaped quote ('') toggles twice and leaves the state unchanged
+            in_literal = !in_literal;
+        }
+        out.push(ch);
+    }
+
+    out
+}
@@ -0,0 +1,367 @@
+//! Helpers that run generated queries with the `clickhouse` client
+//!
+//! Available with the `client` feature. These cover the round trips that every caller
+//! otherwise writes by hand, such as counting matching rows before building
+//! pagination, fetching a page with its facet counts as a `QueryResult`, paging
+//! through every matching row for an export, checking a
+//! query's estimated cost before running it, or verifying a query against a live
+//! server. Each query runs with the filters' `ExecutionPolicy`, retrying network
+//! errors and timeouts as it says.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn example(client: clickhouse::Client) -> eyre::Result<()> {
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry, PaginationOptions};
+//!
+//! let columns: ColumnRegistry = column_registry! { name: String };
+//! let filters = ClickHouseFilters::with_auto_count(
+//!     &client,
+//!     "db.users",
+//!     PaginationOptions::new(2, 20, 100, 0),
+//!     vec![],
+//!     None,
+//!     columns,
+//! )
+//! .await?;
+//!
+//! let pagination = &filters.pagination.as_ref().unwrap().pagination;
+//! println!("page {} of {}", pagination.current_page, pagination.total_pages);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cost::{CostLimits, QueryEstimate};
+use crate::count::CountExpression;
+use crate::execution::ExecutionPolicy;
+use crate::pagination::Paginate;
+use crate::result::{FacetCount, FacetCounts, QueryResult};
+use crate::sorting::SortedColumn;
+use crate::table::TableRef;
+use crate::{ClickHouseFilters, ColumnRegistry, FilteringOptions, PaginationOptions};
+use eyre::Result;
+use futures_util::stream::{self, Stream};
+use serde::de::DeserializeOwned;
+use std::future::Future;
+
+impl ClickHouseFilters {
+    /// Create filters whose pagination uses the real number of matching rows
+    ///
+    /// Runs `count_sql()` for the filters first, then builds pagination with the
+    /// result as `total_records`; the `total_records` in `pagination` is ignored.
+    /// The count runs with the default `ExecutionPolicy`; to count with another
+    /// one, set it on the filters and call `fetch_count()` and
+    /// `set_total_records()` instead.
+    pub async fn with_auto_count(
+        client: &clickhouse::Client,
+        table: impl Into<TableRef>,
+        pagination: PaginationOptions,
+        sorting_columns: Vec<SortedColumn>,
+        filtering_options: Option<FilteringOptions>,
+        column_defs: impl Into<ColumnRegistry>,
+    ) -> Result<ClickHouseFilters> {
+        if pagination.with_ties && sorting_columns.is_empty() {
+            return Err(eyre::eyre!("WITH TIES pagination requires sorting"));
+        }
+        let mut filters =
+            ClickHouseFilters::new(None, sorting_columns, filtering_options, column_defs)?;
+        let total_records = filters.fetch_count(client, table).await?;
+
+        let paginate = Paginate::new(
+            pagination.current_page,
+            pagination.per_page,
+            pagination.per_page_limit,
+            total_records,
+        )
+        .with_syntax(pagination.syntax);
+        filters.pagination = Some(match pagination.with_ties {
+            true => paginate.with_ties(),
+            false => paginate,
+        });
+        Ok(filters)
+    }
+
+    /// Run `count_sql()` and return the number of matching rows
+    pub async fn fetch_count(
+        &self,
+        client: &clickhouse::Client,
+        table: impl Into<TableRef>,
+    ) -> Result<i64> {
+        let sql = self.count_sql(table)?;
+
+        // `sum(sign)` is signed, `COUNT(*)` and `uniqExact` are not
+        let count = if self.column_defs.sign_column().is_some() {
+            self.execution
+                .run(|| client.query(&sql).fetch_one::<i64>())
+                .await?
+                .max(0)
+        } else {
+            i64::try_from(
+                self.execution
+                    .run(|| client.query(&sql).fetch_one::<u64>())
+                    .await?,
+            )?
+        };
+        Ok(count)
+    }
+
+    /// Stream every matching row in batches, paging with LIMIT/OFFSET
+    ///
+    /// Each batch is a separate `query_sql()` with `batch_size` rows per page,
+    /// ignoring `pagination`. The stream ends after the first short batch. Use a
+    /// sort on a unique key so rows do not move between pages while streaming.
+    pub fn fetch_all_pages<'a, T>(
+        &'a self,
+        client: &'a clickhouse::Client,
+        table: impl Into<TableRef>,
+        columns: &'a [&'a str],
+        batch_size: u64,
+    ) -> impl Stream<Item = Result<Vec<T>>> + 'a
+    where
+        T: clickhouse::Row + DeserializeOwned + 'a,
+    {
+        let per_page = batch_size.max(1) as i64;
+        let table = std::sync::Arc::new(table.into());
+        stream::try_unfold(Some(1_i64), move |page| {
+            let table = std::sync::Arc::clone(&table);
+            async move {
+                let Some(page) = page else {
+                    return Ok(None);
+                };
+
+                let mut batch = self.clone();
+                batch.pagination = Some(Paginate::new(page, per_page, per_page, 0));
+                let sql = batch.query_sql(&*table, columns)?;
+                let rows = self
+                    .execution
+                    .run(|| client.query(&sql).fetch_all::<T>())
+                    .await?;
+
+                let next = (rows.len() as i64 == per_page).then_some(page + 1);
+                Ok(match rows.is_empty() {
+                    true => None,
+                    false => Some((rows, next)),
+                })
+            }
+        })
+    }
+
+    /// Stream every matching row in batches, paging by a key column
+    ///
+    /// Rows are read in ascending `key_column` order, and each batch after the
+    /// first only reads rows whose key is greater than the last one seen, as
+    /// returned by `key`. This keeps every batch as cheap as the first, unlike
+    /// OFFSET paging. `key_column` must be unique and registered in
+    /// `column_defs`; `sorting` and `pagination` are ignored. Each batch is the
+    /// query of `with_key_batch()`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn fetch_all_pages_by_key<'a, T, F>(
+        &'a self,
+        client: &'a clickhouse::Client,
+        table: impl Into<TableRef>,
+        columns: &'a [&'a str],
+        batch_size: u64,
+        key_column: &'a str,
+        key: F,
+    ) -> impl Stream<Item = Result<Vec<T>>> + 'a
+    where
+        T: clickhouse::Row + DeserializeOwned + 'a,
+        F: Fn(&T) -> String + 'a,
+    {
+        let per_page = batch_size.max(1) as i64;
+        let key = std::sync::Arc::new(key);
+        let table = std::sync::Arc::new(table.into());
+        stream::try_unfold(Some(None::<String>), move |last| {
+            let key = std::sync::Arc::clone(&key);
+            let table = std::sync::Arc::clone(&table);
+            async move {
+                let Some(last) = last else {
+                    return Ok(None);
+                };
+                let batch = self
+                    .clone()
+                    .with_key_batch(key_column, last.as_deref(), batch_size)?;
+                let sql = batch.query_sql(&*table, columns)?;
+                let rows = self
+                    .execution
+                    .run(|| client.query(&sql).fetch_all::<T>())
+                    .await?;
+
+                let next = match rows.last() {
+                    Some(row) if rows.len() as i64 == per_page => Some(Some(key(row))),
+                    _ => None,
+                };
+                Ok(match rows.is_empty() {
+                    true => None,
+                    false => Some((rows, next)),
+                })
+            }
+        })
+    }
+
+    /// Run `query_sql()` and return the rows with the pagination metadata
+    ///
+    /// Without pagination, the result is a single page holding every row. Call
+    /// `set_total_records()` or use `with_auto_count()` first for the metadata to
+    /// reflect the real number of matching rows.
+    pub async fn fetch_page<T>(
+        &self,
+        client: &clickhouse::Client,
+        table: impl Into<TableRef>,
+        columns: &[&str],
+    ) -> Result<QueryResult<T>>
+    where
+        T: clickhouse::Row + DeserializeOwned,
+    {
+        let sql = self.query_sql(table, columns)?;
+        let rows = self
+            .execution
+            .run(|| client.query(&sql).fetch_all::<T>())
+            .await?;
+        Ok(match &self.pagination {
+            Some(paginate) => QueryResult::new(rows, paginate.pagination.clone()),
+            None => QueryResult::single_page(rows),
+        })
+    }
+
+    /// Count the matching rows per value of each of `columns`
+    ///
+    /// Keeps the `limit` most frequent values of each column, with values
+    /// converted to text. Sign columns and count deduplication are honored as in
+    /// `count_sql()`.
+    pub async fn fetch_facets(
+        &self,
+        client: &clickhouse::Client,
+        table: impl Into<TableRef>,
+        columns: &[&str],
+        limit: u64,
+    ) -> Result<FacetCounts> {
+        if limit == 0 {
+            return Err(eyre::eyre!("Facet limit must be greater than zero"));
+        }
+        let table = table.into();
+        let mut facets = FacetCounts::new();
+        for column in columns {
+            if !self.column_defs.contains(column) {
+                return Err(eyre::eyre!("Column not found: {}", column));
+            }
+            let group = format!("toString({})", column);
+            let sql =
+                self.count_query(&table, &CountExpression::Rows, Some(&group), Some(limit))?;
+            let counts = self
+                .execution
+                .run(|| client.query(&sql).fetch_all::<(String, u64)>())
+                .await?;
+            facets.insert(
+                column,
+                counts
+                    .into_iter()
+                    .map(|(value, count)| FacetCount { value, count })
+                    .collect(),
+            );
+        }
+        Ok(facets)
+    }
+
+    /// Run `explain_estimate_sql()` and sum the estimate over every table read
+    pub async fn estimate(
+        &self,
+        client: &clickhouse::Client,
+        table: impl Into<TableRef>,
+        columns: &[&str],
+    ) -> Result<QueryEstimate> {
+        let sql = self.explain_estimate_sql(table, columns)?;
+        let rows = self
+            .execution
+            .run(|| {
+                client
+                    .query(&sql)
+                    .fetch_all::<(String, String, u64, u64, u64)>()
+            })
+            .await?;
+
+        Ok(rows.into_iter().fold(
+            QueryEstimate::default(),
+            |total, (_database, _table, parts, rows, marks)| QueryEstimate {
+                parts: total.parts + parts,
+                rows: total.rows + rows,
+                marks: total.marks + marks,
+            },
+        ))
+    }
+
+    /// Estimate the query and check it against `limits` before running it
+    ///
+    /// Fails when a limit is breached, unless the limits are warn-only, in which
+    /// case the breaches are returned alongside the estimate.
+    pub async fn check_cost(
+        &self,
+        client: &clickhouse::Client,
+        table: impl Into<TableRef>,
+        columns: &[&str],
+        limits: &CostLimits,
+    ) -> Result<(QueryEstimate, Vec<String>)> {
+        let estimate = self.estimate(client, table, columns).await?;
+        let warnings = limits.check(&estimate)?;
+        Ok((estimate, warnings))
+    }
+
+    /// Run `verify_sql()` to check the query against a live server
+    ///
+    /// Fails with the server's error when the query doesn't parse or refers to
+    /// tables or columns that don't exist. No data is read.
+    pub async fn verify_against(
+        &self,
+        client: &clickhouse::Client,
+        table: impl Into<TableRef>,
+        columns: &[&str],
+    ) -> Result<()> {
+        let sql = self.verify_sql(table, columns)?;
+        self.execution
+            .run(|| client.query(&sql).fetch_all::<String>())
+            .await
+            .map_err(|e| eyre::eyre!("Query failed verification: {}", e))?;
+        Ok(())
+    }
+}
+
+impl ExecutionPolicy {
+    /// Run a query, retrying network errors and timeouts as the policy says
+    ///
+    /// `query` is called for every attempt, so it should build the query anew,
+    /// e.g. `|| client.query(&sql).fetch_all::<Row>()`. Other errors are returned
+    /// straight away.
+    pub async fn run<T, F, Fut>(&self, mut query: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = clickhouse::error::Result<T>>,
+    {
+        let mut retry = 0;
+        loop {
+            let result = match self.per_query_timeout {
+                Some(limit) => tokio::time::timeout(limit, query())
+                    .await
+                    .unwrap_or(Err(clickhouse::error::Error::TimedOut)),
+                None => query().await,
+            };
+            let err = match result {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+
+            let transient = matches!(
+                err,
+                clickhouse::error::Error::Network(_) | clickhouse::error::Error::TimedOut
+            );
+            if transient && retry < self.retries {
+                tokio::time::sleep(self.backoff_for(retry)).await;
+                retry += 1;
+                continue;
+            }
+            return Err(match retry {
+                0 => err.into(),
+                _ => eyre::eyre!("Query failed after {} attempts: {}", retry + 1, err),
+            });
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Cost guardrails based on `EXPLAIN ESTIMATE`
+//!
+//! `EXPLAIN ESTIMATE` asks the server how many parts, rows and marks a query would
+//! read, using only the primary index and partition pruning, without running it.
+//! `CostLimits` holds thresholds for those numbers, so interactive endpoints can
+//! reject filters that miss every index before they reach the cluster.
+//!
+//! `ClickHouseFilters::explain_estimate_sql()` renders the statement. With the
+//! `client` feature, `estimate()` runs it and `check_cost()` applies the limits.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::cost::{CostLimits, QueryEstimate};
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry};
+//!
+//! let columns: ColumnRegistry = column_registry! { name: String };
+//! let filters = ClickHouseFilters::new(None, vec![], None, columns).unwrap();
+//! assert_eq!(
+//!     filters.explain_estimate_sql("db.users", &["name"]).unwrap(),
+//!     "EXPLAIN ESTIMATE SELECT name FROM db.users"
+//! );
+//!
+//! let limits = CostLimits::new().with_max_rows(1_000_000);
+//! let estimate = QueryEstimate { parts: 12, rows: 5_000_000, marks: 610 };
+//! assert_eq!(
+//!     limits.check(&estimate).unwrap_err().to_string(),
+//!     "Estimated rows to read exceed limit: 5000000 > 1000000"
+//! );
+//!
+//! // In warn-only mode the same breach is reported instead of rejected
+//! let warnings = limits.warn_only().check(&estimate).unwrap();
+//! assert_eq!(warnings, vec!["Estimated rows to read exceed limit: 5000000 > 1000000"]);
+//! ```
+
+use crate::table::TableRef;
+use crate::ClickHouseFilters;
+use eyre::Result;
+
+/// What `EXPLAIN ESTIMATE` reports for a query, summed over every table it reads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryEstimate {
+    pub parts: u64,
+    pub rows: u64,
+    pub marks: u64,
+}
+
+/// Thresholds on the estimated cost of a query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostLimits {
+    max_rows: Option<u64>,
+    max_parts: Option<u64>,
+    warn_only: bool,
+}
+
+impl CostLimits {
+    /// Create limits with no thresholds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the estimated number of rows to read
+    pub fn with_max_rows(mut self, rows: u64) -> Self {
+        self.max_rows = Some(rows);
+        self
+    }
+
+    /// Limit the estimated number of parts to read
+    pub fn with_max_parts(mut self, parts: u64) -> Self {
+        self.max_parts = Some(parts);
+        self
+    }
+
+    /// Report breached limits as warnings instead of rejecting the query
+    pub fn warn_only(mut self) -> Self {
+        self.warn_only = true;
+        self
+    }
+
+    pub fn max_rows(&self) -> Option<u64> {
+        self.max_rows
+    }
+
+    pub fn max_parts(&self) -> Option<u64> {
+        self.max_parts
+    }
+
+    pub fn is_warn_only(&self) -> bool {
+        self.warn_only
+    }
+
+    /// Check an estimate against the limits
+    ///
+    /// Returns an error for the first breached limit, or in warn-only mode a
+    /// message for every breached limit (empty when the query is within limits).
+    pub fn check(&self, estimate: &QueryEstimate) -> Result<Vec<String>> {
+        let mut breaches = Vec::new();
+        if let Some(max) = self.max_rows.filter(|max| estimate.rows > *max) {
+            breaches.push(format!(
+                "Estimated rows to read exceed limit: {} > {}",
+                estimate.rows, max
+            ));
+        }
+        if let Some(max) = self.max_parts.filter(|max| estimate.parts > *max) {
+            breaches.push(format!(
+                "Estimated parts to read exceed limit: {} > {}",
+                estimate.parts, max
+            ));
+        }
+
+        match breaches.first() {
+            Some(breach) if !self.warn_only => Err(eyre::eyre!("{}", breach)),
+            _ => Ok(breaches),
+        }
+    }
+}
+
+impl ClickHouseFilters {
+    /// Generate `EXPLAIN ESTIMATE` for the query built by `query_sql()`
+    pub fn explain_estimate_sql(
+        &self,
+        table: impl Into<TableRef>,
+        columns: &[&str],
+    ) -> Result<String> {
+        Ok(format!(
+            "EXPLAIN ESTIMATE {}",
+            self.query_sql(table, columns)?
+        ))
+    }
+}
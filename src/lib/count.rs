@@ -0,0 +1,238 @@
+//! Count expressions for ClickHouse COUNT queries
+//!
+//! `ClickHouseFilters::count_sql()` counts matching rows. `count_sql_with()` takes a
+//! `CountExpression` to count distinct values instead, either exactly or with one of
+//! ClickHouse's approximate `uniq` functions. `count_by_sql()` counts matching rows
+//! per value of a column, e.g. for "12 open, 3 closed" badges, and `top_n_sql()`
+//! keeps the groups with the highest value of any count expression, e.g. the top
+//! 10 pages by views.
+//!
+//! For CollapsingMergeTree tables with a declared sign column (see
+//! `ColumnRegistry::with_sign_column`), row counts are rendered as `sum(sign)`.
+//! ReplacingMergeTree tables can declare a `Deduplication` strategy (see
+//! `ColumnRegistry::with_deduplicated_count`) so row counts use `FINAL` or
+//! `uniqExact` over the sorting key.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::count::CountExpression;
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry};
+//!
+//! let columns: ColumnRegistry = column_registry! { user_id: UInt64 };
+//! let filters = ClickHouseFilters::new(None, vec![], None, columns).unwrap();
+//!
+//! assert_eq!(
+//!     filters
+//!         .count_sql_with("analytics.events", &CountExpression::uniq("user_id"))
+//!         .unwrap(),
+//!     "SELECT uniq(user_id) FROM analytics.events"
+//! );
+//! ```
+
+use crate::table::TableRef;
+use crate::ClickHouseFilters;
+use eyre::Result;
+use std::fmt::Write;
+
+/// What a COUNT query counts
+#[derive(Debug, Clone, PartialEq)]
+pub enum CountExpression {
+    /// `COUNT(*)`
+    Rows,
+    /// `COUNT(DISTINCT column)`
+    Distinct(String),
+    /// `uniq(column)`, an approximate distinct count
+    Uniq(String),
+    /// `uniqExact(column)`
+    UniqExact(String),
+    /// A raw aggregate expression, rendered as-is (not validated)
+    Custom(String),
+}
+
+impl CountExpression {
+    pub fn distinct(column: &str) -> Self {
+        CountExpression::Distinct(column.to_string())
+    }
+
+    pub fn uniq(column: &str) -> Self {
+        CountExpression::Uniq(column.to_string())
+    }
+
+    pub fn uniq_exact(column: &str) -> Self {
+        CountExpression::UniqExact(column.to_string())
+    }
+
+    pub fn custom(expression: &str) -> Self {
+        CountExpression::Custom(expression.to_string())
+    }
+
+    /// The column being counted, if any
+    pub fn column(&self) -> Option<&str> {
+        match self {
+            CountExpression::Distinct(column)
+            | CountExpression::Uniq(column)
+            | CountExpression::UniqExact(column) => Some(column),
+            CountExpression::Rows | CountExpression::Custom(_) => None,
+        }
+    }
+
+    /// Render the aggregate expression into an existing buffer
+    pub fn write_sql(&self, out: &mut String) -> Result<()> {
+        match self {
+            CountExpression::Rows => out.push_str("COUNT(*)"),
+            CountExpression::Distinct(column) => write!(out, "COUNT(DISTINCT {})", column)?,
+            CountExpression::Uniq(column) => write!(out, "uniq({})", column)?,
+            CountExpression::UniqExact(column) => write!(out, "uniqExact({})", column)?,
+            CountExpression::Custom(expression) => out.push_str(expression),
+        }
+        Ok(())
+    }
+}
+
+/// How row counts are deduplicated for ReplacingMergeTree tables
+///
+/// Until background merges complete, a ReplacingMergeTree table can hold several
+/// versions of a row, so a plain `COUNT(*)` over-reports.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Deduplication {
+    /// Count with `FINAL`, merging versions at query time
+    Final,
+    /// Count distinct sorting key values with `uniqExact(...)`
+    UniqueKey(Vec<&'static str>),
+}
+
+impl ClickHouseFilters {
+    /// Generate a SQL COUNT query for this filter using a custom count expression
+    ///
+    /// Columns counted by `Distinct`, `Uniq` and `UniqExact` must be present in
+    /// `column_defs`.
+    pub fn count_sql_with(
+        &self,
+        table: impl Into<TableRef>,
+        count: &CountExpression,
+    ) -> Result<String> {
+        self.count_query(&table.into(), count, None, None)
+    }
+
+    /// Generate a SQL query counting matching rows per value of `group_column`
+    ///
+    /// Renders `SELECT status, COUNT(*) FROM ... GROUP BY status ORDER BY COUNT(*) DESC`
+    /// with the current filters, for showing the number of results per value next
+    /// to a filtered list. Sign columns and count deduplication are honored as in
+    /// `count_sql`.
+    pub fn count_by_sql(&self, table: impl Into<TableRef>, group_column: &str) -> Result<String> {
+        if !self.column_defs.contains(group_column) {
+            return Err(eyre::eyre!("Column not found: {}", group_column));
+        }
+        self.count_query(
+            &table.into(),
+            &CountExpression::Rows,
+            Some(group_column),
+            None,
+        )
+    }
+
+    /// Generate a SQL query for the `n` values of `group_column` with the highest metric
+    ///
+    /// Renders `SELECT page, sum(views) FROM ... GROUP BY page ORDER BY sum(views) DESC
+    /// LIMIT 10` with the current filters. The metric is any count expression;
+    /// `CountExpression::custom("sum(views)")` covers other aggregates. Sign columns
+    /// and count deduplication apply to `CountExpression::Rows` as in `count_sql`.
+    pub fn top_n_sql(
+        &self,
+        table: impl Into<TableRef>,
+        group_column: &str,
+        metric_expr: &CountExpression,
+        n: u64,
+    ) -> Result<String> {
+        if !self.column_defs.contains(group_column) {
+            return Err(eyre::eyre!("Column not found: {}", group_column));
+        }
+        if n == 0 {
+            return Err(eyre::eyre!("Top-N limit must be greater than zero"));
+        }
+        self.count_query(&table.into(), metric_expr, Some(group_column), Some(n))
+    }
+
+    pub(crate) fn count_query(
+        &self,
+        table: &TableRef,
+        count: &CountExpression,
+        group_column: Option<&str>,
+        limit: Option<u64>,
+    ) -> Result<String> {
+        if let Some(column) = count.column() {
+            if !self.column_defs.contains(column) {
+                return Err(eyre::eyre!("Column not found: {}", column));
+            }
+        }
+
+        // Collapsing tables count rows as the sum of their signs, replacing tables
+        // count deduplicated rows
+        let (sign, deduplication) = match count {
+            CountExpression::Rows => (
+                self.column_defs.sign_column(),
+                self.column_defs.deduplication(),
+            ),
+            _ => (None, None),
+        };
+        if sign.is_some() && deduplication.is_some() {
+            return Err(eyre::eyre!(
+                "A sign column and count deduplication cannot be combined"
+            ));
+        }
+
+        let mut aggregate = String::new();
+        match (sign, deduplication) {
+            (Some(column), _) => write!(aggregate, "sum({})", column)?,
+            (None, Some(Deduplication::UniqueKey(key))) => {
+                if key.is_empty() {
+                    return Err(eyre::eyre!("Deduplication key must not be empty"));
+                }
+                aggregate.push_str("uniqExact(");
+                for (i, column) in key.iter().enumerate() {
+                    if !self.column_defs.contains(column) {
+                        return Err(eyre::eyre!("Column not found: {}", column));
+                    }
+                    if i > 0 {
+                        aggregate.push_str(", ");
+                    }
+                    aggregate.push_str(column);
+                }
+                aggregate.push(')');
+            }
+            _ => count.write_sql(&mut aggregate)?,
+        }
+
+        let mut sql = String::with_capacity(32 + aggregate.len() + self.estimated_sql_len());
+        sql.push_str("SELECT ");
+        if let Some(column) = group_column {
+            write!(sql, "{}, ", column)?;
+        }
+        sql.push_str(&aggregate);
+        sql.push_str(" FROM ");
+        table.write_sql(&mut sql, deduplication == Some(&Deduplication::Final))?;
+
+        // Add WHERE clause from filters
+        if sign.is_some() {
+            // Validate the sign column even though its condition is not rendered
+            self.column_defs.sign_condition()?;
+            if let Some(filters) = self.filters_with_implicit(false)? {
+                filters.write_sql(&mut sql)?;
+            }
+        } else {
+            sql.push_str(self.where_clause()?);
+        }
+
+        if let Some(column) = group_column {
+            write!(sql, " GROUP BY {} ORDER BY {} DESC", column, aggregate)?;
+        }
+        if let Some(limit) = limit {
+            write!(sql, " LIMIT {}", limit)?;
+        }
+
+        self.write_settings(&mut sql, true)?;
+        Ok(sql)
+    }
+}
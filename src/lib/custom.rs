@@ -0,0 +1,300 @@
+//! Custom column types, operators and conditions with application-supplied rendering
+//!
+//! `ColumnDef::Custom` covers ClickHouse types the crate has no variant for, such as
+//! `AggregateFunction` states, geo types or the experimental `Object` type. The
+//! column's `ColumnRenderer` checks operators and values when a condition is built
+//! and writes the SQL for it. Custom columns are otherwise ordinary registry entries,
+//! so they are validated, sortable and usable from JSON filters like any other.
+//!
+//! Custom operators (see `ColumnRegistry::with_operator`) work the other way around:
+//! they add house-specific operators, such as `WITHIN_SLA`, to every column of a
+//! registry.
+//!
+//! For condition kinds that don't fit a single column and operator, implement
+//! `ToSqlCondition` and wrap it in `FilterExpression::Custom`. Custom expressions
+//! nest in groups and builders like any other expression. `write_string_literal()`
+//! and `escape_like()` give implementations the same escaping the crate uses.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::custom::ColumnRenderer;
+//! use clickhouse_filters::filtering::{FilterOperator, JsonFilter};
+//! use clickhouse_filters::{ColumnDef, ColumnRegistry, FilteringOptions};
+//! use std::fmt::Write;
+//!
+//! // `location` is a `Point`; `<` means "within this many metres of lon,lat"
+//! #[derive(Debug)]
+//! struct Distance;
+//!
+//! impl ColumnRenderer for Distance {
+//!     fn validate(&self, _column: &str, operator: &FilterOperator, value: Option<&str>) -> eyre::Result<()> {
+//!         let parts = value.unwrap_or_default().split(',').map(|v| v.trim().parse::<f64>());
+//!         match (operator, parts.collect::<Result<Vec<_>, _>>()) {
+//!             (FilterOperator::LessThan, Ok(parts)) if parts.len() == 3 => Ok(()),
+//!             _ => Err(eyre::eyre!("Expected < lon,lat,metres")),
+//!         }
+//!     }
+//!
+//!     fn write_sql(
+//!         &self,
+//!         out: &mut String,
+//!         column: &str,
+//!         _operator: &FilterOperator,
+//!         value: Option<&str>,
+//!         _case_insensitive: bool,
+//!     ) -> eyre::Result<()> {
+//!         let parts: Vec<&str> = value.unwrap_or_default().split(',').map(str::trim).collect();
+//!         write!(out, "geoDistance({0}.1, {0}.2, {1}, {2}) < {3}", column, parts[0], parts[1], parts[2])?;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let columns = ColumnRegistry::from_iter([("location", ColumnDef::custom("location", Distance))]);
+//! let filters = vec![JsonFilter {
+//!     n: "location".to_string(),
+//!     f: "<".to_string(),
+//!     v: "-0.12, 51.5, 500".to_string(),
+//!     c: None,
+//! }];
+//!
+//! let filtering = FilteringOptions::from_json_filters(&filters, &columns).unwrap().unwrap();
+//! assert_eq!(
+//!     filtering.to_sql().unwrap(),
+//!     " WHERE geoDistance(location.1, location.2, -0.12, 51.5) < 500"
+//! );
+//!
+//! let bad = vec![JsonFilter { v: "nowhere".to_string(), ..filters.into_iter().next().unwrap() }];
+//! assert!(FilteringOptions::from_json_filters(&bad, &columns).is_err());
+//! ```
+//!
+//! A custom operator renders from the column, value and case-sensitivity flag:
+//!
+//! ```rust
+//! use clickhouse_filters::{column_registry, ColumnRegistry};
+//! use std::fmt::Write;
+//!
+//! let columns: ColumnRegistry = column_registry! { resolved_at: DateTime };
+//! let columns = columns.with_operator("WITHIN_SLA", |out, column, value, _case_insensitive| {
+//!     let hours: u32 = value.unwrap_or_default().parse()?;
+//!     write!(out, "{} <= created_at + INTERVAL {} HOUR", column, hours)?;
+//!     Ok(())
+//! });
+//!
+//! let condition = columns.to_filter_condition("resolved_at", "within_sla", "4").unwrap();
+//! assert_eq!(
+//!     condition.to_sql(false).unwrap(),
+//!     "resolved_at <= created_at + INTERVAL 4 HOUR"
+//! );
+//! ```
+//!
+//! A custom condition renders itself and reports the columns it reads:
+//!
+//! ```rust
+//! use clickhouse_filters::custom::{write_string_literal, ToSqlCondition};
+//! use clickhouse_filters::filtering::{FilterBuilder, FilterCondition, FilterExpression, FilterOperator};
+//! use std::sync::Arc;
+//!
+//! // Rows whose `title` or `body` mentions a term
+//! #[derive(Debug)]
+//! struct Mentions(String);
+//!
+//! impl ToSqlCondition for Mentions {
+//!     fn write_sql(&self, out: &mut String, _case_insensitive: bool) -> eyre::Result<()> {
+//!         out.push_str("hasToken(concat(title, ' ', body), ");
+//!         write_string_literal(out, &self.0);
+//!         out.push(')');
+//!         Ok(())
+//!     }
+//!
+//!     fn columns(&self) -> Vec<&str> {
+//!         vec!["title", "body"]
+//!     }
+//! }
+//!
+//! let builder = FilterBuilder::new()
+//!     .add_condition(FilterCondition::string("status", FilterOperator::Equal, Some("open")))
+//!     .add_expression(FilterExpression::Custom(Arc::new(Mentions("it's".to_string()))));
+//! assert_eq!(
+//!     builder.build().unwrap(),
+//!     r" WHERE (status = 'open' AND hasToken(concat(title, ' ', body), 'it\'s'))"
+//! );
+//! ```
+
+use crate::filtering::FilterOperator;
+use crate::ColumnDef;
+use eyre::Result;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+/// Rendering logic for a `ColumnDef::Custom` column
+pub trait ColumnRenderer: fmt::Debug + Send + Sync {
+    /// Check an operator and value when a condition is built for the column
+    ///
+    /// `value` is `None` for `IS NULL` / `IS NOT NULL`. Accepts everything by default.
+    fn validate(
+        &self,
+        _column: &str,
+        _operator: &FilterOperator,
+        _value: Option<&str>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Render the condition into the buffer
+    ///
+    /// Values come straight from the caller, so the renderer is responsible for
+    /// quoting and escaping them.
+    fn write_sql(
+        &self,
+        out: &mut String,
+        column: &str,
+        operator: &FilterOperator,
+        value: Option<&str>,
+        case_insensitive: bool,
+    ) -> Result<()>;
+}
+
+/// Shared handle to a `ColumnRenderer`, held by custom conditions
+///
+/// Renderers can't be compared, so handles are equal when they point to the same
+/// renderer instance.
+#[derive(Clone)]
+pub struct RendererRef(pub Arc<dyn ColumnRenderer>);
+
+impl fmt::Debug for RendererRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl PartialEq for RendererRef {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Rendering function of a custom operator
+///
+/// Called with the output buffer, the column, the filter value and the
+/// case-insensitive flag.
+pub type OperatorFn = dyn Fn(&mut String, &str, Option<&str>, bool) -> Result<()> + Send + Sync;
+
+/// A named operator registered with `ColumnRegistry::with_operator`
+#[derive(Clone)]
+pub struct CustomOperator {
+    name: &'static str,
+    render: Arc<OperatorFn>,
+}
+
+impl CustomOperator {
+    pub(crate) fn new(name: &'static str, render: Arc<OperatorFn>) -> Self {
+        CustomOperator { name, render }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl fmt::Debug for CustomOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CustomOperator").field(&self.name).finish()
+    }
+}
+
+impl ColumnRenderer for CustomOperator {
+    fn write_sql(
+        &self,
+        out: &mut String,
+        column: &str,
+        _operator: &FilterOperator,
+        value: Option<&str>,
+        case_insensitive: bool,
+    ) -> Result<()> {
+        (self.render)(out, column, value, case_insensitive)
+    }
+}
+
+impl ColumnDef {
+    /// Define a custom column rendered by `renderer`
+    pub fn custom(name: &'static str, renderer: impl ColumnRenderer + 'static) -> ColumnDef {
+        ColumnDef::Custom {
+            name,
+            renderer: Arc::new(renderer),
+        }
+    }
+}
+
+/// A condition kind implemented outside the crate, see `FilterExpression::Custom`
+pub trait ToSqlCondition: fmt::Debug + Send + Sync {
+    /// Render the condition into the buffer
+    fn write_sql(&self, out: &mut String, case_insensitive: bool) -> Result<()>;
+
+    /// Columns the condition reads, reported by `referenced_columns()`
+    fn columns(&self) -> Vec<&str> {
+        Vec::new()
+    }
+}
+
+// Custom conditions can't be compared, so they are equal when they are the same instance
+impl PartialEq for dyn ToSqlCondition {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+}
+
+/// Write `value` as a single-quoted string literal, escaping quotes and backslashes
+pub fn write_string_literal(out: &mut String, value: &str) {
+    out.reserve(value.len() + 2);
+    out.push('\'');
+    for c in value.chars() {
+        if c == '\\' || c == '\'' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('\'');
+}
+
+/// Quote `name` as an identifier with backticks, unless it is a plain identifier
+///
+/// Plain identifiers start with a letter or underscore and contain only ASCII
+/// letters, digits and underscores.
+pub fn quote_identifier(name: &str) -> Cow<'_, str> {
+    let mut chars = name.chars();
+    let plain = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        return Cow::Borrowed(name);
+    }
+
+    let mut quoted = String::with_capacity(name.len() + 2);
+    quoted.push('`');
+    for c in name.chars() {
+        if c == '\\' || c == '`' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('`');
+    Cow::Owned(quoted)
+}
+
+/// Escape `%`, `_` and `\` so `value` matches literally inside a LIKE pattern
+///
+/// The result still needs to be written as a literal, e.g. with
+/// `write_string_literal()`.
+pub fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,398 @@
+//! Human-readable descriptions of filters
+//!
+//! `FilterExpression::describe()` renders a filter tree as a sentence such as
+//! `Signup date is after 2024-01-01 and Status is one of open, new`, for showing
+//! the active filters above a report or recording them in an audit log. Columns are
+//! named by the labels declared with `ColumnRegistry::with_label`, falling back to
+//! the column name. The sentence is for people to read; its wording may change
+//! between releases, so don't parse it.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! use clickhouse_filters::{column_registry, ColumnRegistry};
+//!
+//! let columns: ColumnRegistry = column_registry! {
+//!     created_at: Date,
+//!     status: String,
+//! };
+//! let columns = columns
+//!     .with_label("created_at", "Signup date")
+//!     .with_label("status", "Status");
+//!
+//! let expr = FilterExpression::and(vec![
+//!     FilterExpression::Condition(FilterCondition::date(
+//!         "created_at",
+//!         FilterOperator::GreaterThan,
+//!         Some("2024-01-01"),
+//!     )),
+//!     FilterExpression::Condition(FilterCondition::in_values(
+//!         "status",
+//!         FilterOperator::In,
+//!         vec!["open".to_string(), "new".to_string()],
+//!         None,
+//!     )),
+//! ]);
+//!
+//! assert_eq!(
+//!     expr.describe(&columns),
+//!     "Signup date is after 2024-01-01 and Status is one of open, new"
+//! );
+//! ```
+
+use crate::filtering::{
+    DateRangeType, FilterCondition, FilterExpression, FilterOperator, LogicalOperator,
+};
+use crate::{ClickHouseFilters, ColumnRegistry, FilteringOptions};
+use std::fmt::Display;
+
+impl FilterExpression {
+    /// Describe the expression in words, naming columns by their labels
+    pub fn describe(&self, columns: &ColumnRegistry) -> String {
+        let mut out = String::new();
+        write_expression(&mut out, self, columns, false);
+        out
+    }
+}
+
+impl FilteringOptions {
+    /// Describe the filter expressions in words, or `None` without expressions
+    ///
+    /// Conditions added by build hooks are not included.
+    pub fn describe(&self) -> Option<String> {
+        match self.expressions.as_slice() {
+            [] => None,
+            [expression] => Some(expression.describe(&self.column_defs)),
+            expressions => Some(
+                FilterExpression::Group {
+                    operator: self.root_operator,
+                    expressions: expressions.to_vec(),
+                }
+                .describe(&self.column_defs),
+            ),
+        }
+    }
+}
+
+impl ClickHouseFilters {
+    /// Describe the requested filters in words, or `None` without filters
+    ///
+    /// Only the filters as requested are described; the registry's soft-delete
+    /// and sign conditions, row policies and build hooks are left out.
+    pub fn describe(&self) -> Option<String> {
+        let root = self.filters.as_ref()?.root.as_ref()?;
+        Some(root.describe(&self.column_defs))
+    }
+}
+
+fn write_expression(
+    out: &mut String,
+    expression: &FilterExpression,
+    columns: &ColumnRegistry,
+    nested: bool,
+) {
+    match expression {
+        FilterExpression::Condition(condition) => write_condition(out, condition, columns),
+        FilterExpression::Group {
+            operator,
+            expressions,
+        } => {
+            if expressions.is_empty() {
+                out.push_str("any row");
+                return;
+            }
+            let parenthesize = nested && expressions.len() > 1;
+            if parenthesize {
+                out.push('(');
+            }
+            for (i, expression) in expressions.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(match operator {
+                        LogicalOperator::And => " and ",
+                        LogicalOperator::Or => " or ",
+                    });
+                }
+                write_expression(out, expression, columns, true);
+            }
+            if parenthesize {
+                out.push(')');
+            }
+        }
+        FilterExpression::Custom(_) => out.push_str("a custom condition"),
+    }
+}
+
+fn write_condition(out: &mut String, condition: &FilterCondition, columns: &ColumnRegistry) {
+    let label = columns.display_name(condition.column());
+    match condition {
+        FilterCondition::StringValue {
+            operator, value, ..
+        }
+        | FilterCondition::FixedStringValue {
+            operator, value, ..
+        }
+        | FilterCondition::UUIDValue {
+            operator, value, ..
+        }
+        | FilterCondition::Custom {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::DateValue {
+            operator, value, ..
+        }
+        | FilterCondition::DateTimeValue {
+            operator, value, ..
+        }
+        | FilterCondition::DateTime64Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, true, &one(value)),
+        FilterCondition::IndexedText {
+            operator, value, ..
+        }
+        | FilterCondition::ArrayContains {
+            operator, value, ..
+        }
+        | FilterCondition::ArrayHas {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, std::slice::from_ref(value)),
+        FilterCondition::UInt8Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::UInt16Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::UInt32Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::UInt64Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::Int8Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::Int16Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::Int32Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::Int64Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::Float32Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::Float64Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::BooleanValue {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::IPv4Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::IPv6Value {
+            operator, value, ..
+        } => write_clause(out, label, operator, false, &one(value)),
+        FilterCondition::InValues {
+            operator, values, ..
+        }
+        | FilterCondition::ArrayJoinIn {
+            operator, values, ..
+        }
+        | FilterCondition::ArrayValues {
+            operator, values, ..
+        } => write_clause(out, label, operator, false, values),
+        FilterCondition::InExternal {
+            operator, table, ..
+        } => {
+            let negated = matches!(operator, FilterOperator::NotIn);
+            out.push_str(label);
+            out.push_str(if negated { " is not in " } else { " is in " });
+            out.push_str(table);
+        }
+        FilterCondition::DateRange { range_type, .. } => {
+            out.push_str(label);
+            match range_type {
+                DateRangeType::Exact(timestamp) => push_words(out, &["is", timestamp]),
+                DateRangeType::DateOnly(date) => push_words(out, &["is on", date]),
+                DateRangeType::Range { start, end } => {
+                    push_words(out, &["is between", start, "and", end])
+                }
+                DateRangeType::Relative(expr) => push_words(out, &["is after", expr]),
+                DateRangeType::Bucket { interval, start } => push_words(
+                    out,
+                    &["is in the", &interval.to_lowercase(), "starting at", start],
+                ),
+            }
+        }
+        FilterCondition::NumericRange { range, .. } => {
+            out.push_str(label);
+            let min = range.min.as_deref().map(|min| match range.inclusive_min {
+                true => ("is at least", min),
+                false => ("is greater than", min),
+            });
+            let max = range.max.as_deref().map(|max| match range.inclusive_max {
+                true => ("is at most", max),
+                false => ("is less than", max),
+            });
+            match (min, max) {
+                (Some(("is at least", min)), Some(("is at most", max))) => {
+                    push_words(out, &["is between", min, "and", max])
+                }
+                (Some((min_phrase, min)), Some((max_phrase, max))) => {
+                    push_words(out, &[min_phrase, min, "and", max_phrase, max])
+                }
+                (Some((phrase, bound)), None) | (None, Some((phrase, bound))) => {
+                    push_words(out, &[phrase, bound])
+                }
+                (None, None) => push_words(out, &["is any value"]),
+            }
+        }
+        FilterCondition::ArrayElement {
+            index,
+            operator,
+            value,
+            ..
+        } => {
+            let subject = format!("{} item {}", label, index);
+            write_clause(out, &subject, operator, false, std::slice::from_ref(value));
+        }
+        FilterCondition::ArrayCount {
+            value,
+            operator,
+            count,
+            ..
+        } => {
+            let times = match operator {
+                FilterOperator::NotEqual => "other than",
+                FilterOperator::GreaterThan => "more than",
+                FilterOperator::GreaterThanOrEqual => "at least",
+                FilterOperator::LessThan => "fewer than",
+                FilterOperator::LessThanOrEqual => "at most",
+                _ => "exactly",
+            };
+            out.push_str(label);
+            push_words(out, &["has", value, times, &count.to_string(), "times"]);
+        }
+        FilterCondition::ArrayOverlap {
+            values,
+            min_overlap,
+            ..
+        } => {
+            out.push_str(label);
+            push_words(
+                out,
+                &[
+                    "shares at least",
+                    &min_overlap.to_string(),
+                    "of",
+                    &values.join(", "),
+                ],
+            );
+        }
+        FilterCondition::ArrayJSONValue { key, value, .. } => {
+            out.push_str(label);
+            push_words(out, &["has an item with", key, value]);
+        }
+        FilterCondition::MapValue {
+            key,
+            operator,
+            value,
+            ..
+        } => {
+            let subject = format!("{} {}", label, key);
+            write_clause(out, &subject, operator, false, &one(value));
+        }
+        FilterCondition::JSONNumeric {
+            path,
+            operator,
+            value,
+            ..
+        } => {
+            let subject = format!("{} {}", label, path);
+            write_clause(out, &subject, operator, false, std::slice::from_ref(value));
+        }
+        FilterCondition::JSONValue {
+            operator,
+            value,
+            path,
+            ..
+        } => {
+            let subject = match path {
+                Some(path) => format!("{} {}", label, path),
+                None => label.to_string(),
+            };
+            write_clause(out, &subject, operator, false, &one(value));
+        }
+    }
+}
+
+// `subject phrase values`, with date comparisons read as before/after
+fn write_clause(
+    out: &mut String,
+    subject: &str,
+    operator: &FilterOperator,
+    temporal: bool,
+    values: &[String],
+) {
+    out.push_str(subject);
+    push_words(out, &[phrase(operator, temporal)]);
+    if !values.is_empty() {
+        push_words(out, &[&values.join(", ")]);
+    }
+}
+
+fn phrase(operator: &FilterOperator, temporal: bool) -> &'static str {
+    match operator {
+        FilterOperator::Equal => "is",
+        FilterOperator::NotEqual => "is not",
+        FilterOperator::GreaterThan if temporal => "is after",
+        FilterOperator::GreaterThan => "is greater than",
+        FilterOperator::GreaterThanOrEqual if temporal => "is on or after",
+        FilterOperator::GreaterThanOrEqual => "is at least",
+        FilterOperator::LessThan if temporal => "is before",
+        FilterOperator::LessThan => "is less than",
+        FilterOperator::LessThanOrEqual if temporal => "is on or before",
+        FilterOperator::LessThanOrEqual => "is at most",
+        FilterOperator::Like => "matches",
+        FilterOperator::NotLike => "does not match",
+        FilterOperator::In => "is one of",
+        FilterOperator::NotIn => "is not one of",
+        FilterOperator::IsNull => "is not set",
+        FilterOperator::IsNotNull => "is set",
+        FilterOperator::IsEmptyOrNull | FilterOperator::IsEmpty => "is empty",
+        FilterOperator::StartsWith => "starts with",
+        FilterOperator::EndsWith => "ends with",
+        FilterOperator::Contains => "contains",
+        FilterOperator::MultiMatchAny => "matches any of",
+        FilterOperator::MultiSearchAny => "contains any of",
+        FilterOperator::ArrayContains => "includes all of",
+        FilterOperator::ArrayHas => "includes",
+        FilterOperator::ArrayAll => "only includes",
+        FilterOperator::ArrayAny => "includes any of",
+        FilterOperator::ArrayHasSubsequence => "includes in order",
+        FilterOperator::ArrayNotHas => "does not include",
+        FilterOperator::ArrayNotContains => "does not include all of",
+        FilterOperator::ArrayNotAny => "includes none of",
+        FilterOperator::ArrayJsonHas => "has an item with",
+        FilterOperator::Exists => "is present",
+        FilterOperator::DateEqual => "is on",
+        FilterOperator::DateRange => "is between",
+        FilterOperator::RelativeDate => "is after",
+        FilterOperator::BucketEqual => "is in the bucket starting at",
+        FilterOperator::Custom(_) => operator.as_sql(),
+    }
+}
+
+fn one<T: Display>(value: &Option<T>) -> Vec<String> {
+    value.iter().map(ToString::to_string).collect()
+}
+
+fn push_words(out: &mut String, words: &[&str]) {
+    for word in words {
+        out.push(' ');
+        out.push_str(word);
+    }
+}
@@ -0,0 +1,96 @@
+//! Retries and timeouts for the client helpers
+//!
+//! `ExecutionPolicy` says how the `client` feature's helpers run each query: how
+//! many times to retry a query that failed on a transient error, how long to wait
+//! between attempts and how long a single attempt may take. Set it with
+//! `ClickHouseFilters::with_execution_policy`. The default runs every query once,
+//! without a time limit.
+//!
+//! Only network errors and timeouts are retried. Errors reported by the server,
+//! such as syntax errors or unknown columns, fail straight away, since running
+//! the same query again would fail the same way.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::execution::ExecutionPolicy;
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry};
+//! use std::time::Duration;
+//!
+//! let policy = ExecutionPolicy::new()
+//!     .with_retries(3)
+//!     .with_backoff(Duration::from_millis(200))
+//!     .with_timeout(Duration::from_secs(10));
+//! assert_eq!(policy.backoff_for(2), Duration::from_millis(800));
+//!
+//! let columns: ColumnRegistry = column_registry! { name: String };
+//! let filters = ClickHouseFilters::new(None, vec![], None, columns)
+//!     .unwrap()
+//!     .with_execution_policy(policy.clone());
+//! assert_eq!(filters.execution_policy(), &policy);
+//! ```
+
+use crate::ClickHouseFilters;
+use std::time::Duration;
+
+/// How many times and for how long the client helpers try each query
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExecutionPolicy {
+    /// Attempts after the first one fails on a transient error
+    pub retries: u32,
+    /// Wait before the first retry, doubled for each retry after it
+    pub backoff: Duration,
+    /// Time limit of a single attempt; `None` waits for as long as the query runs
+    pub per_query_timeout: Option<Duration>,
+}
+
+impl Default for ExecutionPolicy {
+    fn default() -> Self {
+        ExecutionPolicy {
+            retries: 0,
+            backoff: Duration::from_millis(500),
+            per_query_timeout: None,
+        }
+    }
+}
+
+impl ExecutionPolicy {
+    /// Policy running each query once, without a time limit
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Give up on an attempt after `timeout`, counting it as a transient failure
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.per_query_timeout = Some(timeout);
+        self
+    }
+
+    /// Wait before retry number `retry`, starting at 0
+    pub fn backoff_for(&self, retry: u32) -> Duration {
+        self.backoff.saturating_mul(1 << retry.min(16))
+    }
+}
+
+impl ClickHouseFilters {
+    /// Run the client helpers' queries with `policy`
+    pub fn with_execution_policy(mut self, policy: ExecutionPolicy) -> Self {
+        self.execution = policy;
+        self
+    }
+
+    /// The policy the client helpers run queries with
+    pub fn execution_policy(&self) -> &ExecutionPolicy {
+        &self.execution
+    }
+}
@@ -0,0 +1,117 @@
+//! External data tables for large IN lists
+//!
+//! ClickHouse accepts temporary tables sent alongside a query ("external data"), so
+//! a list of thousands of IDs doesn't have to be inlined into the SQL.
+//! `FilterCondition::in_external` renders `column IN ext_ids` against such a table,
+//! and `ExternalTable` describes it: the `<name>_structure` and `<name>_format`
+//! arguments for the HTTP interface (or `--structure` for `clickhouse-client
+//! --external`) and the rows encoded as `TabSeparated` data.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::external::ExternalTable;
+//! use clickhouse_filters::filtering::{FilterBuilder, FilterOperator};
+//!
+//! let ids = ExternalTable::new("ext_ids", &[("id", "UInt64")]).unwrap();
+//!
+//! let builder = FilterBuilder::new().add_condition(ids.condition("user_id", FilterOperator::In));
+//! assert_eq!(builder.build().unwrap(), " WHERE user_id IN ext_ids");
+//!
+//! assert_eq!(
+//!     ids.http_params(),
+//!     vec![
+//!         ("ext_ids_structure".to_string(), "id UInt64".to_string()),
+//!         ("ext_ids_format".to_string(), "TabSeparated".to_string()),
+//!     ]
+//! );
+//! assert_eq!(ExternalTable::encode_rows([["1"], ["2"], ["3"]]), "1\n2\n3\n");
+//! ```
+
+use crate::custom::quote_identifier;
+use crate::filtering::{FilterCondition, FilterOperator};
+use crate::params::escape_value;
+use eyre::Result;
+
+/// Name and columns of an external data table
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalTable {
+    name: String,
+    columns: Vec<(String, String)>,
+}
+
+impl ExternalTable {
+    /// Describe a table by its name and `(column, type)` pairs
+    ///
+    /// Table and column names must be plain identifiers, and there must be at
+    /// least one column. `IN` conditions need a single-column table.
+    pub fn new(name: &str, columns: &[(&str, &str)]) -> Result<Self> {
+        if quote_identifier(name) != name {
+            return Err(eyre::eyre!("Invalid external table name: {}", name));
+        }
+        if columns.is_empty() {
+            return Err(eyre::eyre!("External table has no columns: {}", name));
+        }
+        for (column, _) in columns {
+            if quote_identifier(column) != *column {
+                return Err(eyre::eyre!("Invalid external table column: {}", column));
+            }
+        }
+
+        Ok(ExternalTable {
+            name: name.to_string(),
+            columns: columns
+                .iter()
+                .map(|(column, column_type)| (column.to_string(), column_type.to_string()))
+                .collect(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The table structure, e.g. `id UInt64, name String`
+    pub fn structure(&self) -> String {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|(column, column_type)| format!("{} {}", column, column_type))
+            .collect();
+        columns.join(", ")
+    }
+
+    /// Query-string arguments describing the table to the HTTP interface
+    ///
+    /// The data itself is sent as a multipart file named after the table.
+    pub fn http_params(&self) -> Vec<(String, String)> {
+        vec![
+            (format!("{}_structure", self.name), self.structure()),
+            (format!("{}_format", self.name), "TabSeparated".to_string()),
+        ]
+    }
+
+    /// `column IN <table>` or `column NOT IN <table>`
+    pub fn condition(&self, column: &str, operator: FilterOperator) -> FilterCondition {
+        FilterCondition::in_external(column, operator, &self.name)
+    }
+
+    /// Encode rows as `TabSeparated` data, one line per row
+    pub fn encode_rows<R, V>(rows: impl IntoIterator<Item = R>) -> String
+    where
+        R: IntoIterator<Item = V>,
+        V: AsRef<str>,
+    {
+        let mut data = String::new();
+        for row in rows {
+            for (i, value) in row.into_iter().enumerate() {
+                if i > 0 {
+                    data.push('\t');
+                }
+                data.push_str(&escape_value(value.as_ref()));
+            }
+            data.push('\n');
+        }
+        data
+    }
+}
@@ -4,17 +4,27 @@
 //! It's designed to support complex filtering expressions with AND/OR conditions and various
 //! operators for different data types.
 
+use crate::custom::{escape_like, quote_identifier, RendererRef, ToSqlCondition};
+use crate::hooks::BuildHooks;
 use eyre::Result;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fmt::Debug;
+use std::fmt::Write;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
 
 /// Column type information
-#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ColumnTypeInfo {
     String,
     Numeric,
     UUID,
+    IPv4,
+    IPv6,
     Date,
     Boolean,
     Array,
@@ -22,8 +32,162 @@ pub enum ColumnTypeInfo {
     Other,
 }
 
+/// Element type of a typed array column
+///
+/// Controls how each value is written inside `has`, `hasAll` and `hasAny`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArrayElementType {
+    String,
+    Numeric,
+    UUID,
+    Date,
+    DateTime,
+    Enum,
+    IPv4,
+    IPv6,
+}
+
+impl ArrayElementType {
+    // Write a single array element literal, cast to the element type
+    pub(crate) fn write_element(&self, out: &mut String, value: &str) -> Result<()> {
+        match self {
+            ArrayElementType::String | ArrayElementType::Enum => {
+                write!(out, "'{}'", Escaped(value))?
+            }
+            ArrayElementType::Numeric => {
+                if value.parse::<f64>().is_err() {
+                    return Err(eyre::eyre!("Invalid numeric array element: {}", value));
+                }
+                out.push_str(value);
+            }
+            ArrayElementType::UUID => write!(out, "toUUID('{}')", Escaped(value))?,
+            ArrayElementType::Date => write!(out, "toDate('{}')", Escaped(value))?,
+            ArrayElementType::DateTime => write!(out, "toDateTime('{}')", Escaped(value))?,
+            ArrayElementType::IPv4 => write!(out, "toIPv4('{}')", Escaped(value))?,
+            ArrayElementType::IPv6 => write!(out, "toIPv6('{}')", Escaped(value))?,
+        }
+        Ok(())
+    }
+}
+
+/// How the value stored under a map key is compared
+///
+/// Map values are strings; numeric types convert them with `toInt64OrNull` /
+/// `toFloat64OrNull`, so values that don't parse never match.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapValueType {
+    String,
+    Int64,
+    Float64,
+}
+
+impl MapValueType {
+    /// Pick a value type for an untyped filter
+    ///
+    /// Ordering comparisons on values that parse as numbers compare numerically;
+    /// everything else compares as a string.
+    pub fn infer(operator: &FilterOperator, value: &str) -> Self {
+        let ordering = matches!(
+            operator,
+            FilterOperator::GreaterThan
+                | FilterOperator::GreaterThanOrEqual
+                | FilterOperator::LessThan
+                | FilterOperator::LessThanOrEqual
+        );
+        if !ordering {
+            MapValueType::String
+        } else if value.parse::<i64>().is_ok() {
+            MapValueType::Int64
+        } else if value.parse::<f64>().is_ok() {
+            MapValueType::Float64
+        } else {
+            MapValueType::String
+        }
+    }
+}
+
+/// Text skip index declared on a string column
+///
+/// Set with `ColumnRegistry::with_text_index`. `CONTAINS`, `STARTS WITH` and
+/// `ENDS WITH` filters on the column are then rendered with functions the index
+/// can serve, instead of `LIKE`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextIndex {
+    /// `ngrambf_v1`: substrings use `position()`, prefixes and suffixes use
+    /// `startsWith()` / `endsWith()`
+    NgramBf,
+    /// `tokenbf_v1`: a value that is a single token uses `hasToken()`
+    TokenBf,
+}
+
+impl TextIndex {
+    // The indexed function for an operator and value, if there is one
+    fn function(
+        &self,
+        operator: &FilterOperator,
+        value: &str,
+        case_insensitive: bool,
+    ) -> Option<&'static str> {
+        match (self, operator, case_insensitive) {
+            (TextIndex::NgramBf, FilterOperator::Contains, false) => Some("position"),
+            (TextIndex::NgramBf, FilterOperator::Contains, true) => Some("positionCaseInsensitive"),
+            (TextIndex::NgramBf, FilterOperator::StartsWith, false) => Some("startsWith"),
+            (TextIndex::NgramBf, FilterOperator::EndsWith, false) => Some("endsWith"),
+            // Tokens are runs of alphanumeric ASCII and non-ASCII characters
+            (TextIndex::TokenBf, FilterOperator::Contains, _)
+                if !value.is_empty()
+                    && value
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || !c.is_ascii()) =>
+            {
+                Some(if case_insensitive {
+                    "hasTokenCaseInsensitive"
+                } else {
+                    "hasToken"
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Numeric type of a value at a JSON path
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonNumberType {
+    /// Read with `JSONExtractInt`
+    Int,
+    /// Read with `JSONExtractFloat`
+    Float,
+}
+
+impl JsonNumberType {
+    /// Infer the number type from a literal, `None` if it isn't numeric
+    pub fn infer(value: &str) -> Option<Self> {
+        if value.parse::<i64>().is_ok() {
+            Some(JsonNumberType::Int)
+        } else if value.parse::<f64>().is_ok() {
+            Some(JsonNumberType::Float)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn extract_function(&self) -> &'static str {
+        match self {
+            JsonNumberType::Int => "JSONExtractInt",
+            JsonNumberType::Float => "JSONExtractFloat",
+        }
+    }
+}
+
 /// Logical operators for combining filter expressions
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LogicalOperator {
     And,
     Or,
@@ -48,8 +212,35 @@ impl fmt::Display for LogicalOperator {
     }
 }
 
-/// Filter operators for comparison
+/// A filter value that isn't valid for its column's type
+///
+/// Returned inside the `eyre::Report` from `ColumnDef::to_filter_condition`, so
+/// callers can tell bad input apart from other errors with `downcast_ref`.
 #[derive(Debug, Clone, PartialEq)]
+pub struct InvalidValue {
+    pub column: String,
+    /// ClickHouse type the value was checked against, e.g. `UUID`
+    pub type_name: &'static str,
+    pub value: String,
+}
+
+impl fmt::Display for InvalidValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid value for {}: {}", self.type_name, self.value)
+    }
+}
+
+impl std::error::Error for InvalidValue {}
+
+/// Name of an operator registered with `ColumnRegistry::with_operator`
+///
+/// An alias rather than `&'static str` in the variant, since serde's derive
+/// would otherwise require `'static` input even though the variant is skipped.
+pub type OperatorName = &'static str;
+
+/// Filter operators for comparison
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum FilterOperator {
     Equal,
     NotEqual,
@@ -63,17 +254,33 @@ pub enum FilterOperator {
     NotIn,
     IsNull,
     IsNotNull,
+    IsEmptyOrNull, // NULL or the type's default value, for non-nullable columns
     StartsWith,
     EndsWith,
+    Contains,       // Literal substring, `%` and `_` match themselves
+    MultiMatchAny,  // Any of several regular expressions matches
+    MultiSearchAny, // Any of several plain substrings is present
     // ClickHouse-specific array operators
     ArrayContains,
     ArrayHas, // Similar to PostgreSQL's @> but with different syntax in ClickHouse
     ArrayAll, // Check if all elements match a condition
     ArrayAny, // Check if any elements match a condition
+    ArrayHasSubsequence, // Check that values appear contiguously and in order
+    ArrayNotHas, // Negated ArrayHas
+    ArrayNotContains, // Negated ArrayContains
+    ArrayNotAny, // Negated ArrayAny, no element matches
+    ArrayJsonHas, // Some JSON document in an Array(String) has a key with a value
+    // JSON path operators
+    Exists,  // Path is present, whatever its value
+    IsEmpty, // Path holds an empty string or array
     // ClickHouse-specific date operators
     DateEqual,
     DateRange,
     RelativeDate,
+    BucketEqual, // toStartOfInterval bucket equals a timestamp
+    // Operator registered with `ColumnRegistry::with_operator`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(OperatorName),
 }
 
 impl FilterOperator {
@@ -91,22 +298,49 @@ impl FilterOperator {
             FilterOperator::NotIn => "NOT IN",
             FilterOperator::IsNull => "IS NULL",
             FilterOperator::IsNotNull => "IS NOT NULL",
-            FilterOperator::StartsWith => "LIKE", // Will need special handling
-            FilterOperator::EndsWith => "LIKE",   // Will need special handling
-            FilterOperator::ArrayContains => "hasAll", // ClickHouse function
-            FilterOperator::ArrayHas => "has",    // ClickHouse function
-            FilterOperator::ArrayAll => "ALL",    // ClickHouse ALL
-            FilterOperator::ArrayAny => "ANY",    // ClickHouse ANY
-            FilterOperator::DateEqual => "=",     // Will need special handling
+            FilterOperator::IsEmptyOrNull => "IS NULL", // Will need special handling
+            FilterOperator::StartsWith => "LIKE",       // Will need special handling
+            FilterOperator::EndsWith => "LIKE",         // Will need special handling
+            FilterOperator::Contains => "LIKE",         // Will need special handling
+            FilterOperator::MultiMatchAny => "multiMatchAny", // ClickHouse function
+            FilterOperator::MultiSearchAny => "multiSearchAny", // ClickHouse function
+            FilterOperator::ArrayContains => "hasAll",  // ClickHouse function
+            FilterOperator::ArrayHas => "has",          // ClickHouse function
+            FilterOperator::ArrayAll => "ALL",          // ClickHouse ALL
+            FilterOperator::ArrayAny => "ANY",          // ClickHouse ANY
+            FilterOperator::ArrayHasSubsequence => "hasSubstr", // ClickHouse function
+            FilterOperator::ArrayNotHas => "NOT has",
+            FilterOperator::ArrayNotContains => "NOT hasAll",
+            FilterOperator::ArrayNotAny => "NOT hasAny",
+            FilterOperator::ArrayJsonHas => "arrayExists", // ClickHouse function
+            FilterOperator::Exists => "JSONHas",           // ClickHouse function
+            FilterOperator::IsEmpty => "empty",            // Will need special handling
+            FilterOperator::DateEqual => "=",              // Will need special handling
             FilterOperator::DateRange => "BETWEEN",
             FilterOperator::RelativeDate => ">", // Will need special handling
+            FilterOperator::BucketEqual => "=",  // Will need special handling
+            FilterOperator::Custom(name) => name,
         }
     }
 
+    /// Check whether this is a plain comparison (`=`, `!=`, `>`, `>=`, `<`, `<=`)
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            FilterOperator::Equal
+                | FilterOperator::NotEqual
+                | FilterOperator::GreaterThan
+                | FilterOperator::GreaterThanOrEqual
+                | FilterOperator::LessThan
+                | FilterOperator::LessThanOrEqual
+        )
+    }
+
     pub fn format_value(&self, value: &str) -> String {
         match self {
-            FilterOperator::StartsWith => format!("{}%", value),
-            FilterOperator::EndsWith => format!("%{}", value),
+            FilterOperator::StartsWith => format!("{}%", escape_like(value)),
+            FilterOperator::EndsWith => format!("%{}", escape_like(value)),
+            FilterOperator::Contains => format!("%{}%", escape_like(value)),
             _ => value.to_string(),
         }
     }
@@ -119,7 +353,283 @@ impl fmt::Display for FilterOperator {
     }
 }
 
+/// Operator names accepted in JSON filters, e.g. `"NOT IN"` or `"DATE_RANGE"`
+///
+/// `Display` writes the canonical name and `FromStr` parses one in any case,
+/// failing on unknown names. Each converts to the `FilterOperator` of the same
+/// name; operators registered with `ColumnRegistry::with_operator` have no
+/// `JsonOperator`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonOperator {
+    Equal,
+    NotEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+    Like,
+    NotLike,
+    In,
+    NotIn,
+    IsNull,
+    IsNotNull,
+    IsEmptyOrNull,
+    StartsWith,
+    EndsWith,
+    Contains,
+    MultiMatchAny,
+    MultiSearchAny,
+    ArrayContains,
+    ArrayHas,
+    ArrayAll,
+    ArrayAny,
+    ArrayHasSubsequence,
+    ArrayNotHas,
+    ArrayNotContains,
+    ArrayNotAny,
+    ArrayJsonHas,
+    Exists,
+    IsEmpty,
+    DateEqual,
+    DateRange,
+    RelativeDate,
+    BucketEqual,
+}
+
+impl JsonOperator {
+    /// Every operator, in the order they are documented
+    pub const ALL: [JsonOperator; 33] = [
+        JsonOperator::Equal,
+        JsonOperator::NotEqual,
+        JsonOperator::GreaterThan,
+        JsonOperator::GreaterThanOrEqual,
+        JsonOperator::LessThan,
+        JsonOperator::LessThanOrEqual,
+        JsonOperator::Like,
+        JsonOperator::NotLike,
+        JsonOperator::In,
+        JsonOperator::NotIn,
+        JsonOperator::IsNull,
+        JsonOperator::IsNotNull,
+        JsonOperator::IsEmptyOrNull,
+        JsonOperator::StartsWith,
+        JsonOperator::EndsWith,
+        JsonOperator::Contains,
+        JsonOperator::MultiMatchAny,
+        JsonOperator::MultiSearchAny,
+        JsonOperator::ArrayContains,
+        JsonOperator::ArrayHas,
+        JsonOperator::ArrayAll,
+        JsonOperator::ArrayAny,
+        JsonOperator::ArrayHasSubsequence,
+        JsonOperator::ArrayNotHas,
+        JsonOperator::ArrayNotContains,
+        JsonOperator::ArrayNotAny,
+        JsonOperator::ArrayJsonHas,
+        JsonOperator::Exists,
+        JsonOperator::IsEmpty,
+        JsonOperator::DateEqual,
+        JsonOperator::DateRange,
+        JsonOperator::RelativeDate,
+        JsonOperator::BucketEqual,
+    ];
+
+    /// The canonical name, as written in JSON filters
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JsonOperator::Equal => "=",
+            JsonOperator::NotEqual => "!=",
+            JsonOperator::GreaterThan => ">",
+            JsonOperator::GreaterThanOrEqual => ">=",
+            JsonOperator::LessThan => "<",
+            JsonOperator::LessThanOrEqual => "<=",
+            JsonOperator::Like => "LIKE",
+            JsonOperator::NotLike => "NOT LIKE",
+            JsonOperator::In => "IN",
+            JsonOperator::NotIn => "NOT IN",
+            JsonOperator::IsNull => "IS NULL",
+            JsonOperator::IsNotNull => "IS NOT NULL",
+            JsonOperator::IsEmptyOrNull => "IS EMPTY OR NULL",
+            JsonOperator::StartsWith => "STARTS WITH",
+            JsonOperator::EndsWith => "ENDS WITH",
+            JsonOperator::Contains => "CONTAINS",
+            JsonOperator::MultiMatchAny => "MULTI MATCH ANY",
+            JsonOperator::MultiSearchAny => "MULTI SEARCH ANY",
+            JsonOperator::ArrayContains => "ARRAY CONTAINS",
+            JsonOperator::ArrayHas => "ARRAY HAS",
+            JsonOperator::ArrayAll => "ARRAY ALL",
+            JsonOperator::ArrayAny => "ARRAY ANY",
+            JsonOperator::ArrayHasSubsequence => "ARRAY HAS SUBSEQUENCE",
+            JsonOperator::ArrayNotHas => "ARRAY NOT HAS",
+            JsonOperator::ArrayNotContains => "ARRAY NOT CONTAINS",
+            JsonOperator::ArrayNotAny => "ARRAY NOT ANY",
+            JsonOperator::ArrayJsonHas => "ARRAY JSON HAS",
+            JsonOperator::Exists => "EXISTS",
+            JsonOperator::IsEmpty => "IS EMPTY",
+            JsonOperator::DateEqual => "DATE_ONLY",
+            JsonOperator::DateRange => "DATE_RANGE",
+            JsonOperator::RelativeDate => "RELATIVE",
+            JsonOperator::BucketEqual => "BUCKET_EQUAL",
+        }
+    }
+}
+
+impl fmt::Display for JsonOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for JsonOperator {
+    type Err = eyre::Report;
+
+    fn from_str(operator: &str) -> Result<Self> {
+        Ok(match operator.to_uppercase().as_str() {
+            "=" => JsonOperator::Equal,
+            "!=" => JsonOperator::NotEqual,
+            ">" => JsonOperator::GreaterThan,
+            ">=" => JsonOperator::GreaterThanOrEqual,
+            "<" => JsonOperator::LessThan,
+            "<=" => JsonOperator::LessThanOrEqual,
+            "LIKE" => JsonOperator::Like,
+            "NOT LIKE" => JsonOperator::NotLike,
+            "IN" => JsonOperator::In,
+            "NOT IN" => JsonOperator::NotIn,
+            "IS NULL" => JsonOperator::IsNull,
+            "IS NOT NULL" => JsonOperator::IsNotNull,
+            "IS EMPTY OR NULL" => JsonOperator::IsEmptyOrNull,
+            "STARTS WITH" => JsonOperator::StartsWith,
+            "ENDS WITH" => JsonOperator::EndsWith,
+            "CONTAINS" => JsonOperator::Contains,
+            "MULTI MATCH ANY" => JsonOperator::MultiMatchAny,
+            "MULTI SEARCH ANY" => JsonOperator::MultiSearchAny,
+            "ARRAY CONTAINS" => JsonOperator::ArrayContains,
+            "ARRAY HAS" => JsonOperator::ArrayHas,
+            "ARRAY ALL" => JsonOperator::ArrayAll,
+            "ARRAY ANY" => JsonOperator::ArrayAny,
+            "ARRAY HAS SUBSEQUENCE" => JsonOperator::ArrayHasSubsequence,
+            "ARRAY NOT HAS" => JsonOperator::ArrayNotHas,
+            "ARRAY NOT CONTAINS" => JsonOperator::ArrayNotContains,
+            "ARRAY NOT ANY" => JsonOperator::ArrayNotAny,
+            "ARRAY JSON HAS" => JsonOperator::ArrayJsonHas,
+            "EXISTS" => JsonOperator::Exists,
+            "IS EMPTY" => JsonOperator::IsEmpty,
+            "DATE_ONLY" => JsonOperator::DateEqual,
+            "DATE_RANGE" => JsonOperator::DateRange,
+            "RELATIVE" => JsonOperator::RelativeDate,
+            "BUCKET_EQUAL" => JsonOperator::BucketEqual,
+            _ => return Err(eyre::eyre!("Invalid operator: {}", operator)),
+        })
+    }
+}
+
+impl From<JsonOperator> for FilterOperator {
+    fn from(operator: JsonOperator) -> Self {
+        match operator {
+            JsonOperator::Equal => FilterOperator::Equal,
+            JsonOperator::NotEqual => FilterOperator::NotEqual,
+            JsonOperator::GreaterThan => FilterOperator::GreaterThan,
+            JsonOperator::GreaterThanOrEqual => FilterOperator::GreaterThanOrEqual,
+            JsonOperator::LessThan => FilterOperator::LessThan,
+            JsonOperator::LessThanOrEqual => FilterOperator::LessThanOrEqual,
+            JsonOperator::Like => FilterOperator::Like,
+            JsonOperator::NotLike => FilterOperator::NotLike,
+            JsonOperator::In => FilterOperator::In,
+            JsonOperator::NotIn => FilterOperator::NotIn,
+            JsonOperator::IsNull => FilterOperator::IsNull,
+            JsonOperator::IsNotNull => FilterOperator::IsNotNull,
+            JsonOperator::IsEmptyOrNull => FilterOperator::IsEmptyOrNull,
+            JsonOperator::StartsWith => FilterOperator::StartsWith,
+            JsonOperator::EndsWith => FilterOperator::EndsWith,
+            JsonOperator::Contains => FilterOperator::Contains,
+            JsonOperator::MultiMatchAny => FilterOperator::MultiMatchAny,
+            JsonOperator::MultiSearchAny => FilterOperator::MultiSearchAny,
+            JsonOperator::ArrayContains => FilterOperator::ArrayContains,
+            JsonOperator::ArrayHas => FilterOperator::ArrayHas,
+            JsonOperator::ArrayAll => FilterOperator::ArrayAll,
+            JsonOperator::ArrayAny => FilterOperator::ArrayAny,
+            JsonOperator::ArrayHasSubsequence => FilterOperator::ArrayHasSubsequence,
+            JsonOperator::ArrayNotHas => FilterOperator::ArrayNotHas,
+            JsonOperator::ArrayNotContains => FilterOperator::ArrayNotContains,
+            JsonOperator::ArrayNotAny => FilterOperator::ArrayNotAny,
+            JsonOperator::ArrayJsonHas => FilterOperator::ArrayJsonHas,
+            JsonOperator::Exists => FilterOperator::Exists,
+            JsonOperator::IsEmpty => FilterOperator::IsEmpty,
+            JsonOperator::DateEqual => FilterOperator::DateEqual,
+            JsonOperator::DateRange => FilterOperator::DateRange,
+            JsonOperator::RelativeDate => FilterOperator::RelativeDate,
+            JsonOperator::BucketEqual => FilterOperator::BucketEqual,
+        }
+    }
+}
+
+impl TryFrom<&FilterOperator> for JsonOperator {
+    type Error = eyre::Report;
+
+    /// Fails for custom operators, which are named by their registry instead
+    fn try_from(operator: &FilterOperator) -> Result<Self> {
+        Ok(match operator {
+            FilterOperator::Equal => JsonOperator::Equal,
+            FilterOperator::NotEqual => JsonOperator::NotEqual,
+            FilterOperator::GreaterThan => JsonOperator::GreaterThan,
+            FilterOperator::GreaterThanOrEqual => JsonOperator::GreaterThanOrEqual,
+            FilterOperator::LessThan => JsonOperator::LessThan,
+            FilterOperator::LessThanOrEqual => JsonOperator::LessThanOrEqual,
+            FilterOperator::Like => JsonOperator::Like,
+            FilterOperator::NotLike => JsonOperator::NotLike,
+            FilterOperator::In => JsonOperator::In,
+            FilterOperator::NotIn => JsonOperator::NotIn,
+            FilterOperator::IsNull => JsonOperator::IsNull,
+            FilterOperator::IsNotNull => JsonOperator::IsNotNull,
+            FilterOperator::IsEmptyOrNull => JsonOperator::IsEmptyOrNull,
+            FilterOperator::StartsWith => JsonOperator::StartsWith,
+            FilterOperator::EndsWith => JsonOperator::EndsWith,
+            FilterOperator::Contains => JsonOperator::Contains,
+            FilterOperator::MultiMatchAny => JsonOperator::MultiMatchAny,
+            FilterOperator::MultiSearchAny => JsonOperator::MultiSearchAny,
+            FilterOperator::ArrayContains => JsonOperator::ArrayContains,
+            FilterOperator::ArrayHas => JsonOperator::ArrayHas,
+            FilterOperator::ArrayAll => JsonOperator::ArrayAll,
+            FilterOperator::ArrayAny => JsonOperator::ArrayAny,
+            FilterOperator::ArrayHasSubsequence => JsonOperator::ArrayHasSubsequence,
+            FilterOperator::ArrayNotHas => JsonOperator::ArrayNotHas,
+            FilterOperator::ArrayNotContains => JsonOperator::ArrayNotContains,
+            FilterOperator::ArrayNotAny => JsonOperator::ArrayNotAny,
+            FilterOperator::ArrayJsonHas => JsonOperator::ArrayJsonHas,
+            FilterOperator::Exists => JsonOperator::Exists,
+            FilterOperator::IsEmpty => JsonOperator::IsEmpty,
+            FilterOperator::DateEqual => JsonOperator::DateEqual,
+            FilterOperator::DateRange => JsonOperator::DateRange,
+            FilterOperator::RelativeDate => JsonOperator::RelativeDate,
+            FilterOperator::BucketEqual => JsonOperator::BucketEqual,
+            FilterOperator::Custom(name) => {
+                return Err(eyre::eyre!("Custom operator has no JSON name: {}", name))
+            }
+        })
+    }
+}
+
+/// How JSON filters on the same column are combined
+///
+/// Set with `ColumnRegistry::with_same_column_policy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SameColumnPolicy {
+    /// Combine filters in order using each filter's connector (`c`)
+    #[default]
+    Sequential,
+    /// Faceted-search semantics: equality-like filters (`=`, `IN`, `LIKE`,
+    /// `STARTS WITH`, `ENDS WITH`, `CONTAINS`, `MULTI MATCH ANY`, `MULTI SEARCH ANY`,
+    /// `ARRAY HAS`, `ARRAY ANY`, `DATE EQUAL`) on the
+    /// same column are ORed, and everything else is ANDed; connectors are ignored
+    OrWithinColumn,
+}
+
 /// Filter expression - can be a condition or a group
+///
+/// With the `serde` feature, trees serialize as plain data. Custom conditions
+/// and custom operators hold code rather than data, so serializing a tree that
+/// contains them fails.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum FilterExpression {
     Condition(FilterCondition),
@@ -127,10 +637,18 @@ pub enum FilterExpression {
         operator: LogicalOperator,
         expressions: Vec<FilterExpression>,
     },
+    // Condition kind implemented outside the crate, see the `custom` module
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<dyn ToSqlCondition>),
 }
 
 /// JSON filter structure for API usage
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// Unknown keys are rejected when deserializing, so a typo such as `"cc"` fails
+/// instead of being ignored.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+#[derive(Debug)]
 pub struct JsonFilter {
     pub n: String,         // name/column
     pub f: String,         // filter operator
@@ -141,26 +659,67 @@ pub struct JsonFilter {
 impl FilterExpression {
     // Placeholder implementation - to be expanded
     pub fn to_sql(&self, case_insensitive: bool) -> Result<String> {
+        let mut sql = String::with_capacity(self.estimated_sql_len());
+        self.write_sql(&mut sql, case_insensitive)?;
+        Ok(sql)
+    }
+
+    /// Render this expression into an existing buffer
+    pub fn write_sql(&self, out: &mut String, case_insensitive: bool) -> Result<()> {
+        self.write_sql_with(out, case_insensitive, &HashMap::new())
+    }
+
+    /// Render this expression, with per-column case sensitivity overrides
+    ///
+    /// `case_overrides` maps a column name to its case-insensitive flag; columns
+    /// not in the map use `case_insensitive`.
+    pub fn write_sql_with(
+        &self,
+        out: &mut String,
+        case_insensitive: bool,
+        case_overrides: &HashMap<&'static str, bool>,
+    ) -> Result<()> {
         match self {
-            FilterExpression::Condition(condition) => condition.to_sql(case_insensitive),
+            FilterExpression::Condition(condition) => {
+                let case_insensitive = case_overrides
+                    .get(condition.column())
+                    .copied()
+                    .unwrap_or(case_insensitive);
+                condition.write_sql(out, case_insensitive)
+            }
+            FilterExpression::Custom(condition) => condition.write_sql(out, case_insensitive),
             FilterExpression::Group {
                 operator,
                 expressions,
             } => {
                 if expressions.is_empty() {
-                    return Ok(String::new());
+                    return Ok(());
                 }
 
-                let conditions: Result<Vec<String>> = expressions
+                out.push('(');
+                for (i, expr) in expressions.iter().enumerate() {
+                    if i > 0 {
+                        out.push(' ');
+                        out.push_str(operator.as_sql());
+                        out.push(' ');
+                    }
+                    expr.write_sql_with(out, case_insensitive, case_overrides)?;
+                }
+                out.push(')');
+                Ok(())
+            }
+        }
+    }
+
+    // Rough rendered length, used to pre-size output buffers
+    pub(crate) fn estimated_sql_len(&self) -> usize {
+        match self {
+            FilterExpression::Condition(_) | FilterExpression::Custom(_) => 48,
+            FilterExpression::Group { expressions, .. } => {
+                2 + expressions
                     .iter()
-                    .map(|expr| expr.to_sql(case_insensitive))
-                    .collect();
-
-                let conditions = conditions?;
-                Ok(format!(
-                    "({})",
-                    conditions.join(&format!(" {} ", operator.as_sql()))
-                ))
+                    .map(|expr| expr.estimated_sql_len() + 5)
+                    .sum::<usize>()
             }
         }
     }
@@ -180,12 +739,57 @@ impl FilterExpression {
             expressions,
         }
     }
+
+    /// Combine two expressions with a logical operator
+    ///
+    /// Empty groups on either side are dropped, and a side that is already a
+    /// group with the same operator is flattened into the result. If only one
+    /// side remains it is returned as-is.
+    pub fn combine(self, other: FilterExpression, operator: LogicalOperator) -> Self {
+        let mut expressions = Vec::new();
+        for side in [self, other] {
+            match side {
+                FilterExpression::Group {
+                    operator: side_operator,
+                    expressions: children,
+                } if side_operator == operator || children.is_empty() => {
+                    expressions.extend(children)
+                }
+                side => expressions.push(side),
+            }
+        }
+
+        if expressions.len() == 1 {
+            return expressions.remove(0);
+        }
+        FilterExpression::Group {
+            operator,
+            expressions,
+        }
+    }
+
+    /// Check whether this expression renders to nothing
+    pub fn is_empty(&self) -> bool {
+        match self {
+            FilterExpression::Condition(_) | FilterExpression::Custom(_) => false,
+            FilterExpression::Group { expressions, .. } => {
+                expressions.iter().all(FilterExpression::is_empty)
+            }
+        }
+    }
 }
 
 impl fmt::Display for FilterExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             FilterExpression::Condition(condition) => write!(f, "{}", condition),
+            FilterExpression::Custom(condition) => {
+                let mut sql = String::new();
+                match condition.write_sql(&mut sql, false) {
+                    Ok(()) => write!(f, "{}", sql),
+                    Err(err) => write!(f, "Error: {}", err),
+                }
+            }
             FilterExpression::Group {
                 operator,
                 expressions,
@@ -206,7 +810,8 @@ impl fmt::Display for FilterExpression {
 }
 
 /// Date range type for date filtering
-#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum DateRangeType {
     /// Exact timestamp match
     Exact(String),
@@ -216,9 +821,78 @@ pub enum DateRangeType {
     Range { start: String, end: String },
     /// Relative date expression
     Relative(String),
+    /// Start of a `toStartOfInterval` bucket, with the interval such as `5 MINUTE`
+    Bucket { interval: String, start: String },
 }
 
+/// Bounds of a numeric range, e.g. from a range slider
+///
+/// Either bound may be left open. `FilterCondition::numeric_range` turns a range
+/// into one condition using the fewest comparisons that express it.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumericRange<T> {
+    pub min: Option<T>,
+    pub max: Option<T>,
+    pub inclusive_min: bool,
+    pub inclusive_max: bool,
+}
+
+impl<T> NumericRange<T> {
+    /// Range including both bounds
+    pub fn new(min: Option<T>, max: Option<T>) -> Self {
+        NumericRange {
+            min,
+            max,
+            inclusive_min: true,
+            inclusive_max: true,
+        }
+    }
+
+    /// Exclude the lower bound from the range
+    pub fn exclusive_min(mut self) -> Self {
+        self.inclusive_min = false;
+        self
+    }
+
+    /// Exclude the upper bound from the range
+    pub fn exclusive_max(mut self) -> Self {
+        self.inclusive_max = false;
+        self
+    }
+}
+
+impl NumericRange<String> {
+    // Bounds are written unquoted, so each must be a finite number
+    pub(crate) fn check_bounds(&self, column: &str) -> Result<()> {
+        match [&self.min, &self.max]
+            .into_iter()
+            .flatten()
+            .find(|v| !v.parse::<f64>().is_ok_and(f64::is_finite))
+        {
+            Some(bound) => Err(eyre::eyre!(
+                "Invalid numeric range bound for {}: {}",
+                column,
+                bound
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Numeric types accepted as `NumericRange` bounds
+pub trait RangeValue: fmt::Display {}
+
+macro_rules! range_values {
+    ($($t:ty),*) => {
+        $(impl RangeValue for $t {})*
+    };
+}
+
+range_values!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
 /// Filter condition - represents a single comparison
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum FilterCondition {
     // String Types
@@ -232,6 +906,13 @@ pub enum FilterCondition {
         operator: FilterOperator,
         value: Option<String>,
     },
+    // Substring match on a column with a text skip index, see `TextIndex`
+    IndexedText {
+        column: String,
+        operator: FilterOperator,
+        value: String,
+        index: TextIndex,
+    },
 
     // Numeric types
     UInt8Value {
@@ -308,6 +989,13 @@ pub enum FilterCondition {
         range_type: DateRangeType,
     },
 
+    // Numeric range with optional, inclusive or exclusive bounds; the bounds are
+    // rendered as is, so build it with `numeric_range` from numbers
+    NumericRange {
+        column: String,
+        range: NumericRange<String>,
+    },
+
     // Boolean Type
     BooleanValue {
         column: String,
@@ -322,6 +1010,18 @@ pub enum FilterCondition {
         value: Option<String>,
     },
 
+    // IP address types, compared against `toIPv4('...')` / `toIPv6('...')`
+    IPv4Value {
+        column: String,
+        operator: FilterOperator,
+        value: Option<Ipv4Addr>,
+    },
+    IPv6Value {
+        column: String,
+        operator: FilterOperator,
+        value: Option<Ipv6Addr>,
+    },
+
     // Multi-value conditions for IN/NOT IN
     InValues {
         column: String,
@@ -329,6 +1029,19 @@ pub enum FilterCondition {
         values: Vec<String>,
         column_type: Option<ColumnTypeInfo>,
     },
+    // Large IN/NOT IN lists as `column IN (SELECT arrayJoin([...]))`
+    ArrayJoinIn {
+        column: String,
+        operator: FilterOperator,
+        values: Vec<String>,
+        column_type: Option<ColumnTypeInfo>,
+    },
+    // IN/NOT IN against an external data table sent with the query, `column IN ext_ids`
+    InExternal {
+        column: String,
+        operator: FilterOperator,
+        table: String,
+    },
 
     // Array Types
     ArrayContains {
@@ -341,7 +1054,59 @@ pub enum FilterCondition {
         operator: FilterOperator,
         value: String,
     },
+    ArrayValues {
+        column: String,
+        operator: FilterOperator,
+        values: Vec<String>,
+        element_type: ArrayElementType,
+    },
+    // Comparison on a single array position (1-based; negative counts from the end)
+    ArrayElement {
+        column: String,
+        index: i64,
+        operator: FilterOperator,
+        value: String,
+        element_type: ArrayElementType,
+    },
+    // Comparison on the number of times a value appears in an array
+    ArrayCount {
+        column: String,
+        value: String,
+        operator: FilterOperator,
+        count: u64,
+        element_type: ArrayElementType,
+    },
+    // Minimum number of shared elements between an array and a candidate list
+    ArrayOverlap {
+        column: String,
+        values: Vec<String>,
+        min_overlap: u64,
+        element_type: ArrayElementType,
+    },
+    // Key match in any of the JSON documents stored in an Array(String)
+    ArrayJSONValue {
+        column: String,
+        operator: FilterOperator,
+        key: String,
+        value: String,
+    },
+    // Comparison on the value stored under a map key
+    MapValue {
+        column: String,
+        key: String,
+        operator: FilterOperator,
+        value: Option<String>,
+        value_type: MapValueType,
+    },
 
+    // Numeric comparison on a JSON path
+    JSONNumeric {
+        column: String,
+        path: String,
+        operator: FilterOperator,
+        value: String,
+        number_type: JsonNumberType,
+    },
     // JSON Type
     JSONValue {
         column: String,
@@ -349,19 +1114,205 @@ pub enum FilterCondition {
         value: Option<String>,
         path: Option<String>,
     },
+
+    // Condition on a `ColumnDef::Custom` column, rendered by its renderer
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom {
+        column: String,
+        operator: FilterOperator,
+        value: Option<String>,
+        renderer: RendererRef,
+    },
+}
+
+/// Display adapter that writes a string literal body with quotes and backslashes escaped
+///
+/// Backslashes start escape sequences in ClickHouse string literals, so both
+/// have to be escaped for a value to stay inside its literal. This lets values be
+/// escaped straight into the output buffer instead of allocating an escaped copy
+/// first.
+pub(crate) struct Escaped<'a>(pub(crate) &'a str);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rest = self.0;
+        while let Some(index) = rest.find(['\'', '\\']) {
+            f.write_str(&rest[..index])?;
+            f.write_str(if rest.as_bytes()[index] == b'\'' {
+                "''"
+            } else {
+                "\\\\"
+            })?;
+            rest = &rest[index + 1..];
+        }
+        f.write_str(rest)
+    }
+}
+
+/// Display adapter for a JSON column, extracting the path when one is given
+struct JsonColumn<'a> {
+    column: &'a str,
+    path: Option<&'a str>,
+}
+
+impl fmt::Display for JsonColumn<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path {
+            Some(p) => write!(f, "JSONExtractString({}, '{}')", self.column, Escaped(p)),
+            None => f.write_str(self.column),
+        }
+    }
+}
+
+/// Display adapter for a timestamp literal that keeps its sub-second digits
+///
+/// Plain quoted strings are parsed at whole-second precision when compared with
+/// DateTime values, so timestamps with fractional seconds are rendered as
+/// `toDateTime64('...', digits)` instead.
+struct Timestamp<'a>(&'a str);
+
+impl fmt::Display for Timestamp<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match fraction_digits(self.0) {
+            0 => write!(f, "'{}'", Escaped(self.0)),
+            digits => write!(f, "toDateTime64('{}', {})", Escaped(self.0), digits),
+        }
+    }
+}
+
+/// Number of sub-second digits in a timestamp such as `2024-01-01 10:00:00.123`
+pub(crate) fn fraction_digits(timestamp: &str) -> usize {
+    let Some((time, fraction)) = timestamp.rsplit_once('.') else {
+        return 0;
+    };
+    if !time.contains(':') {
+        return 0;
+    }
+    fraction.bytes().take_while(u8::is_ascii_digit).count()
+}
+
+/// Parse a boolean filter value, or None if it isn't one
+///
+/// Accepts `true`/`false`, `yes`/`no`, `y`/`n` and `on`/`off` in any case, and
+/// numeric strings equal to 1 or 0, such as `1`, `0` or `1.0`.
+pub fn parse_bool(value: &str) -> Option<bool> {
+    let value = value.trim();
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "on" => Some(true),
+        "false" | "no" | "n" | "off" => Some(false),
+        _ => match value.parse::<f64>() {
+            Ok(1.0) => Some(true),
+            Ok(0.0) => Some(false),
+            _ => None,
+        },
+    }
+}
+
+/// Lowercase form of a UUID in the hyphenated RFC 4122 format, or None if it isn't one
+pub(crate) fn canonical_uuid(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let valid = bytes.len() == 36
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => *b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        });
+    valid.then(|| value.to_ascii_lowercase())
+}
+
+/// Write a comparison on an IP column, casting the address with `function`
+fn write_ip(
+    out: &mut String,
+    column: &str,
+    operator: &FilterOperator,
+    function: &str,
+    value: Option<&dyn fmt::Display>,
+) -> Result<()> {
+    match (operator, value) {
+        (operator, Some(v)) if operator.is_comparison() => write!(
+            out,
+            "{} {} {}('{}')",
+            column,
+            operator.as_sql(),
+            function,
+            v
+        )?,
+        (FilterOperator::In | FilterOperator::NotIn, Some(v)) => write!(
+            out,
+            "{} {} ({}('{}'))",
+            column,
+            operator.as_sql(),
+            function,
+            v
+        )?,
+        (FilterOperator::IsNull, _) => write!(out, "{} IS NULL", column)?,
+        (FilterOperator::IsNotNull, _) => write!(out, "{} IS NOT NULL", column)?,
+        _ => {
+            return Err(eyre::eyre!(
+                "Unsupported operator for IP type: {}",
+                operator
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Skip repeated values, keeping the first occurrence of each
+pub(crate) fn unique<'a>(
+    values: impl IntoIterator<Item = &'a str>,
+) -> impl Iterator<Item = &'a str> {
+    let mut seen = HashSet::new();
+    values.into_iter().filter(move |value| seen.insert(*value))
+}
+
+/// Write items into the buffer separated by `separator`
+fn write_separated<T, F>(
+    out: &mut String,
+    items: impl IntoIterator<Item = T>,
+    separator: &str,
+    mut write_item: F,
+) -> fmt::Result
+where
+    F: FnMut(&mut String, T) -> fmt::Result,
+{
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            out.push_str(separator);
+        }
+        write_item(out, item)?;
+    }
+    Ok(())
 }
 
 // Placeholder implementation - will be expanded
 impl FilterCondition {
     // Note: These helper functions have been removed as they are unused
 
-    // Escape single quotes in string values
-    fn escape_string(value: &str) -> String {
-        value.replace('\'', "''")
+    // Numeric value of an integer or float condition, for rendering
+    pub(crate) fn numeric_value(&self) -> Option<&dyn fmt::Display> {
+        match self {
+            FilterCondition::UInt8Value { value, .. } => value.as_ref().map(|v| v as _),
+            FilterCondition::UInt16Value { value, .. } => value.as_ref().map(|v| v as _),
+            FilterCondition::UInt32Value { value, .. } => value.as_ref().map(|v| v as _),
+            FilterCondition::UInt64Value { value, .. } => value.as_ref().map(|v| v as _),
+            FilterCondition::Int8Value { value, .. } => value.as_ref().map(|v| v as _),
+            FilterCondition::Int16Value { value, .. } => value.as_ref().map(|v| v as _),
+            FilterCondition::Int32Value { value, .. } => value.as_ref().map(|v| v as _),
+            FilterCondition::Int64Value { value, .. } => value.as_ref().map(|v| v as _),
+            FilterCondition::Float32Value { value, .. } => value.as_ref().map(|v| v as _),
+            FilterCondition::Float64Value { value, .. } => value.as_ref().map(|v| v as _),
+            _ => None,
+        }
     }
 
     // Complete to_sql implementation with all supported conditions
     pub fn to_sql(&self, case_insensitive: bool) -> Result<String> {
+        let mut sql = String::new();
+        self.write_sql(&mut sql, case_insensitive)?;
+        Ok(sql)
+    }
+
+    /// Render this condition into an existing buffer
+    pub fn write_sql(&self, out: &mut String, case_insensitive: bool) -> Result<()> {
         match self {
             // String Types
             FilterCondition::StringValue {
@@ -374,339 +1325,293 @@ impl FilterCondition {
                 operator,
                 value,
             } => match operator {
-                FilterOperator::Equal | FilterOperator::NotEqual => match value {
+                FilterOperator::Equal
+                | FilterOperator::NotEqual
+                | FilterOperator::Like
+                | FilterOperator::NotLike => match value {
                     Some(v) => {
                         if case_insensitive {
-                            Ok(format!(
+                            write!(
+                                out,
                                 "lower({}) {} lower('{}')",
                                 column,
                                 operator.as_sql(),
-                                Self::escape_string(v)
-                            ))
+                                Escaped(v)
+                            )?;
                         } else {
-                            Ok(format!(
-                                "{} {} '{}'",
-                                column,
-                                operator.as_sql(),
-                                Self::escape_string(v)
-                            ))
+                            write!(out, "{} {} '{}'", column, operator.as_sql(), Escaped(v))?;
                         }
                     }
-                    None => Ok(format!("{} {}", column, operator.as_sql())),
+                    None => write!(out, "{} {}", column, operator.as_sql())?,
                 },
-                FilterOperator::Like | FilterOperator::NotLike => match value {
+                // Matched literally, like startsWith() / endsWith() on indexed text
+                FilterOperator::StartsWith
+                | FilterOperator::EndsWith
+                | FilterOperator::Contains => match value {
                     Some(v) => {
+                        let pattern = operator.format_value(v);
                         if case_insensitive {
-                            Ok(format!(
-                                "lower({}) {} lower('{}')",
-                                column,
-                                operator.as_sql(),
-                                Self::escape_string(v)
-                            ))
+                            write!(out, "lower({}) LIKE lower('{}')", column, Escaped(&pattern))?;
                         } else {
-                            Ok(format!(
-                                "{} {} '{}'",
-                                column,
-                                operator.as_sql(),
-                                Self::escape_string(v)
-                            ))
+                            write!(out, "{} LIKE '{}'", column, Escaped(&pattern))?;
                         }
                     }
-                    None => Ok(format!("{} {}", column, operator.as_sql())),
+                    None => write!(out, "{} LIKE '%'", column)?,
                 },
-                FilterOperator::StartsWith => match value {
+                FilterOperator::In | FilterOperator::NotIn => match value {
                     Some(v) => {
+                        let values = unique(v.split(',').map(|item| item.trim()));
                         if case_insensitive {
-                            Ok(format!(
-                                "lower({}) LIKE lower('{}%')",
-                                column,
-                                Self::escape_string(v)
-                            ))
+                            write!(out, "lower({}) {} (", column, operator.as_sql())?;
+                            write_separated(out, values, ", ", |out, val| {
+                                write!(out, "lower('{}')", Escaped(val))
+                            })?;
                         } else {
-                            Ok(format!("{} LIKE '{}%'", column, Self::escape_string(v)))
+                            write!(out, "{} {} (", column, operator.as_sql())?;
+                            write_separated(out, values, ", ", |out, val| {
+                                write!(out, "'{}'", Escaped(val))
+                            })?;
                         }
+                        out.push(')');
                     }
-                    None => Ok(format!("{} LIKE '%'", column)),
-                },
-                FilterOperator::EndsWith => match value {
-                    Some(v) => {
-                        if case_insensitive {
-                            Ok(format!(
-                                "lower({}) LIKE lower('%{}')",
-                                column,
-                                Self::escape_string(v)
-                            ))
-                        } else {
-                            Ok(format!("{} LIKE '%{}'", column, Self::escape_string(v)))
-                        }
+                    None => {
+                        return Err(eyre::eyre!(
+                            "{} operator requires values",
+                            operator.as_sql()
+                        ))
                     }
-                    None => Ok(format!("{} LIKE '%'", column)),
                 },
-                FilterOperator::In => match value {
-                    Some(v) => {
-                        let values = v.split(',').map(|item| item.trim()).collect::<Vec<_>>();
-                        let formatted_values = values
-                            .iter()
-                            .map(|val| format!("'{}'", Self::escape_string(val)))
-                            .collect::<Vec<_>>()
-                            .join(", ");
-
-                        if case_insensitive {
-                            Ok(format!(
-                                "lower({}) IN ({})",
-                                column,
-                                values
-                                    .iter()
-                                    .map(|val| format!("lower('{}')", Self::escape_string(val)))
-                                    .collect::<Vec<_>>()
-                                    .join(", ")
-                            ))
-                        } else {
-                            Ok(format!("{} IN ({})", column, formatted_values))
-                        }
+                FilterOperator::MultiMatchAny | FilterOperator::MultiSearchAny => {
+                    let patterns = value
+                        .as_deref()
+                        .map(|v| unique(v.split(',').map(str::trim).filter(|p| !p.is_empty())))
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<_>>();
+                    if patterns.is_empty() {
+                        return Err(eyre::eyre!(
+                            "{} operator requires values",
+                            operator.as_sql()
+                        ));
                     }
-                    None => Err(eyre::eyre!("IN operator requires values")),
-                },
-                FilterOperator::NotIn => match value {
-                    Some(v) => {
-                        let values = v.split(',').map(|item| item.trim()).collect::<Vec<_>>();
-                        let formatted_values = values
-                            .iter()
-                            .map(|val| format!("'{}'", Self::escape_string(val)))
-                            .collect::<Vec<_>>()
-                            .join(", ");
 
-                        if case_insensitive {
-                            Ok(format!(
-                                "lower({}) NOT IN ({})",
-                                column,
-                                values
-                                    .iter()
-                                    .map(|val| format!("lower('{}')", Self::escape_string(val)))
-                                    .collect::<Vec<_>>()
-                                    .join(", ")
-                            ))
-                        } else {
-                            Ok(format!("{} NOT IN ({})", column, formatted_values))
+                    // Regexes take an inline `(?i)` flag, substring search has its own function
+                    let is_regex = *operator == FilterOperator::MultiMatchAny;
+                    match (is_regex, case_insensitive) {
+                        (false, true) => {
+                            write!(out, "multiSearchAnyCaseInsensitive({}, [", column)?
                         }
+                        _ => write!(out, "{}({}, [", operator.as_sql(), column)?,
                     }
-                    None => Err(eyre::eyre!("NOT IN operator requires values")),
-                },
-                FilterOperator::IsNull => Ok(format!("{} IS NULL", column)),
-                FilterOperator::IsNotNull => Ok(format!("{} IS NOT NULL", column)),
-                _ => Err(eyre::eyre!("Unsupported operator for string type")),
+                    write_separated(out, patterns, ", ", |out, pattern| {
+                        let flag = if is_regex && case_insensitive {
+                            "(?i)"
+                        } else {
+                            ""
+                        };
+                        write!(out, "'{}{}'", flag, Escaped(pattern))
+                    })?;
+                    out.push_str("])");
+                }
+                FilterOperator::IsNull => write!(out, "{} IS NULL", column)?,
+                FilterOperator::IsNotNull => write!(out, "{} IS NOT NULL", column)?,
+                FilterOperator::IsEmptyOrNull => {
+                    write!(out, "({} IS NULL OR {} = '')", column, column)?
+                }
+                _ => return Err(eyre::eyre!("Unsupported operator for string type")),
             },
 
             // Numeric integer types (similar implementation for all integer types)
             FilterCondition::UInt8Value {
-                column,
-                operator,
-                value: _,
+                column, operator, ..
             }
             | FilterCondition::UInt16Value {
-                column,
-                operator,
-                value: _,
+                column, operator, ..
             }
             | FilterCondition::UInt32Value {
-                column,
-                operator,
-                value: _,
+                column, operator, ..
             }
             | FilterCondition::UInt64Value {
-                column,
-                operator,
-                value: _,
+                column, operator, ..
             }
             | FilterCondition::Int8Value {
-                column,
-                operator,
-                value: _,
+                column, operator, ..
             }
             | FilterCondition::Int16Value {
-                column,
-                operator,
-                value: _,
+                column, operator, ..
             }
             | FilterCondition::Int32Value {
-                column,
-                operator,
-                value: _,
+                column, operator, ..
             }
             | FilterCondition::Int64Value {
-                column,
-                operator,
-                value: _,
+                column, operator, ..
             } => match operator {
                 FilterOperator::Equal
                 | FilterOperator::NotEqual
                 | FilterOperator::GreaterThan
                 | FilterOperator::GreaterThanOrEqual
                 | FilterOperator::LessThan
-                | FilterOperator::LessThanOrEqual => {
-                    // Get the value based on the enum variant
-                    let value_str = match self {
-                        FilterCondition::UInt8Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::UInt16Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::UInt32Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::UInt64Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int8Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int16Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int32Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int64Value { value, .. } => value.map(|v| v.to_string()),
-                        _ => None,
-                    };
-
-                    match value_str {
-                        Some(v) => Ok(format!("{} {} {}", column, operator.as_sql(), v)),
-                        None => Ok(format!("{} {}", column, operator.as_sql())),
-                    }
-                }
-                FilterOperator::In => {
-                    // Get the values based on the enum variant
-                    let value_str = match self {
-                        FilterCondition::UInt8Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::UInt16Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::UInt32Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::UInt64Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int8Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int16Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int32Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int64Value { value, .. } => value.map(|v| v.to_string()),
-                        _ => None,
-                    };
-
-                    match value_str {
-                        Some(v) => {
-                            let values = v
-                                .split(',')
-                                .map(|item| item.trim())
-                                .collect::<Vec<_>>()
-                                .join(", ");
-                            Ok(format!("{} IN ({})", column, values))
-                        }
-                        None => Err(eyre::eyre!("IN operator requires values")),
-                    }
-                }
-                FilterOperator::NotIn => {
-                    // Get the values based on the enum variant
-                    let value_str = match self {
-                        FilterCondition::UInt8Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::UInt16Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::UInt32Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::UInt64Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int8Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int16Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int32Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Int64Value { value, .. } => value.map(|v| v.to_string()),
-                        _ => None,
-                    };
-
-                    match value_str {
-                        Some(v) => {
-                            let values = v
-                                .split(',')
-                                .map(|item| item.trim())
-                                .collect::<Vec<_>>()
-                                .join(", ");
-                            Ok(format!("{} NOT IN ({})", column, values))
-                        }
-                        None => Err(eyre::eyre!("NOT IN operator requires values")),
+                | FilterOperator::LessThanOrEqual => match self.numeric_value() {
+                    Some(v) => write!(out, "{} {} {}", column, operator.as_sql(), v)?,
+                    None => write!(out, "{} {}", column, operator.as_sql())?,
+                },
+                FilterOperator::In | FilterOperator::NotIn => match self.numeric_value() {
+                    Some(v) => write!(out, "{} {} ({})", column, operator.as_sql(), v)?,
+                    None => {
+                        return Err(eyre::eyre!(
+                            "{} operator requires values",
+                            operator.as_sql()
+                        ))
                     }
+                },
+                FilterOperator::IsNull => write!(out, "{} IS NULL", column)?,
+                FilterOperator::IsNotNull => write!(out, "{} IS NOT NULL", column)?,
+                FilterOperator::IsEmptyOrNull => {
+                    write!(out, "({} IS NULL OR {} = 0)", column, column)?
                 }
-                FilterOperator::IsNull => Ok(format!("{} IS NULL", column)),
-                FilterOperator::IsNotNull => Ok(format!("{} IS NOT NULL", column)),
-                _ => Err(eyre::eyre!("Unsupported operator for integer type")),
+                _ => return Err(eyre::eyre!("Unsupported operator for integer type")),
             },
 
             // Floating point types
             FilterCondition::Float32Value {
-                column,
-                operator,
-                value: _,
+                column, operator, ..
             }
             | FilterCondition::Float64Value {
-                column,
-                operator,
-                value: _,
+                column, operator, ..
             } => match operator {
                 FilterOperator::Equal
                 | FilterOperator::NotEqual
                 | FilterOperator::GreaterThan
                 | FilterOperator::GreaterThanOrEqual
                 | FilterOperator::LessThan
-                | FilterOperator::LessThanOrEqual => {
-                    // Get the value based on the enum variant
-                    let value_str = match self {
-                        FilterCondition::Float32Value { value, .. } => value.map(|v| v.to_string()),
-                        FilterCondition::Float64Value { value, .. } => value.map(|v| v.to_string()),
-                        _ => None,
-                    };
-
-                    match value_str {
-                        Some(v) => Ok(format!("{} {} {}", column, operator.as_sql(), v)),
-                        None => Ok(format!("{} {}", column, operator.as_sql())),
+                | FilterOperator::LessThanOrEqual => match self.numeric_value() {
+                    Some(v) => write!(out, "{} {} {}", column, operator.as_sql(), v)?,
+                    None => write!(out, "{} {}", column, operator.as_sql())?,
+                },
+                FilterOperator::In | FilterOperator::NotIn => match self.numeric_value() {
+                    Some(v) => write!(out, "{} {} ({})", column, operator.as_sql(), v)?,
+                    None => {
+                        return Err(eyre::eyre!(
+                            "{} operator requires values",
+                            operator.as_sql()
+                        ))
                     }
+                },
+                FilterOperator::IsNull => write!(out, "{} IS NULL", column)?,
+                FilterOperator::IsNotNull => write!(out, "{} IS NOT NULL", column)?,
+                FilterOperator::IsEmptyOrNull => {
+                    write!(out, "({} IS NULL OR {} = 0)", column, column)?
                 }
-                FilterOperator::IsNull => Ok(format!("{} IS NULL", column)),
-                FilterOperator::IsNotNull => Ok(format!("{} IS NOT NULL", column)),
-                _ => Err(eyre::eyre!("Unsupported operator for float type")),
+                _ => return Err(eyre::eyre!("Unsupported operator for float type")),
             },
 
             // Date/Time Types
             FilterCondition::DateValue {
                 column,
                 operator,
-                value: _,
+                value,
             }
             | FilterCondition::DateTimeValue {
                 column,
                 operator,
-                value: _,
+                value,
             }
             | FilterCondition::DateTime64Value {
                 column,
                 operator,
-                value: _,
+                value,
             } => match operator {
                 FilterOperator::Equal
                 | FilterOperator::NotEqual
                 | FilterOperator::GreaterThan
                 | FilterOperator::GreaterThanOrEqual
                 | FilterOperator::LessThan
-                | FilterOperator::LessThanOrEqual => {
-                    // Get the value based on the enum variant
-                    let value_str = match self {
-                        FilterCondition::DateValue { value, .. } => value.clone(),
-                        FilterCondition::DateTimeValue { value, .. } => value.clone(),
-                        FilterCondition::DateTime64Value { value, .. } => value.clone(),
-                        _ => None,
-                    };
-
-                    match value_str {
-                        Some(v) => Ok(format!("{} {} '{}'", column, operator.as_sql(), v)),
-                        None => Ok(format!("{} {}", column, operator.as_sql())),
+                | FilterOperator::LessThanOrEqual => match value {
+                    Some(v) if matches!(self, FilterCondition::DateTime64Value { .. }) => {
+                        write!(out, "{} {} {}", column, operator.as_sql(), Timestamp(v))?
                     }
-                }
-                FilterOperator::IsNull => Ok(format!("{} IS NULL", column)),
-                FilterOperator::IsNotNull => Ok(format!("{} IS NOT NULL", column)),
-                _ => Err(eyre::eyre!("Unsupported operator for date/time type")),
+                    Some(v) => write!(out, "{} {} '{}'", column, operator.as_sql(), Escaped(v))?,
+                    None => write!(out, "{} {}", column, operator.as_sql())?,
+                },
+                FilterOperator::IsNull => write!(out, "{} IS NULL", column)?,
+                FilterOperator::IsNotNull => write!(out, "{} IS NOT NULL", column)?,
+                _ => return Err(eyre::eyre!("Unsupported operator for date/time type")),
             },
 
             // Date Range specific handling
             FilterCondition::DateRange { column, range_type } => match range_type {
-                DateRangeType::Exact(timestamp) => Ok(format!("{} = '{}'", column, timestamp)),
+                DateRangeType::Exact(timestamp) => {
+                    write!(out, "{} = {}", column, Timestamp(timestamp))?
+                }
                 DateRangeType::DateOnly(date) => {
                     // In ClickHouse we can use toDate function
-                    Ok(format!("toDate({}) = toDate('{}')", column, date))
-                }
-                DateRangeType::Range { start, end } => {
-                    Ok(format!("{} BETWEEN '{}' AND '{}'", column, start, end))
+                    write!(out, "toDate({}) = toDate('{}')", column, Escaped(date))?
                 }
+                DateRangeType::Range { start, end } => write!(
+                    out,
+                    "{} BETWEEN {} AND {}",
+                    column,
+                    Timestamp(start),
+                    Timestamp(end)
+                )?,
                 DateRangeType::Relative(expr) => {
                     // For ClickHouse we directly pass the expression
-                    Ok(format!("{} > {}", column, expr))
+                    write!(out, "{} > {}", column, expr)?
+                }
+                DateRangeType::Bucket { interval, start } => {
+                    write!(
+                        out,
+                        "toStartOfInterval({}, INTERVAL {}) = ",
+                        column, interval
+                    )?;
+                    match fraction_digits(start) {
+                        0 => write!(out, "toDateTime('{}')", Escaped(start))?,
+                        digits => write!(out, "toDateTime64('{}', {})", Escaped(start), digits)?,
+                    }
+                }
+            },
+
+            FilterCondition::NumericRange { column, range } => {
+                range.check_bounds(column)?;
+                let lower = if range.inclusive_min { ">=" } else { ">" };
+                let upper = if range.inclusive_max { "<=" } else { "<" };
+                match (&range.min, &range.max) {
+                    (Some(min), Some(max))
+                        if min == max && range.inclusive_min && range.inclusive_max =>
+                    {
+                        write!(out, "{} = {}", column, min)?
+                    }
+                    (Some(min), Some(max)) if range.inclusive_min && range.inclusive_max => {
+                        write!(out, "{} BETWEEN {} AND {}", column, min, max)?
+                    }
+                    (Some(min), Some(max)) => write!(
+                        out,
+                        "({} {} {} AND {} {} {})",
+                        column, lower, min, column, upper, max
+                    )?,
+                    (Some(min), None) => write!(out, "{} {} {}", column, lower, min)?,
+                    (None, Some(max)) => write!(out, "{} {} {}", column, upper, max)?,
+                    (None, None) => {
+                        return Err(eyre::eyre!("Numeric range has no bounds: {}", column))
+                    }
+                }
+            }
+
+            FilterCondition::IndexedText {
+                column,
+                operator,
+                value,
+                index,
+            } => match index.function(operator, value, case_insensitive) {
+                Some(function) => {
+                    write!(out, "{}({}, '{}')", function, column, Escaped(value))?;
+                    if function.starts_with("position") {
+                        out.push_str(" > 0");
+                    }
                 }
+                // No indexed function fits, so render as a plain string condition
+                None => FilterCondition::string(column, operator.clone(), Some(value))
+                    .write_sql(out, case_insensitive)?,
             },
 
             // Boolean Type
@@ -719,13 +1624,17 @@ impl FilterCondition {
                     Some(v) => {
                         // ClickHouse uses 0/1 for boolean values
                         let bool_val = if *v { 1 } else { 0 };
-                        Ok(format!("{} {} {}", column, operator.as_sql(), bool_val))
+                        write!(out, "{} {} {}", column, operator.as_sql(), bool_val)?
                     }
-                    None => Ok(format!("{} {}", column, operator.as_sql())),
+                    None => write!(out, "{} {}", column, operator.as_sql())?,
+                },
+                FilterOperator::In | FilterOperator::NotIn => match value {
+                    Some(v) => write!(out, "{} {} ({})", column, operator.as_sql(), u8::from(*v))?,
+                    None => return Err(eyre::eyre!("IN on a boolean column requires a value")),
                 },
-                FilterOperator::IsNull => Ok(format!("{} IS NULL", column)),
-                FilterOperator::IsNotNull => Ok(format!("{} IS NOT NULL", column)),
-                _ => Err(eyre::eyre!("Unsupported operator for boolean type")),
+                FilterOperator::IsNull => write!(out, "{} IS NULL", column)?,
+                FilterOperator::IsNotNull => write!(out, "{} IS NOT NULL", column)?,
+                _ => return Err(eyre::eyre!("Unsupported operator for boolean type")),
             },
 
             // UUID Type
@@ -735,57 +1644,313 @@ impl FilterCondition {
                 value,
             } => match operator {
                 FilterOperator::Equal | FilterOperator::NotEqual => match value {
-                    Some(v) => Ok(format!("{} {} '{}'", column, operator.as_sql(), v)),
-                    None => Ok(format!("{} {}", column, operator.as_sql())),
+                    Some(v) => write!(out, "{} {} '{}'", column, operator.as_sql(), Escaped(v))?,
+                    None => write!(out, "{} {}", column, operator.as_sql())?,
                 },
-                FilterOperator::In => match value {
+                FilterOperator::In | FilterOperator::NotIn => match value {
                     Some(v) => {
-                        let values = v
-                            .split(',')
-                            .map(|item| format!("'{}'", item.trim()))
-                            .collect::<Vec<_>>()
-                            .join(", ");
-                        Ok(format!("{} IN ({})", column, values))
+                        write!(out, "{} {} (", column, operator.as_sql())?;
+                        let values = unique(v.split(',').map(|item| item.trim()));
+                        write_separated(out, values, ", ", |out, item| {
+                            write!(out, "'{}'", Escaped(item))
+                        })?;
+                        out.push(')');
                     }
-                    None => Err(eyre::eyre!("IN operator requires values")),
-                },
-                FilterOperator::NotIn => match value {
-                    Some(v) => {
-                        let values = v
-                            .split(',')
-                            .map(|item| format!("'{}'", item.trim()))
-                            .collect::<Vec<_>>()
-                            .join(", ");
-                        Ok(format!("{} NOT IN ({})", column, values))
+                    None => {
+                        return Err(eyre::eyre!(
+                            "{} operator requires values",
+                            operator.as_sql()
+                        ))
                     }
-                    None => Err(eyre::eyre!("NOT IN operator requires values")),
                 },
-                FilterOperator::IsNull => Ok(format!("{} IS NULL", column)),
-                FilterOperator::IsNotNull => Ok(format!("{} IS NOT NULL", column)),
-                _ => Err(eyre::eyre!("Unsupported operator for UUID type")),
+                FilterOperator::IsNull => write!(out, "{} IS NULL", column)?,
+                FilterOperator::IsNotNull => write!(out, "{} IS NOT NULL", column)?,
+                _ => return Err(eyre::eyre!("Unsupported operator for UUID type")),
             },
 
+            FilterCondition::IPv4Value {
+                column,
+                operator,
+                value,
+            } => write_ip(
+                out,
+                column,
+                operator,
+                "toIPv4",
+                value.as_ref().map(|v| v as _),
+            )?,
+            FilterCondition::IPv6Value {
+                column,
+                operator,
+                value,
+            } => write_ip(
+                out,
+                column,
+                operator,
+                "toIPv6",
+                value.as_ref().map(|v| v as _),
+            )?,
+
             // Array Types
             FilterCondition::ArrayContains {
                 column,
-                operator: _,
+                operator,
                 value,
             } => {
                 // In ClickHouse, we use `hasAll` function for array containment
-                let values = value
-                    .split(',')
-                    .map(|s| format!("'{}'", s.trim().replace('\'', "''")))
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                Ok(format!("hasAll({}, array[{}])", column, values))
+                if *operator == FilterOperator::ArrayNotContains {
+                    out.push_str("NOT ");
+                }
+                write!(out, "hasAll({}, array[", column)?;
+                write_separated(out, value.split(','), ", ", |out, s| {
+                    write!(out, "'{}'", Escaped(s.trim()))
+                })?;
+                out.push_str("])");
+            }
+            FilterCondition::ArrayHas {
+                column,
+                operator,
+                value,
+            } => {
+                // In ClickHouse, we use `has` function for checking if array contains a value
+                if *operator == FilterOperator::ArrayNotHas {
+                    out.push_str("NOT ");
+                }
+                write!(out, "has({}, '{}')", column, Escaped(value))?
+            }
+            FilterCondition::ArrayValues {
+                column,
+                operator,
+                values,
+                element_type,
+            } => {
+                // Negated operators render as NOT around the positive function
+                let (negated, operator) = match operator {
+                    FilterOperator::ArrayNotHas => (true, &FilterOperator::ArrayHas),
+                    FilterOperator::ArrayNotContains => (true, &FilterOperator::ArrayContains),
+                    FilterOperator::ArrayNotAny => (true, &FilterOperator::ArrayAny),
+                    _ => (false, operator),
+                };
+                if negated {
+                    out.push_str("NOT ");
+                }
+                let function = match operator {
+                    FilterOperator::ArrayHas => match values.as_slice() {
+                        [value] => {
+                            write!(out, "has({}, ", column)?;
+                            element_type.write_element(out, value)?;
+                            out.push(')');
+                            return Ok(());
+                        }
+                        _ => return Err(eyre::eyre!("ARRAY HAS requires exactly one value")),
+                    },
+                    FilterOperator::ArrayContains | FilterOperator::ArrayAll => "hasAll",
+                    FilterOperator::ArrayAny => "hasAny",
+                    FilterOperator::ArrayHasSubsequence => "hasSubstr",
+                    _ => return Err(eyre::eyre!("Unsupported operator for array type")),
+                };
+
+                write!(out, "{}({}, [", function, column)?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    element_type.write_element(out, value)?;
+                }
+                out.push_str("])");
+            }
+            FilterCondition::ArrayCount {
+                column,
+                value,
+                operator,
+                count,
+                element_type,
+            } => {
+                if !operator.is_comparison() {
+                    return Err(eyre::eyre!("Unsupported operator for array count"));
+                }
+                write!(out, "countEqual({}, ", column)?;
+                element_type.write_element(out, value)?;
+                write!(out, ") {} {}", operator.as_sql(), count)?;
+            }
+            FilterCondition::ArrayOverlap {
+                column,
+                values,
+                min_overlap,
+                element_type,
+            } => {
+                if values.is_empty() {
+                    return Err(eyre::eyre!("Array overlap requires at least one value"));
+                }
+                write!(out, "length(arrayIntersect({}, [", column)?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    element_type.write_element(out, value)?;
+                }
+                write!(out, "])) >= {}", min_overlap)?;
+            }
+            FilterCondition::ArrayJSONValue {
+                column, key, value, ..
+            } => {
+                let element = JsonColumn {
+                    column: "x",
+                    path: Some(key),
+                };
+                if case_insensitive {
+                    write!(
+                        out,
+                        "arrayExists(x -> lower({}) = lower('{}'), {})",
+                        element,
+                        Escaped(value),
+                        column
+                    )?
+                } else {
+                    write!(
+                        out,
+                        "arrayExists(x -> {} = '{}', {})",
+                        element,
+                        Escaped(value),
+                        column
+                    )?
+                }
+            }
+            FilterCondition::ArrayElement {
+                column,
+                index,
+                operator,
+                value,
+                element_type,
+            } => {
+                let is_text = *element_type == ArrayElementType::String;
+                match operator {
+                    op if op.is_comparison() => {}
+                    FilterOperator::Like | FilterOperator::NotLike if is_text => {}
+                    _ => return Err(eyre::eyre!("Unsupported operator for array element")),
+                }
+
+                let lowered = case_insensitive
+                    && is_text
+                    && !matches!(
+                        operator,
+                        FilterOperator::GreaterThan
+                            | FilterOperator::GreaterThanOrEqual
+                            | FilterOperator::LessThan
+                            | FilterOperator::LessThanOrEqual
+                    );
+                if lowered {
+                    out.push_str("lower(");
+                }
+                match *index {
+                    0 => return Err(eyre::eyre!("Array index must not be 0")),
+                    i if i > 0 => write!(out, "{}[{}]", column, i)?,
+                    i => write!(out, "arrayElement({}, {})", column, i)?,
+                }
+                if lowered {
+                    write!(out, ") {} lower(", operator.as_sql())?;
+                    element_type.write_element(out, value)?;
+                    out.push(')');
+                } else {
+                    write!(out, " {} ", operator.as_sql())?;
+                    element_type.write_element(out, value)?;
+                }
+            }
+
+            FilterCondition::MapValue {
+                column,
+                key,
+                operator,
+                value,
+                value_type,
+            } => {
+                let is_text = *value_type == MapValueType::String;
+                let value = match (operator, value) {
+                    // A missing key reads as an empty string, so test for the key itself
+                    (FilterOperator::IsNull | FilterOperator::IsNotNull, _) if is_text => {
+                        if *operator == FilterOperator::IsNull {
+                            out.push_str("NOT ");
+                        }
+                        write!(out, "mapContains({}, '{}')", column, Escaped(key))?;
+                        return Ok(());
+                    }
+                    (FilterOperator::IsNull | FilterOperator::IsNotNull, _) => None,
+                    (op, Some(value)) if op.is_comparison() => Some(value),
+                    (FilterOperator::Like | FilterOperator::NotLike, Some(value)) if is_text => {
+                        Some(value)
+                    }
+                    _ => return Err(eyre::eyre!("Unsupported operator for map value")),
+                };
+
+                let lowered = case_insensitive
+                    && is_text
+                    && matches!(
+                        operator,
+                        FilterOperator::Equal
+                            | FilterOperator::NotEqual
+                            | FilterOperator::Like
+                            | FilterOperator::NotLike
+                    );
+                match value_type {
+                    MapValueType::String if lowered => {
+                        write!(out, "lower({}['{}'])", column, Escaped(key))?
+                    }
+                    MapValueType::String => write!(out, "{}['{}']", column, Escaped(key))?,
+                    MapValueType::Int64 => {
+                        write!(out, "toInt64OrNull({}['{}'])", column, Escaped(key))?
+                    }
+                    MapValueType::Float64 => {
+                        write!(out, "toFloat64OrNull({}['{}'])", column, Escaped(key))?
+                    }
+                }
+
+                match value {
+                    None => write!(out, " {}", operator.as_sql())?,
+                    Some(value) if lowered => {
+                        write!(out, " {} lower('{}')", operator.as_sql(), Escaped(value))?
+                    }
+                    Some(value) if is_text => {
+                        write!(out, " {} '{}'", operator.as_sql(), Escaped(value))?
+                    }
+                    Some(value) => {
+                        let valid = match value_type {
+                            MapValueType::Int64 => value.parse::<i64>().is_ok(),
+                            _ => value.parse::<f64>().is_ok(),
+                        };
+                        if !valid {
+                            return Err(eyre::eyre!("Invalid numeric map value: {}", value));
+                        }
+                        write!(out, " {} {}", operator.as_sql(), value)?
+                    }
+                }
             }
-            FilterCondition::ArrayHas {
+
+            FilterCondition::JSONNumeric {
                 column,
-                operator: _,
+                path,
+                operator,
                 value,
+                number_type,
             } => {
-                // In ClickHouse, we use `has` function for checking if array contains a value
-                Ok(format!("has({}, '{}')", column, value.replace('\'', "''")))
+                if !operator.is_comparison() {
+                    return Err(eyre::eyre!("Unsupported operator for JSON numeric value"));
+                }
+                let valid = match number_type {
+                    JsonNumberType::Int => value.parse::<i64>().is_ok(),
+                    JsonNumberType::Float => value.parse::<f64>().is_ok(),
+                };
+                if !valid {
+                    return Err(eyre::eyre!("Invalid numeric JSON value: {}", value));
+                }
+                write!(
+                    out,
+                    "{}({}, '{}') {} {}",
+                    number_type.extract_function(),
+                    column,
+                    Escaped(path),
+                    operator.as_sql(),
+                    value
+                )?
             }
 
             // JSON Type
@@ -796,40 +1961,83 @@ impl FilterCondition {
                 path,
             } => {
                 // Use ClickHouse's JSONExtract functions based on the path
-                let json_column = match path {
-                    Some(p) => format!("JSONExtractString({}, '{}')", column, p),
-                    None => column.clone(),
+                let json_column = JsonColumn {
+                    column,
+                    path: path.as_deref(),
                 };
 
                 match operator {
                     FilterOperator::Equal | FilterOperator::NotEqual => match value {
                         Some(v) => {
-                            if case_insensitive && operator == &FilterOperator::Equal {
-                                Ok(format!(
-                                    "lower({}) = lower('{}')",
-                                    json_column,
-                                    Self::escape_string(v)
-                                ))
-                            } else if case_insensitive && operator == &FilterOperator::NotEqual {
-                                Ok(format!(
-                                    "lower({}) != lower('{}')",
+                            if case_insensitive {
+                                write!(
+                                    out,
+                                    "lower({}) {} lower('{}')",
                                     json_column,
-                                    Self::escape_string(v)
-                                ))
+                                    operator.as_sql(),
+                                    Escaped(v)
+                                )?
                             } else {
-                                Ok(format!(
+                                write!(
+                                    out,
                                     "{} {} '{}'",
                                     json_column,
                                     operator.as_sql(),
-                                    Self::escape_string(v)
-                                ))
+                                    Escaped(v)
+                                )?
                             }
                         }
-                        None => Ok(format!("{} {}", json_column, operator.as_sql())),
+                        None => write!(out, "{} {}", json_column, operator.as_sql())?,
+                    },
+                    // JSONType reports 'Null' for missing paths too, so check presence separately
+                    FilterOperator::IsNull => match path {
+                        Some(p) => write!(
+                            out,
+                            "(JSONHas({0}, '{1}') AND JSONType({0}, '{1}') = 'Null')",
+                            column,
+                            Escaped(p)
+                        )?,
+                        None => write!(out, "{} IS NULL", column)?,
+                    },
+                    FilterOperator::IsNotNull => match path {
+                        Some(p) => write!(out, "JSONType({}, '{}') != 'Null'", column, Escaped(p))?,
+                        None => write!(out, "{} IS NOT NULL", column)?,
+                    },
+                    FilterOperator::Exists | FilterOperator::IsEmpty => {
+                        let Some(p) = path else {
+                            return Err(eyre::eyre!(
+                                "{:?} on a JSON column requires a path",
+                                operator
+                            ));
+                        };
+                        if *operator == FilterOperator::Exists {
+                            write!(out, "JSONHas({}, '{}')", column, Escaped(p))?
+                        } else {
+                            write!(
+                                out,
+                                "((JSONType({0}, '{1}') = 'String' AND JSONExtractString({0}, '{1}') = '') \
+                                 OR (JSONType({0}, '{1}') = 'Array' AND JSONLength({0}, '{1}') = 0))",
+                                column,
+                            Escaped(p)
+                            )?
+                        }
+                    }
+                    // Arrays stored inside the JSON document, e.g. `{"tags": ["a", "b"]}`
+                    FilterOperator::ArrayHas => match (path, value) {
+                        (Some(p), Some(v)) => write!(
+                            out,
+                            "has(JSONExtract({}, '{}', 'Array(String)'), '{}')",
+                            column,
+                            Escaped(p),
+                            Escaped(v)
+                        )?,
+                        _ => {
+                            return Err(eyre::eyre!(
+                                "ARRAY HAS on a JSON column requires a path and a value"
+                            ))
+                        }
                     },
-                    FilterOperator::IsNull => Ok(format!("{} IS NULL", json_column)),
-                    FilterOperator::IsNotNull => Ok(format!("{} IS NOT NULL", json_column)),
-                    _ => Err(eyre::eyre!("Unsupported operator for JSON type")),
+                    _ => return Err(eyre::eyre!("Unsupported operator for JSON type")),
                 }
             }
 
@@ -839,50 +2047,273 @@ impl FilterCondition {
                 operator,
                 values,
                 column_type,
+            }
+            | FilterCondition::ArrayJoinIn {
+                column,
+                operator,
+                values,
+                column_type,
             } => {
                 let is_text = matches!(column_type, Some(ColumnTypeInfo::String));
 
-                let formatted_values = if is_text {
-                    values
-                        .iter()
-                        .map(|v| {
-                            if case_insensitive {
-                                format!("lower('{}')", Self::escape_string(v))
-                            } else {
-                                format!("'{}'", Self::escape_string(v))
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                } else {
-                    values
-                        .iter()
-                        .map(|v| {
-                            // Check if the value is numeric
-                            if v.parse::<f64>().is_ok() {
-                                v.to_string()
-                            } else {
-                                format!("'{}'", Self::escape_string(v))
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                };
+                if !matches!(operator, FilterOperator::In | FilterOperator::NotIn) {
+                    return Err(eyre::eyre!(
+                        "Invalid operator for {} condition",
+                        self.kind()
+                    ));
+                }
 
-                let column_name = if case_insensitive && is_text {
-                    format!("lower({})", column)
+                if case_insensitive && is_text {
+                    write!(out, "lower({}) {} (", column, operator.as_sql())?;
                 } else {
-                    column.to_string()
+                    write!(out, "{} {} (", column, operator.as_sql())?;
+                }
+                let array_join = matches!(self, FilterCondition::ArrayJoinIn { .. });
+                if array_join {
+                    out.push_str("SELECT arrayJoin([");
+                }
+
+                // An arrayJoin subquery returns Strings, which don't match a UUID column
+                let is_uuid = array_join && matches!(column_type, Some(ColumnTypeInfo::UUID));
+                // IP columns don't compare with strings
+                let ip_function = match column_type {
+                    Some(ColumnTypeInfo::IPv4) => Some("toIPv4"),
+                    Some(ColumnTypeInfo::IPv6) => Some("toIPv6"),
+                    _ => None,
                 };
 
-                match operator {
-                    FilterOperator::In => Ok(format!("{} IN ({})", column_name, formatted_values)),
-                    FilterOperator::NotIn => {
-                        Ok(format!("{} NOT IN ({})", column_name, formatted_values))
+                // Boolean values are written as 1/0, e.g. `true` from a typed column
+                let is_bool = matches!(column_type, Some(ColumnTypeInfo::Boolean));
+
+                let values = unique(values.iter().map(String::as_str));
+                write_separated(out, values, ", ", |out, v| {
+                    if let Some(b) = parse_bool(v).filter(|_| is_bool) {
+                        write!(out, "{}", u8::from(b))
+                    } else if let Some(function) = ip_function {
+                        write!(out, "{}('{}')", function, Escaped(v))
+                    } else if is_uuid {
+                        write!(out, "toUUID('{}')", Escaped(v))
+                    } else if is_text {
+                        if case_insensitive {
+                            write!(out, "lower('{}')", Escaped(v))
+                        } else {
+                            write!(out, "'{}'", Escaped(v))
+                        }
+                    } else if v.parse::<f64>().is_ok() {
+                        // Numeric values are rendered unquoted
+                        out.push_str(v);
+                        Ok(())
+                    } else {
+                        write!(out, "'{}'", Escaped(v))
                     }
-                    _ => Err(eyre::eyre!("Invalid operator for InValues condition")),
+                })?;
+                if array_join {
+                    out.push_str("])");
                 }
+                out.push(')');
             }
+
+            FilterCondition::InExternal {
+                column,
+                operator,
+                table,
+            } => {
+                if !matches!(operator, FilterOperator::In | FilterOperator::NotIn) {
+                    return Err(eyre::eyre!("Invalid operator for InExternal condition"));
+                }
+                if quote_identifier(table) != table.as_str() {
+                    return Err(eyre::eyre!("Invalid external table name: {}", table));
+                }
+                write!(out, "{} {} {}", column, operator.as_sql(), table)?;
+            }
+
+            FilterCondition::Custom {
+                column,
+                operator,
+                value,
+                renderer,
+            } => renderer
+                .0
+                .write_sql(out, column, operator, value.as_deref(), case_insensitive)?,
+        }
+
+        Ok(())
+    }
+
+    /// Column this condition applies to
+    pub fn column(&self) -> &str {
+        match self {
+            FilterCondition::StringValue { column, .. }
+            | FilterCondition::FixedStringValue { column, .. }
+            | FilterCondition::UInt8Value { column, .. }
+            | FilterCondition::UInt16Value { column, .. }
+            | FilterCondition::UInt32Value { column, .. }
+            | FilterCondition::UInt64Value { column, .. }
+            | FilterCondition::Int8Value { column, .. }
+            | FilterCondition::Int16Value { column, .. }
+            | FilterCondition::Int32Value { column, .. }
+            | FilterCondition::Int64Value { column, .. }
+            | FilterCondition::Float32Value { column, .. }
+            | FilterCondition::Float64Value { column, .. }
+            | FilterCondition::DateValue { column, .. }
+            | FilterCondition::DateTimeValue { column, .. }
+            | FilterCondition::DateTime64Value { column, .. }
+            | FilterCondition::DateRange { column, .. }
+            | FilterCondition::NumericRange { column, .. }
+            | FilterCondition::BooleanValue { column, .. }
+            | FilterCondition::UUIDValue { column, .. }
+            | FilterCondition::IPv4Value { column, .. }
+            | FilterCondition::IPv6Value { column, .. }
+            | FilterCondition::IndexedText { column, .. }
+            | FilterCondition::InValues { column, .. }
+            | FilterCondition::ArrayJoinIn { column, .. }
+            | FilterCondition::InExternal { column, .. }
+            | FilterCondition::ArrayContains { column, .. }
+            | FilterCondition::ArrayHas { column, .. }
+            | FilterCondition::ArrayValues { column, .. }
+            | FilterCondition::ArrayElement { column, .. }
+            | FilterCondition::ArrayCount { column, .. }
+            | FilterCondition::ArrayOverlap { column, .. }
+            | FilterCondition::ArrayJSONValue { column, .. }
+            | FilterCondition::MapValue { column, .. }
+            | FilterCondition::JSONNumeric { column, .. }
+            | FilterCondition::JSONValue { column, .. }
+            | FilterCondition::Custom { column, .. } => column,
+        }
+    }
+
+    /// Mutable access to the column name, e.g. for rewriting names in a `map()`
+    pub fn column_mut(&mut self) -> &mut String {
+        match self {
+            FilterCondition::StringValue { column, .. }
+            | FilterCondition::FixedStringValue { column, .. }
+            | FilterCondition::UInt8Value { column, .. }
+            | FilterCondition::UInt16Value { column, .. }
+            | FilterCondition::UInt32Value { column, .. }
+            | FilterCondition::UInt64Value { column, .. }
+            | FilterCondition::Int8Value { column, .. }
+            | FilterCondition::Int16Value { column, .. }
+            | FilterCondition::Int32Value { column, .. }
+            | FilterCondition::Int64Value { column, .. }
+            | FilterCondition::Float32Value { column, .. }
+            | FilterCondition::Float64Value { column, .. }
+            | FilterCondition::DateValue { column, .. }
+            | FilterCondition::DateTimeValue { column, .. }
+            | FilterCondition::DateTime64Value { column, .. }
+            | FilterCondition::DateRange { column, .. }
+            | FilterCondition::NumericRange { column, .. }
+            | FilterCondition::BooleanValue { column, .. }
+            | FilterCondition::UUIDValue { column, .. }
+            | FilterCondition::IPv4Value { column, .. }
+            | FilterCondition::IPv6Value { column, .. }
+            | FilterCondition::IndexedText { column, .. }
+            | FilterCondition::InValues { column, .. }
+            | FilterCondition::ArrayJoinIn { column, .. }
+            | FilterCondition::InExternal { column, .. }
+            | FilterCondition::ArrayContains { column, .. }
+            | FilterCondition::ArrayHas { column, .. }
+            | FilterCondition::ArrayValues { column, .. }
+            | FilterCondition::ArrayElement { column, .. }
+            | FilterCondition::ArrayCount { column, .. }
+            | FilterCondition::ArrayOverlap { column, .. }
+            | FilterCondition::ArrayJSONValue { column, .. }
+            | FilterCondition::MapValue { column, .. }
+            | FilterCondition::JSONNumeric { column, .. }
+            | FilterCondition::JSONValue { column, .. }
+            | FilterCondition::Custom { column, .. } => column,
+        }
+    }
+
+    /// Operator of this condition, if it carries one
+    ///
+    /// Date range conditions are described by their `DateRangeType` instead, and
+    /// array overlap conditions by their minimum overlap.
+    pub fn operator(&self) -> Option<&FilterOperator> {
+        match self {
+            FilterCondition::StringValue { operator, .. }
+            | FilterCondition::FixedStringValue { operator, .. }
+            | FilterCondition::UInt8Value { operator, .. }
+            | FilterCondition::UInt16Value { operator, .. }
+            | FilterCondition::UInt32Value { operator, .. }
+            | FilterCondition::UInt64Value { operator, .. }
+            | FilterCondition::Int8Value { operator, .. }
+            | FilterCondition::Int16Value { operator, .. }
+            | FilterCondition::Int32Value { operator, .. }
+            | FilterCondition::Int64Value { operator, .. }
+            | FilterCondition::Float32Value { operator, .. }
+            | FilterCondition::Float64Value { operator, .. }
+            | FilterCondition::DateValue { operator, .. }
+            | FilterCondition::DateTimeValue { operator, .. }
+            | FilterCondition::DateTime64Value { operator, .. }
+            | FilterCondition::BooleanValue { operator, .. }
+            | FilterCondition::UUIDValue { operator, .. }
+            | FilterCondition::IPv4Value { operator, .. }
+            | FilterCondition::IPv6Value { operator, .. }
+            | FilterCondition::IndexedText { operator, .. }
+            | FilterCondition::InValues { operator, .. }
+            | FilterCondition::ArrayJoinIn { operator, .. }
+            | FilterCondition::InExternal { operator, .. }
+            | FilterCondition::ArrayContains { operator, .. }
+            | FilterCondition::ArrayHas { operator, .. }
+            | FilterCondition::ArrayValues { operator, .. }
+            | FilterCondition::ArrayElement { operator, .. }
+            | FilterCondition::ArrayCount { operator, .. }
+            | FilterCondition::ArrayJSONValue { operator, .. }
+            | FilterCondition::MapValue { operator, .. }
+            | FilterCondition::JSONNumeric { operator, .. }
+            | FilterCondition::JSONValue { operator, .. }
+            | FilterCondition::Custom { operator, .. } => Some(operator),
+            FilterCondition::DateRange { .. }
+            | FilterCondition::NumericRange { .. }
+            | FilterCondition::ArrayOverlap { .. } => None,
+        }
+    }
+
+    /// Variant name, e.g. `UInt32Value`, used when describing the shape of a condition
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FilterCondition::StringValue { .. } => "StringValue",
+            FilterCondition::FixedStringValue { .. } => "FixedStringValue",
+            FilterCondition::UInt8Value { .. } => "UInt8Value",
+            FilterCondition::UInt16Value { .. } => "UInt16Value",
+            FilterCondition::UInt32Value { .. } => "UInt32Value",
+            FilterCondition::UInt64Value { .. } => "UInt64Value",
+            FilterCondition::Int8Value { .. } => "Int8Value",
+            FilterCondition::Int16Value { .. } => "Int16Value",
+            FilterCondition::Int32Value { .. } => "Int32Value",
+            FilterCondition::Int64Value { .. } => "Int64Value",
+            FilterCondition::Float32Value { .. } => "Float32Value",
+            FilterCondition::Float64Value { .. } => "Float64Value",
+            FilterCondition::DateValue { .. } => "DateValue",
+            FilterCondition::DateTimeValue { .. } => "DateTimeValue",
+            FilterCondition::DateTime64Value { .. } => "DateTime64Value",
+            FilterCondition::DateRange { range_type, .. } => match range_type {
+                DateRangeType::Exact(_) => "DateRange::Exact",
+                DateRangeType::DateOnly(_) => "DateRange::DateOnly",
+                DateRangeType::Range { .. } => "DateRange::Range",
+                DateRangeType::Relative(_) => "DateRange::Relative",
+                DateRangeType::Bucket { .. } => "DateRange::Bucket",
+            },
+            FilterCondition::NumericRange { .. } => "NumericRange",
+            FilterCondition::BooleanValue { .. } => "BooleanValue",
+            FilterCondition::UUIDValue { .. } => "UUIDValue",
+            FilterCondition::IPv4Value { .. } => "IPv4Value",
+            FilterCondition::IPv6Value { .. } => "IPv6Value",
+            FilterCondition::IndexedText { .. } => "IndexedText",
+            FilterCondition::InValues { .. } => "InValues",
+            FilterCondition::ArrayJoinIn { .. } => "ArrayJoinIn",
+            FilterCondition::InExternal { .. } => "InExternal",
+            FilterCondition::ArrayContains { .. } => "ArrayContains",
+            FilterCondition::ArrayHas { .. } => "ArrayHas",
+            FilterCondition::ArrayValues { .. } => "ArrayValues",
+            FilterCondition::ArrayElement { .. } => "ArrayElement",
+            FilterCondition::ArrayCount { .. } => "ArrayCount",
+            FilterCondition::ArrayOverlap { .. } => "ArrayOverlap",
+            FilterCondition::ArrayJSONValue { .. } => "ArrayJSONValue",
+            FilterCondition::MapValue { .. } => "MapValue",
+            FilterCondition::JSONNumeric { .. } => "JSONNumeric",
+            FilterCondition::JSONValue { .. } => "JSONValue",
+            FilterCondition::Custom { .. } => "Custom",
         }
     }
 
@@ -987,6 +2418,37 @@ impl FilterCondition {
         }
     }
 
+    /// `CONTAINS`, `STARTS WITH` or `ENDS WITH` using the functions `index` can serve
+    pub fn indexed_text(
+        column: &str,
+        operator: FilterOperator,
+        value: &str,
+        index: TextIndex,
+    ) -> Self {
+        FilterCondition::IndexedText {
+            column: column.to_string(),
+            operator,
+            value: value.to_string(),
+            index,
+        }
+    }
+
+    pub fn ipv4(column: &str, operator: FilterOperator, value: Option<Ipv4Addr>) -> Self {
+        FilterCondition::IPv4Value {
+            column: column.to_string(),
+            operator,
+            value,
+        }
+    }
+
+    pub fn ipv6(column: &str, operator: FilterOperator, value: Option<Ipv6Addr>) -> Self {
+        FilterCondition::IPv6Value {
+            column: column.to_string(),
+            operator,
+            value,
+        }
+    }
+
     // JSON type
     pub fn json(
         column: &str,
@@ -1002,6 +2464,23 @@ impl FilterCondition {
         }
     }
 
+    // Numeric JSON path comparison, e.g. `JSONExtractFloat(metadata, 'score') >= 0.8`
+    pub fn json_numeric(
+        column: &str,
+        path: &str,
+        operator: FilterOperator,
+        value: &str,
+        number_type: JsonNumberType,
+    ) -> Self {
+        FilterCondition::JSONNumeric {
+            column: column.to_string(),
+            path: path.to_string(),
+            operator,
+            value: value.to_string(),
+            number_type,
+        }
+    }
+
     // Array contains (checks if array contains ALL specified values)
     pub fn array_contains(column: &str, values: &str) -> Self {
         FilterCondition::ArrayContains {
@@ -1020,6 +2499,98 @@ impl FilterCondition {
         }
     }
 
+    // Typed array condition (has, hasAll or hasAny depending on the operator)
+    pub fn array_values(
+        column: &str,
+        operator: FilterOperator,
+        values: Vec<String>,
+        element_type: ArrayElementType,
+    ) -> Self {
+        FilterCondition::ArrayValues {
+            column: column.to_string(),
+            operator,
+            values,
+            element_type,
+        }
+    }
+
+    // Comparison on a single array position, e.g. `tags[1] = 'primary'`
+    pub fn array_element(
+        column: &str,
+        index: i64,
+        operator: FilterOperator,
+        value: &str,
+        element_type: ArrayElementType,
+    ) -> Self {
+        FilterCondition::ArrayElement {
+            column: column.to_string(),
+            index,
+            operator,
+            value: value.to_string(),
+            element_type,
+        }
+    }
+
+    // Count of a value inside an array, e.g. `countEqual(tags, 'error') >= 3`
+    pub fn array_count(
+        column: &str,
+        value: &str,
+        operator: FilterOperator,
+        count: u64,
+        element_type: ArrayElementType,
+    ) -> Self {
+        FilterCondition::ArrayCount {
+            column: column.to_string(),
+            value: value.to_string(),
+            operator,
+            count,
+            element_type,
+        }
+    }
+
+    // At least `min_overlap` shared elements, e.g. `length(arrayIntersect(tags, ['a', 'b'])) >= 2`
+    pub fn array_overlap(
+        column: &str,
+        values: Vec<String>,
+        min_overlap: u64,
+        element_type: ArrayElementType,
+    ) -> Self {
+        FilterCondition::ArrayOverlap {
+            column: column.to_string(),
+            values,
+            min_overlap,
+            element_type,
+        }
+    }
+
+    // Key match in an array of JSON documents, e.g.
+    // `arrayExists(x -> JSONExtractString(x, 'type') = 'click', events)`
+    pub fn array_json(column: &str, key: &str, value: &str) -> Self {
+        FilterCondition::ArrayJSONValue {
+            column: column.to_string(),
+            operator: FilterOperator::ArrayJsonHas,
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    // Comparison on a map key's value, e.g. `toInt64OrNull(attrs['retries']) > 3`
+    pub fn map_value(
+        column: &str,
+        key: &str,
+        operator: FilterOperator,
+        value: Option<&str>,
+        value_type: MapValueType,
+    ) -> Self {
+        FilterCondition::MapValue {
+            column: column.to_string(),
+            key: key.to_string(),
+            operator,
+            value: value.map(|v| v.to_string()),
+            value_type,
+        }
+    }
+
     // Date range helpers
 
     pub fn date_exact(column: &str, timestamp: &str) -> Self {
@@ -1046,6 +2617,27 @@ impl FilterCondition {
         }
     }
 
+    /// Condition on a numeric column lying within `range`
+    ///
+    /// Renders a single comparison when one bound is open, `BETWEEN` when both
+    /// are inclusive, `=` when they are also equal, and two comparisons otherwise.
+    /// A range with no bounds, or with a NaN or infinite bound, fails to render.
+    pub fn numeric_range<T: RangeValue>(column: &str, range: NumericRange<T>) -> Self {
+        FilterCondition::NumericRange {
+            column: column.to_string(),
+            range: NumericRange {
+                min: range.min.map(|v| v.to_string()),
+                max: range.max.map(|v| v.to_string()),
+                inclusive_min: range.inclusive_min,
+                inclusive_max: range.inclusive_max,
+            },
+        }
+    }
+
+    /// Rows after a relative date expression such as `now() - INTERVAL 7 DAY`
+    ///
+    /// The expression is rendered as is, so it must not come from untrusted input.
+    /// JSON filters only accept plain offsets from `now()`, `today()` or `yesterday()`.
     pub fn relative_date(column: &str, expr: &str) -> Self {
         FilterCondition::DateRange {
             column: column.to_string(),
@@ -1053,6 +2645,20 @@ impl FilterCondition {
         }
     }
 
+    /// Rows in the `toStartOfInterval` bucket starting at `start`
+    ///
+    /// `interval` such as `5 MINUTE` is rendered as is, so it must not come from
+    /// untrusted input. JSON filters only accept a count and a unit.
+    pub fn date_bucket(column: &str, interval: &str, start: &str) -> Self {
+        FilterCondition::DateRange {
+            column: column.to_string(),
+            range_type: DateRangeType::Bucket {
+                interval: interval.to_string(),
+                start: start.to_string(),
+            },
+        }
+    }
+
     // IN values with type information
     pub fn in_values(
         column: &str,
@@ -1068,6 +2674,17 @@ impl FilterCondition {
         }
     }
 
+    /// IN or NOT IN against an external data table sent with the query
+    ///
+    /// See `ExternalTable` for describing the table to the server.
+    pub fn in_external(column: &str, operator: FilterOperator, table: &str) -> Self {
+        FilterCondition::InExternal {
+            column: column.to_string(),
+            operator,
+            table: table.to_string(),
+        }
+    }
+
     // More constructors will be added for other types
 }
 
@@ -1081,10 +2698,13 @@ impl fmt::Display for FilterCondition {
 }
 
 /// Filter builder for creating complex filter expressions
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FilterBuilder {
     pub root: Option<FilterExpression>,
     pub case_insensitive: bool,
+    /// Per-column case sensitivity, overriding `case_insensitive`
+    pub case_overrides: Arc<HashMap<&'static str, bool>>,
+    pub hooks: BuildHooks,
 }
 
 impl FilterBuilder {
@@ -1092,6 +2712,8 @@ impl FilterBuilder {
         Self {
             root: None,
             case_insensitive: false,
+            case_overrides: Arc::default(),
+            hooks: BuildHooks::default(),
         }
     }
 
@@ -1100,72 +2722,115 @@ impl FilterBuilder {
         self
     }
 
+    /// Use the per-column case sensitivity declared on a column registry
+    pub fn case_overrides(mut self, column_defs: &crate::ColumnRegistry) -> Self {
+        self.case_overrides = column_defs.case_overrides();
+        self
+    }
+
     pub fn add_condition(self, condition: FilterCondition) -> Self {
         self.add_expression(FilterExpression::Condition(condition))
     }
 
+    /// Add an expression to the builder, ANDing it with any existing root
+    ///
+    /// When the root is already an AND group the expression is appended to it
+    /// rather than wrapping the whole tree in a new group, so repeated calls
+    /// stay linear and produce a flat condition list.
     pub fn add_expression(mut self, expression: FilterExpression) -> Self {
-        match &self.root {
-            None => {
-                self.root = Some(expression);
-            }
-            Some(existing) => {
-                self.root = Some(FilterExpression::Group {
+        self.root = Some(match self.root.take() {
+            None => expression,
+            Some(FilterExpression::Group {
+                operator: LogicalOperator::And,
+                mut expressions,
+            }) => {
+                expressions.push(expression);
+                FilterExpression::Group {
                     operator: LogicalOperator::And,
-                    expressions: vec![existing.clone(), expression],
-                });
+                    expressions,
+                }
             }
-        }
+            Some(existing) => FilterExpression::and(vec![existing, expression]),
+        });
         self
     }
 
-    pub fn group(mut self, operator: LogicalOperator, expressions: Vec<FilterExpression>) -> Self {
-        let group = FilterExpression::Group {
+    pub fn group(self, operator: LogicalOperator, expressions: Vec<FilterExpression>) -> Self {
+        self.add_expression(FilterExpression::Group {
             operator,
             expressions,
-        };
+        })
+    }
+
+    /// Register a hook that observes or rewrites each condition at build time
+    ///
+    /// The hook receives every leaf condition and returns the expression to
+    /// render in its place. Returning an error aborts the build.
+    pub fn on_condition<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(FilterCondition) -> Result<FilterExpression> + Send + Sync + 'static,
+    {
+        self.hooks.on_condition(hook);
+        self
+    }
+
+    /// Register a hook that observes or rewrites the whole expression at build time
+    ///
+    /// Build hooks run after all condition hooks.
+    pub fn on_build<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(FilterExpression) -> Result<FilterExpression> + Send + Sync + 'static,
+    {
+        self.hooks.on_build(hook);
+        self
+    }
+
+    /// The root expression after all hooks have been applied
+    pub fn resolved_root(&self) -> Result<Option<FilterExpression>> {
         match &self.root {
-            None => {
-                self.root = Some(group);
-            }
-            Some(existing) => {
-                self.root = Some(FilterExpression::Group {
-                    operator: LogicalOperator::And,
-                    expressions: vec![existing.clone(), group],
-                });
-            }
+            Some(root) if !self.hooks.is_empty() => self.hooks.apply(root.clone()).map(Some),
+            root => Ok(root.clone()),
         }
-        self
     }
 
     /// Create a FilterBuilder from JSON filters
+    ///
+    /// Filters are combined according to the registry's `SameColumnPolicy`.
     pub fn from_json_filters(
         filters: &[JsonFilter],
         case_insensitive: bool,
-        column_defs: &std::collections::HashMap<&'static str, crate::ColumnDef>,
+        column_defs: &crate::ColumnRegistry,
+    ) -> Result<Self> {
+        Self::from_json_filters_with(filters, case_insensitive, column_defs, &|filter| {
+            json_filter_condition(filter, column_defs)
+        })
+    }
+
+    // `from_json_filters` with the condition for each filter built by `condition`
+    pub(crate) fn from_json_filters_with(
+        filters: &[JsonFilter],
+        case_insensitive: bool,
+        column_defs: &crate::ColumnRegistry,
+        condition: &dyn Fn(&JsonFilter) -> Result<FilterCondition>,
     ) -> Result<Self> {
         use LogicalOperator::{And, Or};
 
+        let mut builder = Self::new()
+            .case_insensitive(case_insensitive)
+            .case_overrides(column_defs);
         if filters.is_empty() {
-            return Ok(Self::new().case_insensitive(case_insensitive));
+            return Ok(builder);
+        }
+
+        if column_defs.same_column_policy() == SameColumnPolicy::OrWithinColumn {
+            return Ok(builder.add_expression(facet_expression(filters, condition)?));
         }
 
-        let mut builder = Self::new().case_insensitive(case_insensitive);
         let mut current_group: Option<(LogicalOperator, Vec<FilterExpression>)> = None;
         let mut last_connector: Option<LogicalOperator> = None;
 
         for filter in filters {
-            // Get column definition
-            let column_def = column_defs
-                .get(filter.n.as_str())
-                .ok_or_else(|| eyre::eyre!("Column not found: {}", filter.n))?;
-
-            // Parse operator
-            let operator = &filter.f;
-
-            // Create the condition from column definition
-            let condition = column_def.to_filter_condition(operator, &filter.v)?;
-            let expression = FilterExpression::Condition(condition);
+            let expression = FilterExpression::Condition(condition(filter)?);
 
             // Handle connector logic
             match &filter.c {
@@ -1190,7 +2855,7 @@ impl FilterBuilder {
                                 // Finish current group and start new one
                                 let group = FilterExpression::Group {
                                     operator: *current_op,
-                                    expressions: expressions.clone(),
+                                    expressions: std::mem::take(expressions),
                                 };
 
                                 // Add the group to the builder
@@ -1227,8 +2892,8 @@ impl FilterBuilder {
                     expressions,
                 };
                 builder = builder.add_expression(group);
-            } else if let Some(expr) = expressions.first() {
-                builder = builder.add_expression(expr.clone());
+            } else if let Some(expr) = expressions.into_iter().next() {
+                builder = builder.add_expression(expr);
             }
         }
 
@@ -1236,45 +2901,208 @@ impl FilterBuilder {
     }
 
     pub fn build(&self) -> Result<String> {
-        match &self.root {
-            None => Ok(String::new()),
-            Some(expression) => {
-                let sql = expression.to_sql(self.case_insensitive)?;
-                if sql.is_empty() {
-                    Ok(String::new())
-                } else {
-                    Ok(format!(" WHERE {}", sql))
+        let mut sql = String::new();
+        self.write_sql(&mut sql)?;
+        Ok(sql)
+    }
+
+    /// Build the boolean expression alone, without the leading ` WHERE `
+    ///
+    /// For embedding the filters in JOIN ON or HAVING clauses, or in hand-written
+    /// queries. Empty when there are no filters.
+    pub fn build_fragment(&self) -> Result<String> {
+        let mut sql = String::new();
+        self.write_fragment(&mut sql)?;
+        Ok(sql)
+    }
+
+    /// Write the WHERE clause (with a leading space) into an existing buffer
+    ///
+    /// Hooks are applied first. Nothing is written when there is no root
+    /// expression or it renders empty.
+    pub fn write_sql(&self, out: &mut String) -> Result<()> {
+        const PREFIX: &str = " WHERE ";
+        let start = out.len();
+        out.push_str(PREFIX);
+        self.write_fragment(out)?;
+        if out.len() == start + PREFIX.len() {
+            out.truncate(start);
+        }
+        Ok(())
+    }
+
+    /// Write the boolean expression, without ` WHERE `, into an existing buffer
+    pub fn write_fragment(&self, out: &mut String) -> Result<()> {
+        let hooked;
+        let root = if self.hooks.is_empty() {
+            self.root.as_ref()
+        } else {
+            hooked = self.resolved_root()?;
+            hooked.as_ref()
+        };
+
+        if let Some(expression) = root {
+            out.reserve(expression.estimated_sql_len());
+            expression.write_sql_with(out, self.case_insensitive, &self.case_overrides)?;
+        }
+        Ok(())
+    }
+}
+
+// Build the condition for a single JSON filter, allowing `column[index]` for a
+// single array position and `column['key']` for a map key
+pub(crate) fn json_filter_condition(
+    filter: &JsonFilter,
+    column_defs: &crate::ColumnRegistry,
+) -> Result<FilterCondition> {
+    let condition = json_filter_condition_unlabeled(filter, column_defs);
+    // Name labeled columns the way end users know them, keeping the original
+    // error as the source
+    let column = split_subscript(&filter.n).map_or(filter.n.as_str(), |(name, _)| name);
+    match (condition, column_defs.label(column)) {
+        (Err(err), Some(label)) => {
+            let message = format!("{}: {}", label, err);
+            Err(err.wrap_err(message))
+        }
+        (condition, _) => condition,
+    }
+}
+
+fn json_filter_condition_unlabeled(
+    filter: &JsonFilter,
+    column_defs: &crate::ColumnRegistry,
+) -> Result<FilterCondition> {
+    let operator = &filter.f;
+    let condition = match column_defs.get(filter.n.as_str()) {
+        Some(_) if column_defs.custom_operator(operator).is_some() => {
+            column_defs.to_filter_condition(&filter.n, operator, &filter.v)?
+        }
+        Some(column_def @ crate::ColumnDef::JSON(name)) => {
+            // Declared path types take precedence over inferring from the value
+            let declared = crate::split_json_path(&filter.v).and_then(|(path, value)| {
+                column_defs
+                    .json_path_type(name, path)
+                    .map(|number_type| (path, value, number_type))
+            });
+            match declared {
+                Some((path, value, number_type)) => column_def.to_json_numeric_condition(
+                    path,
+                    operator,
+                    value,
+                    Some(number_type),
+                )?,
+                None => column_def.to_filter_condition(operator, &filter.v)?,
+            }
+        }
+        Some(_) => column_defs.to_filter_condition(&filter.n, operator, &filter.v)?,
+        None => match split_subscript(&filter.n) {
+            Some((name, subscript)) => {
+                let column_def = column_defs
+                    .get(name)
+                    .ok_or_else(|| eyre::eyre!("Column not found: {}", name))?;
+                match column_def {
+                    crate::ColumnDef::Map(_) => {
+                        let key = subscript.trim_matches(|c| c == '\'' || c == '"');
+                        column_def.to_map_condition(key, operator, &filter.v, None)?
+                    }
+                    _ => {
+                        let index = subscript
+                            .parse()
+                            .map_err(|_| eyre::eyre!("Invalid array index: {}", subscript))?;
+                        column_def.to_array_element_condition(index, operator, &filter.v)?
+                    }
                 }
             }
+            None => return Err(eyre::eyre!("Column not found: {}", filter.n)),
+        },
+    };
+    Ok(condition)
+}
+
+// Combine JSON filters with faceted-search semantics: equality-like filters on the
+// same facet are ORed, everything else is ANDed, in order of first appearance
+fn facet_expression(
+    filters: &[JsonFilter],
+    json_condition: &dyn Fn(&JsonFilter) -> Result<FilterCondition>,
+) -> Result<FilterExpression> {
+    type Facet = (String, Option<String>);
+    let mut columns: Vec<(Facet, Vec<FilterExpression>, Vec<FilterExpression>)> = Vec::new();
+    for filter in filters {
+        let condition = json_condition(filter)?;
+        let any_of = matches!(
+            condition.operator(),
+            Some(
+                FilterOperator::Equal
+                    | FilterOperator::In
+                    | FilterOperator::Like
+                    | FilterOperator::StartsWith
+                    | FilterOperator::EndsWith
+                    | FilterOperator::Contains
+                    | FilterOperator::MultiMatchAny
+                    | FilterOperator::MultiSearchAny
+                    | FilterOperator::ArrayHas
+                    | FilterOperator::ArrayAny
+                    | FilterOperator::DateEqual
+            )
+        );
+
+        let facet = facet(&condition);
+        let index = match columns.iter().position(|(other, ..)| *other == facet) {
+            Some(index) => index,
+            None => {
+                columns.push((facet, vec![], vec![]));
+                columns.len() - 1
+            }
+        };
+        let (_, or_terms, and_terms) = &mut columns[index];
+        if any_of {
+            or_terms.push(FilterExpression::Condition(condition));
+        } else {
+            and_terms.push(FilterExpression::Condition(condition));
         }
     }
-}
 
-// Helper function for operator parsing
-pub fn parse_operator(op: &str) -> FilterOperator {
-    match op.to_uppercase().as_str() {
-        "LIKE" => FilterOperator::Like,
-        "=" => FilterOperator::Equal,
-        "!=" => FilterOperator::NotEqual,
-        ">" => FilterOperator::GreaterThan,
-        ">=" => FilterOperator::GreaterThanOrEqual,
-        "<" => FilterOperator::LessThan,
-        "<=" => FilterOperator::LessThanOrEqual,
-        "IN" => FilterOperator::In,
-        "NOT IN" => FilterOperator::NotIn,
-        "IS NULL" => FilterOperator::IsNull,
-        "IS NOT NULL" => FilterOperator::IsNotNull,
-        "STARTS WITH" => FilterOperator::StartsWith,
-        "ENDS WITH" => FilterOperator::EndsWith,
-        "ARRAY CONTAINS" => FilterOperator::ArrayContains,
-        "ARRAY HAS" => FilterOperator::ArrayHas,
-        "ARRAY ALL" => FilterOperator::ArrayAll,
-        "ARRAY ANY" => FilterOperator::ArrayAny,
-        "DATE_ONLY" => FilterOperator::DateEqual,
-        "DATE_RANGE" => FilterOperator::DateRange,
-        "RELATIVE" => FilterOperator::RelativeDate,
-        _ => FilterOperator::Equal,
+    let mut terms = Vec::with_capacity(columns.len());
+    for (_, mut or_terms, and_terms) in columns {
+        match or_terms.len() {
+            0 => {}
+            1 => terms.extend(or_terms.pop()),
+            _ => terms.push(FilterExpression::or(or_terms)),
+        }
+        terms.extend(and_terms);
     }
+    Ok(match terms.len() {
+        1 => terms.remove(0),
+        _ => FilterExpression::and(terms),
+    })
+}
+
+// The facet a condition filters: its column, plus the JSON path or map key it
+// reads, since each path or key of a column is a facet of its own
+fn facet(condition: &FilterCondition) -> (String, Option<String>) {
+    let subkey = match condition {
+        FilterCondition::MapValue { key, .. } | FilterCondition::ArrayJSONValue { key, .. } => {
+            Some(key.clone())
+        }
+        FilterCondition::JSONNumeric { path, .. } => Some(path.clone()),
+        FilterCondition::JSONValue { path, .. } => path.clone(),
+        _ => None,
+    };
+    (condition.column().to_string(), subkey)
+}
+
+// Split `column[index]` or `column['key']` into the column name and subscript
+fn split_subscript(name: &str) -> Option<(&str, &str)> {
+    let (column, rest) = name.split_once('[')?;
+    let subscript = rest.strip_suffix(']')?.trim();
+    Some((column.trim(), subscript))
+}
+
+/// Parse a JSON filter operator name, failing on unknown names
+///
+/// Shorthand for `op.parse::<JsonOperator>()` converted to a `FilterOperator`.
+pub fn parse_operator(op: &str) -> Result<FilterOperator> {
+    op.parse::<JsonOperator>().map(FilterOperator::from)
 }
 
 impl Default for FilterBuilder {
@@ -1282,3 +3110,42 @@ impl Default for FilterBuilder {
         Self::new()
     }
 }
+
+// The serialized form of a builder: its tree with hooks already applied
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SerializedBuilder {
+    root: Option<FilterExpression>,
+    case_insensitive: bool,
+}
+
+/// Serializes `resolved_root()`, so hooks are applied first and not needed
+/// again after deserializing. Per-column case overrides are not serialized;
+/// `ClickHouseFilters::with_column_defs` restores them from the registry.
+#[cfg(feature = "serde")]
+impl Serialize for FilterBuilder {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        SerializedBuilder {
+            root: self.resolved_root().map_err(serde::ser::Error::custom)?,
+            case_insensitive: self.case_insensitive,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for FilterBuilder {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let builder = SerializedBuilder::deserialize(deserializer)?;
+        Ok(FilterBuilder {
+            root: builder.root,
+            case_insensitive: builder.case_insensitive,
+            ..FilterBuilder::new()
+        })
+    }
+}
@@ -0,0 +1,536 @@
+//! Query fingerprinting
+//!
+//! Fingerprints are stable 64-bit hashes of a filter set, suitable for keying result
+//! caches and aggregating query metrics. Two hashes are produced: a `shape` hash that
+//! ignores literal values, so `age > 25` and `age > 40` share a shape, and a `full`
+//! hash that includes them.
+//!
+//! The hash is FNV-1a rather than `std`'s `DefaultHasher`, so fingerprints stay the
+//! same across processes and Rust releases.
+//!
+//! `FilterExpression`, `FilterBuilder` and `ClickHouseFilters` also implement `Eq`
+//! and `Hash`, for keying in-process maps. Unlike fingerprints, these hashes are
+//! not stable across processes.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterBuilder, FilterCondition, FilterOperator};
+//!
+//! let young = FilterBuilder::new()
+//!     .add_condition(FilterCondition::uint32("age", FilterOperator::LessThan, Some(25)));
+//! let old = FilterBuilder::new()
+//!     .add_condition(FilterCondition::uint32("age", FilterOperator::LessThan, Some(65)));
+//!
+//! assert_eq!(young.fingerprint().shape, old.fingerprint().shape);
+//! assert_ne!(young.fingerprint().full, old.fingerprint().full);
+//! ```
+
+use crate::filtering::{FilterBuilder, FilterCondition, FilterExpression};
+use crate::ClickHouseFilters;
+use std::fmt;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Stable hashes of a filter set's structure and values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fingerprint {
+    /// Hash of the structure only, with literal values stripped
+    pub shape: u64,
+    /// Hash of the structure including literal values
+    pub full: u64,
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}-{:016x}", self.shape, self.full)
+    }
+}
+
+/// 64-bit FNV-1a hasher that can be written to with `write!`
+struct Fnv64(u64);
+
+impl Fnv64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    fn new() -> Self {
+        Fnv64(Self::OFFSET_BASIS)
+    }
+}
+
+impl fmt::Write for Fnv64 {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+        Ok(())
+    }
+}
+
+/// Hashers for both fingerprints, fed in a single pass
+struct FingerprintWriter {
+    shape: Fnv64,
+    full: Fnv64,
+}
+
+impl FingerprintWriter {
+    fn new() -> Self {
+        FingerprintWriter {
+            shape: Fnv64::new(),
+            full: Fnv64::new(),
+        }
+    }
+
+    // Write structural information to both hashes
+    fn structure(&mut self, args: fmt::Arguments<'_>) {
+        let _ = self.shape.write_fmt(args);
+        let _ = self.full.write_fmt(args);
+    }
+
+    // Write literal values to the full hash only
+    fn values(&mut self, args: fmt::Arguments<'_>) {
+        let _ = self.full.write_fmt(args);
+    }
+
+    fn expression(&mut self, expression: &FilterExpression) {
+        match expression {
+            FilterExpression::Condition(condition) => self.condition(condition),
+            // Only the columns of a custom condition are known to be shape
+            FilterExpression::Custom(condition) => {
+                self.structure(format_args!("Custom{:?}", condition.columns()));
+                self.values(format_args!(" {:?}", condition));
+            }
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } => {
+                self.structure(format_args!("{}(", operator));
+                for expr in expressions {
+                    self.expression(expr);
+                    self.structure(format_args!(";"));
+                }
+                self.structure(format_args!(")"));
+            }
+        }
+    }
+
+    fn condition(&mut self, condition: &FilterCondition) {
+        self.structure(format_args!("{}[{}", condition.kind(), condition.column()));
+        if let Some(operator) = condition.operator() {
+            self.structure(format_args!(" {:?}", operator));
+        }
+        // JSON paths and IN list types are part of the shape, not values
+        match condition {
+            FilterCondition::JSONValue { path, .. } => {
+                self.structure(format_args!(" {:?}", path));
+            }
+            FilterCondition::JSONNumeric {
+                path, number_type, ..
+            } => {
+                self.structure(format_args!(" {:?} {:?}", path, number_type));
+            }
+            FilterCondition::InValues { column_type, .. }
+            | FilterCondition::ArrayJoinIn { column_type, .. } => {
+                self.structure(format_args!(" {:?}", column_type));
+            }
+            FilterCondition::InExternal { table, .. } => {
+                self.structure(format_args!(" {}", table));
+            }
+            FilterCondition::IndexedText { index, .. } => {
+                self.structure(format_args!(" {:?}", index));
+            }
+            // Which bounds are set, and whether they are inclusive, decides the SQL
+            FilterCondition::NumericRange { range, .. } => {
+                self.structure(format_args!(
+                    " {}{} {}{}",
+                    range.min.is_some(),
+                    range.inclusive_min,
+                    range.max.is_some(),
+                    range.inclusive_max
+                ));
+            }
+            _ => {}
+        }
+        self.values(format_args!(" {:?}", condition));
+        self.structure(format_args!("]"));
+    }
+
+    fn builder(&mut self, builder: &FilterBuilder) {
+        self.structure(format_args!("ci={};", builder.case_insensitive));
+        if !builder.case_overrides.is_empty() {
+            let mut overrides: Vec<_> = builder.case_overrides.iter().collect();
+            overrides.sort_unstable();
+            self.structure(format_args!("case={:?};", overrides));
+        }
+        if let Some(root) = &builder.root {
+            self.expression(root);
+        }
+    }
+
+    fn finish(self) -> Fingerprint {
+        Fingerprint {
+            shape: self.shape.0,
+            full: self.full.0,
+        }
+    }
+}
+
+impl FilterExpression {
+    /// Fingerprint of this expression's structure and values
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut writer = FingerprintWriter::new();
+        writer.expression(self);
+        writer.finish()
+    }
+}
+
+impl FilterBuilder {
+    /// Fingerprint of the filters in this builder
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut writer = FingerprintWriter::new();
+        writer.builder(self);
+        writer.finish()
+    }
+}
+
+impl ClickHouseFilters {
+    /// Fingerprint of the filters, sorting and pagination
+    ///
+    /// The shape includes sort columns and page size; the full hash also
+    /// includes filter values and the current page.
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut writer = FingerprintWriter::new();
+
+        writer.structure(format_args!("filters:"));
+        if let Some(filters) = &self.filters {
+            writer.builder(filters);
+        }
+        if let Some(policy) = &self.row_policy {
+            writer.structure(format_args!("|policy:"));
+            writer.expression(policy);
+        }
+
+        if !self.include_deleted {
+            if let Some(column) = self.column_defs.soft_delete_column() {
+                writer.structure(format_args!("|live:{}", column));
+            }
+        }
+
+        if let Some(column) = self.column_defs.sign_column() {
+            writer.structure(format_args!("|sign:{}", column));
+        }
+        if let Some(deduplication) = self.column_defs.deduplication() {
+            writer.structure(format_args!("|dedup:{:?}", deduplication));
+        }
+
+        writer.structure(format_args!("|sort:"));
+        if let Some(sorting) = &self.sorting {
+            for column in &sorting.columns {
+                writer.structure(format_args!("{} {:?};", column.expression(), column.order));
+            }
+        }
+
+        writer.structure(format_args!("|page:"));
+        if let Some(paginate) = &self.pagination {
+            writer.structure(format_args!("{}", paginate.pagination.per_page));
+            writer.values(format_args!(" {}", paginate.pagination.current_page));
+        }
+
+        writer.finish()
+    }
+}
+
+/// JSON filters reject NaN and infinite float values, so only a condition built
+/// by hand can hold NaN, and such a condition is not equal to itself.
+impl Eq for FilterCondition {}
+
+impl Hash for FilterCondition {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            FilterCondition::StringValue {
+                column,
+                operator,
+                value,
+            }
+            | FilterCondition::FixedStringValue {
+                column,
+                operator,
+                value,
+            }
+            | FilterCondition::DateValue {
+                column,
+                operator,
+                value,
+            }
+            | FilterCondition::DateTimeValue {
+                column,
+                operator,
+                value,
+            }
+            | FilterCondition::DateTime64Value {
+                column,
+                operator,
+                value,
+            }
+            | FilterCondition::UUIDValue {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::IndexedText {
+                column,
+                operator,
+                value,
+                index,
+            } => (column, operator, value, index).hash(state),
+            FilterCondition::UInt8Value {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::UInt16Value {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::UInt32Value {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::UInt64Value {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::Int8Value {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::Int16Value {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::Int32Value {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::Int64Value {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            // -0.0 == 0.0, so both have to hash the same
+            FilterCondition::Float32Value {
+                column,
+                operator,
+                value,
+            } => {
+                let bits = value.map(|v| if v == 0.0 { 0 } else { v.to_bits() });
+                (column, operator, bits).hash(state)
+            }
+            FilterCondition::Float64Value {
+                column,
+                operator,
+                value,
+            } => {
+                let bits = value.map(|v| if v == 0.0 { 0 } else { v.to_bits() });
+                (column, operator, bits).hash(state)
+            }
+            FilterCondition::DateRange { column, range_type } => (column, range_type).hash(state),
+            FilterCondition::NumericRange { column, range } => (
+                column,
+                &range.min,
+                &range.max,
+                range.inclusive_min,
+                range.inclusive_max,
+            )
+                .hash(state),
+            FilterCondition::BooleanValue {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::IPv4Value {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::IPv6Value {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::InValues {
+                column,
+                operator,
+                values,
+                column_type,
+            }
+            | FilterCondition::ArrayJoinIn {
+                column,
+                operator,
+                values,
+                column_type,
+            } => (column, operator, values, column_type).hash(state),
+            FilterCondition::InExternal {
+                column,
+                operator,
+                table,
+            } => (column, operator, table).hash(state),
+            FilterCondition::ArrayContains {
+                column,
+                operator,
+                value,
+            }
+            | FilterCondition::ArrayHas {
+                column,
+                operator,
+                value,
+            } => (column, operator, value).hash(state),
+            FilterCondition::ArrayValues {
+                column,
+                operator,
+                values,
+                element_type,
+            } => (column, operator, values, element_type).hash(state),
+            FilterCondition::ArrayElement {
+                column,
+                index,
+                operator,
+                value,
+                element_type,
+            } => (column, index, operator, value, element_type).hash(state),
+            FilterCondition::ArrayCount {
+                column,
+                value,
+                operator,
+                count,
+                element_type,
+            } => (column, value, operator, count, element_type).hash(state),
+            FilterCondition::ArrayOverlap {
+                column,
+                values,
+                min_overlap,
+                element_type,
+            } => (column, values, min_overlap, element_type).hash(state),
+            FilterCondition::ArrayJSONValue {
+                column,
+                operator,
+                key,
+                value,
+            } => (column, operator, key, value).hash(state),
+            FilterCondition::MapValue {
+                column,
+                key,
+                operator,
+                value,
+                value_type,
+            } => (column, key, operator, value, value_type).hash(state),
+            FilterCondition::JSONNumeric {
+                column,
+                path,
+                operator,
+                value,
+                number_type,
+            } => (column, path, operator, value, number_type).hash(state),
+            FilterCondition::JSONValue {
+                column,
+                operator,
+                value,
+                path,
+            } => (column, operator, value, path).hash(state),
+            // Renderers are equal when they are the same instance
+            FilterCondition::Custom {
+                column,
+                operator,
+                value,
+                renderer,
+            } => {
+                let renderer = Arc::as_ptr(&renderer.0) as *const () as usize;
+                (column, operator, value, renderer).hash(state)
+            }
+        }
+    }
+}
+
+impl Eq for FilterExpression {}
+
+impl Hash for FilterExpression {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            FilterExpression::Condition(condition) => {
+                state.write_u8(0);
+                condition.hash(state);
+            }
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } => {
+                state.write_u8(1);
+                operator.hash(state);
+                expressions.hash(state);
+            }
+            // Custom conditions are equal when they are the same instance
+            FilterExpression::Custom(condition) => {
+                state.write_u8(2);
+                (Arc::as_ptr(condition) as *const () as usize).hash(state);
+            }
+        }
+    }
+}
+
+impl Hash for FilterBuilder {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.root.hash(state);
+        self.case_insensitive.hash(state);
+        let mut overrides: Vec<_> = self.case_overrides.iter().collect();
+        overrides.sort_unstable();
+        overrides.hash(state);
+        self.hooks.hash(state);
+    }
+}
+
+/// Compares the request: filters, sorting, pagination and query options, and
+/// the column definitions, which change the SQL too.
+///
+/// Registries are compared by identity: instances are only equal when built
+/// against clones of the same `ColumnRegistry`, e.g. one shared by the service.
+impl PartialEq for ClickHouseFilters {
+    fn eq(&self, other: &Self) -> bool {
+        self.column_defs.same_as(&other.column_defs)
+            && self.pagination == other.pagination
+            && self.sorting == other.sorting
+            && self.filters == other.filters
+            && self.row_policy == other.row_policy
+            && self.include_deleted == other.include_deleted
+            && self.read_limit == other.read_limit
+            && self.query_tag == other.query_tag
+            && self.settings == other.settings
+            && self.allow_mutations == other.allow_mutations
+    }
+}
+
+impl Eq for ClickHouseFilters {}
+
+impl Hash for ClickHouseFilters {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pagination.hash(state);
+        self.sorting.hash(state);
+        self.filters.hash(state);
+        self.row_policy.hash(state);
+        self.include_deleted.hash(state);
+        self.read_limit.hash(state);
+        self.query_tag.hash(state);
+        self.settings.hash(state);
+        self.allow_mutations.hash(state);
+        self.column_defs.hash_identity(state);
+    }
+}
@@ -0,0 +1,129 @@
+//! Build hooks for filter generation
+//!
+//! Hooks let applications observe or rewrite a filter tree while SQL is built, without
+//! forking the render logic. `on_condition` hooks run for every leaf condition and may
+//! replace it with any expression (or reject it with an error); `on_build` hooks run
+//! once on the whole tree afterwards, e.g. to AND in a tenant predicate.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterBuilder, FilterCondition, FilterExpression, FilterOperator};
+//!
+//! let builder = FilterBuilder::new()
+//!     .add_condition(FilterCondition::string("name", FilterOperator::Equal, Some("John")))
+//!     .on_condition(|condition| {
+//!         if condition.column() == "password" {
+//!             return Err(eyre::eyre!("Filtering on password is not allowed"));
+//!         }
+//!         Ok(FilterExpression::Condition(condition))
+//!     })
+//!     .on_build(|root| {
+//!         let tenant = FilterCondition::uint32("tenant_id", FilterOperator::Equal, Some(7));
+//!         Ok(FilterExpression::and(vec![root, FilterExpression::Condition(tenant)]))
+//!     });
+//!
+//! assert_eq!(
+//!     builder.build().unwrap(),
+//!     " WHERE (name = 'John' AND tenant_id = 7)"
+//! );
+//! ```
+
+use crate::filtering::{FilterCondition, FilterExpression};
+use eyre::Result;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Hook called for each condition; returns the expression to render in its place
+pub type ConditionHook = dyn Fn(FilterCondition) -> Result<FilterExpression> + Send + Sync;
+
+/// Hook called once with the full expression tree before it is rendered
+pub type BuildHook = dyn Fn(FilterExpression) -> Result<FilterExpression> + Send + Sync;
+
+/// Ordered set of build hooks attached to a `FilterBuilder`
+///
+/// Hooks are stored behind `Arc`s, so cloning a builder shares its hooks.
+#[derive(Clone, Default)]
+pub struct BuildHooks {
+    condition: Vec<Arc<ConditionHook>>,
+    build: Vec<Arc<BuildHook>>,
+}
+
+impl BuildHooks {
+    /// Register a condition hook; hooks run in registration order
+    pub fn on_condition<F>(&mut self, hook: F)
+    where
+        F: Fn(FilterCondition) -> Result<FilterExpression> + Send + Sync + 'static,
+    {
+        self.condition.push(Arc::new(hook));
+    }
+
+    /// Register a build hook; hooks run in registration order
+    pub fn on_build<F>(&mut self, hook: F)
+    where
+        F: Fn(FilterExpression) -> Result<FilterExpression> + Send + Sync + 'static,
+    {
+        self.build.push(Arc::new(hook));
+    }
+
+    /// Append all hooks from another set, after the existing ones
+    pub fn extend(&mut self, other: &BuildHooks) {
+        self.condition.extend(other.condition.iter().cloned());
+        self.build.extend(other.build.iter().cloned());
+    }
+
+    /// Check whether no hooks are registered
+    pub fn is_empty(&self) -> bool {
+        self.condition.is_empty() && self.build.is_empty()
+    }
+
+    /// Run all hooks over an expression tree
+    ///
+    /// Each condition hook is applied to every condition produced by the hook
+    /// before it, then the build hooks are applied to the resulting tree.
+    pub fn apply(&self, expression: FilterExpression) -> Result<FilterExpression> {
+        let mut expression = expression;
+        for hook in &self.condition {
+            expression = expression.try_map(|condition| hook(condition))?;
+        }
+        for hook in &self.build {
+            expression = hook(expression)?;
+        }
+        Ok(expression)
+    }
+}
+
+impl fmt::Debug for BuildHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BuildHooks")
+            .field("condition", &self.condition.len())
+            .field("build", &self.build.len())
+            .finish()
+    }
+}
+
+impl PartialEq for BuildHooks {
+    // Closures can't be compared, so hooks are equal when they are the same instances
+    fn eq(&self, other: &Self) -> bool {
+        fn same<T: ?Sized>(a: &[Arc<T>], b: &[Arc<T>]) -> bool {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| Arc::ptr_eq(a, b))
+        }
+        same(&self.condition, &other.condition) && same(&self.build, &other.build)
+    }
+}
+
+impl Eq for BuildHooks {}
+
+impl Hash for BuildHooks {
+    // Consistent with `eq`: hooks hash by identity, not by behaviour
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for hook in &self.condition {
+            (Arc::as_ptr(hook) as *const () as usize).hash(state);
+        }
+        state.write_u8(0xff);
+        for hook in &self.build {
+            (Arc::as_ptr(hook) as *const () as usize).hash(state);
+        }
+    }
+}
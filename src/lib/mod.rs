@@ -9,6 +9,7 @@
 //! ```rust,ignore
 //! use clickhouse_filters::{ClickHouseFilters, PaginationOptions, FilteringOptions};
 //! use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! use clickhouse_filters::pagination::PaginationSyntax;
 //! use clickhouse_filters::sorting::SortedColumn;
 //! use std::collections::HashMap;
 //!
@@ -24,6 +25,8 @@
 //!         per_page: 10,
 //!         per_page_limit: 10,
 //!         total_records: 1000,
+//!         syntax: PaginationSyntax::LimitOffset,
+//!         with_ties: false,
 //!     }),
 //!     vec![SortedColumn::new("name", "asc")],
 //!     Some(FilteringOptions::new(
@@ -42,15 +45,55 @@
 //! ```
 
 use eyre::Result;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, OnceLock};
 
 // Public modules
+pub mod ast;
+pub mod canonical;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod cost;
+pub mod count;
+pub mod custom;
+pub mod describe;
+pub mod execution;
+pub mod external;
 pub mod filtering;
+pub mod fingerprint;
+pub mod hooks;
+pub mod mutation;
+pub mod normalize;
+pub mod optimize;
 pub mod pagination;
+pub mod params;
+pub mod policy;
+pub mod prepared;
+pub mod projection;
+pub mod registry;
+pub mod relative;
+pub mod request;
+pub mod result;
+pub mod schema;
+pub mod settings;
 pub mod sorting;
+pub mod statements;
+pub mod stats;
+pub mod table;
+pub mod testing;
+pub mod typed;
+pub mod visit;
+
+pub use crate::registry::ColumnRegistry;
 
 // Import key types from submodules
-use crate::filtering::{FilterBuilder, FilterCondition, FilterExpression, FilterOperator};
+use crate::custom::{ColumnRenderer, RendererRef};
+use crate::filtering::{
+    DateRangeType, FilterBuilder, FilterCondition, FilterExpression, FilterOperator, JsonNumberType,
+};
+use crate::hooks::BuildHooks;
 use crate::pagination::Paginate;
 use crate::sorting::{SortedColumn, Sorting};
 
@@ -87,6 +130,8 @@ pub enum ColumnDef {
     Date32(&'static str),
     DateTime(&'static str),
     DateTime64(&'static str),
+    // DateTime64 with its declared sub-second precision (0-9)
+    DateTime64Precision(&'static str, u8),
 
     // Boolean Type
     Boolean(&'static str),
@@ -106,6 +151,13 @@ pub enum ColumnDef {
     ArrayInt64(&'static str),
     ArrayFloat32(&'static str),
     ArrayFloat64(&'static str),
+    ArrayUUID(&'static str),
+    ArrayDate(&'static str),
+    ArrayDateTime(&'static str),
+    ArrayEnum8(&'static str),
+    ArrayEnum16(&'static str),
+    ArrayIPv4(&'static str),
+    ArrayIPv6(&'static str),
 
     // Special Types
     Enum8(&'static str),
@@ -114,8 +166,21 @@ pub enum ColumnDef {
     IPv6(&'static str),
     Decimal(&'static str),
 
+    // Map Types
+    Map(&'static str),
+
     // JSON Types
     JSON(&'static str),
+
+    // Types rendered by the application, see the `custom` module
+    Custom {
+        name: &'static str,
+        renderer: Arc<dyn ColumnRenderer>,
+    },
+
+    // A `LowCardinality(...)` column, filtered like the wrapped type but compared
+    // case-sensitively so the dictionary doesn't have to be lowered
+    LowCardinality(Box<ColumnDef>),
 }
 
 /// Placeholder implementation (to be expanded)
@@ -146,7 +211,8 @@ impl ColumnDef {
             ColumnDef::Date(name)
             | ColumnDef::Date32(name)
             | ColumnDef::DateTime(name)
-            | ColumnDef::DateTime64(name) => name.to_string(),
+            | ColumnDef::DateTime64(name)
+            | ColumnDef::DateTime64Precision(name, _) => name.to_string(),
 
             // Boolean Type
             ColumnDef::Boolean(name) => name.to_string(),
@@ -165,7 +231,14 @@ impl ColumnDef {
             | ColumnDef::ArrayInt32(name)
             | ColumnDef::ArrayInt64(name)
             | ColumnDef::ArrayFloat32(name)
-            | ColumnDef::ArrayFloat64(name) => name.to_string(),
+            | ColumnDef::ArrayFloat64(name)
+            | ColumnDef::ArrayUUID(name)
+            | ColumnDef::ArrayDate(name)
+            | ColumnDef::ArrayDateTime(name)
+            | ColumnDef::ArrayEnum8(name)
+            | ColumnDef::ArrayEnum16(name)
+            | ColumnDef::ArrayIPv4(name)
+            | ColumnDef::ArrayIPv6(name) => name.to_string(),
 
             // Special Types
             ColumnDef::Enum8(name)
@@ -174,40 +247,223 @@ impl ColumnDef {
             | ColumnDef::IPv6(name)
             | ColumnDef::Decimal(name) => name.to_string(),
 
+            // Map Types
+            ColumnDef::Map(name) => name.to_string(),
+
             // JSON Types
             ColumnDef::JSON(name) => name.to_string(),
+
+            // Custom Types
+            ColumnDef::Custom { name, .. } => name.to_string(),
+
+            ColumnDef::LowCardinality(inner) => inner.get_column_name(),
+        }
+    }
+
+    /// Wrap this definition in `LowCardinality(...)`
+    pub fn low_cardinality(self) -> ColumnDef {
+        ColumnDef::LowCardinality(Box::new(self))
+    }
+
+    pub fn is_low_cardinality(&self) -> bool {
+        matches!(self, ColumnDef::LowCardinality(_))
+    }
+
+    /// The definition with any `LowCardinality` wrapper removed
+    pub fn base(&self) -> &ColumnDef {
+        match self {
+            ColumnDef::LowCardinality(inner) => inner.base(),
+            column_def => column_def,
+        }
+    }
+
+    /// Element type of a typed array column
+    pub fn array_element_type(&self) -> Option<filtering::ArrayElementType> {
+        use filtering::ArrayElementType;
+
+        match self.base() {
+            ColumnDef::ArrayString(_) => Some(ArrayElementType::String),
+            ColumnDef::ArrayUInt8(_)
+            | ColumnDef::ArrayUInt16(_)
+            | ColumnDef::ArrayUInt32(_)
+            | ColumnDef::ArrayUInt64(_)
+            | ColumnDef::ArrayInt8(_)
+            | ColumnDef::ArrayInt16(_)
+            | ColumnDef::ArrayInt32(_)
+            | ColumnDef::ArrayInt64(_)
+            | ColumnDef::ArrayFloat32(_)
+            | ColumnDef::ArrayFloat64(_) => Some(ArrayElementType::Numeric),
+            ColumnDef::ArrayUUID(_) => Some(ArrayElementType::UUID),
+            ColumnDef::ArrayDate(_) => Some(ArrayElementType::Date),
+            ColumnDef::ArrayDateTime(_) => Some(ArrayElementType::DateTime),
+            ColumnDef::ArrayEnum8(_) | ColumnDef::ArrayEnum16(_) => Some(ArrayElementType::Enum),
+            ColumnDef::ArrayIPv4(_) => Some(ArrayElementType::IPv4),
+            ColumnDef::ArrayIPv6(_) => Some(ArrayElementType::IPv6),
+            _ => None,
         }
     }
 
+    /// Build a condition on a single position of an array column
+    ///
+    /// `index` is 1-based; negative indexes count from the end of the array.
+    pub fn to_array_element_condition(
+        &self,
+        index: i64,
+        operator: &str,
+        value: &str,
+    ) -> Result<FilterCondition> {
+        let element_type = self
+            .array_element_type()
+            .ok_or_else(|| eyre::eyre!("Not an array column: {}", self.get_column_name()))?;
+        let op = filtering::parse_operator(operator)?;
+
+        Ok(FilterCondition::array_element(
+            &self.get_column_name(),
+            index,
+            op,
+            value,
+            element_type,
+        ))
+    }
+
+    /// Build a condition on how many times a value appears in an array column
+    pub fn to_array_count_condition(
+        &self,
+        value: &str,
+        operator: &str,
+        count: u64,
+    ) -> Result<FilterCondition> {
+        let element_type = self
+            .array_element_type()
+            .ok_or_else(|| eyre::eyre!("Not an array column: {}", self.get_column_name()))?;
+        let op = filtering::parse_operator(operator)?;
+
+        Ok(FilterCondition::array_count(
+            &self.get_column_name(),
+            value,
+            op,
+            count,
+            element_type,
+        ))
+    }
+
+    /// Build a condition on the value stored under `key` in a map column
+    ///
+    /// Without a `value_type` the type is inferred from the operator and value;
+    /// see `MapValueType::infer`.
+    pub fn to_map_condition(
+        &self,
+        key: &str,
+        operator: &str,
+        value: &str,
+        value_type: Option<filtering::MapValueType>,
+    ) -> Result<FilterCondition> {
+        let ColumnDef::Map(name) = self else {
+            return Err(eyre::eyre!("Not a map column: {}", self.get_column_name()));
+        };
+        let op = filtering::parse_operator(operator)?;
+        let is_null_check = matches!(op, FilterOperator::IsNull | FilterOperator::IsNotNull);
+        let value_type = value_type.unwrap_or_else(|| filtering::MapValueType::infer(&op, value));
+
+        Ok(FilterCondition::map_value(
+            name,
+            key,
+            op,
+            (!is_null_check).then_some(value),
+            value_type,
+        ))
+    }
+
+    /// Build a numeric comparison on a path inside a JSON column
+    ///
+    /// Without a `number_type` the type is inferred from the value.
+    pub fn to_json_numeric_condition(
+        &self,
+        path: &str,
+        operator: &str,
+        value: &str,
+        number_type: Option<JsonNumberType>,
+    ) -> Result<FilterCondition> {
+        let ColumnDef::JSON(name) = self else {
+            return Err(eyre::eyre!("Not a JSON column: {}", self.get_column_name()));
+        };
+        let number_type = number_type
+            .or_else(|| JsonNumberType::infer(value))
+            .ok_or_else(|| eyre::eyre!("Invalid numeric JSON value: {}", value))?;
+
+        Ok(FilterCondition::json_numeric(
+            name,
+            path,
+            filtering::parse_operator(operator)?,
+            value,
+            number_type,
+        ))
+    }
+
+    /// Build a condition requiring at least `min_overlap` of the comma-separated
+    /// `values` to be present in an array column
+    pub fn to_array_overlap_condition(
+        &self,
+        values: &str,
+        min_overlap: u64,
+    ) -> Result<FilterCondition> {
+        let element_type = self
+            .array_element_type()
+            .ok_or_else(|| eyre::eyre!("Not an array column: {}", self.get_column_name()))?;
+
+        Ok(FilterCondition::array_overlap(
+            &self.get_column_name(),
+            values.split(',').map(|v| v.trim().to_string()).collect(),
+            min_overlap,
+            element_type,
+        ))
+    }
+
     // Convert ColumnDef to appropriate FilterCondition
     pub fn to_filter_condition(&self, operator: &str, value: &str) -> Result<FilterCondition> {
-        let op = match operator.to_uppercase().as_str() {
-            "=" => FilterOperator::Equal,
-            "!=" => FilterOperator::NotEqual,
-            ">" => FilterOperator::GreaterThan,
-            ">=" => FilterOperator::GreaterThanOrEqual,
-            "<" => FilterOperator::LessThan,
-            "<=" => FilterOperator::LessThanOrEqual,
-            "LIKE" => FilterOperator::Like,
-            "NOT LIKE" => FilterOperator::NotLike,
-            "IN" => FilterOperator::In,
-            "NOT IN" => FilterOperator::NotIn,
-            "IS NULL" => FilterOperator::IsNull,
-            "IS NOT NULL" => FilterOperator::IsNotNull,
-            "STARTS WITH" => FilterOperator::StartsWith,
-            "ENDS WITH" => FilterOperator::EndsWith,
-            "ARRAY CONTAINS" => FilterOperator::ArrayContains,
-            "ARRAY HAS" => FilterOperator::ArrayHas,
-            "ARRAY ALL" => FilterOperator::ArrayAll,
-            "ARRAY ANY" => FilterOperator::ArrayAny,
-            "DATE_ONLY" => FilterOperator::DateEqual,
-            "DATE_RANGE" => FilterOperator::DateRange,
-            "RELATIVE" => FilterOperator::RelativeDate,
-            _ => return Err(eyre::eyre!("Invalid operator: {}", operator)),
-        };
+        if let ColumnDef::LowCardinality(inner) = self {
+            return inner.to_filter_condition(operator, value);
+        }
+        let op = filtering::parse_operator(operator)?;
 
         // Check if operator is for NULL checks
-        let is_null_check = op == FilterOperator::IsNull || op == FilterOperator::IsNotNull;
+        let is_null_check = matches!(
+            op,
+            FilterOperator::IsNull | FilterOperator::IsNotNull | FilterOperator::IsEmptyOrNull
+        );
+
+        let is_path_check = matches!(op, FilterOperator::Exists | FilterOperator::IsEmpty);
+        if is_path_check && !matches!(self, ColumnDef::JSON(_)) {
+            return Err(eyre::eyre!(
+                "Operator {} is only supported on JSON columns",
+                operator
+            ));
+        }
+
+        // Compared with '' or 0, so only types whose default is one of those
+        if op == FilterOperator::IsEmptyOrNull
+            && !matches!(
+                self,
+                ColumnDef::String(_)
+                    | ColumnDef::FixedString(_)
+                    | ColumnDef::UInt8(_)
+                    | ColumnDef::UInt16(_)
+                    | ColumnDef::UInt32(_)
+                    | ColumnDef::UInt64(_)
+                    | ColumnDef::Int8(_)
+                    | ColumnDef::Int16(_)
+                    | ColumnDef::Int32(_)
+                    | ColumnDef::Int64(_)
+                    | ColumnDef::Float32(_)
+                    | ColumnDef::Float64(_)
+                    | ColumnDef::Decimal(_)
+            )
+        {
+            return Err(eyre::eyre!(
+                "Operator {} is only supported on string and numeric columns",
+                operator
+            ));
+        }
 
         match self {
             // String types
@@ -435,14 +691,17 @@ impl ColumnDef {
                         operator: op,
                         value: None,
                     })
+                } else if op == FilterOperator::In || op == FilterOperator::NotIn {
+                    numeric_in_values(name, op, value, "Float32")
                 } else {
+                    // NaN and infinities are rejected, as in IN lists
                     match value.parse::<f32>() {
-                        Ok(parsed) => Ok(FilterCondition::Float32Value {
+                        Ok(parsed) if parsed.is_finite() => Ok(FilterCondition::Float32Value {
                             column: name.to_string(),
                             operator: op,
                             value: Some(parsed),
                         }),
-                        Err(_) => Err(eyre::eyre!("Invalid value for Float32: {}", value)),
+                        _ => Err(eyre::eyre!("Invalid value for Float32: {}", value)),
                     }
                 }
             }
@@ -453,14 +712,17 @@ impl ColumnDef {
                         operator: op,
                         value: None,
                     })
+                } else if op == FilterOperator::In || op == FilterOperator::NotIn {
+                    numeric_in_values(name, op, value, "Float64")
                 } else {
+                    // NaN and infinities are rejected, as in IN lists
                     match value.parse::<f64>() {
-                        Ok(parsed) => Ok(FilterCondition::Float64Value {
+                        Ok(parsed) if parsed.is_finite() => Ok(FilterCondition::Float64Value {
                             column: name.to_string(),
                             operator: op,
                             value: Some(parsed),
                         }),
-                        Err(_) => Err(eyre::eyre!("Invalid value for Float64: {}", value)),
+                        _ => Err(eyre::eyre!("Invalid value for Float64: {}", value)),
                     }
                 }
             }
@@ -492,7 +754,13 @@ impl ColumnDef {
                     }
                 } else if op == FilterOperator::RelativeDate {
                     // RELATIVE operator
-                    Ok(FilterCondition::relative_date(name, value))
+                    Ok(FilterCondition::relative_date(
+                        name,
+                        check_relative_date(value)?,
+                    ))
+                } else if op == FilterOperator::BucketEqual {
+                    // BUCKET_EQUAL operator
+                    date_bucket_condition(name, value)
                 } else {
                     Ok(FilterCondition::DateValue {
                         column: name.to_string(),
@@ -524,7 +792,12 @@ impl ColumnDef {
                         ))
                     }
                 } else if op == FilterOperator::RelativeDate {
-                    Ok(FilterCondition::relative_date(name, value))
+                    Ok(FilterCondition::relative_date(
+                        name,
+                        check_relative_date(value)?,
+                    ))
+                } else if op == FilterOperator::BucketEqual {
+                    date_bucket_condition(name, value)
                 } else {
                     Ok(FilterCondition::DateValue {
                         column: name.to_string(),
@@ -556,7 +829,12 @@ impl ColumnDef {
                         ))
                     }
                 } else if op == FilterOperator::RelativeDate {
-                    Ok(FilterCondition::relative_date(name, value))
+                    Ok(FilterCondition::relative_date(
+                        name,
+                        check_relative_date(value)?,
+                    ))
+                } else if op == FilterOperator::BucketEqual {
+                    date_bucket_condition(name, value)
                 } else {
                     Ok(FilterCondition::DateTimeValue {
                         column: name.to_string(),
@@ -588,7 +866,12 @@ impl ColumnDef {
                         ))
                     }
                 } else if op == FilterOperator::RelativeDate {
-                    Ok(FilterCondition::relative_date(name, value))
+                    Ok(FilterCondition::relative_date(
+                        name,
+                        check_relative_date(value)?,
+                    ))
+                } else if op == FilterOperator::BucketEqual {
+                    date_bucket_condition(name, value)
                 } else {
                     Ok(FilterCondition::DateTime64Value {
                         column: name.to_string(),
@@ -598,6 +881,26 @@ impl ColumnDef {
                 }
             }
 
+            ColumnDef::DateTime64Precision(name, precision) => {
+                if *precision > 9 {
+                    return Err(eyre::eyre!("Invalid DateTime64 precision: {}", precision));
+                }
+                // Reject values more precise than the column, which could never match
+                if !is_null_check && op != FilterOperator::RelativeDate {
+                    for timestamp in value.split(',') {
+                        if filtering::fraction_digits(timestamp.trim()) > *precision as usize {
+                            return Err(eyre::eyre!(
+                                "Value is more precise than DateTime64({}) column {}: {}",
+                                precision,
+                                name,
+                                timestamp.trim()
+                            ));
+                        }
+                    }
+                }
+                ColumnDef::DateTime64(name).to_filter_condition(operator, value)
+            }
+
             // Boolean type
             ColumnDef::Boolean(name) => {
                 if is_null_check {
@@ -606,20 +909,19 @@ impl ColumnDef {
                         operator: op,
                         value: None,
                     })
+                } else if op == FilterOperator::In || op == FilterOperator::NotIn {
+                    Ok(FilterCondition::InValues {
+                        column: name.to_string(),
+                        operator: op,
+                        values: bool_values(name, value)?,
+                        column_type: Some(filtering::ColumnTypeInfo::Boolean),
+                    })
                 } else {
-                    match value.to_lowercase().as_str() {
-                        "true" | "1" | "yes" | "y" => Ok(FilterCondition::BooleanValue {
-                            column: name.to_string(),
-                            operator: op,
-                            value: Some(true),
-                        }),
-                        "false" | "0" | "no" | "n" => Ok(FilterCondition::BooleanValue {
-                            column: name.to_string(),
-                            operator: op,
-                            value: Some(false),
-                        }),
-                        _ => Err(eyre::eyre!("Invalid boolean value: {}", value)),
-                    }
+                    Ok(FilterCondition::BooleanValue {
+                        column: name.to_string(),
+                        operator: op,
+                        value: Some(bool_value(name, value)?),
+                    })
                 }
             }
 
@@ -635,32 +937,52 @@ impl ColumnDef {
                     Ok(FilterCondition::InValues {
                         column: name.to_string(),
                         operator: op,
-                        values: value.split(',').map(|v| v.trim().to_string()).collect(),
+                        values: uuid_values(name, value)?,
                         column_type: Some(filtering::ColumnTypeInfo::UUID),
                     })
                 } else {
                     Ok(FilterCondition::UUIDValue {
                         column: name.to_string(),
                         operator: op,
-                        value: Some(value.to_string()),
+                        value: Some(uuid_value(name, value.trim())?),
                     })
                 }
             }
 
             // Array types
             ColumnDef::ArrayString(name) => {
-                if op == FilterOperator::ArrayContains {
+                if op == FilterOperator::ArrayJsonHas {
+                    // The value is `key.value`, as for JSON columns
+                    let (key, key_value) = split_json_path(value).ok_or_else(|| {
+                        eyre::eyre!("ARRAY JSON HAS requires a key and a value: {}", value)
+                    })?;
+                    Ok(FilterCondition::array_json(name, key, key_value))
+                } else if matches!(
+                    op,
+                    FilterOperator::ArrayContains | FilterOperator::ArrayNotContains
+                ) {
                     Ok(FilterCondition::ArrayContains {
                         column: name.to_string(),
                         operator: op,
                         value: value.to_string(),
                     })
-                } else if op == FilterOperator::ArrayHas {
+                } else if matches!(op, FilterOperator::ArrayHas | FilterOperator::ArrayNotHas) {
                     Ok(FilterCondition::ArrayHas {
                         column: name.to_string(),
                         operator: op,
                         value: value.to_string(),
                     })
+                } else if matches!(
+                    op,
+                    FilterOperator::ArrayHasSubsequence | FilterOperator::ArrayNotAny
+                ) {
+                    Ok(FilterCondition::array_values(
+                        name,
+                        op,
+                        value.split(',').map(|v| v.trim().to_string()).collect(),
+                        self.array_element_type()
+                            .unwrap_or(filtering::ArrayElementType::String),
+                    ))
                 } else if is_null_check {
                     Ok(FilterCondition::StringValue {
                         column: name.to_string(),
@@ -684,18 +1006,32 @@ impl ColumnDef {
             | ColumnDef::ArrayInt64(name)
             | ColumnDef::ArrayFloat32(name)
             | ColumnDef::ArrayFloat64(name) => {
-                if op == FilterOperator::ArrayContains {
+                if matches!(
+                    op,
+                    FilterOperator::ArrayContains | FilterOperator::ArrayNotContains
+                ) {
                     Ok(FilterCondition::ArrayContains {
                         column: name.to_string(),
                         operator: op,
                         value: value.to_string(),
                     })
-                } else if op == FilterOperator::ArrayHas {
+                } else if matches!(op, FilterOperator::ArrayHas | FilterOperator::ArrayNotHas) {
                     Ok(FilterCondition::ArrayHas {
                         column: name.to_string(),
                         operator: op,
                         value: value.to_string(),
                     })
+                } else if matches!(
+                    op,
+                    FilterOperator::ArrayHasSubsequence | FilterOperator::ArrayNotAny
+                ) {
+                    Ok(FilterCondition::array_values(
+                        name,
+                        op,
+                        value.split(',').map(|v| v.trim().to_string()).collect(),
+                        self.array_element_type()
+                            .unwrap_or(filtering::ArrayElementType::String),
+                    ))
                 } else if is_null_check {
                     Ok(FilterCondition::StringValue {
                         column: name.to_string(),
@@ -710,26 +1046,95 @@ impl ColumnDef {
                 }
             }
 
+            ColumnDef::ArrayUUID(name)
+            | ColumnDef::ArrayDate(name)
+            | ColumnDef::ArrayDateTime(name)
+            | ColumnDef::ArrayEnum8(name)
+            | ColumnDef::ArrayEnum16(name)
+            | ColumnDef::ArrayIPv4(name)
+            | ColumnDef::ArrayIPv6(name) => match op {
+                FilterOperator::ArrayContains
+                | FilterOperator::ArrayHas
+                | FilterOperator::ArrayAll
+                | FilterOperator::ArrayAny
+                | FilterOperator::ArrayHasSubsequence
+                | FilterOperator::ArrayNotHas
+                | FilterOperator::ArrayNotContains
+                | FilterOperator::ArrayNotAny => Ok(FilterCondition::array_values(
+                    name,
+                    op,
+                    if matches!(self, ColumnDef::ArrayUUID(_)) {
+                        uuid_values(name, value)?
+                    } else {
+                        value.split(',').map(|v| v.trim().to_string()).collect()
+                    },
+                    self.array_element_type()
+                        .unwrap_or(filtering::ArrayElementType::String),
+                )),
+                _ if is_null_check => Ok(FilterCondition::StringValue {
+                    column: name.to_string(),
+                    operator: op,
+                    value: None,
+                }),
+                _ => Err(eyre::eyre!(
+                    "Unsupported operator for array type: {}",
+                    operator
+                )),
+            },
+
+            // Map values are addressed by key, e.g. `attrs['retries']`
+            ColumnDef::Map(name) => Err(eyre::eyre!(
+                "Map column filters require a key, e.g. {}['key']",
+                name
+            )),
+
+            // Custom types validate and render through their renderer
+            ColumnDef::Custom { name, renderer } => {
+                let value = (!is_null_check).then_some(value);
+                renderer.validate(name, &op, value)?;
+                Ok(FilterCondition::Custom {
+                    column: name.to_string(),
+                    operator: op,
+                    value: value.map(str::to_string),
+                    renderer: RendererRef(Arc::clone(renderer)),
+                })
+            }
+
             // JSON type
             ColumnDef::JSON(name) => {
                 // Extract path if provided (separated by dot or in JSONPath format)
                 let mut json_path = None;
                 let mut json_value = value.to_string();
 
-                if value.contains('.') && !is_null_check {
-                    let parts: Vec<&str> = value.splitn(2, '.').collect();
-                    if parts.len() == 2 {
-                        json_path = Some(parts[0].to_string());
-                        json_value = parts[1].to_string();
+                if !is_null_check {
+                    if let Some((path, path_value)) = split_json_path(value) {
+                        // Ordering comparisons on numbers compare numerically
+                        let ordering = op.is_comparison()
+                            && !matches!(op, FilterOperator::Equal | FilterOperator::NotEqual);
+                        if let Some(number_type) = JsonNumberType::infer(path_value) {
+                            if ordering {
+                                return Ok(FilterCondition::json_numeric(
+                                    name,
+                                    path,
+                                    op,
+                                    path_value,
+                                    number_type,
+                                ));
+                            }
+                        }
+                        json_path = Some(path.to_string());
+                        json_value = path_value.to_string();
                     }
                 }
 
-                if is_null_check {
+                if is_null_check || is_path_check {
+                    // The value names the path to check, if any
+                    let path = value.trim();
                     Ok(FilterCondition::JSONValue {
                         column: name.to_string(),
                         operator: op,
                         value: None,
-                        path: json_path,
+                        path: (!path.is_empty()).then(|| path.to_string()),
                     })
                 } else {
                     Ok(FilterCondition::JSONValue {
@@ -758,20 +1163,49 @@ impl ColumnDef {
                 }
             }
 
-            // Network address types (treated as strings)
-            ColumnDef::IPv4(name) | ColumnDef::IPv6(name) => {
+            // Network address types, parsed and compared as addresses
+            ColumnDef::IPv4(name) => {
                 if is_null_check {
-                    Ok(FilterCondition::StringValue {
-                        column: name.to_string(),
-                        operator: op,
-                        value: None,
-                    })
+                    Ok(FilterCondition::ipv4(name, op, None))
+                } else if op == FilterOperator::In || op == FilterOperator::NotIn {
+                    let values = value
+                        .split(',')
+                        .map(|v| Ok(ipv4_value(name, v.trim())?.to_string()))
+                        .collect::<Result<_>>()?;
+                    Ok(FilterCondition::in_values(
+                        name,
+                        op,
+                        values,
+                        Some(filtering::ColumnTypeInfo::IPv4),
+                    ))
                 } else {
-                    Ok(FilterCondition::StringValue {
-                        column: name.to_string(),
-                        operator: op,
-                        value: Some(value.to_string()),
-                    })
+                    Ok(FilterCondition::ipv4(
+                        name,
+                        op,
+                        Some(ipv4_value(name, value.trim())?),
+                    ))
+                }
+            }
+            ColumnDef::IPv6(name) => {
+                if is_null_check {
+                    Ok(FilterCondition::ipv6(name, op, None))
+                } else if op == FilterOperator::In || op == FilterOperator::NotIn {
+                    let values = value
+                        .split(',')
+                        .map(|v| Ok(ipv6_value(name, v.trim())?.to_string()))
+                        .collect::<Result<_>>()?;
+                    Ok(FilterCondition::in_values(
+                        name,
+                        op,
+                        values,
+                        Some(filtering::ColumnTypeInfo::IPv6),
+                    ))
+                } else {
+                    Ok(FilterCondition::ipv6(
+                        name,
+                        op,
+                        Some(ipv6_value(name, value.trim())?),
+                    ))
                 }
             }
 
@@ -783,14 +1217,16 @@ impl ColumnDef {
                         operator: op,
                         value: None,
                     })
+                } else if op == FilterOperator::In || op == FilterOperator::NotIn {
+                    numeric_in_values(name, op, value, "Decimal")
                 } else {
                     match value.parse::<f64>() {
-                        Ok(parsed) => Ok(FilterCondition::Float64Value {
+                        Ok(parsed) if parsed.is_finite() => Ok(FilterCondition::Float64Value {
                             column: name.to_string(),
                             operator: op,
                             value: Some(parsed),
                         }),
-                        Err(_) => Err(eyre::eyre!("Invalid decimal value: {}", value)),
+                        _ => Err(eyre::eyre!("Invalid decimal value: {}", value)),
                     }
                 }
             }
@@ -815,6 +1251,234 @@ impl ColumnDef {
     }
 }
 
+// A boolean value, see `filtering::parse_bool`
+fn bool_value(column: &str, value: &str) -> Result<bool> {
+    filtering::parse_bool(value).ok_or_else(|| {
+        eyre::Report::new(filtering::InvalidValue {
+            column: column.to_string(),
+            type_name: "Boolean",
+            value: value.to_string(),
+        })
+    })
+}
+
+// Comma-separated boolean values as `1` and `0`
+fn bool_values(column: &str, value: &str) -> Result<Vec<String>> {
+    value
+        .split(',')
+        .map(|v| Ok(u8::from(bool_value(column, v.trim())?).to_string()))
+        .collect()
+}
+
+// A UUID in canonical lowercase form
+fn uuid_value(column: &str, value: &str) -> Result<String> {
+    filtering::canonical_uuid(value).ok_or_else(|| {
+        eyre::Report::new(filtering::InvalidValue {
+            column: column.to_string(),
+            type_name: "UUID",
+            value: value.to_string(),
+        })
+    })
+}
+
+// Comma-separated UUIDs in canonical lowercase form
+fn uuid_values(column: &str, value: &str) -> Result<Vec<String>> {
+    value
+        .split(',')
+        .map(|v| uuid_value(column, v.trim()))
+        .collect()
+}
+
+// An IPv4 address such as `192.168.1.1`
+fn ipv4_value(column: &str, value: &str) -> Result<Ipv4Addr> {
+    value.parse().map_err(|_| {
+        eyre::Report::new(filtering::InvalidValue {
+            column: column.to_string(),
+            type_name: "IPv4",
+            value: value.to_string(),
+        })
+    })
+}
+
+// An IPv6 address; IPv4 addresses are stored in IPv6 columns as `::ffff:a.b.c.d`
+fn ipv6_value(column: &str, value: &str) -> Result<Ipv6Addr> {
+    match value.parse() {
+        Ok(IpAddr::V6(address)) => Ok(address),
+        Ok(IpAddr::V4(address)) => Ok(address.to_ipv6_mapped()),
+        Err(_) => Err(eyre::Report::new(filtering::InvalidValue {
+            column: column.to_string(),
+            type_name: "IPv6",
+            value: value.to_string(),
+        })),
+    }
+}
+
+// IN list for a float or decimal column; every value must be a finite number,
+// since anything else would be quoted and fail to compare
+fn numeric_in_values(
+    column: &str,
+    operator: FilterOperator,
+    value: &str,
+    type_name: &str,
+) -> Result<FilterCondition> {
+    let values: Vec<String> = value.split(',').map(|v| v.trim().to_string()).collect();
+    if let Some(invalid) = values
+        .iter()
+        .find(|v| !v.parse::<f64>().is_ok_and(f64::is_finite))
+    {
+        return Err(eyre::eyre!("Invalid value for {}: {}", type_name, invalid));
+    }
+    Ok(FilterCondition::in_values(
+        column,
+        operator,
+        values,
+        Some(filtering::ColumnTypeInfo::Numeric),
+    ))
+}
+
+// Parse a BUCKET_EQUAL value such as `5 minute|2024-01-01 10:05:00`
+fn date_bucket_condition(column: &str, value: &str) -> Result<FilterCondition> {
+    let invalid = || eyre::eyre!("Invalid BUCKET_EQUAL value: {}", value);
+    let (interval, start) = value.split_once('|').ok_or_else(invalid)?;
+    let interval = bucket_interval(interval).ok_or_else(invalid)?;
+    if start.trim().is_empty() {
+        return Err(invalid());
+    }
+    Ok(FilterCondition::date_bucket(
+        column,
+        &interval,
+        start.trim(),
+    ))
+}
+
+// Normalize a bucket interval such as `5 minute` to `5 MINUTE`, or None if it
+// isn't a positive count of a time unit, since it is rendered as SQL
+fn bucket_interval(interval: &str) -> Option<String> {
+    let (count, unit) = interval.trim().split_once(' ')?;
+    let unit = unit.trim().to_uppercase();
+    let valid_count = !count.is_empty()
+        && count.bytes().all(|b| b.is_ascii_digit())
+        && count.bytes().any(|b| b != b'0');
+    let valid_unit = matches!(
+        unit.as_str(),
+        "SECOND" | "MINUTE" | "HOUR" | "DAY" | "WEEK" | "MONTH" | "QUARTER" | "YEAR"
+    );
+    (valid_count && valid_unit).then(|| format!("{} {}", count, unit))
+}
+
+// Check a deserialized condition for values that are rendered as SQL
+struct PayloadCheck<'a> {
+    column_defs: &'a ColumnRegistry,
+    result: Result<()>,
+}
+
+impl visit::Visitor for PayloadCheck<'_> {
+    fn visit_condition(&mut self, condition: &FilterCondition) {
+        if self.result.is_ok() {
+            self.result = check_condition(self.column_defs, condition);
+        }
+    }
+}
+
+fn check_condition(column_defs: &ColumnRegistry, condition: &FilterCondition) -> Result<()> {
+    let column = condition.column();
+    check_registered(column_defs, column)?;
+    match condition {
+        FilterCondition::DateRange {
+            range_type: DateRangeType::Relative(expr),
+            ..
+        } => {
+            check_relative_date(expr)?;
+        }
+        FilterCondition::DateRange {
+            range_type: DateRangeType::Bucket { interval, .. },
+            ..
+        } if bucket_interval(interval).as_deref() != Some(interval.as_str()) => {
+            return Err(eyre::eyre!("Invalid bucket interval: {}", interval));
+        }
+        FilterCondition::NumericRange { range, .. } => range.check_bounds(column)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+// Conditions carry the column name from the definition, which may differ from
+// the registry key
+fn check_registered(column_defs: &ColumnRegistry, column: &str) -> Result<()> {
+    if column_defs.contains(column)
+        || column_defs
+            .iter()
+            .any(|(_, def)| def.get_column_name() == column)
+    {
+        Ok(())
+    } else {
+        Err(eyre::eyre!("Column not found: {}", column))
+    }
+}
+
+// Check that a RELATIVE filter value is a plain date offset, e.g.
+// `now() - INTERVAL 7 DAY` or `today() - 1`, since it is rendered as SQL
+fn check_relative_date(expr: &str) -> Result<&str> {
+    let invalid = || eyre::eyre!("Invalid relative date expression: {}", expr);
+    let lower = expr.to_lowercase();
+    let mut tokens = Vec::new();
+    let mut rest = lower.trim();
+    while !rest.is_empty() {
+        let len = match rest.find(|c: char| !c.is_ascii_alphanumeric()) {
+            Some(0) if matches!(rest.as_bytes()[0], b'(' | b')' | b'+' | b'-') => 1,
+            Some(0) => return Err(invalid()),
+            Some(len) => len,
+            None => rest.len(),
+        };
+        tokens.push(&rest[..len]);
+        rest = rest[len..].trim_start();
+    }
+
+    let mut tokens = tokens.into_iter();
+    if !matches!(tokens.next(), Some("now" | "today" | "yesterday"))
+        || tokens.next() != Some("(")
+        || tokens.next() != Some(")")
+    {
+        return Err(invalid());
+    }
+    let is_number =
+        |token: Option<&str>| token.is_some_and(|t| t.bytes().all(|b| b.is_ascii_digit()));
+    while let Some(sign) = tokens.next() {
+        if !matches!(sign, "+" | "-") {
+            return Err(invalid());
+        }
+        let offset = tokens.next();
+        let valid = match offset {
+            Some("interval") => {
+                is_number(tokens.next())
+                    && matches!(
+                        tokens.next(),
+                        Some(
+                            "second"
+                                | "minute"
+                                | "hour"
+                                | "day"
+                                | "week"
+                                | "month"
+                                | "quarter"
+                                | "year"
+                        )
+                    )
+            }
+            _ => is_number(offset),
+        };
+        if !valid {
+            return Err(invalid());
+        }
+    }
+    Ok(expr)
+}
+
+// Split a JSON column filter value into its path and value at the first dot
+pub(crate) fn split_json_path(value: &str) -> Option<(&str, &str)> {
+    value.split_once('.')
+}
+
 /// Pagination options for ClickHouse queries
 #[derive(Debug, Clone)]
 pub struct PaginationOptions {
@@ -822,6 +1486,10 @@ pub struct PaginationOptions {
     pub per_page: i64,
     pub per_page_limit: i64,
     pub total_records: i64,
+    /// How the LIMIT and OFFSET clause is written
+    pub syntax: pagination::PaginationSyntax,
+    /// Include rows that tie with the last row of a page; requires sorting
+    pub with_ties: bool,
 }
 
 impl PaginationOptions {
@@ -831,8 +1499,26 @@ impl PaginationOptions {
             per_page,
             per_page_limit,
             total_records,
+            syntax: pagination::PaginationSyntax::default(),
+            with_ties: false,
         }
     }
+
+    /// Write the clause as `LIMIT offset, count` or `OFFSET ... FETCH NEXT ...`
+    /// instead of `LIMIT count OFFSET offset`
+    pub fn with_syntax(mut self, syntax: pagination::PaginationSyntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Render `WITH TIES` so pages don't cut off rows that sort equal to their
+    /// last row, e.g. for leaderboards
+    ///
+    /// Building filters fails unless sorting columns are given.
+    pub fn with_ties(mut self) -> Self {
+        self.with_ties = true;
+        self
+    }
 }
 
 /// Filtering options for ClickHouse queries
@@ -840,41 +1526,94 @@ impl PaginationOptions {
 pub struct FilteringOptions {
     pub expressions: Vec<FilterExpression>,
     pub case_insensitive: bool,
-    pub column_defs: HashMap<&'static str, ColumnDef>,
+    pub column_defs: ColumnRegistry,
+    pub hooks: BuildHooks,
+    /// How multiple top-level expressions are combined, AND by default
+    pub root_operator: filtering::LogicalOperator,
 }
 
 impl FilteringOptions {
-    pub fn new(
-        expressions: Vec<FilterExpression>,
-        column_defs: HashMap<&'static str, ColumnDef>,
-    ) -> Self {
+    pub fn new(expressions: Vec<FilterExpression>, column_defs: impl Into<ColumnRegistry>) -> Self {
         Self {
             expressions,
             case_insensitive: true,
-            column_defs,
+            column_defs: column_defs.into(),
+            hooks: BuildHooks::default(),
+            root_operator: filtering::LogicalOperator::And,
         }
     }
 
     pub fn case_sensitive(
         expressions: Vec<FilterExpression>,
-        column_defs: HashMap<&'static str, ColumnDef>,
+        column_defs: impl Into<ColumnRegistry>,
     ) -> Self {
         Self {
             expressions,
             case_insensitive: false,
-            column_defs,
+            column_defs: column_defs.into(),
+            hooks: BuildHooks::default(),
+            root_operator: filtering::LogicalOperator::And,
         }
     }
 
+    /// Combine multiple top-level expressions with `operator` instead of AND
+    pub fn root_operator(mut self, operator: filtering::LogicalOperator) -> Self {
+        self.root_operator = operator;
+        self
+    }
+
+    /// Register a hook that observes or rewrites each condition at build time
+    ///
+    /// See `FilterBuilder::on_condition`.
+    pub fn on_condition<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(FilterCondition) -> Result<FilterExpression> + Send + Sync + 'static,
+    {
+        self.hooks.on_condition(hook);
+        self
+    }
+
+    /// Register a hook that observes or rewrites the whole expression at build time
+    ///
+    /// See `FilterBuilder::on_build`.
+    pub fn on_build<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(FilterExpression) -> Result<FilterExpression> + Send + Sync + 'static,
+    {
+        self.hooks.on_build(hook);
+        self
+    }
+
+    /// Render relative dates against a fixed reference time instead of `now()`
+    ///
+    /// Registered as a condition hook, like `normalize_values`, so conditions
+    /// added by later condition hooks are not rewritten.
+    pub fn reference_time(self, reference: relative::ReferenceTime) -> Self {
+        self.on_condition(move |condition| {
+            Ok(FilterExpression::Condition(reference.apply(condition)))
+        })
+    }
+
+    /// Normalize the text values of every condition before it is rendered
+    ///
+    /// Registered as a condition hook, so it runs in order with the other hooks;
+    /// call it first for them to see normalized values.
+    pub fn normalize_values(self, normalization: normalize::ValueNormalization) -> Self {
+        self.on_condition(move |condition| {
+            Ok(FilterExpression::Condition(normalization.apply(condition)))
+        })
+    }
+
     /// Create FilteringOptions from JSON filters
     pub fn from_json_filters(
         filters: &[filtering::JsonFilter],
-        column_defs: HashMap<&'static str, ColumnDef>,
+        column_defs: impl Into<ColumnRegistry>,
     ) -> Result<Option<Self>> {
         if filters.is_empty() {
             return Ok(None);
         }
 
+        let column_defs = column_defs.into();
         let filter_builder =
             filtering::FilterBuilder::from_json_filters(filters, true, &column_defs)?;
         Ok(filter_builder
@@ -882,16 +1621,91 @@ impl FilteringOptions {
             .map(|root| Self::new(vec![root], column_defs)))
     }
 
+    /// Create FilteringOptions from a flat map of column names to values
+    ///
+    /// Each entry becomes an `=` condition, checked against the column's type as a
+    /// JSON filter would be, and the conditions are joined by AND in column name
+    /// order. Returns `None` for an empty map.
+    pub fn from_equality_map(
+        values: HashMap<&str, &str>,
+        column_defs: impl Into<ColumnRegistry>,
+    ) -> Result<Option<Self>> {
+        let mut values: Vec<_> = values.into_iter().collect();
+        values.sort_unstable();
+        let filters: Vec<_> = values
+            .into_iter()
+            .map(|(column, value)| filtering::JsonFilter {
+                n: column.to_string(),
+                f: "=".to_string(),
+                v: value.to_string(),
+                c: None,
+            })
+            .collect();
+        Self::from_json_filters(&filters, column_defs)
+    }
+
+    /// Merge another set of filtering options into this one
+    ///
+    /// Both sides are combined with `operator` using `FilterExpression::combine`,
+    /// so empty sides are ignored and same-operator groups are flattened. The
+    /// case sensitivity and column definitions of `self` are kept; hooks from
+    /// `other` run after those of `self`.
+    pub fn merge(mut self, other: FilteringOptions, operator: filtering::LogicalOperator) -> Self {
+        self.hooks.extend(&other.hooks);
+
+        let combined = match (self.root_expression(), other.root_expression()) {
+            (Some(left), Some(right)) => left.combine(right, operator),
+            (Some(expr), None) | (None, Some(expr)) => expr,
+            (None, None) => {
+                return Self {
+                    expressions: vec![],
+                    ..self
+                }
+            }
+        };
+
+        // Keep a flat expression list when the result uses the root operator
+        self.expressions = match combined {
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } if operator == self.root_operator => expressions,
+            expr => vec![expr],
+        };
+        self
+    }
+
+    // The expressions as a single tree, combined with the root operator; None when empty
+    fn root_expression(&self) -> Option<FilterExpression> {
+        let mut expressions: Vec<_> = self
+            .expressions
+            .iter()
+            .filter(|expr| !expr.is_empty())
+            .cloned()
+            .collect();
+        match expressions.len() {
+            0 => None,
+            1 => expressions.pop(),
+            _ => Some(FilterExpression::Group {
+                operator: self.root_operator,
+                expressions,
+            }),
+        }
+    }
+
     /// Convert to FilterBuilder
     pub fn to_filter_builder(&self) -> Result<filtering::FilterBuilder> {
-        let mut builder = filtering::FilterBuilder::new().case_insensitive(self.case_insensitive);
+        let mut builder = filtering::FilterBuilder::new()
+            .case_insensitive(self.case_insensitive)
+            .case_overrides(&self.column_defs);
 
-        // If there are multiple expressions, wrap them in a group with AND operator
+        // If there are multiple expressions, wrap them in a group with the root operator
         if self.expressions.len() > 1 {
-            builder = builder.group(filtering::LogicalOperator::And, self.expressions.clone());
+            builder = builder.group(self.root_operator, self.expressions.clone());
         } else if let Some(expr) = self.expressions.first() {
             builder = builder.add_expression(expr.clone());
         }
+        builder.hooks = self.hooks.clone();
 
         Ok(builder)
     }
@@ -905,7 +1719,7 @@ impl FilteringOptions {
     /// Create FilteringOptions from expressions with validation
     pub fn try_from_expressions(
         expressions: Vec<Result<FilterExpression, eyre::Error>>,
-        column_defs: HashMap<&'static str, ColumnDef>,
+        column_defs: impl Into<ColumnRegistry>,
     ) -> Result<Option<Self>> {
         let expressions: Result<Vec<_>, _> = expressions.into_iter().collect();
         match expressions {
@@ -917,12 +1731,55 @@ impl FilteringOptions {
 }
 
 /// Main struct for ClickHouse filtering, sorting, and pagination
+///
+/// The WHERE clause is rendered once, on the first call to `sql()`,
+/// `count_sql()` or `query_sql()`, and reused afterwards. The filters and column
+/// definitions are only changed through `set_filters()` and `with_column_defs()`,
+/// which render it again on next use.
+///
+/// With the `serde` feature it can be serialized, e.g. to cache a validated
+/// request between its count query and later page queries. The column
+/// definitions are not part of the serialized form: reattach them with
+/// `with_column_defs()` before generating SQL.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub struct ClickHouseFilters {
     pub pagination: Option<Paginate>,
     pub sorting: Option<Sorting>,
-    pub filters: Option<FilterBuilder>,
-    pub column_defs: HashMap<&'static str, ColumnDef>,
+    // Private so changes go through set_filters(), which resets where_clause
+    filters: Option<FilterBuilder>,
+    // Private so changes go through with_column_defs(), which resets where_clause
+    #[cfg_attr(feature = "serde", serde(skip))]
+    column_defs: ColumnRegistry,
+    // Deserialized without column_defs; SQL generation fails until they're reattached
+    #[cfg_attr(feature = "serde", serde(skip, default = "detached"))]
+    detached: bool,
+    // Row policy predicates, applied to every query like the soft-delete condition
+    #[cfg_attr(feature = "serde", serde(default))]
+    row_policy: Option<FilterExpression>,
+    // Skip the soft-delete condition from column_defs
+    include_deleted: bool,
+    // Multiplier of per_page for the max_rows_to_read setting
+    read_limit: Option<u64>,
+    // log_comment setting for generated queries
+    query_tag: Option<String>,
+    // Request-scoped settings, with their values as given
+    settings: Vec<(String, String)>,
+    // Mutation statements were explicitly requested; not serialized, so a cached
+    // instance has to be confirmed again
+    #[cfg_attr(feature = "serde", serde(skip))]
+    allow_mutations: bool,
+    // Retries and timeouts of the client helpers; set per deployment, not per request
+    #[cfg_attr(feature = "serde", serde(skip))]
+    execution: execution::ExecutionPolicy,
+    // Rendered WHERE clause, cached on first use
+    #[cfg_attr(feature = "serde", serde(skip))]
+    where_clause: OnceLock<String>,
+}
+
+#[cfg(feature = "serde")]
+fn detached() -> bool {
+    true
 }
 
 impl ClickHouseFilters {
@@ -933,23 +1790,34 @@ impl ClickHouseFilters {
         pagination: Option<PaginationOptions>,
         sorting_columns: Vec<SortedColumn>,
         filtering_options: Option<FilteringOptions>,
-        column_defs: HashMap<&'static str, ColumnDef>,
+        column_defs: impl Into<ColumnRegistry>,
     ) -> Result<ClickHouseFilters> {
         // Create sorting component
-        let sorting = if sorting_columns.is_empty() {
+        for column in &sorting_columns {
+            column.validate()?;
+        }
+        let mut sorting = if sorting_columns.is_empty() {
             None
         } else {
-            Some(Sorting::new(sorting_columns))
+            Some(Sorting::in_order(sorting_columns))
         };
 
         // Create pagination component
+        if pagination.as_ref().is_some_and(|opts| opts.with_ties) && sorting.is_none() {
+            return Err(eyre::eyre!("WITH TIES pagination requires sorting"));
+        }
         let pagination = pagination.map(|opts| {
-            pagination::Paginate::new(
+            let paginate = pagination::Paginate::new(
                 opts.current_page,
                 opts.per_page,
                 opts.per_page_limit,
                 opts.total_records,
             )
+            .with_syntax(opts.syntax);
+            match opts.with_ties {
+                true => paginate.with_ties(),
+                false => paginate,
+            }
         });
 
         // Create filtering component
@@ -962,75 +1830,268 @@ impl ClickHouseFilters {
             None => None,
         };
 
+        // Break ties on the unique key so pages don't overlap
+        let column_defs: ColumnRegistry = column_defs.into();
+        if let Some(key) = column_defs.unique_key() {
+            key.validate(&column_defs)?;
+            if sorting.is_some() || pagination.is_some() {
+                let sorting_or_empty = sorting.unwrap_or_else(|| Sorting::new(vec![]));
+                sorting = Some(sorting_or_empty.with_tiebreaker(key));
+            }
+        }
+
         Ok(ClickHouseFilters {
             pagination,
             sorting,
             filters,
             column_defs,
+            detached: false,
+            row_policy: None,
+            include_deleted: false,
+            read_limit: None,
+            query_tag: None,
+            settings: Vec::new(),
+            allow_mutations: false,
+            execution: execution::ExecutionPolicy::default(),
+            where_clause: OnceLock::new(),
         })
     }
 
+    /// Include soft-deleted rows in generated queries
+    ///
+    /// Has no effect unless `column_defs` declares a soft-delete column.
+    pub fn include_deleted(mut self) -> Self {
+        self.include_deleted = true;
+        self.where_clause = OnceLock::new();
+        self
+    }
+
+    /// The filters, without the registry's implicit conditions
+    pub fn filters(&self) -> Option<&FilterBuilder> {
+        self.filters.as_ref()
+    }
+
+    /// Replace the filters
+    ///
+    /// The WHERE clause is rendered again on next use.
+    pub fn set_filters(&mut self, filters: Option<FilterBuilder>) {
+        self.filters = filters;
+        self.where_clause = OnceLock::new();
+    }
+
+    /// The column definitions the SQL is generated against
+    ///
+    /// Use `with_column_defs()` to replace them.
+    pub fn column_defs(&self) -> &ColumnRegistry {
+        &self.column_defs
+    }
+
+    /// Attach column definitions, e.g. to a deserialized instance
+    ///
+    /// The WHERE clause is rendered again on next use. Restores what isn't
+    /// serialized: the registry itself and the per-column case
+    /// overrides of the filters. A cached payload may have been altered, so the
+    /// parts that are rendered as SQL are checked again: request-scoped settings
+    /// must still be allowed, every filter condition, row policy and sort key
+    /// must name a registered column, relative dates and bucket intervals must
+    /// pass the checks JSON filters apply, and numeric range bounds must be
+    /// finite numbers.
+    pub fn with_column_defs(mut self, column_defs: impl Into<ColumnRegistry>) -> Result<Self> {
+        let column_defs: ColumnRegistry = column_defs.into();
+        if let Some((name, _)) = self
+            .settings
+            .iter()
+            .find(|(name, _)| !column_defs.allowed_settings().contains(&name.as_str()))
+        {
+            return Err(eyre::eyre!("Setting not allowed: {}", name));
+        }
+        let roots = self
+            .filters
+            .iter()
+            .filter_map(|filters| filters.root.as_ref());
+        for root in roots.chain(&self.row_policy) {
+            let mut check = PayloadCheck {
+                column_defs: &column_defs,
+                result: Ok(()),
+            };
+            root.walk(&mut check);
+            check.result?;
+        }
+        for column in self.sorting.iter().flat_map(|sorting| &sorting.columns) {
+            check_registered(&column_defs, &column.column)?;
+            column.validate()?;
+        }
+        if let Some(filters) = self.filters.take() {
+            self.filters = Some(filters.case_overrides(&column_defs));
+        }
+        self.column_defs = column_defs;
+        self.detached = false;
+        self.where_clause = OnceLock::new();
+        Ok(self)
+    }
+
+    /// Update the pagination with the real number of matching rows
+    ///
+    /// Meant for the count-then-query flow: run `count_sql()`, then pass its
+    /// result here before `query_sql()`. Does nothing without pagination, and
+    /// fails on a negative count rather than quietly treating it as zero.
+    pub fn set_total_records(&mut self, total_records: i64) -> Result<()> {
+        if total_records < 0 {
+            return Err(eyre::eyre!("Invalid total_records: {}", total_records));
+        }
+        if let Some(pagination) = &mut self.pagination {
+            pagination.recalculate(total_records);
+        }
+        Ok(())
+    }
+
     /// Generate the SQL for this filter
     pub fn sql(&self) -> Result<String> {
-        let mut sql = String::new();
+        let mut sql = String::with_capacity(self.estimated_sql_len());
+        self.write_clauses(&mut sql)?;
+        Ok(sql)
+    }
 
-        // Add WHERE clause from filters
-        if let Some(filters) = &self.filters {
-            sql.push_str(&filters.build()?);
+    /// Generate a SQL COUNT query for this filter
+    ///
+    /// Use `count_sql_with` to count distinct values instead of rows.
+    pub fn count_sql(&self, table: impl Into<table::TableRef>) -> Result<String> {
+        self.count_sql_with(table, &count::CountExpression::Rows)
+    }
+
+    /// Generate a complete SQL query for this filter
+    pub fn query_sql(&self, table: impl Into<table::TableRef>, columns: &[&str]) -> Result<String> {
+        let mut sql = String::with_capacity(32 + self.estimated_sql_len());
+        self.write_select(&mut sql, &table.into(), columns)?;
+        self.write_clauses(&mut sql)?;
+        self.write_settings(&mut sql, false)?;
+        Ok(sql)
+    }
+
+    // Write `SELECT columns FROM table` into the buffer
+    fn write_select(
+        &self,
+        sql: &mut String,
+        table: &table::TableRef,
+        columns: &[&str],
+    ) -> Result<()> {
+        sql.push_str("SELECT ");
+        if columns.is_empty() {
+            sql.push('*');
+        } else {
+            for (i, column) in columns.iter().enumerate() {
+                if i > 0 {
+                    sql.push_str(", ");
+                }
+                sql.push_str(column);
+            }
         }
+        sql.push_str(" FROM ");
+        table.write_sql(sql, false)
+    }
+
+    // Write the WHERE, ORDER BY and LIMIT clauses into the buffer
+    fn write_clauses(&self, sql: &mut String) -> Result<()> {
+        // Add WHERE clause from filters
+        sql.push_str(self.where_clause()?);
 
         // Add ORDER BY clause
         if let Some(sorting) = &self.sorting {
-            sql.push_str(&sorting.sql);
+            sorting.write_sql(sql);
         }
 
         // Add LIMIT and OFFSET
         if let Some(pagination) = &self.pagination {
-            sql.push(' ');
-            sql.push_str(&pagination.sql);
+            pagination.write_sql(sql);
         }
 
-        Ok(sql)
+        Ok(())
     }
 
-    /// Generate a SQL COUNT query for this filter
-    pub fn count_sql(&self, schema: &str, table: &str) -> Result<String> {
-        let mut sql = format!("SELECT COUNT(*) FROM {}.{}", schema, table);
-
-        // Add WHERE clause from filters
-        if let Some(filters) = &self.filters {
-            sql.push_str(&filters.build()?);
+    // Rendered WHERE clause (with leading space), built on first use
+    fn where_clause(&self) -> Result<&str> {
+        if let Some(sql) = self.where_clause.get() {
+            return Ok(sql);
         }
 
-        Ok(sql)
+        let mut sql = String::new();
+        if let Some(filters) = self.effective_filters()? {
+            filters.write_sql(&mut sql)?;
+        }
+        Ok(self.where_clause.get_or_init(|| sql))
     }
 
-    /// Generate a complete SQL query for this filter
-    pub fn query_sql(&self, schema: &str, table: &str, columns: &[&str]) -> Result<String> {
-        let columns_str = if columns.is_empty() {
-            "*".to_string()
-        } else {
-            columns.join(", ")
-        };
+    // The filters to render, with the soft-delete and sign conditions applied
+    pub(crate) fn effective_filters(&self) -> Result<Option<Cow<'_, FilterBuilder>>> {
+        self.filters_with_implicit(true)
+    }
 
-        let mut sql = format!("SELECT {} FROM {}.{}", columns_str, schema, table);
+    // The filters plus the registry's implicit conditions; counts that sum the
+    // sign column leave the sign condition out
+    fn filters_with_implicit(&self, sign: bool) -> Result<Option<Cow<'_, FilterBuilder>>> {
+        self.add_implicit(self.filters.as_ref().map(Cow::Borrowed), sign)
+    }
 
-        // Add WHERE clause from filters
-        if let Some(filters) = &self.filters {
-            sql.push_str(&filters.build()?);
+    // Add the registry's implicit conditions to a set of filters
+    pub(crate) fn add_implicit<'a>(
+        &self,
+        filters: Option<Cow<'a, FilterBuilder>>,
+        sign: bool,
+    ) -> Result<Option<Cow<'a, FilterBuilder>>> {
+        if self.detached {
+            return Err(eyre::eyre!(
+                "Column definitions missing after deserializing, call with_column_defs() first"
+            ));
+        }
+        let mut conditions = Vec::new();
+        if !self.include_deleted {
+            conditions.extend(self.column_defs.soft_delete_condition()?);
+        }
+        if sign {
+            conditions.extend(self.column_defs.sign_condition()?);
         }
 
-        // Add ORDER BY clause
-        if let Some(sorting) = &self.sorting {
-            sql.push_str(&sorting.sql);
+        if conditions.is_empty() && self.row_policy.is_none() {
+            return Ok(filters);
+        }
+        let mut builder = filters.map(Cow::into_owned).unwrap_or_default();
+        builder = self.add_row_policy(builder);
+        for condition in conditions {
+            builder = builder.add_condition(condition);
         }
+        Ok(Some(Cow::Owned(builder)))
+    }
 
-        // Add LIMIT and OFFSET
-        if let Some(pagination) = &self.pagination {
-            sql.push(' ');
-            sql.push_str(&pagination.sql);
+    // AND the row policy predicates with a set of filters, keeping the filters'
+    // tree as a single term so their ORs stay inside it
+    pub(crate) fn add_row_policy(&self, mut builder: FilterBuilder) -> FilterBuilder {
+        let Some(policy) = &self.row_policy else {
+            return builder;
+        };
+        // An empty group would render as a dangling operator, so drop it
+        if matches!(&builder.root, Some(FilterExpression::Group { expressions, .. }) if expressions.is_empty())
+        {
+            builder.root = None;
         }
+        builder.root = Some(match builder.root.take() {
+            Some(root) => FilterExpression::and(vec![root, policy.clone()]),
+            None => policy.clone(),
+        });
+        builder
+    }
 
-        Ok(sql)
+    // Rough rendered length of all clauses, used to pre-size output buffers
+    fn estimated_sql_len(&self) -> usize {
+        let filters = match self.where_clause.get() {
+            Some(sql) => sql.len(),
+            None => self
+                .filters
+                .as_ref()
+                .and_then(|f| f.root.as_ref())
+                .map_or(0, |root| 7 + root.estimated_sql_len()),
+        };
+        let sorting = self.sorting.as_ref().map_or(0, Sorting::estimated_sql_len);
+        let pagination = if self.pagination.is_some() { 32 } else { 0 };
+        filters + sorting + pagination
     }
 }
@@ -0,0 +1,279 @@
+//! Mutation statements built from filters
+//!
+//! `delete_sql()` turns the current filters into an `ALTER TABLE ... DELETE` mutation,
+//! `lightweight_delete_sql()` into a lightweight `DELETE FROM`, and `update_sql()` into
+//! an `ALTER TABLE ... UPDATE`. All are refused unless `allow_mutations()` was called,
+//! and none will run without a filter, so a missing WHERE clause can never touch a
+//! whole table. The schema and table names are validated as `TableRef` names are,
+//! so a name taken from a request can't smuggle in its own WHERE clause.
+//!
+//! Mutations match rows on the filters alone: the registry's soft-delete and sign
+//! conditions only scope reads, so soft-deleted rows are erased too. Sorting and
+//! pagination are ignored.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry, FilteringOptions};
+//!
+//! let columns: ColumnRegistry = column_registry! { user_id: UInt64 };
+//! let filtering = FilteringOptions::case_sensitive(
+//!     vec![FilterExpression::Condition(FilterCondition::UInt64Value {
+//!         column: "user_id".to_string(),
+//!         operator: FilterOperator::Equal,
+//!         value: Some(42),
+//!     })],
+//!     &columns,
+//! );
+//! let filters = ClickHouseFilters::new(None, vec![], Some(filtering), &columns).unwrap();
+//! assert!(filters.delete_sql("db", "events").is_err());
+//!
+//! let filters = filters.allow_mutations();
+//! assert_eq!(
+//!     filters.delete_sql("db", "events").unwrap(),
+//!     "ALTER TABLE db.events DELETE WHERE user_id = 42"
+//! );
+//! assert_eq!(
+//!     filters.lightweight_delete_sql("db", "events").unwrap(),
+//!     "DELETE FROM db.events WHERE user_id = 42"
+//! );
+//! ```
+//!
+//! Values in `update_sql()` assignments are typed and escaped by their column
+//! definition, like filter values:
+//!
+//! ```rust
+//! # use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! # use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry, FilteringOptions};
+//! use clickhouse_filters::mutation::Assignment;
+//!
+//! let columns: ColumnRegistry = column_registry! { id: UInt64, country: String, score: Float64 };
+//! let filtering = FilteringOptions::case_sensitive(
+//!     vec![FilterExpression::Condition(FilterCondition::string(
+//!         "country",
+//!         FilterOperator::Equal,
+//!         Some("UK"),
+//!     ))],
+//!     &columns,
+//! );
+//! let filters = ClickHouseFilters::new(None, vec![], Some(filtering), &columns)
+//!     .unwrap()
+//!     .allow_mutations();
+//!
+//! assert_eq!(
+//!     filters
+//!         .update_sql(
+//!             "db",
+//!             "users",
+//!             &[Assignment::new("country", "GB"), Assignment::new("score", "0")],
+//!         )
+//!         .unwrap(),
+//!     "ALTER TABLE db.users UPDATE country = 'GB', score = 0 WHERE country = 'UK'"
+//! );
+//! ```
+
+use crate::filtering::{parse_bool, Escaped};
+use crate::table::TableRef;
+use crate::{ClickHouseFilters, ColumnDef, ColumnRegistry};
+use eyre::Result;
+use std::fmt::Write;
+
+/// A `column = value` assignment for `update_sql()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub column: String,
+    /// The new value as it would appear in a filter, or `None` for NULL
+    pub value: Option<String>,
+}
+
+impl Assignment {
+    pub fn new(column: &str, value: &str) -> Self {
+        Self {
+            column: column.to_string(),
+            value: Some(value.to_string()),
+        }
+    }
+
+    /// Set the column to NULL
+    pub fn null(column: &str) -> Self {
+        Self {
+            column: column.to_string(),
+            value: None,
+        }
+    }
+
+    /// Render the assignment, typing and escaping the value by its column definition
+    ///
+    /// Array values are comma-separated, as in array filters.
+    pub fn write_sql(&self, out: &mut String, column_defs: &ColumnRegistry) -> Result<()> {
+        let column_def = column_defs
+            .get(&self.column)
+            .ok_or_else(|| eyre::eyre!("Column not found: {}", self.column))?;
+        write!(out, "{} = ", self.column)?;
+
+        let Some(value) = &self.value else {
+            out.push_str("NULL");
+            return Ok(());
+        };
+        if matches!(column_def.base(), ColumnDef::Map(_)) {
+            return Err(eyre::eyre!(
+                "Unsupported assignment column type: {}",
+                self.column
+            ));
+        }
+        write_literal(out, column_def, value)
+    }
+}
+
+// Write `value` as a literal of the column's type, parsing numbers and booleans
+// the way filter values are parsed and quoting everything else
+pub(crate) fn write_literal(out: &mut String, column_def: &ColumnDef, value: &str) -> Result<()> {
+    if let Some(element_type) = column_def.array_element_type() {
+        out.push('[');
+        for (i, element) in value.split(',').map(str::trim).enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            element_type.write_element(out, element)?;
+        }
+        out.push(']');
+        return Ok(());
+    }
+
+    let wide_integer = |type_name: &str, valid: bool| match valid {
+        true => Ok(()),
+        false => Err(eyre::eyre!("Invalid value for {}: {}", type_name, value)),
+    };
+    match column_def.base() {
+        // Parsed by their type exactly as filter values are, then written as given
+        base @ (ColumnDef::UInt8(_)
+        | ColumnDef::UInt16(_)
+        | ColumnDef::UInt32(_)
+        | ColumnDef::UInt64(_)
+        | ColumnDef::Int8(_)
+        | ColumnDef::Int16(_)
+        | ColumnDef::Int32(_)
+        | ColumnDef::Int64(_)
+        | ColumnDef::Float32(_)
+        | ColumnDef::Float64(_)
+        | ColumnDef::Decimal(_)) => {
+            base.to_filter_condition("=", value)?;
+            out.push_str(value);
+        }
+        ColumnDef::UInt128(_) => {
+            wide_integer("UInt128", value.parse::<u128>().is_ok())?;
+            out.push_str(value);
+        }
+        ColumnDef::Int128(_) => {
+            wide_integer("Int128", value.parse::<i128>().is_ok())?;
+            out.push_str(value);
+        }
+        ColumnDef::UInt256(_) => {
+            wide_integer("UInt256", is_digits(value))?;
+            out.push_str(value);
+        }
+        ColumnDef::Int256(_) => {
+            wide_integer(
+                "Int256",
+                is_digits(value.strip_prefix('-').unwrap_or(value)),
+            )?;
+            out.push_str(value);
+        }
+        ColumnDef::Boolean(_) => match parse_bool(value) {
+            Some(true) => out.push_str("true"),
+            Some(false) => out.push_str("false"),
+            None => return Err(eyre::eyre!("Invalid boolean value: {}", value)),
+        },
+        ColumnDef::Map(name) => {
+            return Err(eyre::eyre!("Unsupported literal column type: {}", name))
+        }
+        _ => write!(out, "'{}'", Escaped(value))?,
+    }
+    Ok(())
+}
+
+// Unsigned integer digits, for integers too wide for Rust's types
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+impl ClickHouseFilters {
+    /// Confirm that mutation statements may be generated from these filters
+    pub fn allow_mutations(mut self) -> Self {
+        self.allow_mutations = true;
+        self
+    }
+
+    /// Generate an `ALTER TABLE ... DELETE WHERE ...` mutation
+    pub fn delete_sql(&self, schema: &str, table: &str) -> Result<String> {
+        let where_clause = self.mutation_where_clause()?;
+        let mut sql = String::with_capacity(32 + where_clause.len());
+        sql.push_str("ALTER TABLE ");
+        TableRef::new(schema, table).write_sql(&mut sql, false)?;
+        write!(sql, " DELETE{}", where_clause)?;
+        Ok(sql)
+    }
+
+    /// Generate a lightweight `DELETE FROM ... WHERE ...` statement
+    pub fn lightweight_delete_sql(&self, schema: &str, table: &str) -> Result<String> {
+        let where_clause = self.mutation_where_clause()?;
+        let mut sql = String::with_capacity(32 + where_clause.len());
+        sql.push_str("DELETE FROM ");
+        TableRef::new(schema, table).write_sql(&mut sql, false)?;
+        sql.push_str(&where_clause);
+        Ok(sql)
+    }
+
+    /// Generate an `ALTER TABLE ... UPDATE col = value, ... WHERE ...` mutation
+    pub fn update_sql(
+        &self,
+        schema: &str,
+        table: &str,
+        assignments: &[Assignment],
+    ) -> Result<String> {
+        let where_clause = self.mutation_where_clause()?;
+        if assignments.is_empty() {
+            return Err(eyre::eyre!("UPDATE requires at least one assignment"));
+        }
+
+        let mut sql = String::with_capacity(64 + where_clause.len());
+        sql.push_str("ALTER TABLE ");
+        TableRef::new(schema, table).write_sql(&mut sql, false)?;
+        sql.push_str(" UPDATE ");
+        for (i, assignment) in assignments.iter().enumerate() {
+            if i > 0 {
+                sql.push_str(", ");
+            }
+            assignment.write_sql(&mut sql, &self.column_defs)?;
+        }
+        sql.push_str(&where_clause);
+        Ok(sql)
+    }
+
+    // The WHERE clause (with leading space) for a mutation, from the filters and
+    // row policies only
+    pub(crate) fn mutation_where_clause(&self) -> Result<String> {
+        if !self.allow_mutations {
+            return Err(eyre::eyre!(
+                "Mutations are disabled; call allow_mutations() to generate them"
+            ));
+        }
+
+        let mut sql = String::new();
+        if let Some(filters) = &self.filters {
+            filters.write_sql(&mut sql)?;
+        }
+        if sql.is_empty() {
+            return Err(eyre::eyre!(
+                "Refusing to generate a mutation without a filter"
+            ));
+        }
+        if self.row_policy.is_some() {
+            sql.clear();
+            let filters = self.filters.clone().unwrap_or_default();
+            self.add_row_policy(filters).write_sql(&mut sql)?;
+        }
+        Ok(sql)
+    }
+}
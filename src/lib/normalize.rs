@@ -0,0 +1,173 @@
+//! Normalization of filter values before rendering
+//!
+//! Values pasted into a UI often carry stray whitespace, and `name = 'John '` quietly
+//! matches nothing. `ValueNormalization` cleans the text values of conditions: it can
+//! trim them, collapse runs of whitespace to a single space, apply Unicode NFC
+//! normalization (with the `unicode-normalization` feature), and apply a custom
+//! function. `FilteringOptions::normalize_values` applies it to every condition at
+//! build time.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! use clickhouse_filters::normalize::ValueNormalization;
+//! use clickhouse_filters::{ColumnDef, FilteringOptions};
+//! use std::collections::HashMap;
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("name", ColumnDef::String("name"));
+//!
+//! let filtering = FilteringOptions::case_sensitive(
+//!     vec![FilterExpression::Condition(FilterCondition::string(
+//!         "name",
+//!         FilterOperator::Equal,
+//!         Some("  Mary   Ann "),
+//!     ))],
+//!     columns,
+//! )
+//! .normalize_values(ValueNormalization::new().trim().collapse_whitespace());
+//!
+//! assert_eq!(filtering.to_sql().unwrap(), " WHERE name = 'Mary Ann'");
+//! ```
+
+use crate::filtering::FilterCondition;
+use std::fmt;
+use std::sync::Arc;
+
+/// Custom normalization applied to each value, e.g. `|v| v.to_lowercase()`
+pub type Normalizer = dyn Fn(&str) -> String + Send + Sync;
+
+/// Which normalizations to apply to text values
+///
+/// The custom function runs first, then NFC normalization, trimming and whitespace
+/// collapsing.
+#[derive(Clone, Default)]
+pub struct ValueNormalization {
+    trim: bool,
+    collapse_whitespace: bool,
+    #[cfg(feature = "unicode-normalization")]
+    nfc: bool,
+    custom: Option<Arc<Normalizer>>,
+}
+
+impl ValueNormalization {
+    /// Normalization that leaves values unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove leading and trailing whitespace
+    pub fn trim(mut self) -> Self {
+        self.trim = true;
+        self
+    }
+
+    /// Replace each run of whitespace inside a value with a single space
+    pub fn collapse_whitespace(mut self) -> Self {
+        self.collapse_whitespace = true;
+        self
+    }
+
+    /// Compose values to Unicode NFC, so a decomposed `e` + U+0301 matches `é`
+    #[cfg(feature = "unicode-normalization")]
+    pub fn nfc(mut self) -> Self {
+        self.nfc = true;
+        self
+    }
+
+    /// Apply a custom function to each value
+    pub fn with<F>(mut self, normalizer: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.custom = Some(Arc::new(normalizer));
+        self
+    }
+
+    /// Normalize a single value
+    pub fn normalize(&self, value: &str) -> String {
+        let mut value = match &self.custom {
+            Some(custom) => custom(value),
+            None => value.to_string(),
+        };
+        #[cfg(feature = "unicode-normalization")]
+        if self.nfc {
+            use unicode_normalization::UnicodeNormalization;
+            value = value.nfc().collect();
+        }
+        if self.trim {
+            value = value.trim().to_string();
+        }
+        if self.collapse_whitespace {
+            value = collapse(&value);
+        }
+        value
+    }
+
+    /// Normalize the text values of a condition
+    ///
+    /// Covers string, IN list, array, map and JSON values. Numbers, dates,
+    /// booleans and UUIDs are already validated when the condition is built, and
+    /// are left unchanged.
+    pub fn apply(&self, condition: FilterCondition) -> FilterCondition {
+        let mut condition = condition;
+        match &mut condition {
+            FilterCondition::StringValue { value, .. }
+            | FilterCondition::FixedStringValue { value, .. }
+            | FilterCondition::MapValue { value, .. }
+            | FilterCondition::JSONValue { value, .. } => {
+                if let Some(value) = value {
+                    *value = self.normalize(value);
+                }
+            }
+            FilterCondition::InValues { values, .. }
+            | FilterCondition::ArrayJoinIn { values, .. }
+            | FilterCondition::ArrayValues { values, .. }
+            | FilterCondition::ArrayOverlap { values, .. } => {
+                for value in values {
+                    *value = self.normalize(value);
+                }
+            }
+            FilterCondition::ArrayContains { value, .. }
+            | FilterCondition::ArrayHas { value, .. }
+            | FilterCondition::ArrayElement { value, .. }
+            | FilterCondition::ArrayCount { value, .. }
+            | FilterCondition::IndexedText { value, .. } => {
+                *value = self.normalize(value);
+            }
+            _ => {}
+        }
+        condition
+    }
+}
+
+// Whitespace runs become one space; leading and trailing runs are kept as one space
+fn collapse(value: &str) -> String {
+    let mut collapsed = String::with_capacity(value.len());
+    let mut in_whitespace = false;
+    for c in value.chars() {
+        if c.is_whitespace() {
+            if !in_whitespace {
+                collapsed.push(' ');
+            }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
+        }
+    }
+    collapsed
+}
+
+impl fmt::Debug for ValueNormalization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ValueNormalization");
+        debug
+            .field("trim", &self.trim)
+            .field("collapse_whitespace", &self.collapse_whitespace);
+        #[cfg(feature = "unicode-normalization")]
+        debug.field("nfc", &self.nfc);
+        debug.field("custom", &self.custom.is_some()).finish()
+    }
+}
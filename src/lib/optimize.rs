@@ -0,0 +1,431 @@
+//! Filter simplification
+//!
+//! Composed filters (merged base filters, hooks, UI-built trees) often carry redundant
+//! structure: nested groups with the same operator, repeated conditions, single-child
+//! groups and long chains of `col = a OR col = b`. `FilterExpression::optimize()`
+//! rewrites such trees into an equivalent but simpler form.
+//!
+//! `order_by_selectivity()` reorders the terms of AND groups so the cheapest and most
+//! selective predicates come first: primary-key columns, then equality and IN, then
+//! ranges, with pattern matches and nested groups last. `SelectivityHints` names the
+//! primary-key columns and per-column cardinalities used to rank them.
+//!
+//! `split_in_lists()` rewrites IN/NOT IN lists above a size threshold, either into
+//! several smaller lists or into a single `IN (SELECT arrayJoin([...]))`, for bulk
+//! lookups that would otherwise send one enormous tuple to the server.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//!
+//! let status = |value| {
+//!     FilterExpression::Condition(FilterCondition::string("status", FilterOperator::Equal, Some(value)))
+//! };
+//! let expr = FilterExpression::or(vec![
+//!     status("open"),
+//!     FilterExpression::or(vec![status("paid"), status("open")]),
+//! ]);
+//!
+//! assert_eq!(
+//!     expr.optimize().to_sql(false).unwrap(),
+//!     "status IN ('open', 'paid')"
+//! );
+//! ```
+
+use crate::filtering::{
+    unique, ColumnTypeInfo, FilterBuilder, FilterCondition, FilterExpression, FilterOperator,
+    LogicalOperator,
+};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+impl FilterExpression {
+    /// Simplify this expression without changing its meaning
+    ///
+    /// - nested groups with the same operator are flattened
+    /// - empty groups and duplicate conditions are removed
+    /// - groups with a single child are replaced by that child
+    /// - within OR groups, equality conditions on the same column are merged
+    ///   into a single IN list
+    pub fn optimize(&self) -> FilterExpression {
+        optimize(self.clone())
+    }
+}
+
+impl FilterBuilder {
+    /// Simplify the root expression; see `FilterExpression::optimize`
+    pub fn optimize(mut self) -> Self {
+        self.root = self.root.map(optimize);
+        self
+    }
+}
+
+/// Column statistics used to rank predicates by selectivity
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SelectivityHints {
+    primary_key: Vec<String>,
+    cardinality: HashMap<String, u64>,
+}
+
+impl SelectivityHints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the table's primary-key columns, whose predicates are ranked first
+    pub fn with_primary_key(mut self, columns: &[&str]) -> Self {
+        self.primary_key = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    /// Set the number of distinct values in a column
+    ///
+    /// Among predicates of the same kind, those on higher-cardinality columns are
+    /// ranked first.
+    pub fn with_cardinality(mut self, column: &str, distinct_values: u64) -> Self {
+        self.cardinality.insert(column.to_string(), distinct_values);
+        self
+    }
+
+    // Lower ranks sort first: (kind of predicate, higher cardinality first)
+    fn rank(&self, expression: &FilterExpression) -> (u8, Reverse<u64>) {
+        let FilterExpression::Condition(condition) = expression else {
+            return (6, Reverse(0));
+        };
+        let column = condition.column();
+        let primary_key = self.primary_key.iter().any(|c| c == column);
+        let kind = match condition.operator() {
+            Some(FilterOperator::Equal | FilterOperator::In | FilterOperator::DateEqual) => {
+                if primary_key {
+                    0
+                } else {
+                    2
+                }
+            }
+            Some(
+                FilterOperator::GreaterThan
+                | FilterOperator::GreaterThanOrEqual
+                | FilterOperator::LessThan
+                | FilterOperator::LessThanOrEqual
+                | FilterOperator::DateRange
+                | FilterOperator::RelativeDate
+                | FilterOperator::StartsWith,
+            ) => {
+                if primary_key {
+                    1
+                } else {
+                    3
+                }
+            }
+            Some(
+                FilterOperator::Like
+                | FilterOperator::NotLike
+                | FilterOperator::EndsWith
+                | FilterOperator::Contains
+                | FilterOperator::MultiMatchAny
+                | FilterOperator::MultiSearchAny,
+            ) => 5,
+            _ => 4,
+        };
+        let cardinality = self.cardinality.get(column).copied().unwrap_or(0);
+        (kind, Reverse(cardinality))
+    }
+}
+
+impl FilterExpression {
+    /// Reorder the terms of every AND group by estimated selectivity
+    ///
+    /// Terms are ranked primary-key equality, primary-key range, equality and IN,
+    /// range, other conditions, LIKE, then nested groups. Ties keep their
+    /// original order. OR groups keep their order but their children are
+    /// reordered.
+    pub fn order_by_selectivity(&self, hints: &SelectivityHints) -> FilterExpression {
+        order_by_selectivity(self.clone(), hints)
+    }
+}
+
+impl FilterBuilder {
+    /// Reorder the root expression; see `FilterExpression::order_by_selectivity`
+    pub fn order_by_selectivity(mut self, hints: &SelectivityHints) -> Self {
+        self.root = self.root.map(|root| order_by_selectivity(root, hints));
+        self
+    }
+}
+
+fn order_by_selectivity(
+    expression: FilterExpression,
+    hints: &SelectivityHints,
+) -> FilterExpression {
+    let FilterExpression::Group {
+        operator,
+        expressions,
+    } = expression
+    else {
+        return expression;
+    };
+
+    let mut expressions: Vec<_> = expressions
+        .into_iter()
+        .map(|child| order_by_selectivity(child, hints))
+        .collect();
+    if operator == LogicalOperator::And {
+        expressions.sort_by_cached_key(|child| hints.rank(child));
+    }
+    FilterExpression::Group {
+        operator,
+        expressions,
+    }
+}
+
+/// How `split_in_lists` rewrites large IN/NOT IN lists
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InListStrategy {
+    /// Split into lists of at most this many values: ORed `IN` groups, or
+    /// ANDed `NOT IN` groups
+    Chunks(usize),
+    /// Render as `column IN (SELECT arrayJoin([...]))`
+    ArrayJoin,
+}
+
+impl FilterExpression {
+    /// Rewrite IN/NOT IN lists with more than `threshold` distinct values
+    ///
+    /// Duplicate values are removed first, so only the distinct count is
+    /// compared with `threshold`. Smaller lists are left unchanged.
+    pub fn split_in_lists(&self, threshold: usize, strategy: InListStrategy) -> FilterExpression {
+        self.clone()
+            .map(|condition| split_in_list(condition, threshold, strategy))
+    }
+}
+
+impl FilterBuilder {
+    /// Rewrite large IN lists in the root expression; see `FilterExpression::split_in_lists`
+    pub fn split_in_lists(mut self, threshold: usize, strategy: InListStrategy) -> Self {
+        self.root = self
+            .root
+            .map(|root| root.split_in_lists(threshold, strategy));
+        self
+    }
+}
+
+fn split_in_list(
+    condition: FilterCondition,
+    threshold: usize,
+    strategy: InListStrategy,
+) -> FilterExpression {
+    // Comma-separated string and UUID lists become typed value lists
+    let (column, operator, values, column_type) = match &condition {
+        FilterCondition::InValues {
+            column,
+            operator,
+            values,
+            column_type,
+        } => (
+            column,
+            operator,
+            unique_values(values.iter().map(String::as_str)),
+            column_type.clone(),
+        ),
+        FilterCondition::StringValue {
+            column,
+            operator: operator @ (FilterOperator::In | FilterOperator::NotIn),
+            value: Some(value),
+        }
+        | FilterCondition::FixedStringValue {
+            column,
+            operator: operator @ (FilterOperator::In | FilterOperator::NotIn),
+            value: Some(value),
+        } => (
+            column,
+            operator,
+            unique_values(value.split(',').map(str::trim)),
+            Some(ColumnTypeInfo::String),
+        ),
+        FilterCondition::UUIDValue {
+            column,
+            operator: operator @ (FilterOperator::In | FilterOperator::NotIn),
+            value: Some(value),
+        } => (
+            column,
+            operator,
+            unique_values(value.split(',').map(str::trim)),
+            Some(ColumnTypeInfo::UUID),
+        ),
+        _ => return FilterExpression::Condition(condition),
+    };
+    if values.len() <= threshold || !matches!(operator, FilterOperator::In | FilterOperator::NotIn)
+    {
+        return FilterExpression::Condition(condition);
+    }
+
+    match strategy {
+        InListStrategy::ArrayJoin => FilterExpression::Condition(FilterCondition::ArrayJoinIn {
+            column: column.clone(),
+            operator: operator.clone(),
+            values,
+            column_type,
+        }),
+        InListStrategy::Chunks(size) => {
+            let chunks = values
+                .chunks(size.max(1))
+                .map(|chunk| {
+                    FilterExpression::Condition(FilterCondition::in_values(
+                        column,
+                        operator.clone(),
+                        chunk.to_vec(),
+                        column_type.clone(),
+                    ))
+                })
+                .collect();
+            let group_operator = match operator {
+                FilterOperator::NotIn => LogicalOperator::And,
+                _ => LogicalOperator::Or,
+            };
+            FilterExpression::Group {
+                operator: group_operator,
+                expressions: chunks,
+            }
+        }
+    }
+}
+
+fn unique_values<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    unique(values).map(str::to_string).collect()
+}
+
+fn optimize(expression: FilterExpression) -> FilterExpression {
+    let FilterExpression::Group {
+        operator,
+        expressions,
+    } = expression
+    else {
+        return expression;
+    };
+
+    let mut flat = Vec::with_capacity(expressions.len());
+    for child in expressions {
+        match optimize(child) {
+            FilterExpression::Group {
+                operator: child_operator,
+                expressions: children,
+            } if child_operator == operator || children.is_empty() => flat.extend(children),
+            child => flat.push(child),
+        }
+    }
+
+    let mut unique: Vec<FilterExpression> = Vec::with_capacity(flat.len());
+    for child in flat {
+        if !unique.contains(&child) {
+            unique.push(child);
+        }
+    }
+
+    if operator == LogicalOperator::Or {
+        unique = merge_equalities(unique);
+    }
+
+    if unique.len() == 1 {
+        return unique.remove(0);
+    }
+    FilterExpression::Group {
+        operator,
+        expressions: unique,
+    }
+}
+
+// Values matched by an equality (or IN) condition, grouped by column and type
+fn equality_values(condition: &FilterCondition) -> Option<(&str, ColumnTypeInfo, Vec<String>)> {
+    let column = condition.column();
+    match condition {
+        FilterCondition::StringValue {
+            operator: FilterOperator::Equal,
+            value: Some(value),
+            ..
+        }
+        | FilterCondition::FixedStringValue {
+            operator: FilterOperator::Equal,
+            value: Some(value),
+            ..
+        } => Some((column, ColumnTypeInfo::String, vec![value.clone()])),
+        FilterCondition::UUIDValue {
+            operator: FilterOperator::Equal,
+            value: Some(value),
+            ..
+        } => Some((column, ColumnTypeInfo::UUID, vec![value.clone()])),
+        FilterCondition::IPv4Value {
+            operator: FilterOperator::Equal,
+            value: Some(value),
+            ..
+        } => Some((column, ColumnTypeInfo::IPv4, vec![value.to_string()])),
+        FilterCondition::IPv6Value {
+            operator: FilterOperator::Equal,
+            value: Some(value),
+            ..
+        } => Some((column, ColumnTypeInfo::IPv6, vec![value.to_string()])),
+        FilterCondition::InValues {
+            operator: FilterOperator::In,
+            values,
+            column_type: Some(column_type),
+            ..
+        } => Some((column, column_type.clone(), values.clone())),
+        _ => match (condition.operator(), condition.numeric_value()) {
+            (Some(FilterOperator::Equal), Some(value)) => {
+                Some((column, ColumnTypeInfo::Numeric, vec![value.to_string()]))
+            }
+            _ => None,
+        },
+    }
+}
+
+// Merge equality conditions on the same column into IN lists, in place of the first one
+fn merge_equalities(children: Vec<FilterExpression>) -> Vec<FilterExpression> {
+    struct Slot {
+        column: String,
+        column_type: ColumnTypeInfo,
+        position: usize,
+        values: Vec<String>,
+        merged: usize,
+    }
+
+    let mut slots: Vec<Slot> = Vec::new();
+    let mut out = Vec::with_capacity(children.len());
+
+    for child in children {
+        if let FilterExpression::Condition(condition) = &child {
+            if let Some((column, column_type, values)) = equality_values(condition) {
+                let existing = slots
+                    .iter_mut()
+                    .find(|slot| slot.column == column && slot.column_type == column_type);
+                if let Some(slot) = existing {
+                    for value in values {
+                        if !slot.values.contains(&value) {
+                            slot.values.push(value);
+                        }
+                    }
+                    slot.merged += 1;
+                    continue;
+                }
+                slots.push(Slot {
+                    column: column.to_string(),
+                    column_type,
+                    position: out.len(),
+                    values,
+                    merged: 1,
+                });
+            }
+        }
+        out.push(child);
+    }
+
+    for slot in slots.into_iter().filter(|slot| slot.merged > 1) {
+        out[slot.position] = FilterExpression::Condition(FilterCondition::in_values(
+            &slot.column,
+            FilterOperator::In,
+            slot.values,
+            Some(slot.column_type),
+        ));
+    }
+
+    out
+}
@@ -6,7 +6,7 @@
 //! # Example
 //!
 //! ```rust
-//! use clickhouse_filters::pagination::Paginate;
+//! use clickhouse_filters::pagination::{Paginate, PaginationSyntax};
 //!
 //! let paginate = Paginate::new(1, 10, 10, 1000);
 //! assert_eq!(paginate.pagination.current_page, 1);
@@ -15,11 +15,59 @@
 //! assert_eq!(paginate.pagination.total_pages, 100);
 //! assert_eq!(paginate.pagination.per_page, 10);
 //! assert_eq!(paginate.pagination.total_records, 1000);
-//! assert_eq!(paginate.sql, "LIMIT 10 OFFSET 0");
+//! assert_eq!(paginate.to_sql(), " LIMIT 10 OFFSET 0");
+//! assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 0");
+//!
+//! // Some SQL proxies only understand one form of the clause
+//! let paginate = Paginate::new(3, 10, 10, 1000).with_syntax(PaginationSyntax::OffsetFetch);
+//! assert_eq!(paginate.to_fragment(), "OFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY");
 //! ```
+//!
+//! Event feeds page by time instead: `TimeWindowPagination` returns the rows
+//! older (or newer) than an anchor timestamp, and the last row of each page
+//! anchors the next one. With a unique key in the registry, the anchor row's key
+//! breaks timestamp ties.
+//!
+//! ```rust
+//! use clickhouse_filters::pagination::TimeWindowPagination;
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry};
+//!
+//! let columns: ColumnRegistry = column_registry! { ts: DateTime, kind: String };
+//! let window = TimeWindowPagination::new("ts", 50).with_anchor("2024-06-01 12:00:00");
+//!
+//! let filters = ClickHouseFilters::new(None, vec![], None, &columns)
+//!     .unwrap()
+//!     .with_time_window(&window)
+//!     .unwrap();
+//! assert_eq!(
+//!     filters.sql().unwrap(),
+//!     " WHERE ts < '2024-06-01 12:00:00' ORDER BY ts DESC LIMIT 50 OFFSET 0"
+//! );
+//!
+//! let columns = column_registry! { id: UInt64, ts: DateTime }.with_unique_key(&["id"]);
+//! let filters = ClickHouseFilters::new(None, vec![], None, &columns)
+//!     .unwrap()
+//!     .with_time_window(&window.next_with_key("2024-06-01 11:59:00", &["42"]))
+//!     .unwrap();
+//! assert_eq!(
+//!     filters.sql().unwrap(),
+//!     " WHERE (ts, id) < ('2024-06-01 11:59:00', 42) ORDER BY ts DESC, id DESC LIMIT 50 OFFSET 0"
+//! );
+//! ```
+
+use crate::custom::ToSqlCondition;
+use crate::filtering::{FilterBuilder, FilterExpression};
+use crate::mutation::write_literal;
+use crate::sorting::{SortOrder, SortTarget, SortedColumn, Sorting};
+use crate::{ClickHouseFilters, ColumnDef};
+use eyre::Result;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
 
 /// Pagination metadata
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Pagination {
     pub current_page: i64,
     pub previous_page: i64,
@@ -64,13 +112,36 @@ impl Pagination {
     }
 }
 
+/// How the LIMIT and OFFSET clause is written
+///
+/// ClickHouse accepts all three forms; they only differ for tools that parse or
+/// rewrite the SQL.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PaginationSyntax {
+    /// `LIMIT 10 OFFSET 20`
+    #[default]
+    LimitOffset,
+    /// `LIMIT 20, 10`
+    LimitComma,
+    /// `OFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY`
+    OffsetFetch,
+}
+
 /// SQL pagination with metadata
-#[derive(Debug, Clone)]
+///
+/// The LIMIT and OFFSET clause is rendered on demand from the metadata.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Paginate {
     /// Pagination metadata
     pub pagination: Pagination,
-    /// SQL LIMIT and OFFSET clause
-    pub sql: String,
+    /// How the clause is written
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub syntax: PaginationSyntax,
+    /// Also return rows that tie with the last row of the page, see `with_ties()`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub with_ties: bool,
 }
 
 impl Paginate {
@@ -114,16 +185,325 @@ impl Paginate {
             current_page
         };
 
-        // Calculate LIMIT and OFFSET
-        let limit = per_page;
-        let offset = (limit * current_page) - limit;
-
         // Create pagination metadata
         let pagination = Pagination::new(current_page, per_page, total_pages, total_records);
 
-        // Generate SQL
-        let sql = format!("LIMIT {} OFFSET {}", limit, offset);
+        Paginate {
+            pagination,
+            syntax: PaginationSyntax::default(),
+            with_ties: false,
+        }
+    }
+
+    /// Write the clause with `syntax` instead of `LIMIT n OFFSET m`
+    pub fn with_syntax(mut self, syntax: PaginationSyntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Add `WITH TIES`, so a page also includes the rows that sort equal to its
+    /// last row instead of cutting them off
+    ///
+    /// Pages can then hold more than `per_page` rows, and the next page starts at
+    /// the usual offset, so tied rows may appear on both. ClickHouse requires an
+    /// ORDER BY clause with `WITH TIES`.
+    pub fn with_ties(mut self) -> Self {
+        self.with_ties = true;
+        self
+    }
+
+    /// Number of rows skipped before the current page
+    pub fn offset(&self) -> i64 {
+        self.pagination.per_page * (self.pagination.current_page - 1)
+    }
+
+    /// The LIMIT and OFFSET clause with a leading space, e.g. ` LIMIT 10 OFFSET 20`
+    pub fn to_sql(&self) -> String {
+        let mut sql = String::with_capacity(32);
+        self.write_sql(&mut sql);
+        sql
+    }
+
+    /// The LIMIT and OFFSET clause without the leading space, e.g. `LIMIT 10 OFFSET 20`
+    pub fn to_fragment(&self) -> String {
+        let (limit, offset) = (self.pagination.per_page, self.offset());
+        let ties = if self.with_ties { " WITH TIES" } else { "" };
+        match self.syntax {
+            PaginationSyntax::LimitOffset => {
+                format!("LIMIT {} OFFSET {}{}", limit, offset, ties)
+            }
+            PaginationSyntax::LimitComma => format!("LIMIT {}, {}{}", offset, limit, ties),
+            PaginationSyntax::OffsetFetch if self.with_ties => {
+                format!(
+                    "OFFSET {} ROWS FETCH FIRST {} ROWS WITH TIES",
+                    offset, limit
+                )
+            }
+            PaginationSyntax::OffsetFetch => {
+                format!("OFFSET {} ROWS FETCH NEXT {} ROWS ONLY", offset, limit)
+            }
+        }
+    }
+
+    /// Write the LIMIT and OFFSET clause, with a leading space, into an existing buffer
+    pub fn write_sql(&self, out: &mut String) {
+        out.push(' ');
+        out.push_str(&self.to_fragment());
+    }
+
+    /// Rebuild the metadata for a new number of records
+    ///
+    /// The current page, page size, syntax and `WITH TIES` are kept, except that the page is clamped
+    /// to the last page as in `new`. Use this once the real count is known instead
+    /// of building the pagination again.
+    pub fn recalculate(&mut self, total_records: i64) {
+        let with_ties = self.with_ties;
+        *self = Paginate::new(
+            self.pagination.current_page,
+            self.pagination.per_page,
+            self.pagination.per_page,
+            total_records,
+        )
+        .with_syntax(self.syntax);
+        self.with_ties = with_ties;
+    }
+}
+
+/// Paging through rows by a timestamp column instead of by page number
+///
+/// Each page holds the `window` rows before the anchor, newest first, or after
+/// it, oldest first, with an ascending `direction`. Without an anchor the first
+/// page starts at the newest (or oldest) row. Unlike page numbers, pages don't
+/// shift when new rows arrive.
+///
+/// When the registry declares a unique key, anchor pages with the last row's key
+/// as well, through `next_with_key()`, so rows sharing its timestamp aren't
+/// skipped. Anchored by timestamp alone, those rows are skipped.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TimeWindowPagination {
+    /// Date or time column to page by
+    pub column: String,
+    /// Timestamp the page starts after, exclusive
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub anchor: Option<String>,
+    /// Unique key values of the row the page starts after, one per key column
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub anchor_key: Option<Vec<String>>,
+    /// `Desc` pages back in time, `Asc` forward
+    pub direction: SortOrder,
+    /// Rows per page
+    pub window: i64,
+}
+
+impl TimeWindowPagination {
+    /// First page of `window` rows, newest first
+    pub fn new(column: &str, window: i64) -> Self {
+        TimeWindowPagination {
+            column: column.to_string(),
+            anchor: None,
+            anchor_key: None,
+            direction: SortOrder::Desc,
+            window,
+        }
+    }
+
+    /// Start the page after `anchor`, e.g. the timestamp of the previous page's last row
+    pub fn with_anchor(mut self, anchor: &str) -> Self {
+        self.anchor = Some(anchor.to_string());
+        self
+    }
+
+    /// Break ties on the anchor timestamp by the registry's unique key, e.g. the
+    /// key of the previous page's last row
+    pub fn with_anchor_key(mut self, key: &[&str]) -> Self {
+        self.anchor_key = Some(key.iter().map(|value| value.to_string()).collect());
+        self
+    }
+
+    /// Page forward in time with `SortOrder::Asc`
+    pub fn with_direction(mut self, direction: SortOrder) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// The window following this one, given the timestamp of its last row
+    pub fn next(&self, last: &str) -> Self {
+        let mut next = self.clone().with_anchor(last);
+        next.anchor_key = None;
+        next
+    }
+
+    /// The window following this one, given the timestamp and unique key of its last row
+    pub fn next_with_key(&self, last: &str, key: &[&str]) -> Self {
+        self.clone().with_anchor(last).with_anchor_key(key)
+    }
+}
+
+// A comparison against key values, e.g. `(ts, id) < (anchor, 42)`, rendered up
+// front because filter conditions don't order every key type
+#[derive(Debug)]
+struct KeyComparison {
+    columns: Vec<String>,
+    sql: String,
+}
+
+impl ToSqlCondition for KeyComparison {
+    fn write_sql(&self, out: &mut String, _case_insensitive: bool) -> Result<()> {
+        out.push_str(&self.sql);
+        Ok(())
+    }
+
+    fn columns(&self) -> Vec<&str> {
+        self.columns.iter().map(String::as_str).collect()
+    }
+}
+
+impl ClickHouseFilters {
+    /// Page by time window instead of page number
+    ///
+    /// ANDs `column < anchor` (or `>` for ascending windows) with the existing
+    /// filters, orders by the column, with the registry's unique key as a
+    /// tiebreaker in the same direction, and limits the query to `window` rows,
+    /// capped by the registry's page size limit. Replaces any sorting and
+    /// pagination.
+    ///
+    /// With an anchor key the condition compares tuples instead, e.g.
+    /// `(ts, id) < (anchor, anchor_id)`, which needs a unique key in the registry.
+    pub fn with_time_window(mut self, window: &TimeWindowPagination) -> Result<Self> {
+        let column_def = self
+            .column_defs
+            .get(&window.column)
+            .ok_or_else(|| eyre::eyre!("Column not found: {}", window.column))?;
+        if !matches!(
+            column_def.base(),
+            ColumnDef::Date(_)
+                | ColumnDef::Date32(_)
+                | ColumnDef::DateTime(_)
+                | ColumnDef::DateTime64(_)
+                | ColumnDef::DateTime64Precision(..)
+        ) {
+            return Err(eyre::eyre!(
+                "Unsupported time window column type: {}",
+                window.column
+            ));
+        }
+        if window.window < 1 {
+            return Err(eyre::eyre!("Invalid time window size: {}", window.window));
+        }
+
+        let unique_key = self.column_defs.unique_key();
+        if window.anchor.is_none() && window.anchor_key.is_some() {
+            return Err(eyre::eyre!("Time window anchor key requires an anchor"));
+        }
+        if let Some(anchor) = &window.anchor {
+            let operator = match window.direction {
+                SortOrder::Desc => "<",
+                SortOrder::Asc => ">",
+            };
+            // Validates the anchor as it would be as a filter value
+            let condition = column_def.to_filter_condition(operator, anchor)?;
+            let expression = match (&window.anchor_key, unique_key) {
+                (None, _) => FilterExpression::Condition(condition),
+                (Some(_), None) => {
+                    return Err(eyre::eyre!(
+                        "Time window anchor key requires a unique key in the registry"
+                    ))
+                }
+                (Some(values), Some(key)) => {
+                    let key_columns: Vec<&str> = key
+                        .columns()
+                        .iter()
+                        .copied()
+                        .filter(|column| *column != window.column)
+                        .collect();
+                    if values.len() != key_columns.len() {
+                        return Err(eyre::eyre!(
+                            "Expected {} time window anchor key values, got {}",
+                            key_columns.len(),
+                            values.len()
+                        ));
+                    }
+
+                    let mut columns = vec![window.column.clone()];
+                    columns.extend(key_columns.iter().map(|column| column.to_string()));
+                    let mut sql = format!("({}) {} (", columns.join(", "), operator);
+                    write_literal(&mut sql, column_def, anchor)?;
+                    for (column, value) in key_columns.iter().zip(values) {
+                        let key_def = self
+                            .column_defs
+                            .get(column)
+                            .ok_or_else(|| eyre::eyre!("Column not found: {}", column))?;
+                        sql.push_str(", ");
+                        key_def.to_filter_condition("=", value)?;
+                        write_literal(&mut sql, key_def, value)?;
+                    }
+                    sql.push(')');
+                    FilterExpression::Custom(Arc::new(KeyComparison { columns, sql }))
+                }
+            };
+            let filters = self
+                .filters
+                .take()
+                .unwrap_or_else(|| FilterBuilder::new().case_overrides(&self.column_defs));
+            self.filters = Some(filters.add_expression(expression));
+        }
+
+        // The key sorts in the window's direction, so a keyed anchor continues
+        // exactly where the previous page stopped
+        let mut sorting = Sorting::new(vec![SortedColumn {
+            column: window.column.clone(),
+            order: window.direction.clone(),
+            target: SortTarget::Column,
+        }]);
+        if let Some(key) = unique_key {
+            sorting = sorting.with_ordered_tiebreaker(key, window.direction.clone());
+        }
+        self.sorting = Some(sorting);
+
+        let limit = self.column_defs.per_page_limit().unwrap_or(window.window);
+        self.pagination = Some(Paginate::new(1, window.window, limit, 0));
+        self.where_clause = OnceLock::new();
+        Ok(self)
+    }
+
+    /// One batch of rows paged by a key column, e.g. for exporting every row
+    ///
+    /// ANDs `key_column > after` with the existing filters, orders by the key
+    /// and limits the query to `batch_size` rows. Replaces any sorting and
+    /// pagination. The comparison is rendered directly, so string and UUID keys
+    /// can be paged by too.
+    pub fn with_key_batch(
+        mut self,
+        key_column: &str,
+        after: Option<&str>,
+        batch_size: u64,
+    ) -> Result<Self> {
+        let column_def = self
+            .column_defs
+            .get(key_column)
+            .ok_or_else(|| eyre::eyre!("Column not found: {}", key_column))?;
+
+        if let Some(after) = after {
+            column_def.to_filter_condition("=", after)?;
+            let mut sql = format!("{} > ", key_column);
+            write_literal(&mut sql, column_def, after)?;
+            let comparison = KeyComparison {
+                columns: vec![key_column.to_string()],
+                sql,
+            };
+            let filters = self
+                .filters
+                .take()
+                .unwrap_or_else(|| FilterBuilder::new().case_overrides(&self.column_defs));
+            self.filters =
+                Some(filters.add_expression(FilterExpression::Custom(Arc::new(comparison))));
+        }
 
-        Paginate { pagination, sql }
+        let per_page = batch_size.max(1) as i64;
+        self.sorting = Some(Sorting::new(vec![SortedColumn::new(key_column, "asc")]));
+        self.pagination = Some(Paginate::new(1, per_page, per_page, 0));
+        self.where_clause = OnceLock::new();
+        Ok(self)
     }
 }
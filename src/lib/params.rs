@@ -0,0 +1,691 @@
+//! Named query parameters
+//!
+//! By default filter values are escaped and inlined into the SQL. The `*_with_params`
+//! methods render them as ClickHouse query parameters instead (`{name_0:String}`) and
+//! return the values in `QueryParams`, so the query can be sent through the HTTP
+//! interface with each value as a `param_<name>` query-string argument.
+//!
+//! Parameter names are the column name (non-identifier characters replaced by `_`)
+//! followed by a counter that is unique within the query. `RELATIVE DATE`
+//! expressions, which are SQL by design, and the values of array element, array
+//! count, array overlap, `arrayJoin` IN, map and JSON conditions are still inlined.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterBuilder, FilterCondition, FilterOperator};
+//!
+//! let builder = FilterBuilder::new()
+//!     .add_condition(FilterCondition::string("name", FilterOperator::Equal, Some("O'Brien")))
+//!     .add_condition(FilterCondition::uint32("age", FilterOperator::GreaterThan, Some(30)));
+//!
+//! let (sql, params) = builder.build_with_params().unwrap();
+//! assert_eq!(sql, " WHERE (name = {name_0:String} AND age > {age_1:UInt32})");
+//! assert_eq!(params.get("name_0").unwrap(), "O'Brien");
+//! assert_eq!(
+//!     params.http_params(),
+//!     vec![
+//!         ("param_age_1".to_string(), "30".to_string()),
+//!         ("param_name_0".to_string(), "O'Brien".to_string()),
+//!     ]
+//! );
+//! ```
+//!
+//! `QueryParams::positional()` renders `?` placeholders instead, in the form the
+//! `clickhouse` crate's `query(...).bind(...)` expects, and collects the values in
+//! placeholder order. Any other `?` in the SQL is escaped as `??`. With the `serde`
+//! feature each `BindValue` can be passed straight to `bind()`:
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterBuilder, FilterCondition, FilterOperator};
+//! use clickhouse_filters::params::BindValue;
+//!
+//! let builder = FilterBuilder::new()
+//!     .add_condition(FilterCondition::string("name", FilterOperator::Equal, Some("O'Brien")))
+//!     .add_condition(FilterCondition::uint32("age", FilterOperator::GreaterThan, Some(30)));
+//!
+//! let (sql, values) = builder.build_with_bind_values().unwrap();
+//! assert_eq!(sql, " WHERE (name = ? AND age > ?)");
+//! assert_eq!(
+//!     values,
+//!     vec![BindValue::String("O'Brien".to_string()), BindValue::UInt(30)]
+//! );
+//! ```
+
+use crate::filtering::{
+    fraction_digits, unique, ColumnTypeInfo, DateRangeType, FilterBuilder, FilterCondition,
+    FilterExpression, FilterOperator,
+};
+use crate::table::TableRef;
+use crate::ClickHouseFilters;
+use eyre::Result;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Values of the parameters referenced by a rendered query, keyed by name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryParams {
+    values: HashMap<String, String>,
+    next: usize,
+    // Values of `?` placeholders, in order, when rendering positionally
+    positional: Option<Vec<BindValue>>,
+}
+
+/// The value of a `?` placeholder
+///
+/// Serializes as the bare value, so it can be passed to the `clickhouse` crate's
+/// `bind()`, which quotes and escapes strings itself.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindValue {
+    String(String),
+    UInt(u64),
+    Int(i64),
+    Float(f64),
+}
+
+impl BindValue {
+    // Integers and floats keep their type, everything else is bound as a string
+    fn new(param_type: &str, value: &str) -> Self {
+        let parsed = if param_type.starts_with("UInt") {
+            value.parse().ok().map(BindValue::UInt)
+        } else if param_type.starts_with("Int") {
+            value.parse().ok().map(BindValue::Int)
+        } else if param_type.starts_with("Float") {
+            value.parse().ok().map(BindValue::Float)
+        } else {
+            None
+        };
+        parsed.unwrap_or_else(|| BindValue::String(value.to_string()))
+    }
+}
+
+impl QueryParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parameters rendered as `?` placeholders, for the `clickhouse` crate's `bind()`
+    pub fn positional() -> Self {
+        QueryParams {
+            positional: Some(Vec::new()),
+            ..Self::default()
+        }
+    }
+
+    /// Values of the `?` placeholders in order, empty for named parameters
+    pub fn bind_values(&self) -> &[BindValue] {
+        self.positional.as_deref().unwrap_or_default()
+    }
+
+    pub fn into_bind_values(self) -> Vec<BindValue> {
+        self.positional.unwrap_or_default()
+    }
+
+    /// The value of a parameter, in the escaped text format ClickHouse expects
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.positional {
+            Some(values) => values.len(),
+            None => self.values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All parameter values, keyed by parameter name
+    pub fn values(&self) -> &HashMap<String, String> {
+        &self.values
+    }
+
+    pub fn into_values(self) -> HashMap<String, String> {
+        self.values
+    }
+
+    /// Query-string arguments for the HTTP interface (`param_<name>`), sorted by name
+    pub fn http_params(&self) -> Vec<(String, String)> {
+        let mut params: Vec<_> = self
+            .values
+            .iter()
+            .map(|(name, value)| (format!("param_{}", name), value.clone()))
+            .collect();
+        params.sort_unstable();
+        params
+    }
+
+    // Register a value and write its `{name:Type}` or `?` placeholder
+    fn bind(
+        &mut self,
+        out: &mut String,
+        column: &str,
+        param_type: &str,
+        value: &str,
+    ) -> Result<()> {
+        if let Some(values) = &mut self.positional {
+            out.push('?');
+            values.push(BindValue::new(param_type, value));
+            return Ok(());
+        }
+
+        let mut name = String::with_capacity(column.len() + 4);
+        if !column.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            name.push_str("p_");
+        }
+        name.extend(column.chars().map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        }));
+        write!(name, "_{}", self.next)?;
+        self.next += 1;
+
+        write!(out, "{{{}:{}}}", name, param_type)?;
+        self.values.insert(name, escape_value(value));
+        Ok(())
+    }
+
+    // Write SQL without placeholders, doubling any `?` so the `clickhouse` crate
+    // doesn't take it for one
+    pub(crate) fn write_inline(
+        &self,
+        out: &mut String,
+        write: impl FnOnce(&mut String) -> Result<()>,
+    ) -> Result<()> {
+        if self.positional.is_none() {
+            return write(out);
+        }
+        let mut sql = String::new();
+        write(&mut sql)?;
+        out.push_str(&sql.replace('?', "??"));
+        Ok(())
+    }
+}
+
+// Parameters are parsed in the escaped text format, so backslashes and control
+// characters must be escaped
+pub(crate) fn escape_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// ClickHouse type of an integer or float condition's value
+fn numeric_type(condition: &FilterCondition) -> Option<&'static str> {
+    Some(match condition {
+        FilterCondition::UInt8Value { .. } => "UInt8",
+        FilterCondition::UInt16Value { .. } => "UInt16",
+        FilterCondition::UInt32Value { .. } => "UInt32",
+        FilterCondition::UInt64Value { .. } => "UInt64",
+        FilterCondition::Int8Value { .. } => "Int8",
+        FilterCondition::Int16Value { .. } => "Int16",
+        FilterCondition::Int32Value { .. } => "Int32",
+        FilterCondition::Int64Value { .. } => "Int64",
+        FilterCondition::Float32Value { .. } => "Float32",
+        FilterCondition::Float64Value { .. } => "Float64",
+        _ => return None,
+    })
+}
+
+// Parameter type for an untyped date range bound
+fn timestamp_type(value: &str) -> Cow<'static, str> {
+    match fraction_digits(value) {
+        0 => Cow::Borrowed("String"),
+        digits => Cow::Owned(format!("DateTime64({})", digits)),
+    }
+}
+
+// Parameter type for an untyped IN list value
+fn in_value_type(column_type: Option<&ColumnTypeInfo>, value: &str) -> &'static str {
+    match column_type {
+        Some(ColumnTypeInfo::String) => "String",
+        Some(ColumnTypeInfo::UUID) => "UUID",
+        Some(ColumnTypeInfo::IPv4) => "IPv4",
+        Some(ColumnTypeInfo::IPv6) => "IPv6",
+        Some(ColumnTypeInfo::Boolean) => "Bool",
+        _ if value.parse::<i64>().is_ok() => "Int64",
+        _ if value.parse::<u64>().is_ok() => "UInt64",
+        _ if value.parse::<f64>().is_ok() => "Float64",
+        _ => "String",
+    }
+}
+
+// Bind each value as a parameter, separated by commas and optionally lowered
+fn bind_list<'a>(
+    out: &mut String,
+    params: &mut QueryParams,
+    column: &str,
+    values: impl IntoIterator<Item = (&'static str, &'a str)>,
+    lowered: bool,
+) -> Result<()> {
+    for (i, (param_type, value)) in values.into_iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        if lowered {
+            out.push_str("lower(");
+        }
+        params.bind(out, column, param_type, value)?;
+        if lowered {
+            out.push(')');
+        }
+    }
+    Ok(())
+}
+
+impl FilterCondition {
+    /// Render this condition with its values as named parameters
+    pub fn write_sql_with_params(
+        &self,
+        out: &mut String,
+        case_insensitive: bool,
+        params: &mut QueryParams,
+    ) -> Result<()> {
+        match self {
+            FilterCondition::StringValue {
+                column,
+                operator,
+                value: Some(value),
+            }
+            | FilterCondition::FixedStringValue {
+                column,
+                operator,
+                value: Some(value),
+            } => {
+                let pattern;
+                let (sql_operator, value) = match operator {
+                    FilterOperator::Equal
+                    | FilterOperator::NotEqual
+                    | FilterOperator::Like
+                    | FilterOperator::NotLike => (operator.as_sql(), value.as_str()),
+                    FilterOperator::StartsWith
+                    | FilterOperator::EndsWith
+                    | FilterOperator::Contains => {
+                        pattern = operator.format_value(value);
+                        ("LIKE", pattern.as_str())
+                    }
+                    FilterOperator::In | FilterOperator::NotIn => {
+                        if case_insensitive {
+                            write!(out, "lower({}) {} (", column, operator.as_sql())?;
+                        } else {
+                            write!(out, "{} {} (", column, operator.as_sql())?;
+                        }
+                        let values = unique(value.split(',').map(str::trim)).map(|v| ("String", v));
+                        bind_list(out, params, column, values, case_insensitive)?;
+                        out.push(')');
+                        return Ok(());
+                    }
+                    _ => {
+                        return params
+                            .write_inline(out, |out| self.write_sql(out, case_insensitive))
+                    }
+                };
+                if case_insensitive {
+                    write!(out, "lower({}) {} lower(", column, sql_operator)?;
+                    params.bind(out, column, "String", value)?;
+                    out.push(')');
+                } else {
+                    write!(out, "{} {} ", column, sql_operator)?;
+                    params.bind(out, column, "String", value)?;
+                }
+            }
+
+            FilterCondition::DateValue {
+                column,
+                operator,
+                value: Some(value),
+            }
+            | FilterCondition::DateTimeValue {
+                column,
+                operator,
+                value: Some(value),
+            }
+            | FilterCondition::DateTime64Value {
+                column,
+                operator,
+                value: Some(value),
+            } if operator.is_comparison() => {
+                let param_type = match self {
+                    FilterCondition::DateValue { .. } => Cow::Borrowed("Date"),
+                    FilterCondition::DateTimeValue { .. } => Cow::Borrowed("DateTime"),
+                    _ => Cow::Owned(format!("DateTime64({})", fraction_digits(value).max(6))),
+                };
+                write!(out, "{} {} ", column, operator.as_sql())?;
+                params.bind(out, column, &param_type, value)?;
+            }
+
+            // Column types are unknown here, so values are bound as strings and
+            // converted by ClickHouse as string literals would be, except for
+            // timestamps with sub-second digits
+            FilterCondition::DateRange { column, range_type } => match range_type {
+                DateRangeType::Exact(timestamp) => {
+                    write!(out, "{} = ", column)?;
+                    params.bind(out, column, &timestamp_type(timestamp), timestamp)?;
+                }
+                DateRangeType::DateOnly(date) => {
+                    write!(out, "toDate({}) = toDate(", column)?;
+                    params.bind(out, column, "String", date)?;
+                    out.push(')');
+                }
+                DateRangeType::Range { start, end } => {
+                    write!(out, "{} BETWEEN ", column)?;
+                    params.bind(out, column, &timestamp_type(start), start)?;
+                    out.push_str(" AND ");
+                    params.bind(out, column, &timestamp_type(end), end)?;
+                }
+                DateRangeType::Relative(_) => {
+                    params.write_inline(out, |out| self.write_sql(out, case_insensitive))?
+                }
+                DateRangeType::Bucket { interval, start } => {
+                    write!(
+                        out,
+                        "toStartOfInterval({}, INTERVAL {}) = ",
+                        column, interval
+                    )?;
+                    params.bind(out, column, &timestamp_type(start), start)?;
+                }
+            },
+
+            FilterCondition::BooleanValue {
+                column,
+                operator: operator @ (FilterOperator::Equal | FilterOperator::NotEqual),
+                value: Some(value),
+            } => {
+                write!(out, "{} {} ", column, operator.as_sql())?;
+                params.bind(out, column, "UInt8", if *value { "1" } else { "0" })?;
+            }
+
+            FilterCondition::IPv4Value {
+                column,
+                operator,
+                value: Some(value),
+            } if operator.is_comparison() => {
+                write!(out, "{} {} ", column, operator.as_sql())?;
+                params.bind(out, column, "IPv4", &value.to_string())?;
+            }
+            FilterCondition::IPv6Value {
+                column,
+                operator,
+                value: Some(value),
+            } if operator.is_comparison() => {
+                write!(out, "{} {} ", column, operator.as_sql())?;
+                params.bind(out, column, "IPv6", &value.to_string())?;
+            }
+
+            FilterCondition::UUIDValue {
+                column,
+                operator,
+                value: Some(value),
+            } => match operator {
+                FilterOperator::Equal | FilterOperator::NotEqual => {
+                    write!(out, "{} {} ", column, operator.as_sql())?;
+                    params.bind(out, column, "UUID", value)?;
+                }
+                FilterOperator::In | FilterOperator::NotIn => {
+                    write!(out, "{} {} (", column, operator.as_sql())?;
+                    let values = unique(value.split(',').map(str::trim)).map(|v| ("UUID", v));
+                    bind_list(out, params, column, values, false)?;
+                    out.push(')');
+                }
+                _ => params.write_inline(out, |out| self.write_sql(out, case_insensitive))?,
+            },
+
+            FilterCondition::ArrayContains {
+                column,
+                operator,
+                value,
+            } => {
+                if *operator == FilterOperator::ArrayNotContains {
+                    out.push_str("NOT ");
+                }
+                write!(out, "hasAll({}, array[", column)?;
+                let values = value.split(',').map(|v| ("String", v.trim()));
+                bind_list(out, params, column, values, false)?;
+                out.push_str("])");
+            }
+            FilterCondition::ArrayHas {
+                column,
+                operator,
+                value,
+            } => {
+                if *operator == FilterOperator::ArrayNotHas {
+                    out.push_str("NOT ");
+                }
+                write!(out, "has({}, ", column)?;
+                params.bind(out, column, "String", value)?;
+                out.push(')');
+            }
+
+            FilterCondition::InValues {
+                column,
+                operator: operator @ (FilterOperator::In | FilterOperator::NotIn),
+                values,
+                column_type,
+            } => {
+                let lowered = case_insensitive && column_type == &Some(ColumnTypeInfo::String);
+                if lowered {
+                    write!(out, "lower({}) {} (", column, operator.as_sql())?;
+                } else {
+                    write!(out, "{} {} (", column, operator.as_sql())?;
+                }
+                let values = unique(values.iter().map(String::as_str))
+                    .map(|v| (in_value_type(column_type.as_ref(), v), v));
+                bind_list(out, params, column, values, lowered)?;
+                out.push(')');
+            }
+
+            condition => match (numeric_type(condition), condition.numeric_value()) {
+                (Some(param_type), Some(value)) => {
+                    let column = condition.column();
+                    let value = value.to_string();
+                    match condition.operator() {
+                        Some(op) if op.is_comparison() => {
+                            write!(out, "{} {} ", column, op.as_sql())?;
+                            params.bind(out, column, param_type, &value)?;
+                        }
+                        // Floats don't support IN, so they fall through to the error below
+                        Some(op @ (FilterOperator::In | FilterOperator::NotIn))
+                            if !param_type.starts_with("Float") =>
+                        {
+                            write!(out, "{} {} (", column, op.as_sql())?;
+                            params.bind(out, column, param_type, &value)?;
+                            out.push(')');
+                        }
+                        _ => params
+                            .write_inline(out, |out| condition.write_sql(out, case_insensitive))?,
+                    }
+                }
+                _ => params.write_inline(out, |out| condition.write_sql(out, case_insensitive))?,
+            },
+        }
+        Ok(())
+    }
+}
+
+impl FilterExpression {
+    /// Render this expression with its values as named parameters
+    pub fn write_sql_with_params(
+        &self,
+        out: &mut String,
+        case_insensitive: bool,
+        case_overrides: &HashMap<&'static str, bool>,
+        params: &mut QueryParams,
+    ) -> Result<()> {
+        match self {
+            FilterExpression::Condition(condition) => {
+                let case_insensitive = case_overrides
+                    .get(condition.column())
+                    .copied()
+                    .unwrap_or(case_insensitive);
+                condition.write_sql_with_params(out, case_insensitive, params)
+            }
+            // Custom conditions render and escape their own values
+            FilterExpression::Custom(condition) => {
+                params.write_inline(out, |out| condition.write_sql(out, case_insensitive))
+            }
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } => {
+                if expressions.is_empty() {
+                    return Ok(());
+                }
+
+                out.push('(');
+                for (i, expr) in expressions.iter().enumerate() {
+                    if i > 0 {
+                        write!(out, " {} ", operator.as_sql())?;
+                    }
+                    expr.write_sql_with_params(out, case_insensitive, case_overrides, params)?;
+                }
+                out.push(')');
+                Ok(())
+            }
+        }
+    }
+
+    /// Render this expression, returning the SQL and its parameter values
+    pub fn to_sql_with_params(&self, case_insensitive: bool) -> Result<(String, QueryParams)> {
+        let mut sql = String::with_capacity(self.estimated_sql_len());
+        let mut params = QueryParams::new();
+        self.write_sql_with_params(&mut sql, case_insensitive, &HashMap::new(), &mut params)?;
+        Ok((sql, params))
+    }
+
+    /// Render this expression with `?` placeholders, returning the SQL and the values
+    /// to bind in order
+    pub fn to_sql_with_bind_values(
+        &self,
+        case_insensitive: bool,
+    ) -> Result<(String, Vec<BindValue>)> {
+        let mut sql = String::with_capacity(self.estimated_sql_len());
+        let mut params = QueryParams::positional();
+        self.write_sql_with_params(&mut sql, case_insensitive, &HashMap::new(), &mut params)?;
+        Ok((sql, params.into_bind_values()))
+    }
+}
+
+impl FilterBuilder {
+    /// Build the WHERE clause with its values as named parameters
+    pub fn build_with_params(&self) -> Result<(String, QueryParams)> {
+        let mut sql = String::new();
+        let mut params = QueryParams::new();
+        self.write_sql_with_params(&mut sql, &mut params)?;
+        Ok((sql, params))
+    }
+
+    /// Build the WHERE clause with `?` placeholders and the values to bind in order
+    pub fn build_with_bind_values(&self) -> Result<(String, Vec<BindValue>)> {
+        let mut sql = String::new();
+        let mut params = QueryParams::positional();
+        self.write_sql_with_params(&mut sql, &mut params)?;
+        Ok((sql, params.into_bind_values()))
+    }
+
+    /// Write the WHERE clause (with a leading space), binding values into `params`
+    pub fn write_sql_with_params(&self, out: &mut String, params: &mut QueryParams) -> Result<()> {
+        if let Some(expression) = self.resolved_root()? {
+            const PREFIX: &str = " WHERE ";
+            let start = out.len();
+            out.push_str(PREFIX);
+            expression.write_sql_with_params(
+                out,
+                self.case_insensitive,
+                &self.case_overrides,
+                params,
+            )?;
+            if out.len() == start + PREFIX.len() {
+                out.truncate(start);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ClickHouseFilters {
+    /// Generate the SQL for this filter with its values as named parameters
+    pub fn sql_with_params(&self) -> Result<(String, QueryParams)> {
+        let mut sql = String::with_capacity(self.estimated_sql_len());
+        let mut params = QueryParams::new();
+        self.write_clauses_with_params(&mut sql, &mut params)?;
+        Ok((sql, params))
+    }
+
+    /// Generate a complete SQL query with its filter values as named parameters
+    pub fn query_sql_with_params(
+        &self,
+        table: impl Into<TableRef>,
+        columns: &[&str],
+    ) -> Result<(String, QueryParams)> {
+        let mut params = QueryParams::new();
+        let sql = self.write_query_with_params(&table.into(), columns, &mut params)?;
+        Ok((sql, params))
+    }
+
+    /// Generate the SQL for this filter with `?` placeholders and the values to bind
+    /// in order
+    pub fn sql_with_bind_values(&self) -> Result<(String, Vec<BindValue>)> {
+        let mut sql = String::with_capacity(self.estimated_sql_len());
+        let mut params = QueryParams::positional();
+        self.write_clauses_with_params(&mut sql, &mut params)?;
+        Ok((sql, params.into_bind_values()))
+    }
+
+    /// Generate a complete SQL query with `?` placeholders, for executing with the
+    /// `clickhouse` crate's `query(...).bind(...)`
+    pub fn query_sql_with_bind_values(
+        &self,
+        table: impl Into<TableRef>,
+        columns: &[&str],
+    ) -> Result<(String, Vec<BindValue>)> {
+        let mut params = QueryParams::positional();
+        let sql = self.write_query_with_params(&table.into(), columns, &mut params)?;
+        Ok((sql, params.into_bind_values()))
+    }
+
+    fn write_query_with_params(
+        &self,
+        table: &TableRef,
+        columns: &[&str],
+        params: &mut QueryParams,
+    ) -> Result<String> {
+        let mut sql = String::with_capacity(32 + self.estimated_sql_len());
+        params.write_inline(&mut sql, |sql| self.write_select(sql, table, columns))?;
+        self.write_clauses_with_params(&mut sql, params)?;
+        params.write_inline(&mut sql, |sql| self.write_settings(sql, false))?;
+        Ok(sql)
+    }
+
+    fn write_clauses_with_params(&self, sql: &mut String, params: &mut QueryParams) -> Result<()> {
+        if let Some(filters) = self.effective_filters()? {
+            filters.write_sql_with_params(sql, params)?;
+        }
+        params.write_inline(sql, |sql| {
+            if let Some(sorting) = &self.sorting {
+                sorting.write_sql(sql);
+            }
+            if let Some(pagination) = &self.pagination {
+                pagination.write_sql(sql);
+            }
+            Ok(())
+        })
+    }
+}
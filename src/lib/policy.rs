@@ -0,0 +1,198 @@
+//! Row-level security policies
+//!
+//! A `RowPolicies` set holds always-on predicates per table. Each policy is a function
+//! of an application-defined context (the current user, tenant, roles, ...) that
+//! returns the predicate every query against that table must satisfy.
+//!
+//! Precedence rules:
+//!
+//! - Policies are ANDed with the user-supplied filters as separate top-level terms, so
+//!   user filters can only narrow the result. A user `OR` stays inside its own group and
+//!   cannot widen the policy.
+//! - Multiple policies on the same table are ANDed together.
+//! - `apply()` keeps the predicate apart from the user filters, so it still applies
+//!   when those are pruned, e.g. by `distinct_values_sql()`.
+//! - A policy that returns `None` places no restriction for that context (e.g. an admin).
+//! - A policy that returns an error fails the query.
+//! - With `deny_unlisted_tables()`, querying a table that has no policies is an error.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! use clickhouse_filters::policy::RowPolicies;
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry, FilteringOptions};
+//!
+//! struct User {
+//!     tenant_id: u32,
+//! }
+//!
+//! let policies = RowPolicies::new().add("db.orders", |user: &User| {
+//!     Ok(Some(FilterExpression::Condition(FilterCondition::uint32(
+//!         "tenant_id",
+//!         FilterOperator::Equal,
+//!         Some(user.tenant_id),
+//!     ))))
+//! });
+//!
+//! let columns: ColumnRegistry = column_registry! { status: String, tenant_id: UInt32 };
+//! let user_filters = FilteringOptions::case_sensitive(
+//!     vec![FilterExpression::or(vec![
+//!         FilterExpression::Condition(FilterCondition::string("status", FilterOperator::Equal, Some("open"))),
+//!         FilterExpression::Condition(FilterCondition::string("status", FilterOperator::Equal, Some("paid"))),
+//!     ])],
+//!     &columns,
+//! );
+//!
+//! let mut filters = ClickHouseFilters::new(None, vec![], Some(user_filters), &columns).unwrap();
+//! policies.apply("db.orders", &User { tenant_id: 7 }, &mut filters).unwrap();
+//!
+//! assert_eq!(
+//!     filters.sql().unwrap(),
+//!     " WHERE ((status = 'open' OR status = 'paid') AND tenant_id = 7)"
+//! );
+//! ```
+
+use crate::filtering::{FilterBuilder, FilterExpression};
+use crate::ClickHouseFilters;
+use eyre::Result;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+/// A policy predicate for a given context
+pub type PolicyFn<C> = dyn Fn(&C) -> Result<Option<FilterExpression>> + Send + Sync;
+
+/// Per-table row-level security policies
+pub struct RowPolicies<C> {
+    tables: HashMap<String, Vec<Arc<PolicyFn<C>>>>,
+    deny_unlisted: bool,
+}
+
+impl<C> RowPolicies<C> {
+    pub fn new() -> Self {
+        Self {
+            tables: HashMap::new(),
+            deny_unlisted: false,
+        }
+    }
+
+    /// Add a policy for a table
+    ///
+    /// `table` is matched exactly against the name passed to `predicate` and
+    /// `apply`, so use the same form (e.g. `db.table`) throughout.
+    pub fn add<F>(mut self, table: &str, policy: F) -> Self
+    where
+        F: Fn(&C) -> Result<Option<FilterExpression>> + Send + Sync + 'static,
+    {
+        self.tables
+            .entry(table.to_string())
+            .or_default()
+            .push(Arc::new(policy));
+        self
+    }
+
+    /// Reject queries against tables that have no policies
+    pub fn deny_unlisted_tables(mut self) -> Self {
+        self.deny_unlisted = true;
+        self
+    }
+
+    /// The combined policy predicate for a table and context
+    ///
+    /// Returns `None` when no policy restricts this context.
+    pub fn predicate(&self, table: &str, context: &C) -> Result<Option<FilterExpression>> {
+        let policies = match self.tables.get(table) {
+            Some(policies) => policies,
+            None if self.deny_unlisted => {
+                return Err(eyre::eyre!("No row policy defined for table: {}", table))
+            }
+            None => return Ok(None),
+        };
+
+        let mut predicates = Vec::with_capacity(policies.len());
+        for policy in policies {
+            if let Some(predicate) = policy(context)? {
+                predicates.push(predicate);
+            }
+        }
+
+        Ok(match predicates.len() {
+            0 => None,
+            1 => predicates.pop(),
+            _ => Some(FilterExpression::and(predicates)),
+        })
+    }
+
+    /// Merge the table's policy predicate into a filter builder
+    pub fn apply_to_builder(
+        &self,
+        table: &str,
+        context: &C,
+        builder: FilterBuilder,
+    ) -> Result<FilterBuilder> {
+        let Some(predicate) = self.predicate(table, context)? else {
+            return Ok(builder);
+        };
+
+        // An empty user group would render as a dangling operator, so drop it
+        let mut builder = builder;
+        if matches!(&builder.root, Some(FilterExpression::Group { expressions, .. }) if expressions.is_empty())
+        {
+            builder.root = None;
+        }
+
+        // Keep the user tree as a single term so its ORs stay inside it
+        let user_root = builder.root.take();
+        builder.root = match user_root {
+            Some(root) => Some(FilterExpression::and(vec![root, predicate])),
+            None => Some(predicate),
+        };
+        Ok(builder)
+    }
+
+    /// Apply the table's policy predicate to every query generated from a set of filters
+    ///
+    /// The predicate is kept apart from the filters, like the registry's
+    /// soft-delete condition, so nothing that prunes or replaces the filters
+    /// (e.g. `distinct_values_sql()` excluding a column's own filter) removes it.
+    pub fn apply(&self, table: &str, context: &C, filters: &mut ClickHouseFilters) -> Result<()> {
+        let Some(predicate) = self.predicate(table, context)? else {
+            return Ok(());
+        };
+        filters.row_policy = Some(match filters.row_policy.take() {
+            Some(existing) => FilterExpression::and(vec![existing, predicate]),
+            None => predicate,
+        });
+
+        // The WHERE clause may already have been rendered without the policy
+        filters.where_clause = OnceLock::new();
+        Ok(())
+    }
+}
+
+impl<C> Default for RowPolicies<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> Clone for RowPolicies<C> {
+    fn clone(&self) -> Self {
+        Self {
+            tables: self.tables.clone(),
+            deny_unlisted: self.deny_unlisted,
+        }
+    }
+}
+
+impl<C> fmt::Debug for RowPolicies<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tables: Vec<_> = self.tables.keys().collect();
+        tables.sort_unstable();
+        f.debug_struct("RowPolicies")
+            .field("tables", &tables)
+            .field("deny_unlisted", &self.deny_unlisted)
+            .finish()
+    }
+}
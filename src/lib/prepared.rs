@@ -0,0 +1,268 @@
+//! Filters prepared once and bound per request
+//!
+//! Hot endpoints often run the same filter shape with different values, e.g.
+//! `status = :status AND created_at >= :since`. `PreparedFilter` takes JSON filters
+//! whose values may be `:name` placeholders, checks the columns and operators and
+//! renders the WHERE clause once, with a slot for each placeholder. `bind()` then
+//! only validates and renders the bound values, exactly as JSON filter values are,
+//! and splices them into the cached SQL.
+//!
+//! A value is a placeholder when it is `:` followed by an identifier. Filters with
+//! other values are fixed and rendered into the cached SQL as they are.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::JsonFilter;
+//! use clickhouse_filters::prepared::PreparedFilter;
+//! use clickhouse_filters::{column_registry, ColumnRegistry};
+//! use std::collections::HashMap;
+//!
+//! let columns: ColumnRegistry = column_registry! {
+//!     status: String,
+//!     created_at: Date,
+//! };
+//! let filter = |n: &str, f: &str, v: &str| JsonFilter {
+//!     n: n.to_string(),
+//!     f: f.to_string(),
+//!     v: v.to_string(),
+//!     c: Some("AND".to_string()),
+//! };
+//!
+//! let prepared = PreparedFilter::case_sensitive(
+//!     &[filter("status", "=", ":status"), filter("created_at", ">=", ":since")],
+//!     &columns,
+//! )
+//! .unwrap();
+//! assert_eq!(prepared.placeholders(), ["status", "since"]);
+//!
+//! let values = HashMap::from([("status", "open"), ("since", "2024-01-01")]);
+//! assert_eq!(
+//!     prepared.bind(&values).unwrap(),
+//!     " WHERE (status = 'open' AND created_at >= '2024-01-01')"
+//! );
+//! ```
+
+use crate::custom::{ColumnRenderer, RendererRef};
+use crate::filtering::{
+    json_filter_condition, parse_operator, FilterBuilder, FilterCondition, FilterOperator,
+    JsonFilter,
+};
+use crate::ColumnRegistry;
+use eyre::Result;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+// Marks a slot in the rendered skeleton, around `<slot>:<case_insensitive>`
+const MARKER: char = '\u{0}';
+
+/// A filter shape with `:name` placeholders, rendered once and bound per request
+#[derive(Debug, Clone)]
+pub struct PreparedFilter {
+    columns: ColumnRegistry,
+    slots: Vec<Slot>,
+    segments: Vec<Segment>,
+    placeholders: Vec<String>,
+    sql_len: usize,
+}
+
+// A filter whose value is a placeholder
+#[derive(Debug, Clone)]
+struct Slot {
+    placeholder: String,
+    column: String,
+    operator: String,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Sql(String),
+    Slot { slot: usize, case_insensitive: bool },
+}
+
+impl PreparedFilter {
+    /// Prepare JSON filters, comparing strings case-insensitively as
+    /// `FilteringOptions::from_json_filters` does
+    pub fn new(filters: &[JsonFilter], columns: impl Into<ColumnRegistry>) -> Result<Self> {
+        Self::prepare(filters, columns.into(), true)
+    }
+
+    /// Prepare JSON filters, comparing strings case-sensitively
+    pub fn case_sensitive(
+        filters: &[JsonFilter],
+        columns: impl Into<ColumnRegistry>,
+    ) -> Result<Self> {
+        Self::prepare(filters, columns.into(), false)
+    }
+
+    fn prepare(
+        filters: &[JsonFilter],
+        columns: ColumnRegistry,
+        case_insensitive: bool,
+    ) -> Result<Self> {
+        let slots = RefCell::new(Vec::new());
+        let builder = FilterBuilder::from_json_filters_with(
+            filters,
+            case_insensitive,
+            &columns,
+            &|filter| {
+                let Some(placeholder) = placeholder_name(&filter.v) else {
+                    if filter.v.contains(MARKER) {
+                        return Err(eyre::eyre!("Invalid filter value: {:?}", filter.v));
+                    }
+                    return json_filter_condition(filter, &columns);
+                };
+                slot_condition(filter, placeholder, &columns, &mut slots.borrow_mut())
+            },
+        )?;
+        let skeleton = builder.build()?;
+
+        let slots = slots.into_inner();
+        let mut placeholders: Vec<String> = Vec::new();
+        for slot in &slots {
+            if !placeholders.contains(&slot.placeholder) {
+                placeholders.push(slot.placeholder.clone());
+            }
+        }
+
+        Ok(PreparedFilter {
+            columns,
+            segments: split_skeleton(&skeleton)?,
+            slots,
+            placeholders,
+            sql_len: skeleton.len(),
+        })
+    }
+
+    /// Placeholder names, in order of first appearance
+    pub fn placeholders(&self) -> &[String] {
+        &self.placeholders
+    }
+
+    /// Render the WHERE clause with the placeholders bound to `values`
+    ///
+    /// Every placeholder needs a value, and values for unknown placeholders are
+    /// rejected, so a misspelled name doesn't silently drop a condition. Values
+    /// are checked against the column type as JSON filter values are.
+    pub fn bind(&self, values: &HashMap<&str, &str>) -> Result<String> {
+        for name in values.keys() {
+            if !self.placeholders.iter().any(|p| p == name) {
+                return Err(eyre::eyre!("Unknown placeholder: {}", name));
+            }
+        }
+
+        let mut sql = String::with_capacity(self.sql_len + values.len() * 16);
+        for segment in &self.segments {
+            match segment {
+                Segment::Sql(fragment) => sql.push_str(fragment),
+                Segment::Slot {
+                    slot,
+                    case_insensitive,
+                } => {
+                    let slot = &self.slots[*slot];
+                    let value = values.get(slot.placeholder.as_str()).ok_or_else(|| {
+                        eyre::eyre!("Missing value for placeholder: {}", slot.placeholder)
+                    })?;
+                    let filter = JsonFilter {
+                        n: slot.column.clone(),
+                        f: slot.operator.clone(),
+                        v: value.to_string(),
+                        c: None,
+                    };
+                    json_filter_condition(&filter, &self.columns)?
+                        .write_sql(&mut sql, *case_insensitive)?;
+                }
+            }
+        }
+        Ok(sql)
+    }
+}
+
+// `name` for a `:name` value, where name is an identifier
+fn placeholder_name(value: &str) -> Option<&str> {
+    let name = value.strip_prefix(':')?;
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then_some(name)
+}
+
+// Check the column and operator of a placeholder filter and stand in a condition
+// that renders the slot marker
+fn slot_condition(
+    filter: &JsonFilter,
+    placeholder: &str,
+    columns: &ColumnRegistry,
+    slots: &mut Vec<Slot>,
+) -> Result<FilterCondition> {
+    let column = filter.n.split('[').next().unwrap_or_default().trim();
+    if !columns.contains(column) {
+        return Err(eyre::eyre!("Column not found: {}", column));
+    }
+    let operator = match columns.custom_operator(&filter.f) {
+        Some(custom) => FilterOperator::Custom(custom.name()),
+        None => parse_operator(&filter.f)?,
+    };
+
+    slots.push(Slot {
+        placeholder: placeholder.to_string(),
+        column: filter.n.clone(),
+        operator: filter.f.clone(),
+    });
+    Ok(FilterCondition::Custom {
+        column: column.to_string(),
+        operator,
+        value: Some((slots.len() - 1).to_string()),
+        renderer: RendererRef(Arc::new(SlotMarker)),
+    })
+}
+
+// Split the rendered skeleton at the slot markers
+fn split_skeleton(skeleton: &str) -> Result<Vec<Segment>> {
+    let invalid = || eyre::eyre!("Invalid prepared filter skeleton");
+    let mut segments = Vec::new();
+    let mut rest = skeleton;
+    while let Some((sql, marked)) = rest.split_once(MARKER) {
+        if !sql.is_empty() {
+            segments.push(Segment::Sql(sql.to_string()));
+        }
+        let (marker, after) = marked.split_once(MARKER).ok_or_else(invalid)?;
+        let (slot, case_insensitive) = marker.split_once(':').ok_or_else(invalid)?;
+        segments.push(Segment::Slot {
+            slot: slot.parse().map_err(|_| invalid())?,
+            case_insensitive: case_insensitive == "1",
+        });
+        rest = after;
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Sql(rest.to_string()));
+    }
+    Ok(segments)
+}
+
+// Renders a placeholder condition as its marker, recording the case sensitivity
+// the builder resolved for the column
+#[derive(Debug)]
+struct SlotMarker;
+
+impl ColumnRenderer for SlotMarker {
+    fn write_sql(
+        &self,
+        out: &mut String,
+        _column: &str,
+        _operator: &FilterOperator,
+        value: Option<&str>,
+        case_insensitive: bool,
+    ) -> Result<()> {
+        write!(
+            out,
+            "{}{}:{}{}",
+            MARKER,
+            value.unwrap_or_default(),
+            u8::from(case_insensitive),
+            MARKER
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Typed projections for SELECT queries
+//!
+//! `ClickHouseFilters::query_sql()` pastes raw column names into the SELECT list.
+//! `query_sql_with()` takes `Projection` entries instead: each one names a column that
+//! must exist in the column definitions, optionally wrapped in a function call and
+//! given an alias.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::projection::Projection;
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry};
+//!
+//! let columns: ColumnRegistry = column_registry! {
+//!     name: String,
+//!     created_at: DateTime,
+//!     tags: ArrayString,
+//! };
+//! let filters = ClickHouseFilters::new(None, vec![], None, columns).unwrap();
+//!
+//! let sql = filters
+//!     .query_sql_with(
+//!         "db.users",
+//!         &[
+//!             Projection::column("name"),
+//!             Projection::function("toDate", "created_at").alias("day"),
+//!             Projection::function("length", "tags").alias("tag_count"),
+//!         ],
+//!     )
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     sql,
+//!     "SELECT name, toDate(created_at) AS day, length(tags) AS tag_count FROM db.users"
+//! );
+//! ```
+
+use crate::table::TableRef;
+use crate::{ClickHouseFilters, ColumnRegistry};
+use eyre::Result;
+use std::fmt::Write;
+
+/// A single entry in a SELECT list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection {
+    pub column: String,
+    pub function: Option<String>,
+    pub alias: Option<String>,
+}
+
+impl Projection {
+    /// Select a column as-is
+    pub fn column(column: &str) -> Self {
+        Self {
+            column: column.to_string(),
+            function: None,
+            alias: None,
+        }
+    }
+
+    /// Select `function(column)`
+    pub fn function(function: &str, column: &str) -> Self {
+        Self {
+            column: column.to_string(),
+            function: Some(function.to_string()),
+            alias: None,
+        }
+    }
+
+    /// Set the output alias (`... AS alias`)
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.alias = Some(alias.to_string());
+        self
+    }
+
+    /// Check the projection against the column definitions
+    ///
+    /// The column must be registered, and function names and aliases must be
+    /// plain identifiers.
+    pub fn validate(&self, column_defs: &ColumnRegistry) -> Result<()> {
+        if !column_defs.contains(&self.column) {
+            return Err(eyre::eyre!("Column not found: {}", self.column));
+        }
+        if let Some(function) = &self.function {
+            if !is_identifier(function) {
+                return Err(eyre::eyre!("Invalid function name: {}", function));
+            }
+        }
+        if let Some(alias) = &self.alias {
+            if !is_identifier(alias) {
+                return Err(eyre::eyre!("Invalid alias: {}", alias));
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the projection into an existing buffer
+    pub fn write_sql(&self, out: &mut String) -> Result<()> {
+        match &self.function {
+            Some(function) => write!(out, "{}({})", function, self.column)?,
+            None => out.push_str(&self.column),
+        }
+        if let Some(alias) = &self.alias {
+            write!(out, " AS {}", alias)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&str> for Projection {
+    fn from(column: &str) -> Self {
+        Projection::column(column)
+    }
+}
+
+impl ClickHouseFilters {
+    /// Generate a complete SQL query selecting typed projections
+    ///
+    /// Every projection is validated against `column_defs`. An empty list
+    /// selects `*`.
+    pub fn query_sql_with(
+        &self,
+        table: impl Into<TableRef>,
+        projections: &[Projection],
+    ) -> Result<String> {
+        for projection in projections {
+            projection.validate(&self.column_defs)?;
+        }
+
+        let mut sql = String::with_capacity(32 + self.estimated_sql_len());
+        sql.push_str("SELECT ");
+        if projections.is_empty() {
+            sql.push('*');
+        } else {
+            for (i, projection) in projections.iter().enumerate() {
+                if i > 0 {
+                    sql.push_str(", ");
+                }
+                projection.write_sql(&mut sql)?;
+            }
+        }
+        sql.push_str(" FROM ");
+        table.into().write_sql(&mut sql, false)?;
+
+        self.write_clauses(&mut sql)?;
+        self.write_settings(&mut sql, false)?;
+        Ok(sql)
+    }
+}
+
+// Function names and aliases are limited to plain identifiers
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
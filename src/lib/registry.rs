@@ -0,0 +1,623 @@
+//! Column registry for ClickHouse filters
+//!
+//! A `ColumnRegistry` holds the column definitions for a table behind an `Arc`, so it
+//! can be built once at startup and shared across request handlers without cloning
+//! the underlying map for every `FilteringOptions` or `ClickHouseFilters`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::{column_registry, ColumnDef, ColumnRegistry};
+//!
+//! let columns: ColumnRegistry = column_registry! {
+//!     name: String,
+//!     age: UInt32,
+//!     tags: ArrayString,
+//! };
+//!
+//! assert_eq!(columns.len(), 3);
+//! assert!(matches!(columns.get("age"), Some(ColumnDef::UInt32("age"))));
+//! assert_eq!(columns.column_names(), vec!["age", "name", "tags"]);
+//! ```
+
+use crate::count::Deduplication;
+use crate::custom::{escape_like, ColumnRenderer, CustomOperator, RendererRef};
+use crate::filtering::{
+    FilterCondition, FilterExpression, FilterOperator, JsonNumberType, SameColumnPolicy, TextIndex,
+};
+use crate::sorting::Sorting;
+use crate::ColumnDef;
+use eyre::Result;
+use std::collections::hash_map;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Shared, cheaply clonable set of column definitions
+#[derive(Debug, Clone, Default)]
+pub struct ColumnRegistry {
+    columns: Arc<HashMap<&'static str, ColumnDef>>,
+    case_overrides: Arc<HashMap<&'static str, bool>>,
+    soft_delete: Option<&'static str>,
+    sign: Option<&'static str>,
+    deduplication: Option<Deduplication>,
+    json_paths: Arc<HashMap<&'static str, HashMap<&'static str, JsonNumberType>>>,
+    same_column_policy: SameColumnPolicy,
+    search: Arc<Vec<&'static str>>,
+    per_page_limit: Option<i64>,
+    operators: Arc<HashMap<String, Arc<CustomOperator>>>,
+    allowed_settings: Arc<Vec<&'static str>>,
+    unique_key: Option<UniqueKey>,
+    text_indexes: Arc<HashMap<&'static str, TextIndex>>,
+    labels: Arc<HashMap<&'static str, ColumnLabel>>,
+}
+
+/// Human-readable name and description of a column, e.g. `Signup date` for
+/// `created_at`
+///
+/// Declared with `ColumnRegistry::with_label` and `with_description`. Labels
+/// name the column in JSON filter errors and in `FilterExpression::describe()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnLabel {
+    pub label: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Columns that together identify a row, e.g. `(tenant_id, id)`
+///
+/// Declared with `ColumnRegistry::with_unique_key`. Sorting by the key makes the
+/// row order total, so paging never skips or repeats rows with equal sort values.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UniqueKey {
+    columns: Vec<&'static str>,
+}
+
+impl UniqueKey {
+    pub fn new(columns: &[&'static str]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+        }
+    }
+
+    /// The key columns, in declaration order
+    pub fn columns(&self) -> &[&'static str] {
+        &self.columns
+    }
+
+    /// Check that the key has at least one column and all are in `registry`
+    pub fn validate(&self, registry: &ColumnRegistry) -> Result<()> {
+        if self.columns.is_empty() {
+            return Err(eyre::eyre!("Unique key must not be empty"));
+        }
+        for column in &self.columns {
+            if !registry.contains(column) {
+                return Err(eyre::eyre!("Column not found: {}", column));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `sorting` orders by every key column, e.g. before keyset paging
+    pub fn check_sorting(&self, sorting: &Sorting) -> Result<()> {
+        for column in &self.columns {
+            if !sorting.sorts_by(column) {
+                return Err(eyre::eyre!(
+                    "Sorting does not include unique key column: {}",
+                    column
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<&UniqueKey> for Deduplication {
+    fn from(key: &UniqueKey) -> Self {
+        Deduplication::UniqueKey(key.columns.clone())
+    }
+}
+
+impl ColumnRegistry {
+    /// Create a new registry from a column map
+    ///
+    /// `LowCardinality` columns are declared case-sensitive, see `with_case_sensitive`.
+    pub fn new(columns: HashMap<&'static str, ColumnDef>) -> Self {
+        let case_overrides = columns
+            .iter()
+            .filter(|(_, column_def)| column_def.is_low_cardinality())
+            .map(|(name, _)| (*name, false))
+            .collect();
+        Self {
+            columns: Arc::new(columns),
+            case_overrides: Arc::new(case_overrides),
+            soft_delete: None,
+            sign: None,
+            deduplication: None,
+            json_paths: Arc::default(),
+            same_column_policy: SameColumnPolicy::default(),
+            search: Arc::default(),
+            per_page_limit: None,
+            operators: Arc::default(),
+            allowed_settings: Arc::default(),
+            unique_key: None,
+            text_indexes: Arc::default(),
+            labels: Arc::default(),
+        }
+    }
+
+    /// Always compare this column case-sensitively
+    ///
+    /// Overrides the filter-wide `case_insensitive` flag, so exact-match key
+    /// columns (codes, slugs, external IDs) are not wrapped in `lower()`. This is
+    /// the default for `LowCardinality` columns, where `lower()` would have to
+    /// materialize the dictionary; `with_case_insensitive` opts them back in.
+    pub fn with_case_sensitive(self, column: &'static str) -> Self {
+        self.with_case(column, false)
+    }
+
+    /// Always compare this column case-insensitively
+    pub fn with_case_insensitive(self, column: &'static str) -> Self {
+        self.with_case(column, true)
+    }
+
+    fn with_case(mut self, column: &'static str, case_insensitive: bool) -> Self {
+        Arc::make_mut(&mut self.case_overrides).insert(column, case_insensitive);
+        self
+    }
+
+    /// Declared case sensitivity for a column, if it overrides the filter default
+    pub fn is_case_insensitive(&self, column: &str) -> Option<bool> {
+        self.case_overrides.get(column).copied()
+    }
+
+    /// All per-column case sensitivity overrides
+    pub fn case_overrides(&self) -> Arc<HashMap<&'static str, bool>> {
+        Arc::clone(&self.case_overrides)
+    }
+
+    /// Declare a soft-delete column for this table
+    ///
+    /// Queries built with this registry exclude deleted rows unless
+    /// `ClickHouseFilters::include_deleted()` is called. Date and time columns
+    /// (e.g. `deleted_at`) mark live rows with NULL; `UInt8` and `Boolean`
+    /// columns (e.g. `is_deleted`) mark them with 0.
+    pub fn with_soft_delete(mut self, column: &'static str) -> Self {
+        self.soft_delete = Some(column);
+        self
+    }
+
+    /// The declared soft-delete column, if any
+    pub fn soft_delete_column(&self) -> Option<&'static str> {
+        self.soft_delete
+    }
+
+    /// The condition matching rows that are not soft-deleted
+    pub fn soft_delete_condition(&self) -> Result<Option<FilterCondition>> {
+        let Some(column) = self.soft_delete else {
+            return Ok(None);
+        };
+
+        let condition = match self.get(column).map(ColumnDef::base) {
+            Some(
+                ColumnDef::Date(_)
+                | ColumnDef::Date32(_)
+                | ColumnDef::DateTime(_)
+                | ColumnDef::DateTime64(_)
+                | ColumnDef::DateTime64Precision(..),
+            ) => FilterCondition::date_time(column, FilterOperator::IsNull, None),
+            Some(ColumnDef::UInt8(_)) => {
+                FilterCondition::uint8(column, FilterOperator::Equal, Some(0))
+            }
+            Some(ColumnDef::Boolean(_)) => {
+                FilterCondition::boolean(column, FilterOperator::Equal, Some(false))
+            }
+            Some(_) => {
+                return Err(eyre::eyre!(
+                    "Unsupported soft-delete column type: {}",
+                    column
+                ))
+            }
+            None => return Err(eyre::eyre!("Column not found: {}", column)),
+        };
+
+        Ok(Some(condition))
+    }
+
+    /// Declare the sign column of a CollapsingMergeTree table
+    ///
+    /// Row queries built with this registry only return `sign = 1` rows, and
+    /// `count_sql()` counts with `sum(sign)` so cancelled rows that have not been
+    /// collapsed yet are not double-counted.
+    pub fn with_sign_column(mut self, column: &'static str) -> Self {
+        self.sign = Some(column);
+        self
+    }
+
+    /// The declared sign column, if any
+    pub fn sign_column(&self) -> Option<&'static str> {
+        self.sign
+    }
+
+    /// The condition matching the current state rows of a collapsing table
+    pub fn sign_condition(&self) -> Result<Option<FilterCondition>> {
+        let Some(column) = self.sign else {
+            return Ok(None);
+        };
+
+        match self.get(column) {
+            Some(ColumnDef::Int8(_)) => Ok(Some(FilterCondition::Int8Value {
+                column: column.to_string(),
+                operator: FilterOperator::Equal,
+                value: Some(1),
+            })),
+            Some(_) => Err(eyre::eyre!("Unsupported sign column type: {}", column)),
+            None => Err(eyre::eyre!("Column not found: {}", column)),
+        }
+    }
+
+    /// Count deduplicated rows in `count_sql()` for a ReplacingMergeTree table
+    pub fn with_deduplicated_count(mut self, deduplication: Deduplication) -> Self {
+        self.deduplication = Some(deduplication);
+        self
+    }
+
+    /// The declared count deduplication strategy, if any
+    pub fn deduplication(&self) -> Option<&Deduplication> {
+        self.deduplication.as_ref()
+    }
+
+    /// Declare the numeric type stored at a path inside a JSON column
+    ///
+    /// JSON filters on this path then compare numerically with
+    /// `JSONExtractInt`/`JSONExtractFloat` instead of as strings.
+    pub fn with_json_path_type(
+        mut self,
+        column: &'static str,
+        path: &'static str,
+        number_type: JsonNumberType,
+    ) -> Self {
+        Arc::make_mut(&mut self.json_paths)
+            .entry(column)
+            .or_default()
+            .insert(path, number_type);
+        self
+    }
+
+    /// Declared numeric type of a JSON path, if any
+    pub fn json_path_type(&self, column: &str, path: &str) -> Option<JsonNumberType> {
+        self.json_paths.get(column)?.get(path).copied()
+    }
+
+    /// Set how JSON filters on the same column are combined
+    pub fn with_same_column_policy(mut self, policy: SameColumnPolicy) -> Self {
+        self.same_column_policy = policy;
+        self
+    }
+
+    /// How JSON filters on the same column are combined
+    pub fn same_column_policy(&self) -> SameColumnPolicy {
+        self.same_column_policy
+    }
+
+    /// Declare the string columns searched by a request's free-text `search`
+    pub fn with_search_columns(mut self, columns: &[&'static str]) -> Self {
+        self.search = Arc::new(columns.to_vec());
+        self
+    }
+
+    /// The declared search columns
+    pub fn search_columns(&self) -> &[&'static str] {
+        &self.search
+    }
+
+    /// The condition matching rows where any search column contains `term`
+    ///
+    /// `%`, `_` and `\` in the term match literally.
+    pub fn search_condition(&self, term: &str) -> Result<FilterExpression> {
+        if self.search.is_empty() {
+            return Err(eyre::eyre!("No search columns declared"));
+        }
+
+        let pattern = format!("%{}%", escape_like(term));
+
+        let mut conditions = Vec::with_capacity(self.search.len());
+        for column in self.search.iter() {
+            match self.get(column).map(ColumnDef::base) {
+                Some(ColumnDef::String(_) | ColumnDef::FixedString(_)) => {}
+                Some(_) => return Err(eyre::eyre!("Unsupported search column type: {}", column)),
+                None => return Err(eyre::eyre!("Column not found: {}", column)),
+            }
+            conditions.push(FilterExpression::Condition(FilterCondition::string(
+                column,
+                FilterOperator::Like,
+                Some(&pattern),
+            )));
+        }
+        Ok(match conditions.len() {
+            1 => conditions.remove(0),
+            _ => FilterExpression::or(conditions),
+        })
+    }
+
+    /// Cap the page size a request can ask for
+    pub fn with_per_page_limit(mut self, limit: i64) -> Self {
+        self.per_page_limit = Some(limit);
+        self
+    }
+
+    /// The declared page size cap, if any
+    pub fn per_page_limit(&self) -> Option<i64> {
+        self.per_page_limit
+    }
+
+    /// Declare the query settings a request may override
+    ///
+    /// See `ClickHouseFilters::with_setting`.
+    pub fn with_allowed_settings(mut self, names: &[&'static str]) -> Self {
+        self.allowed_settings = Arc::new(names.to_vec());
+        self
+    }
+
+    /// The settings a request may override
+    pub fn allowed_settings(&self) -> &[&'static str] {
+        &self.allowed_settings
+    }
+
+    /// Declare a text skip index on a string column
+    ///
+    /// `CONTAINS`, `STARTS WITH` and `ENDS WITH` filters built through the
+    /// registry then use the functions the index can serve, see `TextIndex`.
+    pub fn with_text_index(mut self, column: &'static str, index: TextIndex) -> Self {
+        Arc::make_mut(&mut self.text_indexes).insert(column, index);
+        self
+    }
+
+    /// The text skip index declared on a column, if any
+    pub fn text_index(&self, column: &str) -> Option<TextIndex> {
+        self.text_indexes.get(column).copied()
+    }
+
+    // Use the column's text index for a plain string condition it can serve
+    pub(crate) fn apply_text_index(&self, condition: FilterCondition) -> FilterCondition {
+        match condition {
+            FilterCondition::StringValue {
+                column,
+                operator:
+                    operator @ (FilterOperator::Contains
+                    | FilterOperator::StartsWith
+                    | FilterOperator::EndsWith),
+                value: Some(value),
+            } => match self.text_index(&column) {
+                Some(index) => FilterCondition::IndexedText {
+                    column,
+                    operator,
+                    value,
+                    index,
+                },
+                None => FilterCondition::StringValue {
+                    column,
+                    operator,
+                    value: Some(value),
+                },
+            },
+            condition => condition,
+        }
+    }
+
+    /// Give a column a human-readable label, e.g. `Signup date` for `created_at`
+    ///
+    /// JSON filter errors on the column are prefixed with the label, and
+    /// `FilterExpression::describe()` names the column by it.
+    pub fn with_label(mut self, column: &'static str, label: &str) -> Self {
+        Arc::make_mut(&mut self.labels)
+            .entry(column)
+            .or_default()
+            .label = Some(label.to_string());
+        self
+    }
+
+    /// Describe a column for end users, e.g. as help text next to a filter input
+    pub fn with_description(mut self, column: &'static str, description: &str) -> Self {
+        Arc::make_mut(&mut self.labels)
+            .entry(column)
+            .or_default()
+            .description = Some(description.to_string());
+        self
+    }
+
+    /// The declared label of a column, if any
+    pub fn label(&self, column: &str) -> Option<&str> {
+        self.labels.get(column)?.label.as_deref()
+    }
+
+    /// The declared description of a column, if any
+    pub fn description(&self, column: &str) -> Option<&str> {
+        self.labels.get(column)?.description.as_deref()
+    }
+
+    /// The label of a column, or its name when it has none
+    pub fn display_name<'a>(&'a self, column: &'a str) -> &'a str {
+        self.label(column).unwrap_or(column)
+    }
+
+    /// Declare the columns that together identify a row
+    ///
+    /// `ClickHouseFilters` then appends any key column missing from the ORDER BY
+    /// of a sorted or paginated query, so the order is stable across pages. The
+    /// columns are checked when the filters are created.
+    pub fn with_unique_key(mut self, columns: &[&'static str]) -> Self {
+        self.unique_key = Some(UniqueKey::new(columns));
+        self
+    }
+
+    /// The declared unique key, if any
+    pub fn unique_key(&self) -> Option<&UniqueKey> {
+        self.unique_key.as_ref()
+    }
+
+    /// Register a custom operator for every column
+    ///
+    /// The operator becomes available to JSON filters and `to_filter_condition()`
+    /// under `name`, matched case-insensitively. Custom operators take precedence
+    /// over built-in operators of the same name.
+    pub fn with_operator<F>(mut self, name: &'static str, render: F) -> Self
+    where
+        F: Fn(&mut String, &str, Option<&str>, bool) -> Result<()> + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.operators).insert(
+            name.to_uppercase(),
+            Arc::new(CustomOperator::new(name, Arc::new(render))),
+        );
+        self
+    }
+
+    /// Look up a custom operator by name, case-insensitively
+    pub fn custom_operator(&self, name: &str) -> Option<&CustomOperator> {
+        self.operators.get(&name.to_uppercase()).map(Arc::as_ref)
+    }
+
+    /// Build a condition on a registered column, resolving custom operators
+    pub fn to_filter_condition(
+        &self,
+        column: &str,
+        operator: &str,
+        value: &str,
+    ) -> Result<FilterCondition> {
+        let column_def = self
+            .get(column)
+            .ok_or_else(|| eyre::eyre!("Column not found: {}", column))?;
+
+        match self.operators.get(&operator.to_uppercase()) {
+            Some(custom) => Ok(FilterCondition::Custom {
+                column: column_def.get_column_name(),
+                operator: FilterOperator::Custom(custom.name()),
+                value: Some(value.to_string()),
+                renderer: RendererRef(Arc::clone(custom) as Arc<dyn ColumnRenderer>),
+            }),
+            None => Ok(self.apply_text_index(column_def.to_filter_condition(operator, value)?)),
+        }
+    }
+
+    /// Look up a column definition by name
+    pub fn get(&self, name: &str) -> Option<&ColumnDef> {
+        self.columns.get(name)
+    }
+
+    /// Check whether a column is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.columns.contains_key(name)
+    }
+
+    /// Names of all filterable columns, sorted alphabetically
+    pub fn column_names(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self.columns.keys().copied().collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Iterate over all registered columns
+    pub fn iter(&self) -> hash_map::Iter<'_, &'static str, ColumnDef> {
+        self.columns.iter()
+    }
+
+    /// Number of registered columns
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Check whether the registry has no columns
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Borrow the underlying column map
+    pub fn as_map(&self) -> &HashMap<&'static str, ColumnDef> {
+        &self.columns
+    }
+    // Whether both are clones of the same registry. The shared maps are compared
+    // by address, so registries built separately never match, even with the same
+    // columns; that keeps the check cheap and never wrongly true.
+    pub(crate) fn same_as(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.columns, &other.columns)
+            && Arc::ptr_eq(&self.case_overrides, &other.case_overrides)
+            && Arc::ptr_eq(&self.json_paths, &other.json_paths)
+            && Arc::ptr_eq(&self.search, &other.search)
+            && Arc::ptr_eq(&self.operators, &other.operators)
+            && Arc::ptr_eq(&self.allowed_settings, &other.allowed_settings)
+            && Arc::ptr_eq(&self.text_indexes, &other.text_indexes)
+            && Arc::ptr_eq(&self.labels, &other.labels)
+            && self.soft_delete == other.soft_delete
+            && self.sign == other.sign
+            && self.deduplication == other.deduplication
+            && self.same_column_policy == other.same_column_policy
+            && self.per_page_limit == other.per_page_limit
+            && self.unique_key == other.unique_key
+    }
+
+    // Hash consistent with `same_as`
+    pub(crate) fn hash_identity<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.columns).hash(state);
+        Arc::as_ptr(&self.case_overrides).hash(state);
+        Arc::as_ptr(&self.json_paths).hash(state);
+        Arc::as_ptr(&self.search).hash(state);
+        Arc::as_ptr(&self.operators).hash(state);
+        Arc::as_ptr(&self.allowed_settings).hash(state);
+        Arc::as_ptr(&self.text_indexes).hash(state);
+        Arc::as_ptr(&self.labels).hash(state);
+        self.soft_delete.hash(state);
+        self.sign.hash(state);
+        self.deduplication.hash(state);
+        self.same_column_policy.hash(state);
+        self.per_page_limit.hash(state);
+        self.unique_key.hash(state);
+    }
+}
+
+impl From<HashMap<&'static str, ColumnDef>> for ColumnRegistry {
+    fn from(columns: HashMap<&'static str, ColumnDef>) -> Self {
+        Self::new(columns)
+    }
+}
+
+impl From<&ColumnRegistry> for ColumnRegistry {
+    fn from(registry: &ColumnRegistry) -> Self {
+        registry.clone()
+    }
+}
+
+impl FromIterator<(&'static str, ColumnDef)> for ColumnRegistry {
+    fn from_iter<I: IntoIterator<Item = (&'static str, ColumnDef)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a ColumnRegistry {
+    type Item = (&'a &'static str, &'a ColumnDef);
+    type IntoIter = hash_map::Iter<'a, &'static str, ColumnDef>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Build a `ColumnRegistry` from `column: Type` pairs
+///
+/// Each type is a `ColumnDef` variant name, optionally wrapped as
+/// `LowCardinality(String)`, and the column name is used as-is.
+#[macro_export]
+macro_rules! column_registry {
+    (@def $column:ident LowCardinality($kind:ident)) => {
+        $crate::ColumnDef::$kind(stringify!($column)).low_cardinality()
+    };
+    (@def $column:ident $kind:ident) => {
+        $crate::ColumnDef::$kind(stringify!($column))
+    };
+    ($($column:ident : $kind:ident $(($inner:ident))?),* $(,)?) => {
+        <$crate::ColumnRegistry as ::std::iter::FromIterator<_>>::from_iter([
+            $((
+                stringify!($column),
+                $crate::column_registry!(@def $column $kind $(($inner))?),
+            )),*
+        ])
+    };
+}
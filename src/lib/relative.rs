@@ -0,0 +1,164 @@
+//! Relative dates against a fixed reference time
+//!
+//! `RELATIVE` filters such as `now() - INTERVAL 7 DAY` are evaluated by ClickHouse
+//! when the query runs, so the same filters produce different results from one run
+//! to the next. `ReferenceTime` replaces `now()`, `today()` and `yesterday()` with
+//! literals for a fixed timestamp, which makes generated SQL deterministic for
+//! snapshot tests and lets historical reports be replayed as of a past date.
+//! `FilteringOptions::reference_time` applies it to every condition at build time.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterCondition, FilterExpression};
+//! use clickhouse_filters::relative::ReferenceTime;
+//! use clickhouse_filters::{ColumnDef, FilteringOptions};
+//! use std::collections::HashMap;
+//!
+//! let mut columns = HashMap::new();
+//! columns.insert("created_at", ColumnDef::DateTime("created_at"));
+//!
+//! let filtering = FilteringOptions::new(
+//!     vec![FilterExpression::Condition(FilterCondition::relative_date(
+//!         "created_at",
+//!         "now() - INTERVAL 7 DAY",
+//!     ))],
+//!     columns,
+//! )
+//! .reference_time(ReferenceTime::new("2024-06-01 12:00:00").unwrap());
+//!
+//! assert_eq!(
+//!     filtering.to_sql().unwrap(),
+//!     " WHERE created_at > toDateTime('2024-06-01 12:00:00') - INTERVAL 7 DAY"
+//! );
+//! ```
+
+use crate::filtering::{fraction_digits, DateRangeType, Escaped, FilterCondition};
+use eyre::Result;
+use std::fmt::Write;
+
+/// A fixed timestamp standing in for the current time
+///
+/// The timestamp is read in the server's time zone, as `now()` would be.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReferenceTime {
+    timestamp: String,
+}
+
+impl ReferenceTime {
+    /// Reference time from `YYYY-MM-DD HH:MM:SS`, with optional fractional
+    /// seconds, or a plain `YYYY-MM-DD` for midnight
+    pub fn new(timestamp: &str) -> Result<Self> {
+        let timestamp = timestamp.trim();
+        if !timestamp.is_ascii() {
+            return Err(eyre::eyre!("Invalid reference time: {}", timestamp));
+        }
+        let shape = |pattern: &str, value: &str| {
+            value.len() == pattern.len()
+                && pattern.bytes().zip(value.bytes()).all(|(p, v)| match p {
+                    b'd' => v.is_ascii_digit(),
+                    _ => p == v,
+                })
+        };
+        let (date, time) = timestamp.split_at(timestamp.len().min(10));
+        let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+        let valid = shape("dddd-dd-dd", date)
+            && (time.is_empty() && fraction.is_empty() || shape(" dd:dd:dd", time))
+            && fraction.bytes().all(|b| b.is_ascii_digit());
+        if !valid {
+            return Err(eyre::eyre!("Invalid reference time: {}", timestamp));
+        }
+
+        Ok(ReferenceTime {
+            timestamp: if time.is_empty() {
+                format!("{} 00:00:00", date)
+            } else {
+                timestamp.to_string()
+            },
+        })
+    }
+
+    /// The timestamp, e.g. `2024-06-01 12:00:00`
+    pub fn timestamp(&self) -> &str {
+        &self.timestamp
+    }
+
+    /// Replace `now()`, `today()` and `yesterday()` in a relative date expression
+    ///
+    /// Function names match in any case. Other calls, such as `now('UTC')`, are
+    /// left unchanged.
+    pub fn rewrite(&self, expr: &str) -> String {
+        let mut out = String::with_capacity(expr.len() + 32);
+        let mut rest = expr;
+        let mut word_start = true;
+        while let Some(c) = rest.chars().next() {
+            if word_start {
+                if let Some((function, len)) = reference_call(rest) {
+                    self.write_function(&mut out, function);
+                    rest = &rest[len..];
+                    word_start = false;
+                    continue;
+                }
+            }
+            out.push(c);
+            word_start = !(c.is_alphanumeric() || c == '_');
+            rest = &rest[c.len_utf8()..];
+        }
+        out
+    }
+
+    /// Rewrite the expression of a relative date condition; other conditions are
+    /// returned unchanged
+    pub fn apply(&self, condition: FilterCondition) -> FilterCondition {
+        match condition {
+            FilterCondition::DateRange {
+                column,
+                range_type: DateRangeType::Relative(expr),
+            } => FilterCondition::DateRange {
+                column,
+                range_type: DateRangeType::Relative(self.rewrite(&expr)),
+            },
+            condition => condition,
+        }
+    }
+
+    fn write_function(&self, out: &mut String, function: &str) {
+        let date = &self.timestamp[..10];
+        // Writing to a String can't fail
+        let _ = match function {
+            "now" => match fraction_digits(&self.timestamp) {
+                0 => write!(out, "toDateTime('{}')", Escaped(&self.timestamp)),
+                digits => write!(
+                    out,
+                    "toDateTime64('{}', {})",
+                    Escaped(&self.timestamp),
+                    digits
+                ),
+            },
+            "today" => write!(out, "toDate('{}')", date),
+            _ => write!(out, "(toDate('{}') - 1)", date),
+        };
+    }
+}
+
+// Match `now()`, `today()` or `yesterday()` at the start of `input`, returning the
+// lowercase function name and the length of the call
+fn reference_call(input: &str) -> Option<(&'static str, usize)> {
+    for function in ["now", "today", "yesterday"] {
+        let Some(name) = input.get(..function.len()) else {
+            continue;
+        };
+        if !name.eq_ignore_ascii_case(function) {
+            continue;
+        }
+        let after = &input[function.len()..];
+        let Some(args) = after.trim_start().strip_prefix('(') else {
+            continue;
+        };
+        let Some(end) = args.trim_start().strip_prefix(')') else {
+            continue;
+        };
+        return Some((function, input.len() - end.len()));
+    }
+    None
+}
@@ -0,0 +1,226 @@
+//! List-endpoint request bodies
+//!
+//! `QueryRequest` is the JSON envelope of a typical list endpoint: filters, sorting,
+//! pagination and a free-text search term. `ClickHouseFilters::from_request()` validates
+//! the whole body against the column definitions and returns a ready filter object.
+//!
+//! - `filters` are parsed as by `FilteringOptions::from_json_filters`.
+//! - `sort` is parsed as by `Sorting::from_json_sorts`.
+//! - `search` matches rows where any of the registry's search columns (see
+//!   `ColumnRegistry::with_search_columns`) contains the term, case-insensitively.
+//! - `page` defaults to 1 and `per_page` to 10, capped by the registry's
+//!   `per_page_limit` (100 unless declared). The total record count is not known
+//!   yet, so it is 0 until pagination is rebuilt with the real count.
+//! - `version` marks the payload format. Bodies with `"version": 1` reject unknown
+//!   keys, at the top level and inside filters and sorts, so a typo such as `"pge"`
+//!   is an error rather than silently ignored. Bodies without a version are the
+//!   old format, whose unknown keys are still ignored. Standalone `JsonFilter` and
+//!   `JsonSort` values always reject unknown keys.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::JsonFilter;
+//! use clickhouse_filters::request::{QueryRequest, QUERY_FORMAT_VERSION};
+//! use clickhouse_filters::sorting::JsonSort;
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry};
+//!
+//! let columns: ColumnRegistry = column_registry! { name: String, email: String, age: UInt32 };
+//! let columns = columns.with_search_columns(&["name", "email"]);
+//!
+//! let request = QueryRequest {
+//!     filters: vec![JsonFilter {
+//!         n: "age".to_string(),
+//!         f: ">".to_string(),
+//!         v: "30".to_string(),
+//!         c: None,
+//!     }],
+//!     sort: vec![JsonSort { n: "-age".to_string(), d: None }],
+//!     page: Some(2),
+//!     per_page: Some(20),
+//!     search: Some("smith".to_string()),
+//!     version: Some(QUERY_FORMAT_VERSION),
+//! };
+//!
+//! let filters = ClickHouseFilters::from_request(&request, columns).unwrap();
+//! assert_eq!(
+//!     filters.sql().unwrap(),
+//!     " WHERE (age > 30 AND (lower(name) LIKE lower('%smith%') OR lower(email) LIKE lower('%smith%'))) \
+//!      ORDER BY age DESC LIMIT 20 OFFSET 20"
+//! );
+//! ```
+
+use crate::filtering::{JsonFilter, LogicalOperator};
+use crate::sorting::{JsonSort, Sorting};
+use crate::{ClickHouseFilters, ColumnRegistry, FilteringOptions, PaginationOptions};
+use eyre::Result;
+#[cfg(feature = "serde")]
+use serde::de::IgnoredAny;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::collections::BTreeMap;
+
+/// Page size cap used when the registry does not declare one
+pub const DEFAULT_PER_PAGE_LIMIT: i64 = 100;
+
+/// The newest payload format version `from_request()` understands
+pub const QUERY_FORMAT_VERSION: u32 = 1;
+
+/// JSON body of a list-endpoint request
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Default)]
+pub struct QueryRequest {
+    pub filters: Vec<JsonFilter>,
+    pub sort: Vec<JsonSort>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub search: Option<String>,
+    /// Payload format version; `None` for the old, unversioned format
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub version: Option<u32>,
+}
+
+// The body as received, with any unknown keys collected so they can be rejected
+// for versioned payloads and ignored for the old format
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RawRequest {
+    #[serde(default)]
+    filters: Vec<RawFilter>,
+    #[serde(default)]
+    sort: Vec<RawSort>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+    search: Option<String>,
+    version: Option<u32>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, IgnoredAny>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RawFilter {
+    n: String,
+    f: String,
+    v: String,
+    c: Option<String>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, IgnoredAny>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RawSort {
+    n: String,
+    d: Option<String>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, IgnoredAny>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for QueryRequest {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let raw = RawRequest::deserialize(deserializer)?;
+        if raw.version.is_some() {
+            let unknown = raw
+                .unknown
+                .keys()
+                .map(|key| {
+                    (
+                        key,
+                        &["filters", "sort", "page", "per_page", "search", "version"][..],
+                    )
+                })
+                .chain(raw.filters.iter().flat_map(|filter| {
+                    filter
+                        .unknown
+                        .keys()
+                        .map(|key| (key, &["n", "f", "v", "c"][..]))
+                }))
+                .chain(
+                    raw.sort
+                        .iter()
+                        .flat_map(|sort| sort.unknown.keys().map(|key| (key, &["n", "d"][..]))),
+                )
+                .next();
+            if let Some((key, expected)) = unknown {
+                return Err(D::Error::unknown_field(key, expected));
+            }
+        }
+
+        Ok(QueryRequest {
+            filters: raw
+                .filters
+                .into_iter()
+                .map(|filter| JsonFilter {
+                    n: filter.n,
+                    f: filter.f,
+                    v: filter.v,
+                    c: filter.c,
+                })
+                .collect(),
+            sort: raw
+                .sort
+                .into_iter()
+                .map(|sort| JsonSort {
+                    n: sort.n,
+                    d: sort.d,
+                })
+                .collect(),
+            page: raw.page,
+            per_page: raw.per_page,
+            search: raw.search,
+            version: raw.version,
+        })
+    }
+}
+
+impl ClickHouseFilters {
+    /// Build filters from a list-endpoint request body
+    ///
+    /// Unknown columns, operators and sort directions are rejected, as is a
+    /// search term when the registry declares no search columns, and a format
+    /// version newer than `QUERY_FORMAT_VERSION`.
+    pub fn from_request(
+        request: &QueryRequest,
+        column_defs: impl Into<ColumnRegistry>,
+    ) -> Result<ClickHouseFilters> {
+        if let Some(version) = request.version {
+            if version == 0 || version > QUERY_FORMAT_VERSION {
+                return Err(eyre::eyre!(
+                    "Unsupported request format version: {}",
+                    version
+                ));
+            }
+        }
+        let column_defs = column_defs.into();
+
+        let mut filtering = FilteringOptions::from_json_filters(&request.filters, &column_defs)?;
+        let search = request.search.as_deref().map(str::trim);
+        if let Some(term) = search.filter(|term| !term.is_empty()) {
+            let search =
+                FilteringOptions::new(vec![column_defs.search_condition(term)?], &column_defs);
+            filtering = Some(match filtering {
+                Some(filtering) => filtering.merge(search, LogicalOperator::And),
+                None => search,
+            });
+        }
+
+        let sorting = Sorting::from_json_sorts(&request.sort, &column_defs)?;
+        let pagination = PaginationOptions::new(
+            request.page.unwrap_or(1),
+            request.per_page.unwrap_or(0),
+            column_defs
+                .per_page_limit()
+                .unwrap_or(DEFAULT_PER_PAGE_LIMIT),
+            0,
+        );
+
+        ClickHouseFilters::new(Some(pagination), sorting.columns, filtering, column_defs)
+    }
+}
@@ -0,0 +1,135 @@
+//! Response envelope for a page of results
+//!
+//! `QueryResult` bundles the rows of a page with its pagination metadata and,
+//! optionally, the number of matching rows per value of some columns, so services
+//! return the same shape for every filtered list. With the `serde` feature it
+//! serializes as `{"rows": [...], "pagination": {...}, "facets": {...}}`, leaving
+//! out `facets` when there are none. The `client` feature's `fetch_page()` and
+//! `fetch_facets()` fill it from a live server.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::pagination::Paginate;
+//! use clickhouse_filters::result::{FacetCount, FacetCounts, QueryResult};
+//!
+//! let paginate = Paginate::new(1, 2, 10, 5);
+//! let mut facets = FacetCounts::new();
+//! facets.insert("status", vec![FacetCount::new("open", 3), FacetCount::new("closed", 2)]);
+//!
+//! let result = QueryResult::new(vec!["Jane", "John"], paginate.pagination).with_facets(facets);
+//! assert_eq!(result.pagination.total_pages, 3);
+//! assert_eq!(result.facets.unwrap().get("status").unwrap()[0].count, 3);
+//! ```
+
+use crate::pagination::Pagination;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A page of rows with its pagination metadata and optional facet counts
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResult<T> {
+    pub rows: Vec<T>,
+    pub pagination: Pagination,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub facets: Option<FacetCounts>,
+}
+
+impl<T> QueryResult<T> {
+    pub fn new(rows: Vec<T>, pagination: Pagination) -> Self {
+        QueryResult {
+            rows,
+            pagination,
+            facets: None,
+        }
+    }
+
+    /// Result of an unpaginated query: a single page holding every row
+    pub fn single_page(rows: Vec<T>) -> Self {
+        let total = rows.len() as i64;
+        let pagination = Pagination::new(1, total, i64::from(total > 0), total);
+        QueryResult::new(rows, pagination)
+    }
+
+    pub fn with_facets(mut self, facets: FacetCounts) -> Self {
+        self.facets = Some(facets);
+        self
+    }
+
+    /// Convert the rows, e.g. from database rows to API types, keeping the metadata
+    pub fn map<U, F>(self, f: F) -> QueryResult<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        QueryResult {
+            rows: self.rows.into_iter().map(f).collect(),
+            pagination: self.pagination,
+            facets: self.facets,
+        }
+    }
+}
+
+/// Number of matching rows per value, for each faceted column
+///
+/// Serializes as a map from column name to `[{"value": ..., "count": ...}]`, with
+/// the values in descending order of count.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FacetCounts {
+    columns: BTreeMap<String, Vec<FacetCount>>,
+}
+
+impl FacetCounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the counts of a column, replacing any it had
+    pub fn insert(&mut self, column: &str, counts: Vec<FacetCount>) {
+        self.columns.insert(column.to_string(), counts);
+    }
+
+    /// The counts of a column, if it was faceted
+    pub fn get(&self, column: &str) -> Option<&[FacetCount]> {
+        self.columns.get(column).map(Vec::as_slice)
+    }
+
+    /// Faceted columns and their counts, by column name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[FacetCount])> {
+        self.columns
+            .iter()
+            .map(|(column, counts)| (column.as_str(), counts.as_slice()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+/// A column value and the number of matching rows holding it
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FacetCount {
+    /// The value as text, whatever the column type
+    pub value: String,
+    pub count: u64,
+}
+
+impl FacetCount {
+    pub fn new(value: &str, count: u64) -> Self {
+        FacetCount {
+            value: value.to_string(),
+            count,
+        }
+    }
+}
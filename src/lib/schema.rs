@@ -0,0 +1,345 @@
+//! Schema helpers for ClickHouse column definitions
+//!
+//! This module maps ClickHouse type names (as reported by `system.columns` or
+//! `DESCRIBE TABLE`) to `ColumnDef` values. With the `client` feature enabled it can
+//! also build a `ColumnRegistry` directly from a live table. Tools that only have SQL
+//! access can instead run the statement from `schema_sql()` themselves and pass the
+//! `TabSeparated` output to `ColumnRegistry::from_tab_separated()`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::schema::{schema_sql, SchemaSource};
+//! use clickhouse_filters::{ColumnDef, ColumnRegistry};
+//!
+//! let column = ColumnDef::from_clickhouse_type("tags", "Array(LowCardinality(String))");
+//! assert!(matches!(column, Some(ColumnDef::ArrayString("tags"))));
+//!
+//! let column = ColumnDef::from_clickhouse_type("score", "Nullable(Float64)");
+//! assert!(matches!(column, Some(ColumnDef::Float64("score"))));
+//!
+//! assert_eq!(
+//!     schema_sql("db.users", SchemaSource::Describe).unwrap(),
+//!     "DESCRIBE TABLE db.users"
+//! );
+//!
+//! let columns = ColumnRegistry::from_tab_separated("id\tUInt64\nname\tNullable(String)\n").unwrap();
+//! assert!(matches!(columns.get("name"), Some(ColumnDef::String("name"))));
+//! ```
+
+use crate::custom::quote_identifier;
+use crate::table::TableRef;
+use crate::{ColumnDef, ColumnRegistry};
+use eyre::Result;
+use std::fmt::Write;
+
+/// Where `schema_sql()` reads the columns of a table from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SchemaSource {
+    /// `DESCRIBE TABLE db.table`
+    Describe,
+    /// `SELECT name, type FROM system.columns WHERE ...`, in column order
+    SystemColumns,
+}
+
+/// Generate a statement listing the columns of `table` with their types
+///
+/// The first two columns of the result are the name and type, as
+/// `ColumnRegistry::from_tab_separated()` expects. A `cluster()` or `remote()`
+/// table function on the reference is kept, so the schema is read from the same
+/// servers as the data; `FINAL` and `SAMPLE` are rejected.
+pub fn schema_sql(table: impl Into<TableRef>, source: SchemaSource) -> Result<String> {
+    let table = table.into();
+    if table.final_ || table.sample.is_some() {
+        return Err(eyre::eyre!(
+            "Schema queries don't take FINAL or SAMPLE: {}.{}",
+            table.database,
+            table.table
+        ));
+    }
+    table.validate()?;
+
+    let mut sql = String::with_capacity(96);
+    match source {
+        SchemaSource::Describe => {
+            sql.push_str("DESCRIBE TABLE ");
+            table.write_sql(&mut sql, false)?;
+        }
+        SchemaSource::SystemColumns => {
+            let columns = TableRef {
+                function: table.function.clone(),
+                ..TableRef::new("system", "columns")
+            };
+            sql.push_str("SELECT name, type FROM ");
+            columns.write_sql(&mut sql, false)?;
+            // Both names are plain identifiers, so they need no escaping
+            write!(
+                sql,
+                " WHERE database = '{}' AND table = '{}' ORDER BY position",
+                table.database, table.table
+            )?;
+        }
+    }
+    Ok(sql)
+}
+
+impl ColumnRegistry {
+    /// Build a column registry from `(name, type)` pairs, e.g. rows of `DESCRIBE TABLE`
+    ///
+    /// Columns are mapped and skipped as in `ColumnDef::from_system_columns`, and
+    /// names are leaked in the same way.
+    pub fn from_schema_rows<N, T>(rows: impl IntoIterator<Item = (N, T)>) -> ColumnRegistry
+    where
+        N: AsRef<str>,
+        T: AsRef<str>,
+    {
+        rows.into_iter()
+            .filter_map(|(name, type_name)| {
+                let name = quote_identifier(name.as_ref()).into_owned();
+                let name: &'static str = Box::leak(name.into_boxed_str());
+                ColumnDef::from_clickhouse_type(name, type_name.as_ref()).map(|def| (name, def))
+            })
+            .collect()
+    }
+
+    /// Build a column registry from the `TabSeparated` output of `schema_sql()`
+    ///
+    /// Each line holds a name and a type, followed by any number of other
+    /// columns, such as the default and comment columns of `DESCRIBE TABLE`. A
+    /// header line of `name` and `type` from `TabSeparatedWithNames` is skipped. Fails on
+    /// lines without a type and on output without any columns.
+    pub fn from_tab_separated(output: &str) -> Result<ColumnRegistry> {
+        let mut rows = Vec::new();
+        for (i, line) in output.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields = line.split('\t');
+            let (Some(name), Some(type_name)) = (fields.next(), fields.next()) else {
+                return Err(eyre::eyre!("Invalid schema row: {}", line));
+            };
+            if i == 0 && name == "name" && type_name == "type" {
+                continue;
+            }
+            rows.push((
+                unescape_tab_separated(name),
+                unescape_tab_separated(type_name),
+            ));
+        }
+        if rows.is_empty() {
+            return Err(eyre::eyre!("Schema output has no columns"));
+        }
+        Ok(ColumnRegistry::from_schema_rows(rows))
+    }
+}
+
+impl ColumnDef {
+    /// Map a ClickHouse type name to a column definition
+    ///
+    /// `Nullable(...)` wrappers are unwrapped, including inside `Array(...)`.
+    /// `LowCardinality(...)` columns become `ColumnDef::LowCardinality`, while
+    /// array elements are unwrapped. Returns `None` for types that have no
+    /// `ColumnDef`.
+    pub fn from_clickhouse_type(name: &'static str, type_name: &str) -> Option<ColumnDef> {
+        let low_cardinality = has_low_cardinality(type_name.trim());
+        let type_name = unwrap_modifiers(type_name.trim());
+
+        if let Some(inner) = strip_wrapper(type_name, "Array") {
+            return array_column(name, unwrap_modifiers(inner));
+        }
+
+        // Parameterised types are matched on their base name only
+        let base = type_name
+            .split_once('(')
+            .map_or(type_name, |(base, _)| base)
+            .trim();
+
+        let column = match base {
+            "String" => ColumnDef::String(name),
+            "FixedString" => ColumnDef::FixedString(name),
+            "UInt8" => ColumnDef::UInt8(name),
+            "UInt16" => ColumnDef::UInt16(name),
+            "UInt32" => ColumnDef::UInt32(name),
+            "UInt64" => ColumnDef::UInt64(name),
+            "UInt128" => ColumnDef::UInt128(name),
+            "UInt256" => ColumnDef::UInt256(name),
+            "Int8" => ColumnDef::Int8(name),
+            "Int16" => ColumnDef::Int16(name),
+            "Int32" => ColumnDef::Int32(name),
+            "Int64" => ColumnDef::Int64(name),
+            "Int128" => ColumnDef::Int128(name),
+            "Int256" => ColumnDef::Int256(name),
+            "Float32" => ColumnDef::Float32(name),
+            "Float64" => ColumnDef::Float64(name),
+            "Date" => ColumnDef::Date(name),
+            "Date32" => ColumnDef::Date32(name),
+            "DateTime" => ColumnDef::DateTime(name),
+            "DateTime64" => match datetime64_precision(type_name) {
+                Some(precision) => ColumnDef::DateTime64Precision(name, precision),
+                None => ColumnDef::DateTime64(name),
+            },
+            "Bool" | "Boolean" => ColumnDef::Boolean(name),
+            "UUID" => ColumnDef::UUID(name),
+            "Enum8" => ColumnDef::Enum8(name),
+            "Enum16" => ColumnDef::Enum16(name),
+            "IPv4" => ColumnDef::IPv4(name),
+            "IPv6" => ColumnDef::IPv6(name),
+            "Decimal" | "Decimal32" | "Decimal64" | "Decimal128" | "Decimal256" => {
+                ColumnDef::Decimal(name)
+            }
+            // Map filters compare string keys and string values
+            "Map" if is_string_map(type_name) => ColumnDef::Map(name),
+            "JSON" | "Object" => ColumnDef::JSON(name),
+            _ => return None,
+        };
+
+        Some(if low_cardinality {
+            column.low_cardinality()
+        } else {
+            column
+        })
+    }
+
+    /// Build a column registry from `system.columns` for a live table
+    ///
+    /// Columns whose type has no `ColumnDef` (see `from_clickhouse_type`) are
+    /// skipped. Column names are leaked to satisfy the `&'static str` names held
+    /// by `ColumnDef`, so this is intended to be called once at startup. Names
+    /// that are not plain identifiers are registered quoted, e.g. `` `order id` ``.
+    #[cfg(feature = "client")]
+    pub async fn from_system_columns(
+        client: &clickhouse::Client,
+        database: &str,
+        table: &str,
+    ) -> Result<ColumnRegistry> {
+        #[derive(clickhouse::Row, serde::Deserialize)]
+        struct SystemColumn {
+            name: String,
+            #[serde(rename = "type")]
+            type_name: String,
+        }
+
+        let rows = client
+            .query(
+                "SELECT name, type FROM system.columns WHERE database = ? AND table = ? ORDER BY position",
+            )
+            .bind(database)
+            .bind(table)
+            .fetch_all::<SystemColumn>()
+            .await?;
+
+        if rows.is_empty() {
+            return Err(eyre::eyre!("Table not found: {}.{}", database, table));
+        }
+
+        Ok(ColumnRegistry::from_schema_rows(
+            rows.into_iter().map(|row| (row.name, row.type_name)),
+        ))
+    }
+}
+
+// Undo the `\t`, `\n` and `\\` style escapes of TabSeparated fields
+fn unescape_tab_separated(field: &str) -> String {
+    let mut value = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => value.push('\t'),
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('0') => value.push('\0'),
+            Some(other) => value.push(other),
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+// Strip `Wrapper(...)` and return the inner type, if the type uses that wrapper
+fn strip_wrapper<'a>(type_name: &'a str, wrapper: &str) -> Option<&'a str> {
+    type_name
+        .strip_prefix(wrapper)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')
+        .map(str::trim)
+}
+
+// Remove any Nullable/LowCardinality wrappers around a type
+fn unwrap_modifiers(mut type_name: &str) -> &str {
+    loop {
+        match strip_wrapper(type_name, "Nullable")
+            .or_else(|| strip_wrapper(type_name, "LowCardinality"))
+        {
+            Some(inner) => type_name = inner,
+            None => return type_name,
+        }
+    }
+}
+
+// Check for a LowCardinality wrapper, possibly inside or around Nullable
+fn has_low_cardinality(mut type_name: &str) -> bool {
+    loop {
+        if strip_wrapper(type_name, "LowCardinality").is_some() {
+            return true;
+        }
+        match strip_wrapper(type_name, "Nullable") {
+            Some(inner) => type_name = inner,
+            None => return false,
+        }
+    }
+}
+
+// Read the precision from `DateTime64(3)` or `DateTime64(3, 'UTC')`
+fn datetime64_precision(type_name: &str) -> Option<u8> {
+    let inner = strip_wrapper(type_name, "DateTime64")?;
+    let precision = inner.split(',').next()?.trim();
+    precision.parse().ok()
+}
+
+// Check for `Map(String, String)`, allowing LowCardinality/Nullable wrappers
+fn is_string_map(type_name: &str) -> bool {
+    let Some(inner) = strip_wrapper(type_name, "Map") else {
+        return false;
+    };
+    let Some((key, value)) = inner.split_once(',') else {
+        return false;
+    };
+    unwrap_modifiers(key.trim()) == "String" && unwrap_modifiers(value.trim()) == "String"
+}
+
+// Map the element type of an Array(...) to an array column definition
+fn array_column(name: &'static str, element: &str) -> Option<ColumnDef> {
+    let base = element
+        .split_once('(')
+        .map_or(element, |(base, _)| base)
+        .trim();
+
+    let column = match base {
+        "String" | "FixedString" => ColumnDef::ArrayString(name),
+        "UInt8" => ColumnDef::ArrayUInt8(name),
+        "UInt16" => ColumnDef::ArrayUInt16(name),
+        "UInt32" => ColumnDef::ArrayUInt32(name),
+        "UInt64" => ColumnDef::ArrayUInt64(name),
+        "Int8" => ColumnDef::ArrayInt8(name),
+        "Int16" => ColumnDef::ArrayInt16(name),
+        "Int32" => ColumnDef::ArrayInt32(name),
+        "Int64" => ColumnDef::ArrayInt64(name),
+        "Float32" => ColumnDef::ArrayFloat32(name),
+        "Float64" => ColumnDef::ArrayFloat64(name),
+        "UUID" => ColumnDef::ArrayUUID(name),
+        "Date" | "Date32" => ColumnDef::ArrayDate(name),
+        "DateTime" => ColumnDef::ArrayDateTime(name),
+        "Enum8" => ColumnDef::ArrayEnum8(name),
+        "Enum16" => ColumnDef::ArrayEnum16(name),
+        "IPv4" => ColumnDef::ArrayIPv4(name),
+        "IPv6" => ColumnDef::ArrayIPv6(name),
+        _ => return None,
+    };
+
+    Some(column)
+}
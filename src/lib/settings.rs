@@ -0,0 +1,162 @@
+//! Query-level SETTINGS for generated SQL
+//!
+//! Full queries built by `query_sql()`, `query_sql_with()`, `count_sql()` and
+//! `count_sql_with()` can end with a `SETTINGS` clause. The `sql()` fragment never
+//! carries one, since callers splice it into their own statements.
+//!
+//! `with_read_limit()` derives `max_rows_to_read` and, except for `WITH TIES` pages,
+//! `max_result_rows` from the page size, so a filter that misses every index is killed by the server instead of
+//! scanning the whole table. `with_query_tag()` sets `log_comment`, so queries in
+//! `system.query_log` can be traced back to the API request that produced them.
+//!
+//! `with_setting()` adds a request-scoped setting, such as a higher `max_threads` for
+//! a dashboard. Only settings the registry allows with `with_allowed_settings()`
+//! are accepted, and they take precedence over the settings derived above.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry, PaginationOptions};
+//!
+//! let columns: ColumnRegistry = column_registry! { name: String };
+//! let filters = ClickHouseFilters::new(
+//!     Some(PaginationOptions::new(1, 20, 100, 1000)),
+//!     vec![],
+//!     None,
+//!     columns,
+//! )
+//! .unwrap()
+//! .with_read_limit(1000)
+//! .with_query_tag("req-42");
+//!
+//! assert_eq!(
+//!     filters.query_sql("db.users", &["name"]).unwrap(),
+//!     "SELECT name FROM db.users LIMIT 20 OFFSET 0 \
+//!      SETTINGS max_rows_to_read = 20000, max_result_rows = 20, log_comment = 'req-42'"
+//! );
+//! ```
+//!
+//! Request-scoped overrides:
+//!
+//! ```rust
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry};
+//!
+//! let columns: ColumnRegistry = column_registry! { name: String };
+//! let columns = columns.with_allowed_settings(&["max_threads", "use_query_cache"]);
+//! let filters = ClickHouseFilters::new(None, vec![], None, columns)
+//!     .unwrap()
+//!     .with_setting("max_threads", "16")
+//!     .unwrap()
+//!     .with_setting("use_query_cache", "1")
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     filters.query_sql("db.users", &["name"]).unwrap(),
+//!     "SELECT name FROM db.users SETTINGS max_threads = 16, use_query_cache = 1"
+//! );
+//! assert!(filters.with_setting("max_memory_usage", "0").is_err());
+//! ```
+
+use crate::ClickHouseFilters;
+use eyre::Result;
+use std::fmt::Write;
+
+impl ClickHouseFilters {
+    /// Limit rows read and returned based on the page size
+    ///
+    /// Row queries get `max_rows_to_read = per_page * multiplier` and
+    /// `max_result_rows = per_page`; count queries and `WITH TIES` pages only get
+    /// `max_rows_to_read`, since ties past the page boundary would exceed the result
+    /// limit. Has no effect without pagination.
+    pub fn with_read_limit(mut self, multiplier: u64) -> Self {
+        self.read_limit = Some(multiplier);
+        self
+    }
+
+    /// Tag generated queries with `log_comment`
+    ///
+    /// The tag is escaped, so it can carry arbitrary request identifiers.
+    pub fn with_query_tag(mut self, tag: &str) -> Self {
+        self.query_tag = Some(tag.to_string());
+        self
+    }
+
+    /// Set a query setting for this request
+    ///
+    /// `name` must be one of the registry's `allowed_settings()`. Numbers are
+    /// written as-is and other values as escaped strings. Setting the same name
+    /// again replaces its value.
+    pub fn with_setting(mut self, name: &str, value: &str) -> Result<Self> {
+        let name = self
+            .column_defs
+            .allowed_settings()
+            .iter()
+            .copied()
+            .find(|allowed| *allowed == name)
+            .ok_or_else(|| eyre::eyre!("Setting not allowed: {}", name))?;
+
+        self.settings.retain(|(existing, _)| existing != name);
+        self.settings.push((name.to_string(), value.to_string()));
+        Ok(self)
+    }
+
+    // Write the SETTINGS clause (with leading space), if any settings apply
+    pub(crate) fn write_settings(&self, out: &mut String, count: bool) -> Result<()> {
+        let mut settings: Vec<(&str, String)> = Vec::new();
+
+        if let (Some(multiplier), Some(pagination)) = (self.read_limit, &self.pagination) {
+            let per_page = pagination.pagination.per_page.max(0) as u64;
+            settings.push((
+                "max_rows_to_read",
+                per_page.saturating_mul(multiplier).to_string(),
+            ));
+            // WITH TIES pages can return more than per_page rows
+            if !count && !pagination.with_ties {
+                settings.push(("max_result_rows", per_page.to_string()));
+            }
+        }
+
+        if let Some(tag) = &self.query_tag {
+            settings.push(("log_comment", string_literal(tag)));
+        }
+
+        // Request-scoped settings replace derived ones of the same name
+        settings.retain(|(name, _)| self.settings.iter().all(|(other, _)| other != name));
+        settings.extend(
+            self.settings
+                .iter()
+                .map(|(name, value)| (name.as_str(), setting_literal(value))),
+        );
+
+        for (i, (name, value)) in settings.iter().enumerate() {
+            out.push_str(if i == 0 { " SETTINGS " } else { ", " });
+            write!(out, "{} = {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+// Render a request-scoped setting value: finite numbers as-is, anything else
+// as a string. Values are kept raw until now, so a cached instance can't carry
+// SQL in them.
+fn setting_literal(value: &str) -> String {
+    if value.parse::<i64>().is_ok() || value.parse::<f64>().is_ok_and(f64::is_finite) {
+        value.to_string()
+    } else {
+        string_literal(value)
+    }
+}
+
+// Quote a string setting value, escaping backslashes and single quotes
+fn string_literal(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('\'');
+    for c in value.chars() {
+        if c == '\\' || c == '\'' {
+            literal.push('\\');
+        }
+        literal.push(c);
+    }
+    literal.push('\'');
+    literal
+}
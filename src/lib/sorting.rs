@@ -14,83 +14,489 @@
 //! ]);
 //!
 //! assert_eq!(sorting.columns.len(), 2);
-//! assert_eq!(sorting.sql, " ORDER BY age DESC, name ASC");
+//! assert_eq!(sorting.to_sql(), " ORDER BY age DESC, name ASC");
+//! assert_eq!(sorting.to_fragment(), "ORDER BY age DESC, name ASC");
+//! ```
+//!
+//! Sorting can also come from API payloads as `JsonSort` entries, validated
+//! against the column definitions:
+//!
+//! ```rust
+//! use clickhouse_filters::sorting::{JsonSort, Sorting};
+//! use clickhouse_filters::{column_registry, ColumnRegistry};
+//!
+//! let columns: ColumnRegistry = column_registry! { name: String, created_at: DateTime };
+//! let sorts = vec![
+//!     JsonSort { n: "-created_at".to_string(), d: None },
+//!     JsonSort { n: "name".to_string(), d: Some("asc".to_string()) },
+//! ];
+//!
+//! let sorting = Sorting::from_json_sorts(&sorts, &columns).unwrap();
+//! assert_eq!(sorting.to_sql(), " ORDER BY created_at DESC, name ASC");
+//! ```
+//!
+//! Values inside a JSON column are sorted by path. They are extracted as strings
+//! unless the path's number type is given, or declared on the registry for JSON
+//! sorts (`data.priority`):
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::JsonNumberType;
+//! use clickhouse_filters::sorting::{JsonSort, SortedColumn, Sorting};
+//! use clickhouse_filters::{column_registry, ColumnRegistry};
+//!
+//! let sorting = Sorting::new(vec![SortedColumn::json("data", "priority", "desc")]);
+//! assert_eq!(sorting.to_sql(), " ORDER BY JSONExtractString(data, 'priority') DESC");
+//!
+//! let columns: ColumnRegistry = column_registry! { data: JSON };
+//! let columns = columns.with_json_path_type("data", "priority", JsonNumberType::Int);
+//! let sorts = vec![JsonSort { n: "-data.priority".to_string(), d: None }];
+//!
+//! let sorting = Sorting::from_json_sorts(&sorts, &columns).unwrap();
+//! assert_eq!(sorting.to_sql(), " ORDER BY JSONExtractInt(data, 'priority') DESC");
+//! ```
+//!
+//! Array columns can be sorted by their length or by one of their elements:
+//!
+//! ```rust
+//! use clickhouse_filters::sorting::{SortedColumn, Sorting};
+//!
+//! let sorting = Sorting::in_order(vec![
+//!     SortedColumn::array_length("tags", "desc"),
+//!     SortedColumn::array_element("scores", 1, "asc"),
+//! ]);
+//! assert_eq!(sorting.to_sql(), " ORDER BY length(tags) DESC, arrayElement(scores, 1) ASC");
+//! ```
+//!
+//! Explicit ranks per value sort by business priority rather than alphabetically:
+//!
+//! ```rust
+//! use clickhouse_filters::sorting::{SortedColumn, Sorting};
+//!
+//! let sorting = Sorting::new(vec![SortedColumn::case("status", "asc")
+//!     .when("critical", 0)
+//!     .when("high", 1)]);
+//! assert_eq!(
+//!     sorting.to_sql(),
+//!     " ORDER BY CASE WHEN status = 'critical' THEN 0 WHEN status = 'high' THEN 1 ELSE 2 END ASC"
+//! );
 //! ```
 
+use crate::filtering::{Escaped, JsonNumberType};
+use crate::registry::UniqueKey;
+use crate::{ColumnDef, ColumnRegistry};
+use eyre::Result;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::str::FromStr;
+
 /// SortOrder enum represents sort direction
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Serialized as `asc` or `desc`; `ASC` and `DESC` are accepted too.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SortOrder {
+    #[cfg_attr(feature = "serde", serde(alias = "ASC"))]
     Asc,
+    #[cfg_attr(feature = "serde", serde(alias = "DESC"))]
     Desc,
 }
 
+impl FromStr for SortOrder {
+    type Err = eyre::Report;
+
+    /// Parse `asc` or `desc` in any case
+    fn from_str(direction: &str) -> Result<Self> {
+        match direction.to_lowercase().as_str() {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(eyre::eyre!("Invalid sort direction: {}", direction)),
+        }
+    }
+}
+
 /// SortedColumn represents a column to sort by with direction
-#[derive(Debug, Clone)]
+///
+/// With the `serde` feature it deserializes from e.g.
+/// `{"column": "name", "order": "desc"}`; unknown directions are rejected, and
+/// `target` defaults to the column's value.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SortedColumn {
     /// Column name
     pub column: String,
     /// Sorting order
     pub order: SortOrder,
+    /// What to sort by: the column itself or a value derived from it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target: SortTarget,
+}
+
+/// The value of a column a `SortedColumn` sorts by
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum SortTarget {
+    /// The column's value
+    #[default]
+    Column,
+    /// A path inside a JSON column
+    Json {
+        path: String,
+        /// Extract the value as a number instead of a string
+        number_type: Option<JsonNumberType>,
+    },
+    /// The number of elements of an array column, `length(tags)`
+    ArrayLength,
+    /// An element of an array column, `arrayElement(scores, 1)`
+    ///
+    /// Indexes start at 1; negative indexes count from the end.
+    ArrayElement(i64),
+    /// A rank per value, `CASE WHEN status = 'critical' THEN 0 ... ELSE 2 END`
+    Case {
+        /// Values and their ranks, checked in order
+        whens: Vec<(String, i64)>,
+        /// Rank of every other value; one more than the highest rank when unset
+        otherwise: Option<i64>,
+    },
 }
 
 impl SortedColumn {
     /// Create a new SortedColumn
+    ///
+    /// Directions other than `asc` and `desc` fall back to `ASC`; use
+    /// `try_new` to reject them instead.
     pub fn new(column: &str, order: &str) -> SortedColumn {
-        let order = match order.to_lowercase().as_str() {
-            "asc" => SortOrder::Asc,
-            "desc" => SortOrder::Desc,
-            _ => SortOrder::Asc,
-        };
         SortedColumn {
             column: column.to_string(),
-            order,
+            order: order.parse().unwrap_or(SortOrder::Asc),
+            target: SortTarget::Column,
+        }
+    }
+
+    /// Create a new SortedColumn, failing if the direction isn't `asc` or `desc`
+    pub fn try_new(column: &str, order: &str) -> Result<SortedColumn> {
+        Ok(SortedColumn {
+            order: order.parse()?,
+            ..SortedColumn::new(column, order)
+        })
+    }
+
+    /// Sort by the string at `path` inside the JSON column `column`
+    pub fn json(column: &str, path: &str, order: &str) -> SortedColumn {
+        SortedColumn {
+            target: SortTarget::Json {
+                path: path.to_string(),
+                number_type: None,
+            },
+            ..SortedColumn::new(column, order)
+        }
+    }
+
+    /// Sort by the number of elements of the array column `column`
+    pub fn array_length(column: &str, order: &str) -> SortedColumn {
+        SortedColumn {
+            target: SortTarget::ArrayLength,
+            ..SortedColumn::new(column, order)
+        }
+    }
+
+    /// Sort by the element at `index` (starting at 1) of the array column `column`
+    ///
+    /// Rows with shorter arrays sort as the element type's default value.
+    pub fn array_element(column: &str, index: i64, order: &str) -> SortedColumn {
+        SortedColumn {
+            target: SortTarget::ArrayElement(index),
+            ..SortedColumn::new(column, order)
+        }
+    }
+
+    /// Sort by ranks given to the values of `column`, e.g. for business priorities
+    ///
+    /// Add the ranks with `when()`. Without any, the column sorts by its value.
+    pub fn case(column: &str, order: &str) -> SortedColumn {
+        SortedColumn {
+            target: SortTarget::Case {
+                whens: Vec::new(),
+                otherwise: None,
+            },
+            ..SortedColumn::new(column, order)
+        }
+    }
+
+    /// Rank rows where the column equals `value`
+    ///
+    /// Has no effect on columns not sorted with `case()`.
+    pub fn when(mut self, value: &str, rank: i64) -> SortedColumn {
+        if let SortTarget::Case { whens, .. } = &mut self.target {
+            whens.push((value.to_string(), rank));
+        }
+        self
+    }
+
+    /// Rank rows matching no `when()` value
+    ///
+    /// Has no effect on columns not sorted with `case()`.
+    pub fn otherwise(mut self, rank: i64) -> SortedColumn {
+        if let SortTarget::Case { otherwise, .. } = &mut self.target {
+            *otherwise = Some(rank);
+        }
+        self
+    }
+
+    /// Extract the JSON path as a number, e.g. with `JSONExtractInt`
+    ///
+    /// Has no effect on columns without a JSON path.
+    pub fn with_number_type(mut self, number_type: JsonNumberType) -> SortedColumn {
+        if let SortTarget::Json {
+            number_type: target,
+            ..
+        } = &mut self.target
+        {
+            *target = Some(number_type);
+        }
+        self
+    }
+
+    /// Check that a `case()` column can rank the values without a `when()`
+    ///
+    /// They rank one above the highest rank unless `otherwise()` is set, which
+    /// doesn't exist when the highest rank is `i64::MAX`.
+    pub fn validate(&self) -> Result<()> {
+        if let SortTarget::Case {
+            whens,
+            otherwise: None,
+        } = &self.target
+        {
+            if whens.iter().any(|(_, rank)| *rank == i64::MAX) {
+                return Err(eyre::eyre!(
+                    "Sort rank out of range for {}: {}",
+                    self.column,
+                    i64::MAX
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The expression sorted by, e.g. `name` or `length(tags)`
+    ///
+    /// Dots in a JSON path separate nested keys, so `user.name` extracts
+    /// `JSONExtractString(data, 'user', 'name')`.
+    pub fn expression(&self) -> String {
+        match &self.target {
+            SortTarget::Column => self.column.clone(),
+            SortTarget::Json { path, number_type } => {
+                let function = number_type.map_or("JSONExtractString", |t| t.extract_function());
+                let keys: Vec<String> = path
+                    .split('.')
+                    .map(|key| format!("'{}'", Escaped(key)))
+                    .collect();
+                format!("{}({}, {})", function, self.column, keys.join(", "))
+            }
+            SortTarget::ArrayLength => format!("length({})", self.column),
+            SortTarget::ArrayElement(index) => format!("arrayElement({}, {})", self.column, index),
+            SortTarget::Case { whens, .. } if whens.is_empty() => self.column.clone(),
+            SortTarget::Case { whens, otherwise } => {
+                let mut sql = String::from("CASE");
+                for (value, rank) in whens {
+                    // Writing to a String can't fail
+                    let _ = write!(
+                        sql,
+                        " WHEN {} = '{}' THEN {}",
+                        self.column,
+                        Escaped(value),
+                        rank
+                    );
+                }
+                // `validate()` rejects a highest rank with nothing above it, so
+                // saturating only keeps unvalidated columns from overflowing
+                let otherwise = otherwise.unwrap_or_else(|| {
+                    whens
+                        .iter()
+                        .map(|(_, rank)| rank.saturating_add(1))
+                        .max()
+                        .unwrap_or(0)
+                });
+                let _ = write!(sql, " ELSE {} END", otherwise);
+                sql
+            }
         }
     }
 }
 
+/// JSON sort structure for API usage
+///
+/// A leading `-` on the column name (`-created_at`) is shorthand for a
+/// descending sort when no direction is given. Unknown keys are rejected when
+/// deserializing.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonSort {
+    pub n: String,         // name/column
+    pub d: Option<String>, // optional direction (asc/desc)
+}
+
 /// Sorting represents a complete ORDER BY clause
-#[derive(Debug, Clone)]
+///
+/// The clause is rendered on demand from `columns`, so columns changed after
+/// `new` are reflected in `to_sql()`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Sorting {
     /// Vector of columns to sort by
     pub columns: Vec<SortedColumn>,
-    /// The generated SQL
-    pub sql: String,
 }
 
 impl Sorting {
     /// Create a new Sorting from a list of SortedColumns
+    ///
+    /// Columns are ordered by their expression and deduplicated, so the same
+    /// columns always render the same clause. Use `in_order()` when the order of
+    /// the keys matters.
     pub fn new(columns: Vec<SortedColumn>) -> Sorting {
-        let mut columns = columns;
+        let mut columns: Vec<(String, SortedColumn)> = columns
+            .into_iter()
+            .map(|column| (column.expression(), column))
+            .collect();
         // Sort and deduplicate columns to ensure consistent ordering
-        columns.sort_by(|a, b| a.column.cmp(&b.column));
-        columns.dedup_by(|a, b| a.column == b.column);
-
-        let mut sql = if !columns.is_empty() {
-            " ORDER BY ".to_string()
-        } else {
-            "".to_string()
-        };
-
-        // Build the SQL ORDER BY clause
-        let mut first = true;
-        for column in columns.iter() {
-            if first {
-                first = false;
-            } else {
-                sql.push_str(", ");
+        columns.sort_by(|a, b| a.0.cmp(&b.0));
+        columns.dedup_by(|a, b| a.0 == b.0);
+
+        Sorting {
+            columns: columns.into_iter().map(|(_, column)| column).collect(),
+        }
+    }
+
+    /// Create a Sorting that keeps the columns in the order given
+    ///
+    /// Sorting by an expression a second time has no effect, so later repeats
+    /// are dropped.
+    pub fn in_order(columns: Vec<SortedColumn>) -> Sorting {
+        let mut seen = HashSet::with_capacity(columns.len());
+        Sorting {
+            columns: columns
+                .into_iter()
+                .filter(|column| seen.insert(column.expression()))
+                .collect(),
+        }
+    }
+
+    /// Whether a column is sorted by its own value
+    pub fn sorts_by(&self, column: &str) -> bool {
+        self.columns
+            .iter()
+            .any(|c| c.column == column && c.target == SortTarget::Column)
+    }
+
+    /// Append each key column not yet sorted by, ascending, as a tiebreaker
+    pub fn with_tiebreaker(self, key: &UniqueKey) -> Sorting {
+        self.with_ordered_tiebreaker(key, SortOrder::Asc)
+    }
+
+    // Append each key column not yet sorted by, in `order`
+    pub(crate) fn with_ordered_tiebreaker(mut self, key: &UniqueKey, order: SortOrder) -> Sorting {
+        for column in key.columns() {
+            if !self.sorts_by(column) {
+                self.columns.push(SortedColumn {
+                    column: column.to_string(),
+                    order: order.clone(),
+                    target: SortTarget::Column,
+                });
             }
-            match column.order {
-                SortOrder::Asc => {
-                    sql.push_str(&column.column);
-                    sql.push_str(" ASC");
-                }
-                SortOrder::Desc => {
-                    sql.push_str(&column.column);
-                    sql.push_str(" DESC");
+        }
+        self
+    }
+
+    /// The ORDER BY clause with a leading space, e.g. ` ORDER BY age DESC`
+    ///
+    /// Empty when there are no columns.
+    pub fn to_sql(&self) -> String {
+        let mut sql = String::with_capacity(self.estimated_sql_len());
+        self.write_sql(&mut sql);
+        sql
+    }
+
+    /// The ORDER BY clause without the leading space, e.g. `ORDER BY age DESC`
+    pub fn to_fragment(&self) -> String {
+        let mut sql = self.to_sql();
+        if !sql.is_empty() {
+            sql.remove(0);
+        }
+        sql
+    }
+
+    /// Write the ORDER BY clause, with a leading space, into an existing buffer
+    pub fn write_sql(&self, out: &mut String) {
+        for (i, column) in self.columns.iter().enumerate() {
+            out.push_str(if i == 0 { " ORDER BY " } else { ", " });
+            out.push_str(&column.expression());
+            out.push_str(match column.order {
+                SortOrder::Asc => " ASC",
+                SortOrder::Desc => " DESC",
+            });
+        }
+    }
+
+    // Rough length of the rendered clause, for sizing buffers
+    pub(crate) fn estimated_sql_len(&self) -> usize {
+        match self.columns.len() {
+            0 => 0,
+            n => 10 + self.columns.iter().map(|c| c.column.len()).sum::<usize>() + n * 7,
+        }
+    }
+
+    /// Create a Sorting from JSON sorts
+    ///
+    /// Every column must be present in `column_defs`, and directions must be
+    /// `asc` or `desc` (in any case). JSON columns can be sorted by a path with
+    /// `column.path`, using the path's declared number type if it has one.
+    pub fn from_json_sorts(sorts: &[JsonSort], column_defs: &ColumnRegistry) -> Result<Sorting> {
+        let mut columns = Vec::with_capacity(sorts.len());
+        for sort in sorts {
+            let (name, descending) = match sort.n.strip_prefix('-') {
+                Some(name) => (name, true),
+                None => (sort.n.as_str(), false),
+            };
+            let (column, target) = match name.split_once('.') {
+                Some((column, path))
+                    if !column_defs.contains(name)
+                        && matches!(
+                            column_defs.get(column).map(ColumnDef::base),
+                            Some(ColumnDef::JSON(_))
+                        ) =>
+                {
+                    let target = SortTarget::Json {
+                        path: path.to_string(),
+                        number_type: column_defs.json_path_type(column, path),
+                    };
+                    (column, target)
                 }
+                _ => (name, SortTarget::Column),
+            };
+            if !column_defs.contains(column) {
+                return Err(eyre::eyre!("Column not found: {}", column));
             }
-        }
 
-        Sorting { columns, sql }
+            let order = match sort.d.as_deref() {
+                None if descending => SortOrder::Desc,
+                None => SortOrder::Asc,
+                Some(direction) => match direction.parse()? {
+                    SortOrder::Asc if descending => {
+                        return Err(eyre::eyre!("Conflicting sort direction for: {}", column))
+                    }
+                    order => order,
+                },
+            };
+            columns.push(SortedColumn {
+                column: column.to_string(),
+                order,
+                target,
+            });
+        }
+        Ok(Sorting::in_order(columns))
     }
 }
@@ -0,0 +1,124 @@
+//! Statements built around the filtered SELECT
+//!
+//! `insert_select_sql()` wraps the query generated by `query_sql()` in an
+//! `INSERT INTO ... SELECT`, so extracts and backfills are driven by the same filter
+//! definitions as the API. `create_view_sql()` persists the filters as a view, e.g.
+//! for saved user segments. `verify_sql()` wraps the query in `EXPLAIN`, so a live
+//! server can check its syntax, tables and columns without reading any data.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry, FilteringOptions};
+//!
+//! let columns: ColumnRegistry = column_registry! { id: UInt64, country: String };
+//! let filtering = FilteringOptions::case_sensitive(
+//!     vec![FilterExpression::Condition(FilterCondition::string(
+//!         "country",
+//!         FilterOperator::Equal,
+//!         Some("NZ"),
+//!     ))],
+//!     &columns,
+//! );
+//! let filters = ClickHouseFilters::new(None, vec![], Some(filtering), &columns).unwrap();
+//!
+//! assert_eq!(
+//!     filters
+//!         .insert_select_sql("db.users", "extracts", "nz_users", &["id", "country"])
+//!         .unwrap(),
+//!     "INSERT INTO extracts.nz_users (id, country) \
+//!      SELECT id, country FROM db.users WHERE country = 'NZ'"
+//! );
+//!
+//! assert_eq!(
+//!     filters
+//!         .create_view_sql("db.users", "segments", "nz_users", &[])
+//!         .unwrap(),
+//!     "CREATE OR REPLACE VIEW segments.nz_users AS SELECT * FROM db.users WHERE country = 'NZ'"
+//! );
+//!
+//! assert_eq!(
+//!     filters.verify_sql("db.users", &["id"]).unwrap(),
+//!     "EXPLAIN SELECT id FROM db.users WHERE country = 'NZ'"
+//! );
+//! ```
+
+use crate::table::TableRef;
+use crate::ClickHouseFilters;
+use eyre::Result;
+use std::fmt::Write;
+
+impl ClickHouseFilters {
+    /// Generate an `INSERT INTO target SELECT ... FROM source` statement
+    ///
+    /// The SELECT is the one `query_sql()` generates for the source table, with
+    /// filters, sorting, pagination and settings. `columns` must be present in
+    /// `column_defs` and are used for both the target and the SELECT list; an empty
+    /// list copies all columns with `SELECT *`. The target names are validated
+    /// like the source table's.
+    pub fn insert_select_sql(
+        &self,
+        table: impl Into<TableRef>,
+        target_schema: &str,
+        target_table: &str,
+        columns: &[&str],
+    ) -> Result<String> {
+        if let Some(column) = columns.iter().find(|c| !self.column_defs.contains(c)) {
+            return Err(eyre::eyre!("Column not found: {}", column));
+        }
+
+        let select = self.query_sql(table, columns)?;
+        let mut sql = String::with_capacity(32 + select.len() * 2);
+        sql.push_str("INSERT INTO ");
+        TableRef::new(target_schema, target_table).write_sql(&mut sql, false)?;
+        sql.push(' ');
+        if !columns.is_empty() {
+            write!(sql, "({}) ", columns.join(", "))?;
+        }
+        sql.push_str(&select);
+        Ok(sql)
+    }
+
+    /// Generate a `CREATE OR REPLACE VIEW ... AS SELECT ... WHERE ...` statement
+    ///
+    /// The view holds the filters only (including soft-delete and sign
+    /// conditions); sorting, pagination and settings are left to the queries run
+    /// against it. `columns` and the view names are validated as in
+    /// `insert_select_sql()`.
+    pub fn create_view_sql(
+        &self,
+        table: impl Into<TableRef>,
+        view_schema: &str,
+        view_name: &str,
+        columns: &[&str],
+    ) -> Result<String> {
+        if let Some(column) = columns.iter().find(|c| !self.column_defs.contains(c)) {
+            return Err(eyre::eyre!("Column not found: {}", column));
+        }
+
+        let mut sql = String::with_capacity(64 + self.estimated_sql_len());
+        sql.push_str("CREATE OR REPLACE VIEW ");
+        TableRef::new(view_schema, view_name).write_sql(&mut sql, false)?;
+        sql.push_str(" AS SELECT ");
+        if columns.is_empty() {
+            sql.push('*');
+        } else {
+            sql.push_str(&columns.join(", "));
+        }
+        sql.push_str(" FROM ");
+        table.into().write_sql(&mut sql, false)?;
+        sql.push_str(self.where_clause()?);
+        Ok(sql)
+    }
+
+    /// Generate `EXPLAIN` for the query built by `query_sql()`
+    ///
+    /// The server plans the query without running it, so this fails on syntax
+    /// errors and on tables or columns that don't exist, which makes it a cheap
+    /// check for saved filter presets in CI. See `verify_against()` with the
+    /// `client` feature.
+    pub fn verify_sql(&self, table: impl Into<TableRef>, columns: &[&str]) -> Result<String> {
+        Ok(format!("EXPLAIN {}", self.query_sql(table, columns)?))
+    }
+}
@@ -0,0 +1,142 @@
+//! Queries describing filtered rows, for configuring filter UIs
+//!
+//! `ClickHouseFilters::stats_sql()` renders the minimum, maximum, mean and median
+//! of a numeric column over the rows the current filters match, e.g. to set the
+//! bounds of a range slider. `distinct_values_sql()` lists the values of a column,
+//! e.g. the options of a dropdown that respect the other active filters.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry};
+//!
+//! let columns: ColumnRegistry = column_registry! { price: Float64 };
+//! let filters = ClickHouseFilters::new(None, vec![], None, columns).unwrap();
+//!
+//! assert_eq!(
+//!     filters.stats_sql("shop.products", "price").unwrap(),
+//!     "SELECT min(price), max(price), avg(price), quantile(0.5)(price) FROM shop.products"
+//! );
+//! ```
+//!
+//! A dropdown usually ignores its own selection, so the options it offers don't
+//! shrink to the value already picked:
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry, FilteringOptions};
+//!
+//! let columns: ColumnRegistry = column_registry! { country: String, status: String };
+//! let filtering = FilteringOptions::case_sensitive(
+//!     vec![
+//!         FilterExpression::Condition(FilterCondition::string("country", FilterOperator::Equal, Some("NZ"))),
+//!         FilterExpression::Condition(FilterCondition::string("status", FilterOperator::Equal, Some("open"))),
+//!     ],
+//!     &columns,
+//! );
+//! let filters = ClickHouseFilters::new(None, vec![], Some(filtering), &columns).unwrap();
+//!
+//! assert_eq!(
+//!     filters.distinct_values_sql("db.orders", "status", 100, true).unwrap(),
+//!     "SELECT DISTINCT status FROM db.orders WHERE country = 'NZ' ORDER BY status LIMIT 100"
+//! );
+//! ```
+
+use crate::filtering::FilterBuilder;
+use crate::table::TableRef;
+use crate::{ClickHouseFilters, ColumnDef};
+use eyre::Result;
+use std::borrow::Cow;
+use std::fmt::Write;
+
+impl ClickHouseFilters {
+    /// Generate a query for the min, max, mean and median of `column`
+    ///
+    /// The column must be a numeric column in `column_defs`. The median is
+    /// ClickHouse's approximate `quantile(0.5)`.
+    pub fn stats_sql(&self, table: impl Into<TableRef>, column: &str) -> Result<String> {
+        if !self.column_defs.contains(column) {
+            return Err(eyre::eyre!("Column not found: {}", column));
+        }
+        let numeric = matches!(
+            self.column_defs.get(column).map(ColumnDef::base),
+            Some(
+                ColumnDef::UInt8(_)
+                    | ColumnDef::UInt16(_)
+                    | ColumnDef::UInt32(_)
+                    | ColumnDef::UInt64(_)
+                    | ColumnDef::UInt128(_)
+                    | ColumnDef::UInt256(_)
+                    | ColumnDef::Int8(_)
+                    | ColumnDef::Int16(_)
+                    | ColumnDef::Int32(_)
+                    | ColumnDef::Int64(_)
+                    | ColumnDef::Int128(_)
+                    | ColumnDef::Int256(_)
+                    | ColumnDef::Float32(_)
+                    | ColumnDef::Float64(_)
+                    | ColumnDef::Decimal(_)
+            )
+        );
+        if !numeric {
+            return Err(eyre::eyre!(
+                "Statistics are only supported on numeric columns: {}",
+                column
+            ));
+        }
+
+        let mut sql = String::with_capacity(64 + 4 * column.len() + self.estimated_sql_len());
+        write!(
+            sql,
+            "SELECT min({0}), max({0}), avg({0}), quantile(0.5)({0}) FROM ",
+            column
+        )?;
+        table.into().write_sql(&mut sql, false)?;
+        sql.push_str(self.where_clause()?);
+        self.write_settings(&mut sql, true)?;
+        Ok(sql)
+    }
+
+    /// Generate a query for up to `limit` distinct values of `column`, in order
+    ///
+    /// With `exclude_own_filter`, conditions on `column` itself are left out (see
+    /// `FilterExpression::without_column`) so the other active filters narrow the
+    /// values but the current selection doesn't. Row policies and the soft-delete
+    /// and sign conditions are always kept.
+    pub fn distinct_values_sql(
+        &self,
+        table: impl Into<TableRef>,
+        column: &str,
+        limit: u64,
+        exclude_own_filter: bool,
+    ) -> Result<String> {
+        if !self.column_defs.contains(column) {
+            return Err(eyre::eyre!("Column not found: {}", column));
+        }
+
+        let mut sql = String::with_capacity(64 + 2 * column.len() + self.estimated_sql_len());
+        write!(sql, "SELECT DISTINCT {} FROM ", column)?;
+        table.into().write_sql(&mut sql, false)?;
+        if exclude_own_filter {
+            let filters = match &self.filters {
+                Some(filters) => {
+                    let root = filters.resolved_root()?;
+                    Some(Cow::Owned(FilterBuilder {
+                        root: root.and_then(|root| root.without_column(column)),
+                        hooks: Default::default(),
+                        ..filters.clone()
+                    }))
+                }
+                None => None,
+            };
+            if let Some(filters) = self.add_implicit(filters, true)? {
+                filters.write_sql(&mut sql)?;
+            }
+        } else {
+            sql.push_str(self.where_clause()?);
+        }
+        write!(sql, " ORDER BY {} LIMIT {}", column, limit)?;
+        self.write_settings(&mut sql, true)?;
+        Ok(sql)
+    }
+}
@@ -0,0 +1,232 @@
+//! Tables read by generated queries
+//!
+//! `TableRef` names the table after `FROM` in `query_sql()`, `count_sql()` and the
+//! other SELECT helpers. Besides `database.table`, it carries the `FINAL` and
+//! `SAMPLE` modifiers, and can wrap the table in `cluster()` or
+//! `clusterAllReplicas()` to read every shard of a distributed setup, or in
+//! `remote()` to read it from another server. Names are validated when the query
+//! is generated, so a table name taken from a request can't inject SQL.
+//!
+//! `&str` converts as `"database.table"` and `(&str, &str)` as
+//! `(database, table)`, so simple queries don't need to build a `TableRef`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::table::TableRef;
+//!
+//! let table = TableRef::new("analytics", "events")
+//!     .cluster_all_replicas("main")
+//!     .sample("0.1");
+//! assert_eq!(
+//!     table.to_sql().unwrap(),
+//!     "clusterAllReplicas('main', analytics, events) SAMPLE 0.1"
+//! );
+//!
+//! let table = TableRef::new("analytics", "events").remote("shard-02:9000");
+//! assert_eq!(
+//!     table.to_sql().unwrap(),
+//!     "remote('shard-02:9000', analytics, events)"
+//! );
+//!
+//! let table = TableRef::from("analytics.users").final_();
+//! assert_eq!(table.to_sql().unwrap(), "analytics.users FINAL");
+//! ```
+
+use crate::custom::quote_identifier;
+use eyre::Result;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Table function wrapping a table
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TableFunction {
+    /// `cluster('name', db, table)`, reading one replica of each shard
+    Cluster(String),
+    /// `clusterAllReplicas('name', db, table)`, reading every replica
+    ClusterAllReplicas(String),
+    /// `remote('addresses', db, table)`, reading from other servers
+    ///
+    /// The addresses are `host[:port]`, optionally several separated by commas
+    /// or with `{a,b}` / `{01..03}` patterns. Credentials are not supported;
+    /// configure them on the server instead.
+    Remote(String),
+}
+
+/// A table to read from, with optional modifiers
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TableRef {
+    pub database: String,
+    pub table: String,
+    /// Add `FINAL`, merging row versions at query time
+    pub final_: bool,
+    /// `SAMPLE` clause, e.g. `0.1`, `1/10`, `10000` or `1/10 OFFSET 1/2`
+    pub sample: Option<String>,
+    pub function: Option<TableFunction>,
+}
+
+impl TableRef {
+    /// Reference `database.table` without modifiers
+    pub fn new(database: &str, table: &str) -> Self {
+        TableRef {
+            database: database.to_string(),
+            table: table.to_string(),
+            final_: false,
+            sample: None,
+            function: None,
+        }
+    }
+
+    /// Read the table with `FINAL`
+    pub fn final_(mut self) -> Self {
+        self.final_ = true;
+        self
+    }
+
+    /// Read a sample of the table; the table must have a sampling key
+    pub fn sample(mut self, sample: &str) -> Self {
+        self.sample = Some(sample.to_string());
+        self
+    }
+
+    /// Read the table on every shard of `cluster` with `cluster()`
+    pub fn cluster(mut self, cluster: &str) -> Self {
+        self.function = Some(TableFunction::Cluster(cluster.to_string()));
+        self
+    }
+
+    /// Read the table on every replica of `cluster` with `clusterAllReplicas()`
+    pub fn cluster_all_replicas(mut self, cluster: &str) -> Self {
+        self.function = Some(TableFunction::ClusterAllReplicas(cluster.to_string()));
+        self
+    }
+
+    /// Read the table from the servers at `addresses` with `remote()`
+    pub fn remote(mut self, addresses: &str) -> Self {
+        self.function = Some(TableFunction::Remote(addresses.to_string()));
+        self
+    }
+
+    /// Check the database, table, cluster and remote names and the sample clause
+    ///
+    /// Database and table names must be plain identifiers. Cluster names may also
+    /// contain `-` and `.`, as they often do in server configs, and remote
+    /// addresses may also contain `:`, `,`, `[`, `]` and `{}` patterns.
+    pub fn validate(&self) -> Result<()> {
+        if self.database.is_empty() {
+            return Err(eyre::eyre!(
+                "Table reference needs a database: {}",
+                self.table
+            ));
+        }
+        for name in [&self.database, &self.table] {
+            if quote_identifier(name) != name.as_str() {
+                return Err(eyre::eyre!("Invalid table identifier: {}", name));
+            }
+        }
+        match &self.function {
+            Some(TableFunction::Cluster(name) | TableFunction::ClusterAllReplicas(name)) => {
+                let valid = !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+                if !valid {
+                    return Err(eyre::eyre!("Invalid cluster name: {}", name));
+                }
+            }
+            Some(TableFunction::Remote(addresses)) => {
+                let valid = !addresses.is_empty()
+                    && addresses.chars().all(|c| {
+                        c.is_ascii_alphanumeric()
+                            || matches!(c, '_' | '-' | '.' | ':' | ',' | '[' | ']' | '{' | '}')
+                    });
+                if !valid {
+                    return Err(eyre::eyre!("Invalid remote address: {}", addresses));
+                }
+            }
+            None => {}
+        }
+        if let Some(sample) = &self.sample {
+            let (ratio, offset) = match sample.split_once(" OFFSET ") {
+                Some((ratio, offset)) => (ratio, Some(offset)),
+                None => (sample.as_str(), None),
+            };
+            if !is_sample_ratio(ratio) || offset.is_some_and(|offset| !is_sample_ratio(offset)) {
+                return Err(eyre::eyre!("Invalid SAMPLE clause: {}", sample));
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the table with its modifiers, as written after `FROM`
+    pub fn to_sql(&self) -> Result<String> {
+        let mut sql = String::with_capacity(self.database.len() + self.table.len() + 16);
+        self.write_sql(&mut sql, false)?;
+        Ok(sql)
+    }
+
+    // Write the table into the buffer; `force_final` adds FINAL for count
+    // deduplication even when the reference doesn't ask for it
+    pub(crate) fn write_sql(&self, sql: &mut String, force_final: bool) -> Result<()> {
+        self.validate()?;
+        let function = match &self.function {
+            Some(TableFunction::Cluster(name)) => Some(("cluster", name)),
+            Some(TableFunction::ClusterAllReplicas(name)) => Some(("clusterAllReplicas", name)),
+            Some(TableFunction::Remote(addresses)) => Some(("remote", addresses)),
+            None => None,
+        };
+        match function {
+            Some((function, argument)) => write!(
+                sql,
+                "{}('{}', {}, {})",
+                function, argument, self.database, self.table
+            )?,
+            None => write!(sql, "{}.{}", self.database, self.table)?,
+        }
+        if self.final_ || force_final {
+            sql.push_str(" FINAL");
+        }
+        if let Some(sample) = &self.sample {
+            write!(sql, " SAMPLE {}", sample)?;
+        }
+        Ok(())
+    }
+}
+
+// A sample ratio or row count: `0.1`, `1/10` or `10000`
+fn is_sample_ratio(value: &str) -> bool {
+    let is_number = |part: &str| {
+        let (whole, fraction) = part.split_once('.').unwrap_or((part, "0"));
+        !whole.is_empty()
+            && !fraction.is_empty()
+            && whole.bytes().all(|b| b.is_ascii_digit())
+            && fraction.bytes().all(|b| b.is_ascii_digit())
+    };
+    match value.split_once('/') {
+        Some((numerator, denominator)) => is_number(numerator) && is_number(denominator),
+        None => is_number(value),
+    }
+}
+
+impl From<&str> for TableRef {
+    /// Split `database.table` at the first `.`; a name without one fails validation
+    fn from(name: &str) -> Self {
+        let (database, table) = name.split_once('.').unwrap_or(("", name));
+        TableRef::new(database, table)
+    }
+}
+
+impl From<(&str, &str)> for TableRef {
+    fn from((database, table): (&str, &str)) -> Self {
+        TableRef::new(database, table)
+    }
+}
+
+impl From<&TableRef> for TableRef {
+    fn from(table: &TableRef) -> Self {
+        table.clone()
+    }
+}
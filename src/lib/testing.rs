@@ -0,0 +1,466 @@
+//! Helpers for testing filter configurations
+//!
+//! Downstream services usually want to check two things: that their column
+//! definitions accept the filters their API documents, and that those filters
+//! render the SQL they expect. This module has what the crate's own tests use
+//! for both:
+//!
+//! - `assert_sql_eq()` compares SQL ignoring layout, so expectations can be wrapped
+//!   and indented freely.
+//! - `fixture_columns()` is a registry covering the common column types.
+//! - `json_filter_corpus()` generates a valid JSON filter for each operator that
+//!   every column of a registry supports, with sample values.
+//! - `adversarial_values()` generates values built from quotes, backslashes,
+//!   comment markers, pattern metacharacters and unusual unicode, each carrying
+//!   `INJECTION_MARKER`. `assert_no_breakout()` checks that the marker only
+//!   appears inside string literals of the SQL built from them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::testing::{assert_sql_eq, fixture_columns, json_filter_corpus};
+//! use clickhouse_filters::{ClickHouseFilters, FilteringOptions};
+//!
+//! let columns = fixture_columns();
+//!
+//! // Every generated filter is accepted and renders
+//! for filter in json_filter_corpus(&columns) {
+//!     let filtering = FilteringOptions::from_json_filters(&[filter], &columns).unwrap();
+//!     assert!(filtering.unwrap().to_sql().is_ok());
+//! }
+//!
+//! let filters = ClickHouseFilters::new(None, vec![], None, columns).unwrap();
+//! assert_sql_eq(
+//!     &filters.query_sql("db.users", &["id", "name"]).unwrap(),
+//!     "
+//!     SELECT id, name
+//!     FROM db.users
+//!     ",
+//! );
+//! ```
+
+use crate::filtering::JsonFilter;
+use crate::{ColumnDef, ColumnRegistry};
+use eyre::Result;
+
+/// Normalize SQL layout for comparison
+///
+/// Runs of whitespace outside quoted strings and identifiers become a single
+/// space, whitespace after `(` and before `)` or `,` is dropped, and the result
+/// is trimmed. Quoted text is kept as is.
+pub fn normalize_sql(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut quote = None;
+    let mut escaped = false;
+    let mut space = false;
+
+    for c in sql.chars() {
+        if let Some(open) = quote {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == open {
+                quote = None;
+            }
+            continue;
+        }
+
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        if space && !out.is_empty() && !out.ends_with('(') && !matches!(c, ')' | ',') {
+            out.push(' ');
+        }
+        space = false;
+        if matches!(c, '\'' | '"' | '`') {
+            quote = Some(c);
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Assert that two SQL strings are equal after `normalize_sql()`
+///
+/// Panics with both normalized statements when they differ.
+#[track_caller]
+pub fn assert_sql_eq(actual: &str, expected: &str) {
+    let actual = normalize_sql(actual);
+    let expected = normalize_sql(expected);
+    assert!(
+        actual == expected,
+        "SQL does not match\n  actual: {}\nexpected: {}",
+        actual,
+        expected
+    );
+}
+
+/// A registry with one column of each common type
+///
+/// | Column | Type |
+/// |---|---|
+/// | `id` | `UInt64` |
+/// | `name`, `email` | `String` |
+/// | `age` | `UInt32` |
+/// | `balance` | `Int64` |
+/// | `score` | `Float64` |
+/// | `price` | `Decimal` |
+/// | `active` | `Boolean` |
+/// | `status` | `Enum8` |
+/// | `birth_date` | `Date` |
+/// | `created_at` | `DateTime` |
+/// | `updated_at` | `DateTime64` |
+/// | `user_id` | `UUID` |
+/// | `ip` | `IPv4` |
+/// | `tags` | `Array(String)` |
+/// | `scores` | `Array(UInt32)` |
+/// | `attrs` | `Map(String, String)` |
+/// | `metadata` | `JSON` |
+pub fn fixture_columns() -> ColumnRegistry {
+    crate::column_registry! {
+        id: UInt64,
+        name: String,
+        email: String,
+        age: UInt32,
+        balance: Int64,
+        score: Float64,
+        price: Decimal,
+        active: Boolean,
+        status: Enum8,
+        birth_date: Date,
+        created_at: DateTime,
+        updated_at: DateTime64,
+        user_id: UUID,
+        ip: IPv4,
+        tags: ArrayString,
+        scores: ArrayUInt32,
+        attrs: Map,
+        metadata: JSON,
+    }
+}
+
+/// Generate JSON filters exercising every operator each column supports
+///
+/// Columns are visited in name order. Map columns are filtered by a sample
+/// key, and custom columns are skipped because only their renderer knows which
+/// values are valid.
+pub fn json_filter_corpus(columns: &ColumnRegistry) -> Vec<JsonFilter> {
+    let mut corpus = Vec::new();
+    for name in columns.column_names() {
+        let Some(column_def) = columns.get(name) else {
+            continue;
+        };
+        let column = match column_def {
+            ColumnDef::Map(_) => format!("{}['key']", name),
+            _ => name.to_string(),
+        };
+        corpus.extend(
+            samples(column_def)
+                .into_iter()
+                .map(|(operator, value)| JsonFilter {
+                    n: column.clone(),
+                    f: operator.to_string(),
+                    v: value.to_string(),
+                    c: None,
+                }),
+        );
+    }
+    corpus
+}
+
+const NULL_CHECKS: [(&str, &str); 2] = [("IS NULL", ""), ("IS NOT NULL", "")];
+
+// Operators and sample values a column accepts
+fn samples(column_def: &ColumnDef) -> Vec<(&'static str, &'static str)> {
+    let samples: &[(&str, &str)] = match column_def.base() {
+        ColumnDef::String(_) | ColumnDef::FixedString(_) => &[
+            ("=", "example"),
+            ("=", "O'Brien"),
+            ("!=", "example"),
+            ("LIKE", "%example%"),
+            ("NOT LIKE", "%example%"),
+            ("STARTS WITH", "ex"),
+            ("ENDS WITH", "le"),
+            ("IN", "alpha, beta"),
+            ("NOT IN", "alpha, beta"),
+            ("MULTI SEARCH ANY", "alpha, beta"),
+        ],
+        ColumnDef::UInt8(_)
+        | ColumnDef::UInt16(_)
+        | ColumnDef::UInt32(_)
+        | ColumnDef::UInt64(_)
+        | ColumnDef::Int8(_)
+        | ColumnDef::Int16(_)
+        | ColumnDef::Int32(_)
+        | ColumnDef::Int64(_) => &[
+            ("=", "42"),
+            ("!=", "42"),
+            (">", "1"),
+            (">=", "1"),
+            ("<", "100"),
+            ("<=", "100"),
+            ("IN", "1, 2, 3"),
+            ("NOT IN", "1, 2, 3"),
+        ],
+        ColumnDef::Float32(_) | ColumnDef::Float64(_) | ColumnDef::Decimal(_) => &[
+            ("=", "1.5"),
+            ("!=", "1.5"),
+            (">", "0.5"),
+            ("<=", "99.99"),
+            ("IN", "0.5, 1.5"),
+        ],
+        ColumnDef::Date(_) | ColumnDef::Date32(_) => &[
+            ("=", "2024-01-15"),
+            (">", "2024-01-01"),
+            ("<", "2024-12-31"),
+            ("DATE_ONLY", "2024-01-15"),
+            ("DATE_RANGE", "2024-01-01, 2024-01-31"),
+            ("RELATIVE", "today() - 7"),
+        ],
+        ColumnDef::DateTime(_) | ColumnDef::DateTime64(_) | ColumnDef::DateTime64Precision(..) => {
+            &[
+                ("=", "2024-01-15 10:30:00"),
+                (">", "2024-01-01 00:00:00"),
+                ("DATE_ONLY", "2024-01-15"),
+                ("DATE_RANGE", "2024-01-01 00:00:00, 2024-01-31 23:59:59"),
+                ("RELATIVE", "now() - INTERVAL 7 DAY"),
+                ("BUCKET_EQUAL", "5 minute|2024-01-15 10:30:00"),
+            ]
+        }
+        ColumnDef::Boolean(_) => &[("=", "true"), ("=", "false"), ("!=", "true"), ("IN", "0,1")],
+        ColumnDef::UUID(_) => &[
+            ("=", "550e8400-e29b-41d4-a716-446655440000"),
+            ("!=", "550e8400-e29b-41d4-a716-446655440000"),
+            (
+                "IN",
+                "550e8400-e29b-41d4-a716-446655440000, 6ba7b810-9dad-11d1-80b4-00c04fd430c8",
+            ),
+        ],
+        ColumnDef::Enum8(_) | ColumnDef::Enum16(_) => {
+            &[("=", "active"), ("!=", "active"), ("IN", "active, pending")]
+        }
+        ColumnDef::IPv4(_) => &[
+            ("=", "192.168.1.1"),
+            ("!=", "192.168.1.1"),
+            ("IN", "10.0.0.1, 10.0.0.2"),
+        ],
+        ColumnDef::IPv6(_) => &[
+            ("=", "2001:db8::1"),
+            ("!=", "2001:db8::1"),
+            ("IN", "2001:db8::1, 10.0.0.1"),
+        ],
+        ColumnDef::ArrayString(_) => &[
+            ("ARRAY HAS", "alpha"),
+            ("ARRAY NOT HAS", "alpha"),
+            ("ARRAY CONTAINS", "alpha, beta"),
+            ("ARRAY NOT CONTAINS", "alpha, beta"),
+            ("ARRAY HAS SUBSEQUENCE", "alpha, beta"),
+            ("ARRAY NOT ANY", "alpha, beta"),
+            ("ARRAY JSON HAS", "type.click"),
+        ],
+        ColumnDef::ArrayEnum8(_) | ColumnDef::ArrayEnum16(_) => &[
+            ("ARRAY HAS", "alpha"),
+            ("ARRAY NOT HAS", "alpha"),
+            ("ARRAY CONTAINS", "alpha, beta"),
+            ("ARRAY NOT CONTAINS", "alpha, beta"),
+            ("ARRAY HAS SUBSEQUENCE", "alpha, beta"),
+            ("ARRAY NOT ANY", "alpha, beta"),
+        ],
+        ColumnDef::ArrayUInt8(_)
+        | ColumnDef::ArrayUInt16(_)
+        | ColumnDef::ArrayUInt32(_)
+        | ColumnDef::ArrayUInt64(_)
+        | ColumnDef::ArrayInt8(_)
+        | ColumnDef::ArrayInt16(_)
+        | ColumnDef::ArrayInt32(_)
+        | ColumnDef::ArrayInt64(_) => &[
+            ("ARRAY HAS", "1"),
+            ("ARRAY NOT HAS", "1"),
+            ("ARRAY CONTAINS", "1, 2"),
+            ("ARRAY NOT CONTAINS", "1, 2"),
+            ("ARRAY HAS SUBSEQUENCE", "1, 2"),
+            ("ARRAY NOT ANY", "1, 2"),
+        ],
+        ColumnDef::ArrayFloat32(_) | ColumnDef::ArrayFloat64(_) => &[
+            ("ARRAY HAS", "1.5"),
+            ("ARRAY CONTAINS", "1.5, 2.5"),
+            ("ARRAY NOT ANY", "1.5, 2.5"),
+        ],
+        ColumnDef::ArrayUUID(_) => &[
+            ("ARRAY HAS", "550e8400-e29b-41d4-a716-446655440000"),
+            ("ARRAY NOT HAS", "550e8400-e29b-41d4-a716-446655440000"),
+        ],
+        ColumnDef::ArrayDate(_) => &[
+            ("ARRAY HAS", "2024-01-15"),
+            ("ARRAY ANY", "2024-01-15, 2024-01-16"),
+        ],
+        ColumnDef::ArrayDateTime(_) => &[
+            ("ARRAY HAS", "2024-01-15 10:30:00"),
+            ("ARRAY ANY", "2024-01-15 10:30:00, 2024-01-16 10:30:00"),
+        ],
+        ColumnDef::ArrayIPv4(_) => &[("ARRAY HAS", "192.168.1.1")],
+        ColumnDef::ArrayIPv6(_) => &[("ARRAY HAS", "2001:db8::1")],
+        ColumnDef::Map(_) => &[("=", "value"), ("!=", "value")],
+        ColumnDef::JSON(_) => &[
+            ("=", "user.name"),
+            (">", "stats.42"),
+            ("EXISTS", "user"),
+            ("IS EMPTY", "tags"),
+        ],
+        ColumnDef::UInt128(_)
+        | ColumnDef::UInt256(_)
+        | ColumnDef::Int128(_)
+        | ColumnDef::Int256(_) => &[("=", "42"), ("!=", "42")],
+        ColumnDef::Custom { .. } | ColumnDef::LowCardinality(_) => return Vec::new(),
+    };
+
+    let mut samples = samples.to_vec();
+    if !matches!(column_def, ColumnDef::Map(_)) {
+        samples.extend(NULL_CHECKS);
+    }
+    samples
+}
+
+/// Text carried by every `adversarial_values()` value
+pub const INJECTION_MARKER: &str = "INJECTION_MARKER";
+
+// Pieces of adversarial values: literal and identifier delimiters, escapes,
+// comments, placeholders, pattern metacharacters and unusual unicode
+const FRAGMENTS: &[&str] = &[
+    "'",
+    "''",
+    "\\",
+    "\\'",
+    "\\\\'",
+    "\"",
+    "`",
+    "%",
+    "_",
+    "--",
+    "/*",
+    "*/",
+    ";",
+    "(",
+    ")",
+    ",",
+    ".",
+    "[",
+    "]",
+    "\n",
+    "\r\n",
+    "\t",
+    "\0",
+    "{",
+    "}",
+    ":",
+    "?",
+    "$1",
+    "é",
+    "日本",
+    "🦀",
+    "\u{2019}",
+    "\u{ff07}",
+    "\u{2028}",
+    "\u{200b}",
+    "' OR 1=1 --",
+    "\\' OR 1=1 --",
+    "'); DROP TABLE users; --",
+    "%' AND '1'='1",
+    "{name:String}",
+];
+
+/// Generate `count` adversarial filter values from `seed`
+///
+/// The first values wrap the marker in each fragment, e.g. `'INJECTION_MARKER'`;
+/// the rest combine up to five random fragments around it. The same seed always
+/// gives the same values.
+pub fn adversarial_values(count: usize, seed: u64) -> Vec<String> {
+    // xorshift64*, which is plenty for picking fragments
+    let mut state = seed | 1;
+    let mut next = |bound: usize| {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        (state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as usize % bound
+    };
+
+    let mut values = Vec::with_capacity(count);
+    for fragment in FRAGMENTS.iter().take(count) {
+        values.push(format!("{0}{1}{0}", fragment, INJECTION_MARKER));
+    }
+    while values.len() < count {
+        let parts = 1 + next(5);
+        let marker_at = next(parts + 1);
+        let mut value = String::new();
+        for i in 0..=parts {
+            if i == marker_at {
+                value.push_str(INJECTION_MARKER);
+            }
+            if i < parts {
+                value.push_str(FRAGMENTS[next(FRAGMENTS.len())]);
+            }
+        }
+        values.push(value);
+    }
+    values
+}
+
+/// Remove the contents of every string literal from `sql`
+///
+/// Literals are lexed the way ClickHouse does: single-quoted, with `\` escaping
+/// the next character and `''` standing for a quote. Each literal is replaced by
+/// `''`, while quoted identifiers, comments and everything else are kept. Fails
+/// when a literal or quoted identifier is not terminated.
+pub fn strip_literals(sql: &str) -> Result<String> {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !matches!(c, '\'' | '"' | '`') {
+            out.push(c);
+            continue;
+        }
+
+        let literal = c == '\'';
+        out.push(c);
+        loop {
+            let Some(next) = chars.next() else {
+                return Err(eyre::eyre!("Unterminated {} in: {}", c, sql));
+            };
+            if next == c && chars.peek() == Some(&c) {
+                if !literal {
+                    out.extend([c, c]);
+                }
+                chars.next();
+            } else if next == c {
+                break;
+            } else if next == '\\' {
+                if let Some(escaped) = chars.next() {
+                    if !literal {
+                        out.extend([next, escaped]);
+                    }
+                }
+            } else if !literal {
+                out.push(next);
+            }
+        }
+        out.push(c);
+    }
+    Ok(out)
+}
+
+/// Assert that `INJECTION_MARKER` only appears inside string literals of `sql`
+#[track_caller]
+pub fn assert_no_breakout(sql: &str) {
+    let stripped = match strip_literals(sql) {
+        Ok(stripped) => stripped,
+        Err(err) => panic!("{}", err),
+    };
+    assert!(
+        !stripped.to_uppercase().contains(INJECTION_MARKER),
+        "Value escaped its literal: {}",
+        sql
+    );
+}
@@ -0,0 +1,428 @@
+//! Typed column handles generated per table by `table!`
+//!
+//! `table!` turns a table definition into a module with one `Column` constant per
+//! column under `col`, plus a `columns()` registry for the same definitions.
+//! Conditions are built by calling methods on the constants, so column names are
+//! checked by the compiler, values must have the column's Rust type, and only the
+//! operators that make sense for the column type are available (`like` only on
+//! strings, `gt` only on ordered types, `has` only on arrays).
+//!
+//! Column types are named after the `ColumnDef` variants, as in `column_registry!`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::FilterExpression;
+//! use clickhouse_filters::{table, FilteringOptions};
+//!
+//! table! {
+//!     pub mod users {
+//!         age: UInt32,
+//!         name: String,
+//!         tags: ArrayString,
+//!         created_at: DateTime,
+//!     }
+//! }
+//!
+//! use users::col;
+//!
+//! let expr = FilterExpression::and(vec![
+//!     col::age.gt(25),
+//!     col::name.like("%smith%"),
+//!     col::tags.has("admin"),
+//!     col::created_at.between("2024-01-01 00:00:00", "2024-01-31 23:59:59"),
+//! ]);
+//! assert_eq!(
+//!     expr.to_sql(false).unwrap(),
+//!     "(age > 25 AND name LIKE '%smith%' AND has(tags, 'admin') \
+//!      AND created_at BETWEEN '2024-01-01 00:00:00' AND '2024-01-31 23:59:59')"
+//! );
+//!
+//! let filtering = FilteringOptions::new(vec![expr], users::columns());
+//! assert_eq!(users::NAME, "users");
+//! ```
+
+use crate::filtering::{ColumnTypeInfo, FilterCondition, FilterExpression, FilterOperator};
+use crate::ColumnDef;
+use std::fmt;
+use std::marker::PhantomData;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// A column type usable with `Column`
+pub trait Kind {
+    /// The column definition for a column of this type
+    fn column_def(name: &'static str) -> ColumnDef;
+}
+
+/// A column of type `K`, as generated by `table!`
+pub struct Column<K> {
+    name: &'static str,
+    kind: PhantomData<fn() -> K>,
+}
+
+impl<K> Column<K> {
+    pub const fn new(name: &'static str) -> Self {
+        Column {
+            name,
+            kind: PhantomData,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<K: Kind> Column<K> {
+    /// The column definition for this column
+    pub fn column_def(&self) -> ColumnDef {
+        K::column_def(self.name)
+    }
+}
+
+impl<K> Clone for Column<K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for Column<K> {}
+
+impl<K> fmt::Debug for Column<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Column").field(&self.name).finish()
+    }
+}
+
+/// Marker types for `Column`, one per `ColumnDef` variant
+pub mod kind {
+    macro_rules! kinds {
+        ($($kind:ident),* $(,)?) => {
+            $(
+                #[derive(Debug, Clone, Copy)]
+                pub struct $kind;
+
+                impl super::Kind for $kind {
+                    fn column_def(name: &'static str) -> crate::ColumnDef {
+                        crate::ColumnDef::$kind(name)
+                    }
+                }
+            )*
+        };
+    }
+
+    kinds! {
+        String, FixedString,
+        UInt8, UInt16, UInt32, UInt64, Int8, Int16, Int32, Int64,
+        Float32, Float64, Decimal,
+        Date, Date32, DateTime, DateTime64,
+        Boolean, UUID, Enum8, Enum16, IPv4, IPv6,
+        ArrayString, ArrayUInt8, ArrayUInt16, ArrayUInt32, ArrayUInt64,
+        ArrayInt8, ArrayInt16, ArrayInt32, ArrayInt64, ArrayFloat32, ArrayFloat64,
+        ArrayUUID, ArrayDate, ArrayDateTime, ArrayEnum8, ArrayEnum16, ArrayIPv4, ArrayIPv6,
+    }
+}
+
+fn condition(condition: FilterCondition) -> FilterExpression {
+    FilterExpression::Condition(condition)
+}
+
+// Methods shared by every scalar type: equality, IN lists and NULL checks.
+// `$item` is `$value` with a named lifetime, for use in iterator bounds.
+// `$variant` is the condition built for a value, `$convert` turns the Rust value
+// into the condition's value.
+macro_rules! scalar {
+    ($kind:ident, $value:ty, <$($lt:lifetime)?> $item:ty, $variant:ident, |$v:ident| $convert:expr, $type_info:ident) => {
+        impl Column<kind::$kind> {
+            fn compare(&self, operator: FilterOperator, value: Option<$value>) -> FilterExpression {
+                condition(FilterCondition::$variant {
+                    column: self.name.to_string(),
+                    operator,
+                    value: value.map(|$v| $convert),
+                })
+            }
+
+            fn list<$($lt,)? I>(&self, operator: FilterOperator, values: I) -> FilterExpression
+            where
+                I: IntoIterator<Item = $item>,
+            {
+                condition(FilterCondition::in_values(
+                    self.name,
+                    operator,
+                    values.into_iter().map(|value| value.to_string()).collect(),
+                    Some(ColumnTypeInfo::$type_info),
+                ))
+            }
+
+            pub fn eq(&self, value: $value) -> FilterExpression {
+                self.compare(FilterOperator::Equal, Some(value))
+            }
+
+            pub fn ne(&self, value: $value) -> FilterExpression {
+                self.compare(FilterOperator::NotEqual, Some(value))
+            }
+
+            pub fn in_list<$($lt,)? I: IntoIterator<Item = $item>>(&self, values: I) -> FilterExpression {
+                self.list(FilterOperator::In, values)
+            }
+
+            pub fn not_in<$($lt,)? I: IntoIterator<Item = $item>>(&self, values: I) -> FilterExpression {
+                self.list(FilterOperator::NotIn, values)
+            }
+
+            pub fn is_null(&self) -> FilterExpression {
+                self.compare(FilterOperator::IsNull, None)
+            }
+
+            pub fn is_not_null(&self) -> FilterExpression {
+                self.compare(FilterOperator::IsNotNull, None)
+            }
+        }
+    };
+}
+
+// Ordering comparisons, for types where `scalar!` has defined `compare`
+macro_rules! ordered {
+    ($kind:ident, $value:ty) => {
+        impl Column<kind::$kind> {
+            pub fn gt(&self, value: $value) -> FilterExpression {
+                self.compare(FilterOperator::GreaterThan, Some(value))
+            }
+
+            pub fn gte(&self, value: $value) -> FilterExpression {
+                self.compare(FilterOperator::GreaterThanOrEqual, Some(value))
+            }
+
+            pub fn lt(&self, value: $value) -> FilterExpression {
+                self.compare(FilterOperator::LessThan, Some(value))
+            }
+
+            pub fn lte(&self, value: $value) -> FilterExpression {
+                self.compare(FilterOperator::LessThanOrEqual, Some(value))
+            }
+        }
+    };
+}
+
+// Pattern matching for string types
+macro_rules! patterns {
+    ($kind:ident) => {
+        impl Column<kind::$kind> {
+            pub fn like(&self, pattern: &str) -> FilterExpression {
+                self.compare(FilterOperator::Like, Some(pattern))
+            }
+
+            pub fn not_like(&self, pattern: &str) -> FilterExpression {
+                self.compare(FilterOperator::NotLike, Some(pattern))
+            }
+
+            pub fn starts_with(&self, prefix: &str) -> FilterExpression {
+                self.compare(FilterOperator::StartsWith, Some(prefix))
+            }
+
+            pub fn ends_with(&self, suffix: &str) -> FilterExpression {
+                self.compare(FilterOperator::EndsWith, Some(suffix))
+            }
+
+            pub fn contains(&self, substring: &str) -> FilterExpression {
+                self.compare(FilterOperator::Contains, Some(substring))
+            }
+        }
+    };
+}
+
+// Whole-day and range matches for date and time types
+macro_rules! temporal {
+    ($kind:ident) => {
+        impl Column<kind::$kind> {
+            /// Match rows on the given day, ignoring the time
+            pub fn on_date(&self, date: &str) -> FilterExpression {
+                condition(FilterCondition::date_only(self.name, date))
+            }
+
+            /// Match rows between `start` and `end`, inclusive
+            pub fn between(&self, start: &str, end: &str) -> FilterExpression {
+                condition(FilterCondition::date_range(self.name, start, end))
+            }
+        }
+    };
+}
+
+// Element checks for array types
+macro_rules! array {
+    ($kind:ident, $value:ty, <$($lt:lifetime)?> $item:ty) => {
+        impl Column<kind::$kind> {
+            fn elements<$($lt,)? I>(&self, operator: FilterOperator, values: I) -> FilterExpression
+            where
+                I: IntoIterator<Item = $item>,
+            {
+                let element_type = self
+                    .column_def()
+                    .array_element_type()
+                    .expect("array kinds have an element type");
+                condition(FilterCondition::array_values(
+                    self.name,
+                    operator,
+                    values.into_iter().map(|value| value.to_string()).collect(),
+                    element_type,
+                ))
+            }
+
+            /// The array contains `value`
+            pub fn has(&self, value: $value) -> FilterExpression {
+                self.elements(FilterOperator::ArrayHas, [value])
+            }
+
+            /// The array does not contain `value`
+            pub fn not_has(&self, value: $value) -> FilterExpression {
+                self.elements(FilterOperator::ArrayNotHas, [value])
+            }
+
+            /// The array contains all of `values`
+            pub fn has_all<$($lt,)? I: IntoIterator<Item = $item>>(&self, values: I) -> FilterExpression {
+                self.elements(FilterOperator::ArrayContains, values)
+            }
+
+            /// The array contains at least one of `values`
+            pub fn has_any<$($lt,)? I: IntoIterator<Item = $item>>(&self, values: I) -> FilterExpression {
+                self.elements(FilterOperator::ArrayAny, values)
+            }
+
+            /// The array contains none of `values`
+            pub fn has_none<$($lt,)? I: IntoIterator<Item = $item>>(&self, values: I) -> FilterExpression {
+                self.elements(FilterOperator::ArrayNotAny, values)
+            }
+        }
+    };
+}
+
+scalar!(String, &str, <'v> &'v str, StringValue, |v| v.to_string(), String);
+scalar!(
+    FixedString,
+    &str,
+    <'v> &'v str,
+    FixedStringValue,
+    |v| v.to_string(),
+    String
+);
+patterns!(String);
+patterns!(FixedString);
+
+scalar!(UInt8, u8, <> u8, UInt8Value, |v| v, Numeric);
+scalar!(UInt16, u16, <> u16, UInt16Value, |v| v, Numeric);
+scalar!(UInt32, u32, <> u32, UInt32Value, |v| v, Numeric);
+scalar!(UInt64, u64, <> u64, UInt64Value, |v| v, Numeric);
+scalar!(Int8, i8, <> i8, Int8Value, |v| v, Numeric);
+scalar!(Int16, i16, <> i16, Int16Value, |v| v, Numeric);
+scalar!(Int32, i32, <> i32, Int32Value, |v| v, Numeric);
+scalar!(Int64, i64, <> i64, Int64Value, |v| v, Numeric);
+scalar!(Float32, f32, <> f32, Float32Value, |v| v, Numeric);
+scalar!(Float64, f64, <> f64, Float64Value, |v| v, Numeric);
+scalar!(Decimal, f64, <> f64, Float64Value, |v| v, Numeric);
+ordered!(UInt8, u8);
+ordered!(UInt16, u16);
+ordered!(UInt32, u32);
+ordered!(UInt64, u64);
+ordered!(Int8, i8);
+ordered!(Int16, i16);
+ordered!(Int32, i32);
+ordered!(Int64, i64);
+ordered!(Float32, f32);
+ordered!(Float64, f64);
+ordered!(Decimal, f64);
+
+scalar!(Date, &str, <'v> &'v str, DateValue, |v| v.to_string(), Date);
+scalar!(Date32, &str, <'v> &'v str, DateValue, |v| v.to_string(), Date);
+scalar!(DateTime, &str, <'v> &'v str, DateTimeValue, |v| v.to_string(), Date);
+scalar!(DateTime64, &str, <'v> &'v str, DateTime64Value, |v| v.to_string(), Date);
+ordered!(Date, &str);
+ordered!(Date32, &str);
+ordered!(DateTime, &str);
+ordered!(DateTime64, &str);
+temporal!(Date);
+temporal!(Date32);
+temporal!(DateTime);
+temporal!(DateTime64);
+
+scalar!(Boolean, bool, <> bool, BooleanValue, |v| v, Boolean);
+scalar!(UUID, &str, <'v> &'v str, UUIDValue, |v| v.to_string(), UUID);
+scalar!(Enum8, &str, <'v> &'v str, StringValue, |v| v.to_string(), String);
+scalar!(Enum16, &str, <'v> &'v str, StringValue, |v| v.to_string(), String);
+scalar!(IPv4, Ipv4Addr, <> Ipv4Addr, IPv4Value, |v| v, IPv4);
+scalar!(IPv6, Ipv6Addr, <> Ipv6Addr, IPv6Value, |v| v, IPv6);
+ordered!(IPv4, Ipv4Addr);
+ordered!(IPv6, Ipv6Addr);
+
+array!(ArrayString, &str, <'v> &'v str);
+array!(ArrayUInt8, u8, <> u8);
+array!(ArrayUInt16, u16, <> u16);
+array!(ArrayUInt32, u32, <> u32);
+array!(ArrayUInt64, u64, <> u64);
+array!(ArrayInt8, i8, <> i8);
+array!(ArrayInt16, i16, <> i16);
+array!(ArrayInt32, i32, <> i32);
+array!(ArrayInt64, i64, <> i64);
+array!(ArrayFloat32, f32, <> f32);
+array!(ArrayFloat64, f64, <> f64);
+array!(ArrayUUID, &str, <'v> &'v str);
+array!(ArrayDate, &str, <'v> &'v str);
+array!(ArrayDateTime, &str, <'v> &'v str);
+array!(ArrayEnum8, &str, <'v> &'v str);
+array!(ArrayEnum16, &str, <'v> &'v str);
+array!(ArrayIPv4, &str, <'v> &'v str);
+array!(ArrayIPv6, &str, <'v> &'v str);
+
+impl Column<kind::ArrayString> {
+    /// Some JSON document in the array has `value` under `key`
+    pub fn has_json(&self, key: &str, value: &str) -> FilterExpression {
+        condition(FilterCondition::array_json(self.name, key, value))
+    }
+}
+
+/// Generate a module with typed column handles for a table
+///
+/// ```rust
+/// clickhouse_filters::table! {
+///     /// The `events` table
+///     pub mod events {
+///         id: UInt64,
+///         kind: Enum8,
+///     }
+/// }
+///
+/// let expr = events::col::kind.in_list(["click", "view"]);
+/// assert_eq!(expr.to_sql(false).unwrap(), "kind IN ('click', 'view')");
+/// assert_eq!(events::columns().len(), 2);
+/// ```
+///
+/// The module contains:
+///
+/// - `NAME`, the table name
+/// - `col`, with a `Column` constant named after each column
+/// - `columns()`, a `ColumnRegistry` with the same column definitions
+#[macro_export]
+macro_rules! table {
+    ($(#[$meta:meta])* $vis:vis mod $table:ident { $($column:ident : $kind:ident),* $(,)? }) => {
+        $(#[$meta])*
+        $vis mod $table {
+            /// Name of the table
+            pub const NAME: &str = stringify!($table);
+
+            /// Typed handles for the table's columns
+            #[allow(non_upper_case_globals)]
+            pub mod col {
+                $(
+                    pub const $column: $crate::typed::Column<$crate::typed::kind::$kind> =
+                        $crate::typed::Column::new(stringify!($column));
+                )*
+            }
+
+            /// Column definitions for the table
+            pub fn columns() -> $crate::ColumnRegistry {
+                <$crate::ColumnRegistry as ::std::iter::FromIterator<_>>::from_iter([
+                    $((stringify!($column), col::$column.column_def())),*
+                ])
+            }
+        }
+    };
+}
@@ -0,0 +1,243 @@
+//! Traversal of filter expression trees
+//!
+//! `FilterExpression::walk()` calls a `Visitor` for every group and condition in a
+//! tree, and `map()` / `try_map()` rebuild a tree with each condition replaced, so
+//! applications can analyze or rewrite filters without matching on every variant.
+//!
+//! `referenced_columns()` on expressions, `FilteringOptions`, `Sorting` and
+//! `ClickHouseFilters` returns the columns a request touches, e.g. for authorization
+//! checks or picking a covering projection.
+//!
+//! # Example
+//!
+//! ```rust
+//! use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+//! use clickhouse_filters::visit::Visitor;
+//!
+//! #[derive(Default)]
+//! struct CountConditions(usize);
+//!
+//! impl Visitor for CountConditions {
+//!     fn visit_condition(&mut self, _condition: &FilterCondition) {
+//!         self.0 += 1;
+//!     }
+//! }
+//!
+//! let expr = FilterExpression::and(vec![
+//!     FilterExpression::Condition(FilterCondition::string("name", FilterOperator::Equal, Some("John"))),
+//!     FilterExpression::Condition(FilterCondition::uint32("age", FilterOperator::GreaterThan, Some(25))),
+//! ]);
+//!
+//! let mut counter = CountConditions::default();
+//! expr.walk(&mut counter);
+//! assert_eq!(counter.0, 2);
+//!
+//! // Rewrite public column names to physical ones
+//! let renamed = expr.map(|mut condition| {
+//!     if condition.column() == "name" {
+//!         *condition.column_mut() = "full_name".to_string();
+//!     }
+//!     FilterExpression::Condition(condition)
+//! });
+//! assert_eq!(renamed.to_sql(false).unwrap(), "(full_name = 'John' AND age > 25)");
+//! ```
+
+use crate::custom::ToSqlCondition;
+use crate::filtering::{FilterCondition, FilterExpression, LogicalOperator};
+use crate::sorting::Sorting;
+use crate::{ClickHouseFilters, FilteringOptions};
+use eyre::Result;
+use std::collections::BTreeSet;
+
+/// Callbacks for `FilterExpression::walk()`
+///
+/// All methods default to doing nothing, so implement only the ones you need.
+pub trait Visitor {
+    /// Called for every condition, in tree order
+    fn visit_condition(&mut self, _condition: &FilterCondition) {}
+
+    /// Called for every `FilterExpression::Custom`, in tree order
+    fn visit_custom(&mut self, _condition: &dyn ToSqlCondition) {}
+
+    /// Called for every group before its children are visited
+    fn visit_group(&mut self, _operator: LogicalOperator, _expressions: &[FilterExpression]) {}
+}
+
+impl FilterExpression {
+    /// Visit every group and condition in the tree, depth first
+    pub fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        match self {
+            FilterExpression::Condition(condition) => visitor.visit_condition(condition),
+            FilterExpression::Custom(condition) => visitor.visit_custom(condition.as_ref()),
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } => {
+                visitor.visit_group(*operator, expressions);
+                for expression in expressions {
+                    expression.walk(visitor);
+                }
+            }
+        }
+    }
+
+    /// Columns referenced by any condition in the tree
+    pub fn referenced_columns(&self) -> BTreeSet<String> {
+        let mut columns = ColumnCollector::default();
+        self.walk(&mut columns);
+        columns.0
+    }
+
+    /// Replace every condition with the expression returned by `f`
+    ///
+    /// Groups keep their operators; the replacement may itself be a group.
+    pub fn map<F>(self, mut f: F) -> FilterExpression
+    where
+        F: FnMut(FilterCondition) -> FilterExpression,
+    {
+        self.map_with(&mut f)
+    }
+
+    /// Like `map()`, stopping at the first error
+    pub fn try_map<F>(self, mut f: F) -> Result<FilterExpression>
+    where
+        F: FnMut(FilterCondition) -> Result<FilterExpression>,
+    {
+        self.try_map_with(&mut f)
+    }
+
+    /// The expression with every condition on `column` removed
+    ///
+    /// Custom conditions reporting the column are removed too. A removed condition
+    /// counts as always true, so the result only ever matches more rows: an OR
+    /// group losing any expression is dropped whole, AND groups left empty are
+    /// dropped and groups left with one expression are replaced by it. None when
+    /// nothing remains.
+    pub fn without_column(&self, column: &str) -> Option<FilterExpression> {
+        match self {
+            FilterExpression::Condition(condition) => {
+                (condition.column() != column).then(|| self.clone())
+            }
+            FilterExpression::Custom(condition) => {
+                (!condition.columns().contains(&column)).then(|| self.clone())
+            }
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } => {
+                let count = expressions.len();
+                let mut expressions: Vec<_> = expressions
+                    .iter()
+                    .filter_map(|expression| expression.without_column(column))
+                    .collect();
+                if *operator == LogicalOperator::Or && expressions.len() < count {
+                    return None;
+                }
+                match expressions.len() {
+                    0 => None,
+                    1 => expressions.pop(),
+                    _ => Some(FilterExpression::Group {
+                        operator: *operator,
+                        expressions,
+                    }),
+                }
+            }
+        }
+    }
+
+    fn map_with<F>(self, f: &mut F) -> FilterExpression
+    where
+        F: FnMut(FilterCondition) -> FilterExpression,
+    {
+        match self {
+            FilterExpression::Condition(condition) => f(condition),
+            FilterExpression::Custom(_) => self,
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } => FilterExpression::Group {
+                operator,
+                expressions: expressions
+                    .into_iter()
+                    .map(|expression| expression.map_with(f))
+                    .collect(),
+            },
+        }
+    }
+
+    fn try_map_with<F>(self, f: &mut F) -> Result<FilterExpression>
+    where
+        F: FnMut(FilterCondition) -> Result<FilterExpression>,
+    {
+        match self {
+            FilterExpression::Condition(condition) => f(condition),
+            FilterExpression::Custom(_) => Ok(self),
+            FilterExpression::Group {
+                operator,
+                expressions,
+            } => Ok(FilterExpression::Group {
+                operator,
+                expressions: expressions
+                    .into_iter()
+                    .map(|expression| expression.try_map_with(f))
+                    .collect::<Result<_>>()?,
+            }),
+        }
+    }
+}
+
+impl FilteringOptions {
+    /// Columns referenced by the filter expressions
+    ///
+    /// Only the expressions as given are inspected; columns added by build hooks
+    /// are not included. Use `ClickHouseFilters::referenced_columns()` for
+    /// everything a generated query touches.
+    pub fn referenced_columns(&self) -> BTreeSet<String> {
+        let mut columns = ColumnCollector::default();
+        for expression in &self.expressions {
+            expression.walk(&mut columns);
+        }
+        columns.0
+    }
+}
+
+impl Sorting {
+    /// Columns in the ORDER BY clause
+    pub fn referenced_columns(&self) -> BTreeSet<String> {
+        self.columns.iter().map(|c| c.column.clone()).collect()
+    }
+}
+
+impl ClickHouseFilters {
+    /// Columns referenced by the generated WHERE and ORDER BY clauses
+    ///
+    /// Includes columns added by build hooks, row policies and the registry's
+    /// soft-delete and sign conditions.
+    pub fn referenced_columns(&self) -> Result<BTreeSet<String>> {
+        let mut columns = match self.effective_filters()? {
+            Some(filters) => filters
+                .resolved_root()?
+                .map(|root| root.referenced_columns())
+                .unwrap_or_default(),
+            None => BTreeSet::new(),
+        };
+        if let Some(sorting) = &self.sorting {
+            columns.extend(sorting.referenced_columns());
+        }
+        Ok(columns)
+    }
+}
+
+#[derive(Default)]
+struct ColumnCollector(BTreeSet<String>);
+
+impl Visitor for ColumnCollector {
+    fn visit_condition(&mut self, condition: &FilterCondition) {
+        self.0.insert(condition.column().to_string());
+    }
+
+    fn visit_custom(&mut self, condition: &dyn ToSqlCondition) {
+        self.0
+            .extend(condition.columns().into_iter().map(str::to_string));
+    }
+}
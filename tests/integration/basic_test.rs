@@ -3,6 +3,7 @@
 //! These tests verify the basic functionality of the crate with a real ClickHouse database.
 
 use crate::integration::run_with_clickhouse;
+use clickhouse_filters::pagination::PaginationSyntax;
 use clickhouse_filters::{
     filtering::{FilterCondition, FilterExpression, FilterOperator},
     sorting::SortedColumn,
@@ -20,7 +21,7 @@ async fn test_basic_query() -> Result<()> {
 
         let filters = ClickHouseFilters::new(None, vec![], None, columns)?;
 
-        let sql = filters.query_sql("test_filters", "users", &["name"])?;
+        let sql = filters.query_sql("test_filters.users", &["name"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -42,7 +43,7 @@ async fn test_count_query() -> Result<()> {
 
         let filters = ClickHouseFilters::new(None, vec![], None, columns)?;
 
-        let sql = filters.count_sql("test_filters", "users")?;
+        let sql = filters.count_sql("test_filters.users")?;
         println!("Generated count SQL: {}", sql);
 
         // Execute the query
@@ -89,7 +90,7 @@ async fn test_all_column_types() -> Result<()> {
         let filters = ClickHouseFilters::new(Some(pagination), sorting, Some(filtering), columns)?;
 
         // Generate SQL
-        let sql = filters.query_sql("test_filters", "users", &["name", "age", "score"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "age", "score"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -137,6 +138,8 @@ async fn test_api_compatibility() -> Result<()> {
                 per_page: 10,
                 per_page_limit: 10,
                 total_records: 5,
+                syntax: PaginationSyntax::LimitOffset,
+                with_ties: false,
             }),
             vec![SortedColumn::new("name", "asc")],
             Some(FilteringOptions::new(
@@ -0,0 +1,201 @@
+//! Integration tests for the client helpers
+
+use crate::integration::run_with_clickhouse;
+use clickhouse_filters::cost::CostLimits;
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::sorting::SortedColumn;
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, FilteringOptions, PaginationOptions};
+use eyre::Result;
+use futures_util::TryStreamExt;
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_with_auto_count() -> Result<()> {
+    run_with_clickhouse(|client| async move {
+        let mut columns = HashMap::new();
+        columns.insert("age", ColumnDef::UInt32("age"));
+
+        let filtering = FilteringOptions::new(
+            vec![FilterExpression::Condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::GreaterThan,
+                Some(25),
+            ))],
+            columns.clone(),
+        );
+
+        // The total passed in is replaced by the real count
+        let filters = ClickHouseFilters::with_auto_count(
+            &client,
+            "test_filters.users",
+            PaginationOptions::new(1, 2, 10, 1000),
+            vec![],
+            Some(filtering),
+            columns,
+        )
+        .await?;
+
+        let pagination = &filters.pagination.as_ref().unwrap().pagination;
+        assert_eq!(pagination.total_records, 3);
+        assert_eq!(pagination.total_pages, 2);
+
+        let sql = filters.query_sql("test_filters.users", &["name"])?;
+        let names = client.query(&sql).fetch_all::<String>().await?;
+        assert_eq!(names.len(), 2);
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_fetch_all_pages() -> Result<()> {
+    run_with_clickhouse(|client| async move {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::String("name"));
+        columns.insert("age", ColumnDef::UInt32("age"));
+
+        let filtering = FilteringOptions::new(
+            vec![FilterExpression::Condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::GreaterThan,
+                Some(25),
+            ))],
+            columns.clone(),
+        );
+        let filters = ClickHouseFilters::new(
+            None,
+            vec![SortedColumn::new("name", "asc")],
+            Some(filtering),
+            columns,
+        )?;
+
+        let batches: Vec<Vec<String>> = filters
+            .fetch_all_pages(&client, "test_filters.users", &["name"], 2)
+            .try_collect()
+            .await?;
+        assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 1]);
+
+        let keyed: Vec<Vec<String>> = filters
+            .fetch_all_pages_by_key(
+                &client,
+                "test_filters.users",
+                &["name"],
+                2,
+                "name",
+                |name: &String| name.clone(),
+            )
+            .try_collect()
+            .await?;
+        assert_eq!(keyed, batches);
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_check_cost() -> Result<()> {
+    run_with_clickhouse(|client| async move {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::String("name"));
+        let filters = ClickHouseFilters::new(None, vec![], None, columns)?;
+
+        let estimate = filters
+            .estimate(&client, "test_filters.users", &["name"])
+            .await?;
+        assert!(estimate.rows > 0);
+
+        let strict = CostLimits::new().with_max_rows(0);
+        assert!(filters
+            .check_cost(&client, "test_filters.users", &["name"], &strict)
+            .await
+            .is_err());
+
+        let (_, warnings) = filters
+            .check_cost(
+                &client,
+                "test_filters.users",
+                &["name"],
+                &strict.warn_only(),
+            )
+            .await?;
+        assert_eq!(warnings.len(), 1);
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_verify_against() -> Result<()> {
+    run_with_clickhouse(|client| async move {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::String("name"));
+        columns.insert("missing", ColumnDef::String("missing"));
+
+        let filtering = FilteringOptions::new(
+            vec![FilterExpression::Condition(FilterCondition::string(
+                "name",
+                FilterOperator::Equal,
+                Some("John"),
+            ))],
+            columns.clone(),
+        );
+        let filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns)?;
+
+        filters
+            .verify_against(&client, "test_filters.users", &["name"])
+            .await?;
+        assert!(filters
+            .verify_against(&client, "test_filters.users", &["missing"])
+            .await
+            .is_err());
+        assert!(filters
+            .verify_against(&client, "test_filters.no_such_table", &["name"])
+            .await
+            .is_err());
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_fetch_page_and_facets() -> Result<()> {
+    run_with_clickhouse(|client| async move {
+        let mut columns = HashMap::new();
+        columns.insert("name", ColumnDef::String("name"));
+        columns.insert("age", ColumnDef::UInt32("age"));
+        columns.insert("active", ColumnDef::Boolean("active"));
+
+        let filters = ClickHouseFilters::with_auto_count(
+            &client,
+            "test_filters.users",
+            PaginationOptions::new(1, 2, 10, 0),
+            vec![SortedColumn::new("name", "asc")],
+            None,
+            columns,
+        )
+        .await?;
+
+        let page = filters
+            .fetch_page::<String>(&client, "test_filters.users", &["name"])
+            .await?;
+        assert_eq!(page.rows, vec!["Alice Johnson", "Bob Brown"]);
+        assert_eq!(page.pagination.total_records, 5);
+
+        let facets = filters
+            .fetch_facets(&client, "test_filters.users", &["active"], 10)
+            .await?;
+        let active = facets.get("active").unwrap();
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[0].count, 4);
+
+        let page = page.with_facets(facets);
+        assert!(page.facets.is_some());
+
+        Ok(())
+    })
+    .await
+}
@@ -40,7 +40,7 @@ async fn test_filter_sort_paginate() -> Result<()> {
             ClickHouseFilters::new(Some(pagination), sorting, Some(filtering), columns.clone())?;
 
         // Get the count SQL first
-        let count_sql = filters.count_sql("test_filters", "users")?;
+        let count_sql = filters.count_sql("test_filters.users")?;
         println!("Count SQL: {}", count_sql);
 
         // Execute count query
@@ -66,7 +66,7 @@ async fn test_filter_sort_paginate() -> Result<()> {
         )?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name", "age", "active"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "age", "active"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -118,7 +118,7 @@ async fn test_filter_sort_paginate() -> Result<()> {
         // Only run second page test if we have more than 2 results
         if total_records > 2 {
             let second_page_sql =
-                second_page.query_sql("test_filters", "users", &["name", "age", "active"])?;
+                second_page.query_sql("test_filters.users", &["name", "age", "active"])?;
             let second_page_result = client
                 .query(&second_page_sql)
                 .fetch_all::<QueryResult>()
@@ -182,7 +182,7 @@ async fn test_complex_query() -> Result<()> {
             ClickHouseFilters::new(Some(pagination), sorting, Some(filtering), columns.clone())?;
 
         // Get total count
-        let count_sql = filters.count_sql("test_filters", "users")?;
+        let count_sql = filters.count_sql("test_filters.users")?;
         println!("Count SQL: {}", count_sql);
 
         let count: u64 = client.query(&count_sql).fetch_one::<u64>().await?;
@@ -203,7 +203,7 @@ async fn test_complex_query() -> Result<()> {
         )?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name", "age", "score", "tags"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "age", "score", "tags"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -279,7 +279,7 @@ async fn test_json_filters_with_pagination() -> Result<()> {
         )?;
 
         // Get count
-        let count_sql = filters.count_sql("test_filters", "users")?;
+        let count_sql = filters.count_sql("test_filters.users")?;
         let count: u64 = client.query(&count_sql).fetch_one::<u64>().await?;
 
         let total_records = count;
@@ -293,7 +293,7 @@ async fn test_json_filters_with_pagination() -> Result<()> {
         )?;
 
         // Generate SQL
-        let sql = filters.query_sql("test_filters", "users", &["name", "age", "active"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "age", "active"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute query
@@ -29,7 +29,7 @@ async fn test_basic_string_filtering() -> Result<()> {
         let filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["id", "name"])?;
+        let sql = filters.query_sql("test_filters.users", &["id", "name"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -74,7 +74,7 @@ async fn test_numeric_range_filtering() -> Result<()> {
         let filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name", "age"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "age"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -115,7 +115,7 @@ async fn test_array_filtering() -> Result<()> {
         let filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name", "tags"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "tags"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -170,8 +170,7 @@ async fn test_complex_condition_filtering() -> Result<()> {
         let filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns)?;
 
         // Generate SQL for the query
-        let sql =
-            filters.query_sql("test_filters", "users", &["name", "age", "active", "score"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "age", "active", "score"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -217,7 +216,7 @@ async fn test_date_filtering() -> Result<()> {
         let filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name", "created_at"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "created_at"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -234,3 +233,35 @@ async fn test_date_filtering() -> Result<()> {
     })
     .await
 }
+
+#[tokio::test]
+async fn test_prefix_matches_with_and_without_text_index() -> Result<()> {
+    run_with_clickhouse(|client| async move {
+        let plain: clickhouse_filters::ColumnRegistry =
+            HashMap::from([("title", ColumnDef::String("title"))]).into();
+        let indexed = plain
+            .clone()
+            .with_text_index("title", clickhouse_filters::filtering::TextIndex::NgramBf);
+
+        for (operator, value) in [("STARTS WITH", "50%_off"), ("ENDS WITH", "_off%")] {
+            let mut counts = Vec::new();
+            for columns in [&plain, &indexed] {
+                let condition = columns.to_filter_condition("title", operator, value)?;
+                let filtering = FilteringOptions::case_sensitive(
+                    vec![FilterExpression::Condition(condition)],
+                    columns,
+                );
+                let filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns)?;
+                let sql = format!(
+                    "SELECT count() FROM (SELECT arrayJoin(['50%_off', '50%_off now', \
+                     '5000 off', 'x_off%', 'xoff!']) AS title){}",
+                    filters.sql()?
+                );
+                counts.push(client.query(&sql).fetch_one::<u64>().await?);
+            }
+            assert_eq!(counts[0], counts[1], "{} {}", operator, value);
+        }
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,63 @@
+//! Integration tests for SQL injection resistance
+//!
+//! These tests send the adversarial corpus to ClickHouse and check the server
+//! parses every marker as a literal.
+
+use crate::integration::run_with_clickhouse;
+use clickhouse_filters::filtering::JsonFilter;
+use clickhouse_filters::testing::{
+    adversarial_values, fixture_columns, json_filter_corpus, INJECTION_MARKER,
+};
+use clickhouse_filters::{ClickHouseFilters, FilteringOptions};
+use eyre::Result;
+
+#[tokio::test]
+#[ignore = "sends thousands of queries, run with --ignored"]
+async fn test_adversarial_corpus_parses_as_literals() -> Result<()> {
+    run_with_clickhouse(|client| async move {
+        let columns = fixture_columns();
+        let values = adversarial_values(60, 2174);
+
+        for sample in json_filter_corpus(&columns) {
+            for value in &values {
+                let filter = JsonFilter {
+                    n: sample.n.clone(),
+                    f: sample.f.clone(),
+                    v: format!("{}{}", sample.v, value),
+                    c: None,
+                };
+                let Ok(Some(filtering)) = FilteringOptions::from_json_filters(&[filter], &columns)
+                else {
+                    continue;
+                };
+                let filters = ClickHouseFilters::new(None, vec![], Some(filtering), &columns)?;
+                let sql = filters.query_sql("test_filters.users", &[])?;
+
+                // EXPLAIN AST parses without touching the table; `?` is the
+                // client's bind placeholder, so it's doubled to send it as is
+                let ast = client
+                    .query(&format!("EXPLAIN AST {}", sql).replace('?', "??"))
+                    .fetch_all::<String>()
+                    .await
+                    .map_err(|e| eyre::eyre!("ClickHouse rejected {}: {}", sql, e))?;
+
+                for line in ast.iter().filter(|line| line.contains(INJECTION_MARKER)) {
+                    assert!(
+                        line.trim_start().starts_with("Literal"),
+                        "Marker outside a literal in {}: {}",
+                        sql,
+                        line
+                    );
+                }
+                assert!(
+                    ast.iter().any(|line| line.contains(INJECTION_MARKER))
+                        || !sql.contains(INJECTION_MARKER),
+                    "Marker missing from the parsed query {}",
+                    sql
+                );
+            }
+        }
+        Ok(())
+    })
+    .await
+}
@@ -31,7 +31,7 @@ async fn test_basic_json_filter() -> Result<()> {
         let filters = ClickHouseFilters::new(None, vec![], filtering, columns)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name", "age"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "age"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -87,7 +87,7 @@ async fn test_multiple_json_filters() -> Result<()> {
         let filters = ClickHouseFilters::new(None, vec![], filtering, columns)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name", "age", "active"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "age", "active"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -145,7 +145,7 @@ async fn test_json_filters_with_or() -> Result<()> {
         let filters = ClickHouseFilters::new(None, vec![], filtering, columns)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name", "age", "active"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "age", "active"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -193,7 +193,7 @@ async fn test_json_filters_with_array() -> Result<()> {
         let filters = ClickHouseFilters::new(None, vec![], filtering, columns)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name", "tags"])?;
+        let sql = filters.query_sql("test_filters.users", &["name", "tags"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -12,10 +12,15 @@ use tokio::sync::Mutex;
 
 // Import test modules
 pub mod basic_test;
+#[cfg(feature = "client")]
+pub mod client_test;
 pub mod combined_test;
 pub mod filtering_test;
+pub mod injection_test;
 pub mod json_test;
 pub mod pagination_test;
+#[cfg(feature = "client")]
+pub mod schema_test;
 pub mod sorting_test;
 pub mod test_schema;
 
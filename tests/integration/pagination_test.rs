@@ -1,7 +1,8 @@
 //! Integration tests for pagination functionality
 
 use crate::integration::run_with_clickhouse;
-use clickhouse_filters::{ClickHouseFilters, PaginationOptions};
+use clickhouse_filters::pagination::TimeWindowPagination;
+use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry, PaginationOptions};
 use eyre::Result;
 use std::collections::HashMap;
 
@@ -18,7 +19,7 @@ async fn test_basic_pagination() -> Result<()> {
         let filters = ClickHouseFilters::new(Some(pagination), vec![], None, columns)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name"])?;
+        let sql = filters.query_sql("test_filters.users", &["name"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -45,7 +46,7 @@ async fn test_second_page_pagination() -> Result<()> {
         let filters = ClickHouseFilters::new(Some(pagination), vec![], None, columns)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name"])?;
+        let sql = filters.query_sql("test_filters.users", &["name"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -62,7 +63,7 @@ async fn test_second_page_pagination() -> Result<()> {
             HashMap::new(),
         )?;
 
-        let first_page_sql = first_page.query_sql("test_filters", "users", &["name"])?;
+        let first_page_sql = first_page.query_sql("test_filters.users", &["name"])?;
         let first_page_result = client.query(&first_page_sql).fetch_all::<String>().await?;
 
         // Ensure second page results are different from first page
@@ -97,7 +98,7 @@ async fn test_last_page_pagination() -> Result<()> {
         let filters = ClickHouseFilters::new(Some(pagination), vec![], None, columns)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name"])?;
+        let sql = filters.query_sql("test_filters.users", &["name"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -125,7 +126,7 @@ async fn test_pagination_with_counting() -> Result<()> {
         let mut filters = ClickHouseFilters::new(Some(pagination), vec![], None, columns)?;
 
         // Get the count SQL
-        let count_sql = filters.count_sql("test_filters", "users")?;
+        let count_sql = filters.count_sql("test_filters.users")?;
         println!("Count SQL: {}", count_sql);
 
         // Execute count query
@@ -134,15 +135,10 @@ async fn test_pagination_with_counting() -> Result<()> {
         let total_records = count;
 
         // Update pagination with correct total records
-        filters = ClickHouseFilters::new(
-            Some(PaginationOptions::new(1, 3, 10, total_records as i64)),
-            vec![],
-            None,
-            HashMap::new(),
-        )?;
+        filters.set_total_records(total_records as i64)?;
 
         // Generate SQL for the query
-        let sql = filters.query_sql("test_filters", "users", &["name"])?;
+        let sql = filters.query_sql("test_filters.users", &["name"])?;
         println!("Generated SQL: {}", sql);
 
         // Execute the query
@@ -155,3 +151,48 @@ async fn test_pagination_with_counting() -> Result<()> {
     })
     .await
 }
+
+#[tokio::test]
+async fn test_time_window_pages_through_tied_timestamps() -> Result<()> {
+    run_with_clickhouse(|client| async move {
+        let columns: ColumnRegistry = column_registry! {
+            id: UInt64,
+            ts: DateTime,
+        }
+        .with_unique_key(&["id"]);
+
+        // Rows 3 and 4 share a timestamp, and the first page of two ends on row 4
+        let rows = "SELECT toUInt64(row.1) AS id, toDateTime(row.2) AS ts FROM (SELECT arrayJoin([\
+                    (1, '2024-06-01 10:00:00'), (2, '2024-06-01 10:01:00'), \
+                    (3, '2024-06-01 10:02:00'), (4, '2024-06-01 10:02:00'), \
+                    (5, '2024-06-01 10:03:00')]) AS row)";
+
+        #[derive(Debug, serde::Deserialize, clickhouse::Row)]
+        struct Event {
+            id: u64,
+            time: String,
+        }
+
+        let mut window = TimeWindowPagination::new("ts", 2);
+        let mut seen = Vec::new();
+        loop {
+            let filters =
+                ClickHouseFilters::new(None, vec![], None, &columns)?.with_time_window(&window)?;
+            let sql = format!(
+                "SELECT id, toString(ts) AS time FROM ({}){}",
+                rows,
+                filters.sql()?
+            );
+            let page = client.query(&sql).fetch_all::<Event>().await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            window = window.next_with_key(&last.time, &[&last.id.to_string()]);
+            seen.extend(page.iter().map(|event| event.id));
+        }
+        assert_eq!(seen, vec![5, 4, 3, 2, 1]);
+
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,55 @@
+//! Integration tests for schema introspection
+//!
+//! These tests verify that column definitions can be built from a live table.
+
+use crate::integration::run_with_clickhouse;
+use clickhouse_filters::{filtering::JsonFilter, ClickHouseFilters, ColumnDef, FilteringOptions};
+use eyre::Result;
+
+#[tokio::test]
+async fn test_from_system_columns() -> Result<()> {
+    run_with_clickhouse(|client| async move {
+        let columns = ColumnDef::from_system_columns(&client, "test_filters", "users").await?;
+
+        // Verify the users table columns were mapped
+        assert_eq!(columns.len(), 9);
+        assert!(matches!(columns.get("id"), Some(ColumnDef::UUID(_))));
+        assert!(matches!(columns.get("age"), Some(ColumnDef::UInt32(_))));
+        assert!(matches!(
+            columns.get("tags"),
+            Some(ColumnDef::ArrayString(_))
+        ));
+        assert!(matches!(
+            columns.get("created_at"),
+            Some(ColumnDef::DateTime(_))
+        ));
+
+        // Use the introspected columns to run a JSON filter
+        let json_filters = vec![JsonFilter {
+            n: "age".to_string(),
+            f: ">".to_string(),
+            v: "25".to_string(),
+            c: None,
+        }];
+        let filtering = FilteringOptions::from_json_filters(&json_filters, &columns)?;
+        let filters = ClickHouseFilters::new(None, vec![], filtering, &columns)?;
+
+        let sql = filters.count_sql("test_filters.users")?;
+        let count = client.query(&sql).fetch_one::<u64>().await?;
+        assert_eq!(count, 3);
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_from_system_columns_missing_table() -> Result<()> {
+    run_with_clickhouse(|client| async move {
+        let result = ColumnDef::from_system_columns(&client, "test_filters", "missing").await;
+        assert!(result.is_err());
+
+        Ok(())
+    })
+    .await
+}
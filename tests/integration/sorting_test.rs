@@ -10,7 +10,7 @@ async fn run_query(client: &Client, sorting: &Sorting) -> Result<Vec<String>> {
     // Build the query using the generated SQL
     let query = format!(
         "SELECT name FROM test_filters.users{} LIMIT 10",
-        sorting.sql
+        sorting.to_sql()
     );
     println!("Executing query: {}", query);
 
@@ -75,7 +75,7 @@ async fn test_sorting_multiple_columns() -> Result<()> {
         // Run a query that returns both columns
         let query = format!(
             "SELECT name, age FROM test_filters.users{} LIMIT 10",
-            sorting.sql
+            sorting.to_sql()
         );
         println!("Executing query: {}", query);
 
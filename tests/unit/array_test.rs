@@ -0,0 +1,281 @@
+use clickhouse_filters::filtering::{
+    ArrayElementType, FilterCondition, FilterOperator, JsonFilter,
+};
+use clickhouse_filters::{ColumnDef, FilteringOptions};
+use std::collections::HashMap;
+
+#[test]
+fn test_array_uuid_filters() {
+    let column = ColumnDef::ArrayUUID("related_ids");
+
+    let condition = column
+        .to_filter_condition("ARRAY HAS", "123e4567-e89b-12d3-a456-426614174000")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "has(related_ids, toUUID('123e4567-e89b-12d3-a456-426614174000'))"
+    );
+
+    let condition = column
+        .to_filter_condition(
+            "ARRAY ANY",
+            "123e4567-e89b-12d3-a456-426614174000, 223e4567-e89b-12d3-a456-426614174000",
+        )
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "hasAny(related_ids, [toUUID('123e4567-e89b-12d3-a456-426614174000'), toUUID('223e4567-e89b-12d3-a456-426614174000')])"
+    );
+}
+
+#[test]
+fn test_array_element_formatting() {
+    let cases = [
+        (
+            ColumnDef::ArrayDate("days"),
+            "ARRAY CONTAINS",
+            "2024-01-01,2024-01-02",
+            "hasAll(days, [toDate('2024-01-01'), toDate('2024-01-02')])",
+        ),
+        (
+            ColumnDef::ArrayDateTime("seen_at"),
+            "ARRAY HAS",
+            "2024-01-01 10:00:00",
+            "has(seen_at, toDateTime('2024-01-01 10:00:00'))",
+        ),
+        (
+            ColumnDef::ArrayEnum8("states"),
+            "ARRAY ALL",
+            "on,o'ff",
+            "hasAll(states, ['on', 'o''ff'])",
+        ),
+        (
+            ColumnDef::ArrayIPv4("hops"),
+            "ARRAY HAS",
+            "10.0.0.1",
+            "has(hops, toIPv4('10.0.0.1'))",
+        ),
+        (
+            ColumnDef::ArrayIPv6("hops6"),
+            "ARRAY ANY",
+            "::1",
+            "hasAny(hops6, [toIPv6('::1')])",
+        ),
+    ];
+
+    for (column, operator, value, expected) in cases {
+        let condition = column.to_filter_condition(operator, value).unwrap();
+        assert_eq!(condition.to_sql(false).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_array_values_errors() {
+    // `has` takes a single element
+    let condition = FilterCondition::array_values(
+        "ids",
+        FilterOperator::ArrayHas,
+        vec!["1".to_string(), "2".to_string()],
+        ArrayElementType::Numeric,
+    );
+    assert!(condition.to_sql(false).is_err());
+
+    // Numeric elements are validated rather than quoted
+    let condition = FilterCondition::array_values(
+        "ids",
+        FilterOperator::ArrayAny,
+        vec!["1".to_string(), "2); DROP TABLE t; --".to_string()],
+        ArrayElementType::Numeric,
+    );
+    assert!(condition.to_sql(false).is_err());
+
+    // Comparison operators are not supported on arrays
+    assert!(ColumnDef::ArrayUUID("ids")
+        .to_filter_condition(">", "1")
+        .is_err());
+}
+
+#[test]
+fn test_array_element_index() {
+    let condition = FilterCondition::array_element(
+        "tags",
+        1,
+        FilterOperator::Equal,
+        "primary",
+        ArrayElementType::String,
+    );
+    assert_eq!(condition.to_sql(false).unwrap(), "tags[1] = 'primary'");
+    assert_eq!(
+        condition.to_sql(true).unwrap(),
+        "lower(tags[1]) = lower('primary')"
+    );
+
+    let condition = ColumnDef::ArrayUInt32("scores")
+        .to_array_element_condition(-1, ">", "90")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(true).unwrap(),
+        "arrayElement(scores, -1) > 90"
+    );
+
+    // Index 0 is not a valid ClickHouse array position
+    let condition = FilterCondition::array_element(
+        "tags",
+        0,
+        FilterOperator::Equal,
+        "primary",
+        ArrayElementType::String,
+    );
+    assert!(condition.to_sql(false).is_err());
+
+    // Only array columns can be indexed
+    assert!(ColumnDef::String("name")
+        .to_array_element_condition(1, "=", "x")
+        .is_err());
+}
+
+#[test]
+fn test_array_element_from_json_filters() {
+    let mut columns = HashMap::new();
+    columns.insert("tags", ColumnDef::ArrayString("tags"));
+    columns.insert("scores", ColumnDef::ArrayFloat64("scores"));
+
+    let json_filters = vec![
+        JsonFilter {
+            n: "tags[1]".to_string(),
+            f: "=".to_string(),
+            v: "primary".to_string(),
+            c: Some("AND".to_string()),
+        },
+        JsonFilter {
+            n: "scores[-1]".to_string(),
+            f: ">=".to_string(),
+            v: "9.5".to_string(),
+            c: None,
+        },
+    ];
+
+    let filtering = FilteringOptions::from_json_filters(&json_filters, columns)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE (lower(tags[1]) = lower('primary') AND arrayElement(scores, -1) >= 9.5)"
+    );
+}
+
+#[test]
+fn test_array_count() {
+    let condition = ColumnDef::ArrayString("events")
+        .to_array_count_condition("error", ">=", 3)
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "countEqual(events, 'error') >= 3"
+    );
+
+    let condition = FilterCondition::array_count(
+        "codes",
+        "500",
+        FilterOperator::Equal,
+        0,
+        ArrayElementType::Numeric,
+    );
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "countEqual(codes, 500) = 0"
+    );
+
+    // Only comparisons make sense against a count
+    let condition = FilterCondition::array_count(
+        "events",
+        "error",
+        FilterOperator::Like,
+        3,
+        ArrayElementType::String,
+    );
+    assert!(condition.to_sql(false).is_err());
+}
+
+#[test]
+fn test_array_has_subsequence() {
+    let condition = ColumnDef::ArrayString("events")
+        .to_filter_condition("ARRAY HAS SUBSEQUENCE", "login, purchase")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "hasSubstr(events, ['login', 'purchase'])"
+    );
+
+    let condition = ColumnDef::ArrayUInt16("steps")
+        .to_filter_condition("ARRAY HAS SUBSEQUENCE", "1,2,3")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "hasSubstr(steps, [1, 2, 3])"
+    );
+}
+
+#[test]
+fn test_array_overlap() {
+    let condition = ColumnDef::ArrayString("tags")
+        .to_array_overlap_condition("a, b, c", 2)
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "length(arrayIntersect(tags, ['a', 'b', 'c'])) >= 2"
+    );
+
+    let condition = FilterCondition::array_overlap("ids", vec![], 1, ArrayElementType::Numeric);
+    assert!(condition.to_sql(false).is_err());
+}
+
+#[test]
+fn test_negated_array_operators() {
+    let tags = ColumnDef::ArrayString("tags");
+    let cases = [
+        ("ARRAY NOT HAS", "spam", "NOT has(tags, 'spam')"),
+        (
+            "ARRAY NOT CONTAINS",
+            "spam,bot",
+            "NOT hasAll(tags, array['spam', 'bot'])",
+        ),
+        (
+            "ARRAY NOT ANY",
+            "spam,bot",
+            "NOT hasAny(tags, ['spam', 'bot'])",
+        ),
+    ];
+    for (operator, value, expected) in cases {
+        let condition = tags.to_filter_condition(operator, value).unwrap();
+        assert_eq!(condition.to_sql(false).unwrap(), expected);
+    }
+
+    let scores = ColumnDef::ArrayUInt32("scores");
+    let condition = scores.to_filter_condition("ARRAY NOT ANY", "1, 2").unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "NOT hasAny(scores, [1, 2])"
+    );
+
+    let related = ColumnDef::ArrayUUID("related_ids");
+    let condition = related
+        .to_filter_condition("ARRAY NOT HAS", "123e4567-e89b-12d3-a456-426614174000")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "NOT has(related_ids, toUUID('123e4567-e89b-12d3-a456-426614174000'))"
+    );
+
+    // Typed constructors take the negated operators too
+    let condition = FilterCondition::array_values(
+        "scores",
+        FilterOperator::ArrayNotContains,
+        vec!["3".to_string(), "4".to_string()],
+        ArrayElementType::Numeric,
+    );
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "NOT hasAll(scores, [3, 4])"
+    );
+}
@@ -0,0 +1,144 @@
+use clickhouse_filters::ast::AstNode;
+use clickhouse_filters::custom::ToSqlCondition;
+use clickhouse_filters::filtering::{
+    FilterBuilder, FilterCondition, FilterExpression, FilterOperator, LogicalOperator,
+};
+use std::sync::Arc;
+
+#[derive(Debug)]
+struct Sampled;
+
+impl ToSqlCondition for Sampled {
+    fn write_sql(&self, out: &mut String, _case_insensitive: bool) -> eyre::Result<()> {
+        out.push_str("cityHash64(id) % 10 = 0");
+        Ok(())
+    }
+
+    fn columns(&self) -> Vec<&str> {
+        vec!["id"]
+    }
+}
+
+fn builder() -> FilterBuilder {
+    FilterBuilder::new()
+        .add_condition(FilterCondition::string(
+            "name",
+            FilterOperator::Equal,
+            Some("John"),
+        ))
+        .group(
+            LogicalOperator::Or,
+            vec![
+                FilterExpression::Condition(FilterCondition::uint32(
+                    "age",
+                    FilterOperator::LessThan,
+                    Some(18),
+                )),
+                FilterExpression::Custom(Arc::new(Sampled)),
+            ],
+        )
+}
+
+#[test]
+fn test_builder_counts() {
+    let builder = builder();
+    assert!(builder.root().is_some());
+    assert_eq!(builder.depth(), 3);
+    assert_eq!(builder.condition_count(), 3);
+    assert_eq!(builder.group_count(), 2);
+
+    let empty = FilterBuilder::new();
+    assert!(empty.root().is_none());
+    assert_eq!(empty.depth(), 0);
+    assert_eq!(empty.condition_count(), 0);
+    assert_eq!(empty.group_count(), 0);
+    assert_eq!(empty.to_ast().unwrap(), None);
+
+    let condition = FilterExpression::Condition(FilterCondition::uint32(
+        "age",
+        FilterOperator::Equal,
+        Some(1),
+    ));
+    assert_eq!(condition.depth(), 1);
+    assert_eq!(condition.group_count(), 0);
+}
+
+#[test]
+fn test_operator_histogram() {
+    let builder = builder()
+        .add_condition(FilterCondition::string(
+            "city",
+            FilterOperator::Equal,
+            Some("Leeds"),
+        ))
+        .add_condition(FilterCondition::date_range(
+            "created_at",
+            "2024-01-01",
+            "2024-02-01",
+        ));
+
+    let histogram = builder.operator_histogram();
+    assert_eq!(
+        histogram.into_iter().collect::<Vec<_>>(),
+        vec![("<", 1), ("=", 2), ("Custom", 1), ("DateRange::Range", 1)]
+    );
+    assert!(FilterBuilder::new().operator_histogram().is_empty());
+}
+
+#[test]
+fn test_builder_to_ast() {
+    let ast = builder().to_ast().unwrap().unwrap();
+    assert_eq!(
+        ast,
+        AstNode::Group {
+            operator: "AND".to_string(),
+            children: vec![
+                AstNode::Condition {
+                    kind: "StringValue".to_string(),
+                    column: "name".to_string(),
+                    operator: Some("=".to_string()),
+                    sql: "name = 'John'".to_string(),
+                },
+                AstNode::Group {
+                    operator: "OR".to_string(),
+                    children: vec![
+                        AstNode::Condition {
+                            kind: "UInt32Value".to_string(),
+                            column: "age".to_string(),
+                            operator: Some("<".to_string()),
+                            sql: "age < 18".to_string(),
+                        },
+                        AstNode::Custom {
+                            columns: vec!["id".to_string()],
+                            sql: "cityHash64(id) % 10 = 0".to_string(),
+                        },
+                    ],
+                },
+            ],
+        }
+    );
+
+    // Expressions render with the given case sensitivity
+    let Some(FilterExpression::Group { expressions, .. }) = builder().root else {
+        panic!("expected a group");
+    };
+    let AstNode::Condition { sql, .. } = expressions[0].to_ast(true).unwrap() else {
+        panic!("expected a condition");
+    };
+    assert_eq!(sql, "lower(name) = lower('John')");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_ast_serialization() {
+    let ast = builder().to_ast().unwrap().unwrap();
+    let json = serde_json::to_value(&ast).unwrap();
+    assert_eq!(json["type"], "group");
+    assert_eq!(json["children"][0]["type"], "condition");
+    assert_eq!(json["children"][0]["sql"], "name = 'John'");
+    assert_eq!(json["children"][1]["children"][1]["type"], "custom");
+    assert_eq!(json["children"][1]["children"][1]["columns"][0], "id");
+
+    let parsed: AstNode = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed, ast);
+}
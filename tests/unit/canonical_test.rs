@@ -0,0 +1,86 @@
+use clickhouse_filters::canonical::normalize_whitespace;
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::sorting::SortedColumn;
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, FilteringOptions, PaginationOptions};
+use std::collections::HashMap;
+
+fn columns() -> HashMap<&'static str, ColumnDef> {
+    let mut columns = HashMap::new();
+    columns.insert("name", ColumnDef::String("name"));
+    columns.insert("age", ColumnDef::UInt32("age"));
+    columns.insert("status", ColumnDef::String("status"));
+    columns
+}
+
+fn filters(expressions: Vec<FilterExpression>) -> ClickHouseFilters {
+    ClickHouseFilters::new(
+        Some(PaginationOptions::new(1, 10, 10, 100)),
+        vec![SortedColumn::new("name", "asc")],
+        Some(FilteringOptions::case_sensitive(expressions, columns())),
+        columns(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_canonical_sql_ignores_sibling_order() {
+    let status = FilterExpression::Condition(FilterCondition::string(
+        "status",
+        FilterOperator::In,
+        Some("pending, active"),
+    ));
+    let age = FilterExpression::Condition(FilterCondition::uint32(
+        "age",
+        FilterOperator::GreaterThan,
+        Some(25),
+    ));
+
+    let a = filters(vec![status.clone(), age.clone()]);
+    let b = filters(vec![age, status]);
+
+    // Plain SQL follows the order the filters were given in
+    assert_ne!(a.sql().unwrap(), b.sql().unwrap());
+
+    assert_eq!(a.sql_canonical().unwrap(), b.sql_canonical().unwrap());
+    assert_eq!(
+        a.sql_canonical().unwrap(),
+        "WHERE (age > 25 AND status IN ('active', 'pending')) ORDER BY name ASC LIMIT 10 OFFSET 0"
+    );
+}
+
+#[test]
+fn test_canonicalize_sorts_nested_groups_and_in_values() {
+    let expr = FilterExpression::or(vec![
+        FilterExpression::and(vec![
+            FilterExpression::Condition(FilterCondition::string(
+                "name",
+                FilterOperator::Equal,
+                Some("b"),
+            )),
+            FilterExpression::Condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::LessThan,
+                Some(30),
+            )),
+        ]),
+        FilterExpression::Condition(FilterCondition::in_values(
+            "age",
+            FilterOperator::In,
+            vec!["3".to_string(), "1".to_string(), "3".to_string()],
+            None,
+        )),
+    ]);
+
+    assert_eq!(
+        expr.canonicalize().to_sql(false).unwrap(),
+        "((age < 30 AND name = 'b') OR age IN (1, 3))"
+    );
+}
+
+#[test]
+fn test_normalize_whitespace_preserves_literals() {
+    assert_eq!(
+        normalize_whitespace("  WHERE  name =  'a  b'\n AND  x = 'it''s  ok'  "),
+        "WHERE name = 'a  b' AND x = 'it''s  ok'"
+    );
+}
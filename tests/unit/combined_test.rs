@@ -1,10 +1,13 @@
 //! Unit tests for the combined functionality (filtering, pagination, sorting)
 
 use clickhouse_filters::filtering::{
-    FilterCondition, FilterExpression, FilterOperator, JsonFilter,
+    FilterBuilder, FilterCondition, FilterExpression, FilterOperator, JsonFilter, NumericRange,
+    SameColumnPolicy,
 };
 use clickhouse_filters::sorting::SortedColumn;
-use clickhouse_filters::{ClickHouseFilters, ColumnDef, FilteringOptions, PaginationOptions};
+use clickhouse_filters::{
+    ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions, PaginationOptions,
+};
 use std::collections::HashMap;
 
 #[test]
@@ -45,14 +48,12 @@ fn test_complete_filtering() {
     assert!(sql.contains("LIMIT 10 OFFSET 0"));
 
     // Generate count SQL
-    let count_sql = filters.count_sql("my_db", "users").unwrap();
+    let count_sql = filters.count_sql("my_db.users").unwrap();
     assert!(count_sql.contains("SELECT COUNT(*) FROM my_db.users"));
     assert!(count_sql.contains("WHERE"));
 
     // Generate query SQL
-    let query_sql = filters
-        .query_sql("my_db", "users", &["name", "age"])
-        .unwrap();
+    let query_sql = filters.query_sql("my_db.users", &["name", "age"]).unwrap();
     assert!(query_sql.contains("SELECT name, age FROM my_db.users"));
     assert!(query_sql.contains("WHERE"));
     assert!(query_sql.contains("ORDER BY"));
@@ -147,3 +148,298 @@ fn test_combined_filters() {
     // Check for correct pagination (page 2 with 15 per page = offset 15)
     assert!(sql.contains("LIMIT 15 OFFSET 15"));
 }
+
+#[test]
+fn test_where_clause_reused_across_queries() {
+    let mut columns = HashMap::new();
+    columns.insert("name", ColumnDef::String("name"));
+    columns.insert("age", ColumnDef::UInt32("age"));
+
+    let filtering = FilteringOptions::new(
+        vec![
+            FilterExpression::Condition(FilterCondition::string(
+                "name",
+                FilterOperator::StartsWith,
+                Some("J"),
+            )),
+            FilterExpression::Condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::GreaterThan,
+                Some(21),
+            )),
+        ],
+        columns.clone(),
+    );
+
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(1, 10, 50, 100)),
+        vec![SortedColumn::new("name", "asc")],
+        Some(filtering),
+        columns.clone(),
+    )
+    .unwrap();
+
+    // count_sql then query_sql, as on a typical list endpoint
+    let count_sql = filters.count_sql("my_db.users").unwrap();
+    let query_sql = filters.query_sql("my_db.users", &[]).unwrap();
+    assert_eq!(
+        count_sql,
+        "SELECT COUNT(*) FROM my_db.users WHERE (lower(name) LIKE lower('J%') AND age > 21)"
+    );
+    assert_eq!(
+        query_sql,
+        "SELECT * FROM my_db.users WHERE (lower(name) LIKE lower('J%') AND age > 21) ORDER BY name ASC LIMIT 10 OFFSET 0"
+    );
+
+    // Repeated calls and clones render identically
+    assert_eq!(filters.sql().unwrap(), filters.clone().sql().unwrap());
+    assert_eq!(filters.count_sql("my_db.users").unwrap(), count_sql);
+
+    // Replacing the filters or the registry renders the clause again
+    let mut filters = filters;
+    filters.set_filters(Some(FilterBuilder::new().add_condition(
+        FilterCondition::uint32("age", FilterOperator::LessThan, Some(65)),
+    )));
+    assert_eq!(
+        filters.sql().unwrap(),
+        " WHERE age < 65 ORDER BY name ASC LIMIT 10 OFFSET 0"
+    );
+    let mut columns = columns;
+    columns.insert("deleted_at", ColumnDef::DateTime("deleted_at"));
+    let filters = filters
+        .with_column_defs(ColumnRegistry::new(columns).with_soft_delete("deleted_at"))
+        .unwrap();
+    assert_eq!(
+        filters.count_sql("my_db.users").unwrap(),
+        "SELECT COUNT(*) FROM my_db.users WHERE (age < 65 AND deleted_at IS NULL)"
+    );
+}
+
+#[test]
+fn test_same_column_policy() {
+    let filter = |n: &str, f: &str, v: &str| JsonFilter {
+        n: n.to_string(),
+        f: f.to_string(),
+        v: v.to_string(),
+        c: Some("AND".to_string()),
+    };
+    let json_filters = vec![
+        filter("status", "=", "open"),
+        filter("age", ">", "18"),
+        filter("status", "=", "paid"),
+        filter("age", "<", "65"),
+        filter("status", "!=", "void"),
+    ];
+    let columns = ColumnRegistry::from_iter([
+        ("status", ColumnDef::String("status")),
+        ("age", ColumnDef::UInt32("age")),
+    ]);
+
+    // By default the connectors decide
+    let sequential = FilterBuilder::from_json_filters(&json_filters, false, &columns).unwrap();
+    assert_eq!(
+        sequential.build().unwrap(),
+        " WHERE (status = 'open' AND age > 18 AND status = 'paid' AND age < 65 AND status != 'void')"
+    );
+
+    let columns = columns.with_same_column_policy(SameColumnPolicy::OrWithinColumn);
+    let faceted = FilterBuilder::from_json_filters(&json_filters, false, &columns).unwrap();
+    assert_eq!(
+        faceted.build().unwrap(),
+        " WHERE ((status = 'open' OR status = 'paid') AND status != 'void' AND age > 18 AND age < 65)"
+    );
+
+    let single = FilterBuilder::from_json_filters(&json_filters[..1], false, &columns).unwrap();
+    assert_eq!(single.build().unwrap(), " WHERE status = 'open'");
+
+    // Each JSON path and map key is a facet of its own
+    let columns = ColumnRegistry::from_iter([
+        ("attrs", ColumnDef::JSON("attrs")),
+        ("labels", ColumnDef::Map("labels")),
+    ])
+    .with_same_column_policy(SameColumnPolicy::OrWithinColumn);
+    let json_filters = vec![
+        filter("attrs", "=", "color.red"),
+        filter("attrs", "=", "size.L"),
+        filter("attrs", "=", "color.blue"),
+        filter("labels['team']", "=", "core"),
+        filter("labels['env']", "=", "prod"),
+        filter("labels['team']", "=", "web"),
+    ];
+    let faceted = FilterBuilder::from_json_filters(&json_filters, false, &columns).unwrap();
+    assert_eq!(
+        faceted.build().unwrap(),
+        " WHERE ((JSONExtractString(attrs, 'color') = 'red' OR JSONExtractString(attrs, 'color') = 'blue') \
+         AND JSONExtractString(attrs, 'size') = 'L' \
+         AND (labels['team'] = 'core' OR labels['team'] = 'web') AND labels['env'] = 'prod')"
+    );
+}
+
+#[test]
+fn test_serde_round_trip() {
+    let mut columns = HashMap::new();
+    columns.insert("name", ColumnDef::String("name"));
+    columns.insert("email", ColumnDef::String("email"));
+    columns.insert("age", ColumnDef::UInt32("age"));
+    columns.insert("deleted_at", ColumnDef::DateTime("deleted_at"));
+    let columns = ColumnRegistry::new(columns)
+        .with_case_insensitive("email")
+        .with_soft_delete("deleted_at")
+        .with_allowed_settings(&["max_threads"]);
+
+    let filtering = FilteringOptions::case_sensitive(
+        vec![
+            FilterExpression::Condition(FilterCondition::string(
+                "email",
+                FilterOperator::Equal,
+                Some("A@example.com"),
+            )),
+            FilterExpression::Condition(FilterCondition::uint32(
+                "years",
+                FilterOperator::GreaterThan,
+                Some(25),
+            )),
+        ],
+        &columns,
+    )
+    // Hooks are applied before serializing
+    .on_condition(|mut condition| {
+        if condition.column() == "years" {
+            *condition.column_mut() = "age".to_string();
+        }
+        Ok(FilterExpression::Condition(condition))
+    });
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(2, 10, 100, 1000)),
+        vec![SortedColumn::new("name", "asc")],
+        Some(filtering),
+        &columns,
+    )
+    .unwrap()
+    .with_setting("max_threads", "4")
+    .unwrap();
+    let expected = filters.query_sql("db.users", &["name"]).unwrap();
+    assert_eq!(
+        expected,
+        "SELECT name FROM db.users WHERE (lower(email) = lower('A@example.com') AND age > 25 \
+         AND deleted_at IS NULL) ORDER BY name ASC LIMIT 10 OFFSET 10 SETTINGS max_threads = 4"
+    );
+
+    let cached = serde_json::to_string(&filters).unwrap();
+    let restored: ClickHouseFilters = serde_json::from_str(&cached).unwrap();
+    assert!(restored.sql().is_err());
+
+    let mut restored = restored.with_column_defs(&columns).unwrap();
+    assert_eq!(restored.query_sql("db.users", &["name"]).unwrap(), expected);
+    restored.set_total_records(15).unwrap();
+    assert_eq!(restored.pagination.unwrap().pagination.total_pages, 2);
+
+    // Settings are checked against the registry they're restored with
+    let restored: ClickHouseFilters = serde_json::from_str(&cached).unwrap();
+    assert!(restored
+        .with_column_defs(columns.with_allowed_settings(&[]))
+        .is_err());
+}
+
+#[test]
+fn test_serde_payload_cannot_carry_sql_or_mutations() {
+    let columns = ColumnRegistry::from_iter([("name", ColumnDef::String("name"))])
+        .with_allowed_settings(&["max_threads"]);
+    let filtering = FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::string(
+            "name",
+            FilterOperator::Equal,
+            Some("Bob"),
+        ))],
+        &columns,
+    );
+    let filters = ClickHouseFilters::new(None, vec![], Some(filtering), &columns)
+        .unwrap()
+        .with_setting("max_threads", "4")
+        .unwrap()
+        .allow_mutations();
+    assert!(filters.delete_sql("db", "users").is_ok());
+
+    // Mutations have to be confirmed again after a round trip
+    let cached = serde_json::to_string(&filters).unwrap();
+    let restored: ClickHouseFilters = serde_json::from_str(&cached).unwrap();
+    let restored = restored.with_column_defs(&columns).unwrap();
+    assert!(restored.delete_sql("db", "users").is_err());
+    assert!(!cached.contains("allow_mutations"));
+
+    // Setting values are stored raw and escaped when rendered
+    assert!(cached.contains(r#"["max_threads","4"]"#));
+    let tampered = cached.replace(
+        r#"["max_threads","4"]"#,
+        r#"["max_threads","4; DROP TABLE users"]"#,
+    );
+    let restored: ClickHouseFilters = serde_json::from_str(&tampered).unwrap();
+    let restored = restored.with_column_defs(&columns).unwrap();
+    assert_eq!(
+        restored.query_sql("db.users", &["name"]).unwrap(),
+        "SELECT name FROM db.users WHERE name = 'Bob' \
+         SETTINGS max_threads = '4; DROP TABLE users'"
+    );
+}
+
+#[test]
+fn test_serde_payload_is_checked_again() {
+    let columns = ColumnRegistry::from_iter([
+        ("name", ColumnDef::String("name")),
+        ("price", ColumnDef::Float64("price")),
+        ("created_at", ColumnDef::DateTime("created_at")),
+    ]);
+    let filtering = FilteringOptions::case_sensitive(
+        vec![
+            FilterExpression::Condition(FilterCondition::relative_date(
+                "created_at",
+                "now() - INTERVAL 7 DAY",
+            )),
+            FilterExpression::Condition(FilterCondition::date_bucket(
+                "created_at",
+                "5 MINUTE",
+                "2024-01-01 10:05:00",
+            )),
+            FilterExpression::Condition(FilterCondition::numeric_range(
+                "price",
+                NumericRange::new(Some(1), Some(5)),
+            )),
+        ],
+        &columns,
+    );
+    let filters = ClickHouseFilters::new(
+        None,
+        vec![SortedColumn::new("name", "asc")],
+        Some(filtering),
+        &columns,
+    )
+    .unwrap();
+    let cached = serde_json::to_string(&filters).unwrap();
+    let restore = |payload: &str| {
+        let restored: ClickHouseFilters = serde_json::from_str(payload).unwrap();
+        restored.with_column_defs(&columns).map(|_| ())
+    };
+    assert!(restore(&cached).is_ok());
+
+    // Each altered part fails to reattach instead of being rendered as SQL
+    let tampered = [
+        (
+            r#""name""#,
+            r#""name; DROP TABLE users""#,
+            "Column not found",
+        ),
+        (r#""price""#, r#""price OR 1""#, "Column not found"),
+        (
+            "now() - INTERVAL 7 DAY",
+            "now() OR 1 = 1",
+            "Invalid relative date expression",
+        ),
+        ("5 MINUTE", "5 MINUTE) OR (1", "Invalid bucket interval"),
+        (r#""1""#, r#""1 OR 1 = 1""#, "Invalid numeric range bound"),
+    ];
+    for (from, to, error) in tampered {
+        assert!(cached.contains(from), "{} not in {}", from, cached);
+        let message = restore(&cached.replace(from, to)).unwrap_err().to_string();
+        assert!(message.starts_with(error), "{}", message);
+    }
+}
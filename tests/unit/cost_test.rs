@@ -0,0 +1,67 @@
+use clickhouse_filters::cost::{CostLimits, QueryEstimate};
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::{
+    ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions, PaginationOptions,
+};
+
+#[test]
+fn test_explain_estimate_sql() {
+    let columns = ColumnRegistry::from_iter([("age", ColumnDef::UInt32("age"))]);
+    let filtering = FilteringOptions::new(
+        vec![FilterExpression::Condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(30),
+        ))],
+        &columns,
+    );
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(1, 10, 100, 1000)),
+        vec![],
+        Some(filtering),
+        &columns,
+    )
+    .unwrap()
+    .with_query_tag("req-1");
+
+    assert_eq!(
+        filters.explain_estimate_sql("db.users", &["age"]).unwrap(),
+        "EXPLAIN ESTIMATE SELECT age FROM db.users WHERE age > 30 LIMIT 10 OFFSET 0 \
+         SETTINGS log_comment = 'req-1'"
+    );
+}
+
+#[test]
+fn test_cost_limits() {
+    let estimate = QueryEstimate {
+        parts: 40,
+        rows: 2_000,
+        marks: 10,
+    };
+
+    // No thresholds, or thresholds that are not exceeded, pass cleanly
+    assert!(CostLimits::new().check(&estimate).unwrap().is_empty());
+    let within = CostLimits::new().with_max_rows(2_000).with_max_parts(40);
+    assert!(within.check(&estimate).unwrap().is_empty());
+
+    let limits = CostLimits::new().with_max_rows(1_000).with_max_parts(20);
+    assert_eq!(
+        limits.check(&estimate).unwrap_err().to_string(),
+        "Estimated rows to read exceed limit: 2000 > 1000"
+    );
+
+    let parts_only = CostLimits::new().with_max_parts(20);
+    assert_eq!(
+        parts_only.check(&estimate).unwrap_err().to_string(),
+        "Estimated parts to read exceed limit: 40 > 20"
+    );
+
+    // Warn-only limits report every breach
+    assert_eq!(
+        limits.warn_only().check(&estimate).unwrap(),
+        vec![
+            "Estimated rows to read exceed limit: 2000 > 1000",
+            "Estimated parts to read exceed limit: 40 > 20",
+        ]
+    );
+}
@@ -0,0 +1,151 @@
+use clickhouse_filters::count::CountExpression;
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, FilteringOptions};
+use std::collections::HashMap;
+
+fn filters() -> ClickHouseFilters {
+    let mut columns = HashMap::new();
+    columns.insert("user_id", ColumnDef::UInt64("user_id"));
+    columns.insert("age", ColumnDef::UInt32("age"));
+
+    let filtering = FilteringOptions::new(
+        vec![FilterExpression::Condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(25),
+        ))],
+        columns.clone(),
+    );
+
+    ClickHouseFilters::new(None, vec![], Some(filtering), columns).unwrap()
+}
+
+#[test]
+fn test_count_sql_distinct_variants() {
+    let filters = filters();
+
+    assert_eq!(
+        filters.count_sql("db.events").unwrap(),
+        "SELECT COUNT(*) FROM db.events WHERE age > 25"
+    );
+    assert_eq!(
+        filters
+            .count_sql_with("db.events", &CountExpression::distinct("user_id"))
+            .unwrap(),
+        "SELECT COUNT(DISTINCT user_id) FROM db.events WHERE age > 25"
+    );
+    assert_eq!(
+        filters
+            .count_sql_with("db.events", &CountExpression::uniq("user_id"))
+            .unwrap(),
+        "SELECT uniq(user_id) FROM db.events WHERE age > 25"
+    );
+    assert_eq!(
+        filters
+            .count_sql_with("db.events", &CountExpression::uniq_exact("user_id"))
+            .unwrap(),
+        "SELECT uniqExact(user_id) FROM db.events WHERE age > 25"
+    );
+}
+
+#[test]
+fn test_count_sql_custom_expression() {
+    let sql = filters()
+        .count_sql_with(
+            "db.events",
+            &CountExpression::custom("uniqCombined(user_id)"),
+        )
+        .unwrap();
+    assert_eq!(
+        sql,
+        "SELECT uniqCombined(user_id) FROM db.events WHERE age > 25"
+    );
+}
+
+#[test]
+fn test_count_sql_rejects_unknown_column() {
+    let err = filters()
+        .count_sql_with("db.events", &CountExpression::distinct("session_id"))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Column not found: session_id");
+}
+
+#[test]
+fn test_count_by_sql() {
+    let mut columns = HashMap::new();
+    columns.insert("status", ColumnDef::String("status"));
+    columns.insert("age", ColumnDef::UInt32("age"));
+    let filtering = FilteringOptions::new(
+        vec![FilterExpression::Condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(25),
+        ))],
+        columns.clone(),
+    );
+    let filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns).unwrap();
+
+    assert_eq!(
+        filters.count_by_sql("db.tickets", "status").unwrap(),
+        "SELECT status, COUNT(*) FROM db.tickets WHERE age > 25 \
+         GROUP BY status ORDER BY COUNT(*) DESC"
+    );
+    assert_eq!(
+        filters
+            .count_by_sql("db.tickets", "priority")
+            .unwrap_err()
+            .to_string(),
+        "Column not found: priority"
+    );
+}
+
+#[test]
+fn test_top_n_sql() {
+    let mut columns = HashMap::new();
+    columns.insert("page", ColumnDef::String("page"));
+    columns.insert("user_id", ColumnDef::UInt64("user_id"));
+    columns.insert("country", ColumnDef::String("country"));
+    let filtering = FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::string(
+            "country",
+            FilterOperator::Equal,
+            Some("NL"),
+        ))],
+        columns.clone(),
+    );
+    let filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns).unwrap();
+
+    assert_eq!(
+        filters
+            .top_n_sql("web.views", "page", &CountExpression::Rows, 10)
+            .unwrap(),
+        "SELECT page, COUNT(*) FROM web.views WHERE country = 'NL' \
+         GROUP BY page ORDER BY COUNT(*) DESC LIMIT 10"
+    );
+    assert_eq!(
+        filters
+            .top_n_sql("web.views", "page", &CountExpression::uniq("user_id"), 5)
+            .unwrap(),
+        "SELECT page, uniq(user_id) FROM web.views WHERE country = 'NL' \
+         GROUP BY page ORDER BY uniq(user_id) DESC LIMIT 5"
+    );
+    assert_eq!(
+        filters
+            .top_n_sql(
+                "web.views",
+                "page",
+                &CountExpression::custom("sum(duration)"),
+                3
+            )
+            .unwrap(),
+        "SELECT page, sum(duration) FROM web.views WHERE country = 'NL' \
+         GROUP BY page ORDER BY sum(duration) DESC LIMIT 3"
+    );
+
+    assert!(filters
+        .top_n_sql("web.views", "referrer", &CountExpression::Rows, 10)
+        .is_err());
+    assert!(filters
+        .top_n_sql("web.views", "page", &CountExpression::Rows, 0)
+        .is_err());
+}
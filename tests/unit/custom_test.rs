@@ -0,0 +1,260 @@
+use clickhouse_filters::custom::{
+    escape_like, write_string_literal, ColumnRenderer, ToSqlCondition,
+};
+use clickhouse_filters::filtering::{
+    FilterBuilder, FilterCondition, FilterExpression, FilterOperator, JsonFilter, LogicalOperator,
+};
+use clickhouse_filters::sorting::SortedColumn;
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::sync::Arc;
+
+// Filters a `uniqState` column by its merged value
+#[derive(Debug)]
+struct UniqState;
+
+impl ColumnRenderer for UniqState {
+    fn validate(
+        &self,
+        column: &str,
+        operator: &FilterOperator,
+        value: Option<&str>,
+    ) -> eyre::Result<()> {
+        if !operator.is_comparison() && value.is_some() {
+            return Err(eyre::eyre!("Unsupported operator for {}", column));
+        }
+        match value.map(str::parse::<u64>) {
+            Some(Err(_)) => Err(eyre::eyre!("Invalid count for {}", column)),
+            _ => Ok(()),
+        }
+    }
+
+    fn write_sql(
+        &self,
+        out: &mut String,
+        column: &str,
+        operator: &FilterOperator,
+        value: Option<&str>,
+        case_insensitive: bool,
+    ) -> eyre::Result<()> {
+        match value {
+            Some(value) => write!(
+                out,
+                "finalizeAggregation({}) {} {}",
+                column,
+                operator.as_sql(),
+                value
+            )?,
+            None => write!(out, "{} {}", column, operator.as_sql())?,
+        }
+        if case_insensitive {
+            out.push_str(" /* ci */");
+        }
+        Ok(())
+    }
+}
+
+fn columns() -> ColumnRegistry {
+    ColumnRegistry::from_iter([
+        ("visitors", ColumnDef::custom("visitors", UniqState)),
+        ("day", ColumnDef::Date("day")),
+    ])
+}
+
+#[test]
+fn test_custom_column_conditions() {
+    let columns = columns();
+    let filters = vec![
+        JsonFilter {
+            n: "visitors".to_string(),
+            f: ">=".to_string(),
+            v: "100".to_string(),
+            c: None,
+        },
+        JsonFilter {
+            n: "day".to_string(),
+            f: "=".to_string(),
+            v: "2024-01-01".to_string(),
+            c: None,
+        },
+    ];
+
+    let filtering = FilteringOptions::from_json_filters(&filters, &columns)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE (finalizeAggregation(visitors) >= 100 /* ci */ AND day = '2024-01-01')"
+    );
+
+    let condition = columns
+        .get("visitors")
+        .unwrap()
+        .to_filter_condition("IS NOT NULL", "")
+        .unwrap();
+    assert_eq!(condition.to_sql(false).unwrap(), "visitors IS NOT NULL");
+    assert_eq!(condition.column(), "visitors");
+
+    // Renderer validation runs when the condition is built
+    let column = columns.get("visitors").unwrap();
+    assert_eq!(
+        column
+            .to_filter_condition("LIKE", "1%")
+            .unwrap_err()
+            .to_string(),
+        "Unsupported operator for visitors"
+    );
+    assert!(column.to_filter_condition("=", "many").is_err());
+}
+
+#[test]
+fn test_custom_column_sorting_and_equality() {
+    let columns = columns();
+    let filters = ClickHouseFilters::new(
+        None,
+        vec![SortedColumn::new("visitors", "desc")],
+        None,
+        &columns,
+    )
+    .unwrap();
+    assert_eq!(filters.sql().unwrap(), " ORDER BY visitors DESC");
+
+    // Conditions built from the same column share the renderer
+    let column = columns.get("visitors").unwrap();
+    assert_eq!(
+        column.to_filter_condition(">", "1").unwrap(),
+        column.to_filter_condition(">", "1").unwrap()
+    );
+    assert_ne!(
+        column.to_filter_condition(">", "1").unwrap(),
+        ColumnDef::custom("visitors", UniqState)
+            .to_filter_condition(">", "1")
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_custom_operators() {
+    let plain = columns();
+    let columns =
+        plain
+            .clone()
+            .with_operator("WITHIN_SLA", |out, column, value, case_insensitive| {
+                let hours: u32 = value.unwrap_or_default().parse()?;
+                write!(out, "{} >= now() - INTERVAL {} HOUR", column, hours)?;
+                if case_insensitive {
+                    out.push_str(" /* ci */");
+                }
+                Ok(())
+            });
+    assert_eq!(
+        columns.custom_operator("within_sla").map(|op| op.name()),
+        Some("WITHIN_SLA")
+    );
+
+    let filters = vec![JsonFilter {
+        n: "day".to_string(),
+        f: "within_sla".to_string(),
+        v: "4".to_string(),
+        c: None,
+    }];
+    let filtering = FilteringOptions::from_json_filters(&filters, &columns)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE day >= now() - INTERVAL 4 HOUR /* ci */"
+    );
+
+    let condition = columns
+        .to_filter_condition("day", "WITHIN_SLA", "2")
+        .unwrap();
+    assert_eq!(
+        condition.operator(),
+        Some(&FilterOperator::Custom("WITHIN_SLA"))
+    );
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "day >= now() - INTERVAL 2 HOUR"
+    );
+
+    // Rendering errors surface when SQL is built; unknown columns and operators still fail
+    let invalid = columns
+        .to_filter_condition("day", "WITHIN_SLA", "soon")
+        .unwrap();
+    assert!(invalid.to_sql(false).is_err());
+    assert_eq!(
+        columns
+            .to_filter_condition("missing", "WITHIN_SLA", "2")
+            .unwrap_err()
+            .to_string(),
+        "Column not found: missing"
+    );
+    assert!(columns.to_filter_condition("day", "NEARLY", "2").is_err());
+    assert!(plain.to_filter_condition("day", "WITHIN_SLA", "2").is_err());
+}
+
+// Rows whose path starts with a literal prefix
+#[derive(Debug)]
+struct PathPrefix(&'static str);
+
+impl ToSqlCondition for PathPrefix {
+    fn write_sql(&self, out: &mut String, case_insensitive: bool) -> eyre::Result<()> {
+        out.push_str(if case_insensitive {
+            "path ILIKE "
+        } else {
+            "path LIKE "
+        });
+        write_string_literal(out, &format!("{}%", escape_like(self.0)));
+        Ok(())
+    }
+
+    fn columns(&self) -> Vec<&str> {
+        vec!["path"]
+    }
+}
+
+#[test]
+fn test_custom_expressions() {
+    let prefix = FilterExpression::Custom(Arc::new(PathPrefix("/it's_100%")));
+    assert_eq!(
+        prefix.to_sql(false).unwrap(),
+        r"path LIKE '/it\'s\\_100\\%%'"
+    );
+    assert_eq!(prefix.to_string(), r"path LIKE '/it\'s\\_100\\%%'");
+    assert!(!prefix.is_empty());
+
+    // Custom expressions nest in groups and builders like any other
+    let builder = FilterBuilder::new()
+        .add_condition(FilterCondition::uint32(
+            "size",
+            FilterOperator::GreaterThan,
+            Some(10),
+        ))
+        .group(
+            LogicalOperator::Or,
+            vec![
+                prefix.clone(),
+                FilterExpression::Custom(Arc::new(PathPrefix("/tmp"))),
+            ],
+        );
+    assert_eq!(
+        builder.build().unwrap(),
+        r" WHERE (size > 10 AND (path LIKE '/it\'s\\_100\\%%' OR path LIKE '/tmp%'))"
+    );
+
+    let expression = builder.root.unwrap();
+    assert_eq!(
+        expression.referenced_columns(),
+        BTreeSet::from(["path".to_string(), "size".to_string()])
+    );
+    assert_eq!(expression.canonicalize().referenced_columns().len(), 2);
+
+    // Equal only to the same instance, since conditions can't be compared
+    assert_eq!(prefix, prefix.clone());
+    assert_ne!(
+        prefix,
+        FilterExpression::Custom(Arc::new(PathPrefix("/it's_100%")))
+    );
+}
@@ -0,0 +1,174 @@
+use clickhouse_filters::filtering::{
+    ArrayElementType, FilterCondition, FilterExpression, FilterOperator, JsonFilter, MapValueType,
+    NumericRange,
+};
+use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry, FilteringOptions};
+
+fn columns() -> ColumnRegistry {
+    let columns: ColumnRegistry = column_registry! {
+        created_at: Date,
+        status: String,
+        age: UInt32,
+        tags: ArrayString,
+        attrs: Map,
+    };
+    columns
+        .with_label("created_at", "Signup date")
+        .with_label("status", "Status")
+        .with_description("status", "Where the account is in onboarding")
+}
+
+fn condition(condition: FilterCondition) -> FilterExpression {
+    FilterExpression::Condition(condition)
+}
+
+#[test]
+fn test_describe_conditions() {
+    let columns = columns();
+    let describe = |c| condition(c).describe(&columns);
+
+    assert_eq!(
+        describe(FilterCondition::date(
+            "created_at",
+            FilterOperator::LessThanOrEqual,
+            Some("2024-01-01")
+        )),
+        "Signup date is on or before 2024-01-01"
+    );
+    assert_eq!(
+        describe(FilterCondition::date_range(
+            "created_at",
+            "2024-01-01",
+            "2024-01-31"
+        )),
+        "Signup date is between 2024-01-01 and 2024-01-31"
+    );
+    // Columns without a label are named as they are
+    assert_eq!(
+        describe(FilterCondition::uint32(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(30)
+        )),
+        "age is greater than 30"
+    );
+    assert_eq!(
+        describe(FilterCondition::numeric_range(
+            "age",
+            NumericRange::new(Some(18), Some(65))
+        )),
+        "age is between 18 and 65"
+    );
+    assert_eq!(
+        describe(FilterCondition::numeric_range(
+            "age",
+            NumericRange::new(Some(18), None).exclusive_min()
+        )),
+        "age is greater than 18"
+    );
+    assert_eq!(
+        describe(FilterCondition::string(
+            "status",
+            FilterOperator::IsNull,
+            None
+        )),
+        "Status is not set"
+    );
+    assert_eq!(
+        describe(FilterCondition::in_values(
+            "status",
+            FilterOperator::NotIn,
+            vec!["closed".to_string(), "spam".to_string()],
+            None
+        )),
+        "Status is not one of closed, spam"
+    );
+    assert_eq!(
+        describe(FilterCondition::array_has("tags", "admin")),
+        "tags includes admin"
+    );
+    assert_eq!(
+        describe(FilterCondition::array_count(
+            "tags",
+            "admin",
+            FilterOperator::GreaterThanOrEqual,
+            2,
+            ArrayElementType::String
+        )),
+        "tags has admin at least 2 times"
+    );
+    assert_eq!(
+        describe(FilterCondition::map_value(
+            "attrs",
+            "plan",
+            FilterOperator::Equal,
+            Some("pro"),
+            MapValueType::String
+        )),
+        "attrs plan is pro"
+    );
+}
+
+#[test]
+fn test_describe_groups() {
+    let columns = columns();
+    let expr = FilterExpression::and(vec![
+        condition(FilterCondition::date(
+            "created_at",
+            FilterOperator::GreaterThan,
+            Some("2024-01-01"),
+        )),
+        FilterExpression::or(vec![
+            condition(FilterCondition::string(
+                "status",
+                FilterOperator::Equal,
+                Some("open"),
+            )),
+            condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::LessThan,
+                Some(18),
+            )),
+        ]),
+    ]);
+
+    assert_eq!(
+        expr.describe(&columns),
+        "Signup date is after 2024-01-01 and (Status is open or age is less than 18)"
+    );
+}
+
+#[test]
+fn test_describe_filters() {
+    let columns = columns();
+    let json_filters = vec![
+        JsonFilter {
+            n: "status".to_string(),
+            f: "STARTS WITH".to_string(),
+            v: "pend".to_string(),
+            c: None,
+        },
+        JsonFilter {
+            n: "age".to_string(),
+            f: ">=".to_string(),
+            v: "21".to_string(),
+            c: Some("AND".to_string()),
+        },
+    ];
+    let filtering = FilteringOptions::from_json_filters(&json_filters, &columns)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        filtering.describe().unwrap(),
+        "Status starts with pend and age is at least 21"
+    );
+
+    let filters = ClickHouseFilters::new(None, vec![], Some(filtering), &columns).unwrap();
+    assert_eq!(
+        filters.describe().unwrap(),
+        "Status starts with pend and age is at least 21"
+    );
+
+    let filters = ClickHouseFilters::new(None, vec![], None, &columns).unwrap();
+    assert_eq!(filters.describe(), None);
+}
@@ -0,0 +1,134 @@
+use clickhouse_filters::execution::ExecutionPolicy;
+use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry};
+use std::time::Duration;
+
+#[test]
+fn test_execution_policy_builders() {
+    let policy = ExecutionPolicy::new();
+    assert_eq!(policy.retries, 0);
+    assert_eq!(policy.per_query_timeout, None);
+
+    let policy = policy
+        .with_retries(4)
+        .with_backoff(Duration::from_millis(100))
+        .with_timeout(Duration::from_secs(2));
+    assert_eq!(policy.retries, 4);
+    assert_eq!(policy.per_query_timeout, Some(Duration::from_secs(2)));
+
+    // The backoff doubles for each retry
+    assert_eq!(policy.backoff_for(0), Duration::from_millis(100));
+    assert_eq!(policy.backoff_for(1), Duration::from_millis(200));
+    assert_eq!(policy.backoff_for(3), Duration::from_millis(800));
+    assert_eq!(
+        ExecutionPolicy::new()
+            .with_backoff(Duration::MAX)
+            .backoff_for(5),
+        Duration::MAX
+    );
+}
+
+#[test]
+fn test_execution_policy_on_filters() {
+    let columns: ColumnRegistry = column_registry! { name: String };
+    let filters = ClickHouseFilters::new(None, vec![], None, columns).unwrap();
+    assert_eq!(filters.execution_policy(), &ExecutionPolicy::default());
+
+    let policy = ExecutionPolicy::new().with_retries(2);
+    let filters = filters.with_execution_policy(policy.clone());
+    assert_eq!(filters.execution_policy(), &policy);
+    assert_eq!(filters.clone().execution_policy(), &policy);
+}
+
+#[cfg(feature = "client")]
+mod run {
+    use clickhouse::error::Error;
+    use clickhouse_filters::execution::ExecutionPolicy;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    fn policy(retries: u32) -> ExecutionPolicy {
+        ExecutionPolicy::new()
+            .with_retries(retries)
+            .with_backoff(Duration::from_millis(1))
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_network_errors() {
+        let attempts = Cell::new(0);
+        let result = policy(3)
+            .run(|| {
+                attempts.set(attempts.get() + 1);
+                let attempt = attempts.get();
+                async move {
+                    match attempt {
+                        1 | 2 => Err(Error::Network("connection reset".into())),
+                        _ => Ok(attempt),
+                    }
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(result, 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_gives_up_after_retries() {
+        let attempts = Cell::new(0);
+        let err = policy(2)
+            .run(|| {
+                attempts.set(attempts.get() + 1);
+                async { Err::<(), _>(Error::Network("connection refused".into())) }
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(attempts.get(), 3);
+        assert_eq!(
+            err.to_string(),
+            "Query failed after 3 attempts: network error: connection refused"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_does_not_retry_server_errors() {
+        let attempts = Cell::new(0);
+        let err = policy(5)
+            .run(|| {
+                attempts.set(attempts.get() + 1);
+                async { Err::<(), _>(Error::BadResponse("Code: 47. Unknown column".into())) }
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(attempts.get(), 1);
+        assert_eq!(err.to_string(), "bad response: Code: 47. Unknown column");
+    }
+
+    #[tokio::test]
+    async fn test_run_times_out_slow_attempts() {
+        let attempts = Cell::new(0);
+        let result = policy(1)
+            .with_timeout(Duration::from_millis(20))
+            .run(|| {
+                attempts.set(attempts.get() + 1);
+                let attempt = attempts.get();
+                async move {
+                    if attempt == 1 {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                    Ok(attempt)
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(result, 2);
+
+        let err = ExecutionPolicy::new()
+            .with_timeout(Duration::from_millis(20))
+            .run(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "timeout expired");
+    }
+}
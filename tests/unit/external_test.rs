@@ -0,0 +1,57 @@
+use clickhouse_filters::external::ExternalTable;
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+
+#[test]
+fn test_in_external_condition() {
+    let condition = FilterCondition::in_external("user_id", FilterOperator::NotIn, "blocked");
+    assert_eq!(condition.to_sql(true).unwrap(), "user_id NOT IN blocked");
+    assert_eq!(condition.kind(), "InExternal");
+    assert_eq!(condition.column(), "user_id");
+
+    // No values to bind, so the parameterized rendering is the same
+    let (sql, params) = FilterExpression::Condition(condition)
+        .to_sql_with_params(false)
+        .unwrap();
+    assert_eq!(sql, "user_id NOT IN blocked");
+    assert!(params.is_empty());
+
+    let condition = FilterCondition::in_external("id", FilterOperator::Equal, "ext_ids");
+    assert!(condition.to_sql(false).is_err());
+    let condition = FilterCondition::in_external("id", FilterOperator::In, "ids; DROP TABLE x");
+    assert_eq!(
+        condition.to_sql(false).unwrap_err().to_string(),
+        "Invalid external table name: ids; DROP TABLE x"
+    );
+}
+
+#[test]
+fn test_external_table() {
+    let table = ExternalTable::new("ext_users", &[("id", "UInt64"), ("name", "String")]).unwrap();
+    assert_eq!(table.name(), "ext_users");
+    assert_eq!(table.structure(), "id UInt64, name String");
+    assert_eq!(
+        table.http_params(),
+        vec![
+            (
+                "ext_users_structure".to_string(),
+                "id UInt64, name String".to_string()
+            ),
+            ("ext_users_format".to_string(), "TabSeparated".to_string()),
+        ]
+    );
+    assert_eq!(
+        ExternalTable::encode_rows([vec!["1", "a\tb"], vec!["2", "line\nbreak\\"]]),
+        "1\ta\\tb\n2\tline\\nbreak\\\\\n"
+    );
+    assert_eq!(
+        table
+            .condition("owner_id", FilterOperator::In)
+            .to_sql(false)
+            .unwrap(),
+        "owner_id IN ext_users"
+    );
+
+    assert!(ExternalTable::new("ext users", &[("id", "UInt64")]).is_err());
+    assert!(ExternalTable::new("ext_ids", &[]).is_err());
+    assert!(ExternalTable::new("ext_ids", &[("id)", "UInt64")]).is_err());
+}
@@ -1,5 +1,9 @@
 use clickhouse_filters::{
-    filtering::{ColumnTypeInfo, FilterCondition, FilterExpression, FilterOperator},
+    filtering::{
+        parse_operator, ColumnTypeInfo, FilterBuilder, FilterCondition, FilterExpression,
+        FilterOperator, InvalidValue, JsonOperator, LogicalOperator, NumericRange, TextIndex,
+    },
+    registry::ColumnRegistry,
     ColumnDef, FilteringOptions,
 };
 use std::collections::HashMap;
@@ -339,3 +343,745 @@ fn test_null_handling_with_multiple_conditions() {
     assert!(sql.contains("AND"));
     assert!(sql.contains("OR"));
 }
+
+#[test]
+fn test_builder_add_condition_stays_flat() {
+    // Repeatedly adding conditions should append to a single AND group
+    let mut builder = FilterBuilder::new();
+    for age in 0..500 {
+        builder = builder.add_condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::NotEqual,
+            Some(age),
+        ));
+    }
+
+    match &builder.root {
+        Some(FilterExpression::Group { expressions, .. }) => {
+            assert_eq!(expressions.len(), 500);
+            assert!(expressions
+                .iter()
+                .all(|expr| matches!(expr, FilterExpression::Condition(_))));
+        }
+        other => panic!("Expected a flat AND group, got {:?}", other),
+    }
+
+    // Verify the SQL output has no nested groups
+    let builder = FilterBuilder::new()
+        .add_condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(25),
+        ))
+        .add_condition(FilterCondition::uint8(
+            "active",
+            FilterOperator::Equal,
+            Some(1),
+        ))
+        .add_condition(FilterCondition::float64(
+            "score",
+            FilterOperator::LessThan,
+            Some(90.0),
+        ));
+    assert_eq!(
+        builder.build().unwrap(),
+        " WHERE (age > 25 AND active = 1 AND score < 90)"
+    );
+}
+
+#[test]
+fn test_builder_add_condition_wraps_or_root() {
+    // An OR root must not be extended; it gets wrapped in a new AND group
+    let builder = FilterBuilder::new()
+        .add_expression(FilterExpression::or(vec![
+            FilterExpression::Condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::LessThan,
+                Some(18),
+            )),
+            FilterExpression::Condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::GreaterThan,
+                Some(65),
+            )),
+        ]))
+        .add_condition(FilterCondition::uint8(
+            "active",
+            FilterOperator::Equal,
+            Some(1),
+        ));
+
+    assert_eq!(
+        builder.build().unwrap(),
+        " WHERE ((age < 18 OR age > 65) AND active = 1)"
+    );
+}
+
+#[test]
+fn test_write_sql_appends_to_existing_buffer() {
+    let expr = FilterExpression::or(vec![
+        FilterExpression::Condition(FilterCondition::string(
+            "name",
+            FilterOperator::In,
+            Some("O'Brien, D'Arcy"),
+        )),
+        FilterExpression::Condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::LessThanOrEqual,
+            Some(40),
+        )),
+    ]);
+
+    // Rendering should append to whatever is already in the buffer
+    let mut sql = String::from("SELECT * FROM users WHERE ");
+    expr.write_sql(&mut sql, false).unwrap();
+    assert_eq!(
+        sql,
+        "SELECT * FROM users WHERE (name IN ('O''Brien', 'D''Arcy') OR age <= 40)"
+    );
+
+    // to_sql should produce the same rendering on its own
+    assert_eq!(
+        expr.to_sql(false).unwrap(),
+        "(name IN ('O''Brien', 'D''Arcy') OR age <= 40)"
+    );
+}
+
+fn age_condition(operator: FilterOperator, age: u32) -> FilterExpression {
+    FilterExpression::Condition(FilterCondition::uint32("age", operator, Some(age)))
+}
+
+#[test]
+fn test_combine_flattens_and_skips_empty() {
+    let base = FilterExpression::and(vec![
+        age_condition(FilterOperator::GreaterThan, 18),
+        age_condition(FilterOperator::LessThan, 65),
+    ]);
+
+    // Same-operator groups are flattened
+    let combined = base.clone().combine(
+        age_condition(FilterOperator::NotEqual, 30),
+        LogicalOperator::And,
+    );
+    assert_eq!(
+        combined.to_sql(false).unwrap(),
+        "(age > 18 AND age < 65 AND age != 30)"
+    );
+
+    // Different operators keep their own group
+    let combined = base.clone().combine(
+        age_condition(FilterOperator::Equal, 99),
+        LogicalOperator::Or,
+    );
+    assert_eq!(
+        combined.to_sql(false).unwrap(),
+        "((age > 18 AND age < 65) OR age = 99)"
+    );
+
+    // Empty sides are dropped without an extra group
+    let combined = FilterExpression::or(vec![]).combine(base.clone(), LogicalOperator::Or);
+    assert_eq!(combined, base);
+}
+
+#[test]
+fn test_filtering_options_merge() {
+    let mut columns = HashMap::new();
+    columns.insert("age", ColumnDef::UInt32("age"));
+
+    let base = FilteringOptions::new(
+        vec![age_condition(FilterOperator::GreaterThan, 18)],
+        columns.clone(),
+    );
+    let request = FilteringOptions::new(
+        vec![FilterExpression::or(vec![
+            age_condition(FilterOperator::Equal, 20),
+            age_condition(FilterOperator::Equal, 30),
+        ])],
+        columns.clone(),
+    );
+
+    let merged = base.clone().merge(request, LogicalOperator::And);
+    assert_eq!(merged.expressions.len(), 2);
+    assert_eq!(
+        merged.to_sql().unwrap(),
+        " WHERE (age > 18 AND (age = 20 OR age = 30))"
+    );
+
+    // Merging with an empty set leaves the other side unchanged
+    let empty = FilteringOptions::new(vec![], columns);
+    let merged = empty.merge(base, LogicalOperator::Or);
+    assert_eq!(merged.to_sql().unwrap(), " WHERE age > 18");
+}
+
+#[test]
+fn test_filtering_options_root_operator() {
+    let mut columns = HashMap::new();
+    columns.insert("age", ColumnDef::UInt32("age"));
+
+    let filtering = FilteringOptions::new(
+        vec![
+            age_condition(FilterOperator::LessThan, 18),
+            age_condition(FilterOperator::GreaterThan, 65),
+        ],
+        columns.clone(),
+    )
+    .root_operator(LogicalOperator::Or);
+    assert_eq!(filtering.to_sql().unwrap(), " WHERE (age < 18 OR age > 65)");
+
+    // The OR list stays flat when more alternatives are merged in
+    let other = FilteringOptions::new(vec![age_condition(FilterOperator::Equal, 30)], columns);
+    let merged = filtering.merge(other, LogicalOperator::Or);
+    assert_eq!(merged.expressions.len(), 3);
+    assert_eq!(
+        merged.to_sql().unwrap(),
+        " WHERE (age < 18 OR age > 65 OR age = 30)"
+    );
+}
+
+#[test]
+fn test_multi_pattern_filters() {
+    let regexes = FilterCondition::string(
+        "path",
+        FilterOperator::MultiMatchAny,
+        Some(r"^/api/v\d+, /health$, ^/api/v\d+"),
+    );
+    assert_eq!(
+        regexes.to_sql(false).unwrap(),
+        r"multiMatchAny(path, ['^/api/v\\d+', '/health$'])"
+    );
+    assert_eq!(
+        regexes.to_sql(true).unwrap(),
+        r"multiMatchAny(path, ['(?i)^/api/v\\d+', '(?i)/health$'])"
+    );
+
+    let substrings = FilterCondition::string(
+        "message",
+        FilterOperator::MultiSearchAny,
+        Some("timeout, it's"),
+    );
+    assert_eq!(
+        substrings.to_sql(false).unwrap(),
+        "multiSearchAny(message, ['timeout', 'it''s'])"
+    );
+    assert_eq!(
+        substrings.to_sql(true).unwrap(),
+        "multiSearchAnyCaseInsensitive(message, ['timeout', 'it''s'])"
+    );
+
+    // JSON filters use the operator names
+    let column = ColumnDef::String("message");
+    let condition = column
+        .to_filter_condition("MULTI SEARCH ANY", "error,fatal")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "multiSearchAny(message, ['error', 'fatal'])"
+    );
+
+    let empty = FilterCondition::string("message", FilterOperator::MultiMatchAny, Some(" , "));
+    assert_eq!(
+        empty.to_sql(false).unwrap_err().to_string(),
+        "multiMatchAny operator requires values"
+    );
+}
+
+#[test]
+fn test_datetime64_sub_second_precision() {
+    let column = ColumnDef::DateTime64("created_at");
+    let condition = column
+        .to_filter_condition(">=", "2024-01-01 10:00:00.123")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "created_at >= toDateTime64('2024-01-01 10:00:00.123', 3)"
+    );
+
+    // Whole-second values render as before
+    let condition = column
+        .to_filter_condition("<", "2024-01-01 10:00:00")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "created_at < '2024-01-01 10:00:00'"
+    );
+
+    let range = FilterCondition::date_range(
+        "created_at",
+        "2024-01-01 10:00:00.5",
+        "2024-01-01 10:00:01.250000",
+    );
+    assert_eq!(
+        range.to_sql(false).unwrap(),
+        "created_at BETWEEN toDateTime64('2024-01-01 10:00:00.5', 1) \
+         AND toDateTime64('2024-01-01 10:00:01.250000', 6)"
+    );
+
+    // Declared precision is validated
+    let precise = ColumnDef::DateTime64Precision("created_at", 3);
+    assert!(precise
+        .to_filter_condition("=", "2024-01-01 10:00:00.123")
+        .is_ok());
+    assert_eq!(
+        precise
+            .to_filter_condition(
+                "DATE_RANGE",
+                "2024-01-01 10:00:00, 2024-01-01 10:00:00.1234"
+            )
+            .unwrap_err()
+            .to_string(),
+        "Value is more precise than DateTime64(3) column created_at: 2024-01-01 10:00:00.1234"
+    );
+    assert_eq!(
+        ColumnDef::DateTime64Precision("created_at", 12)
+            .to_filter_condition("=", "2024-01-01 10:00:00")
+            .unwrap_err()
+            .to_string(),
+        "Invalid DateTime64 precision: 12"
+    );
+}
+
+#[test]
+fn test_build_fragment() {
+    let builder = FilterBuilder::new()
+        .add_condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(25),
+        ))
+        .add_condition(FilterCondition::string(
+            "status",
+            FilterOperator::Equal,
+            Some("open"),
+        ));
+    assert_eq!(
+        builder.build_fragment().unwrap(),
+        "(age > 25 AND status = 'open')"
+    );
+    assert_eq!(
+        builder.build().unwrap(),
+        format!(" WHERE {}", builder.build_fragment().unwrap())
+    );
+
+    let mut sql = "SELECT * FROM a JOIN b ON a.id = b.id AND ".to_string();
+    builder.write_fragment(&mut sql).unwrap();
+    assert!(sql.ends_with("AND (age > 25 AND status = 'open')"));
+
+    assert_eq!(FilterBuilder::new().build_fragment().unwrap(), "");
+    assert_eq!(
+        FilterBuilder::new()
+            .add_expression(FilterExpression::and(vec![]))
+            .build()
+            .unwrap(),
+        ""
+    );
+}
+
+#[test]
+fn test_numeric_range() {
+    let sql = |range| {
+        FilterCondition::numeric_range("price", range)
+            .to_sql(false)
+            .unwrap()
+    };
+
+    assert_eq!(
+        sql(NumericRange::new(Some(10), Some(20))),
+        "price BETWEEN 10 AND 20"
+    );
+    assert_eq!(sql(NumericRange::new(Some(10), Some(10))), "price = 10");
+    assert_eq!(sql(NumericRange::new(Some(10), None)), "price >= 10");
+    assert_eq!(
+        sql(NumericRange::new(None, Some(20)).exclusive_max()),
+        "price < 20"
+    );
+    assert_eq!(
+        sql(NumericRange::new(Some(10), Some(20)).exclusive_min()),
+        "(price > 10 AND price <= 20)"
+    );
+    assert_eq!(
+        FilterCondition::numeric_range("score", NumericRange::new(Some(0.5), Some(1.5)))
+            .to_sql(false)
+            .unwrap(),
+        "score BETWEEN 0.5 AND 1.5"
+    );
+
+    let open = FilterCondition::numeric_range("price", NumericRange::<u32>::new(None, None));
+    assert_eq!(
+        open.to_sql(false).unwrap_err().to_string(),
+        "Numeric range has no bounds: price"
+    );
+
+    // Bounds built directly or deserialized are checked before rendering
+    let injected = FilterCondition::NumericRange {
+        column: "price".to_string(),
+        range: NumericRange::new(Some("1 OR 1 = 1".to_string()), None),
+    };
+    assert_eq!(
+        injected.to_sql(false).unwrap_err().to_string(),
+        "Invalid numeric range bound for price: 1 OR 1 = 1"
+    );
+    let nan = FilterCondition::numeric_range("score", NumericRange::new(None, Some(f64::NAN)));
+    assert!(nan.to_sql(false).is_err());
+
+    // Stays one condition inside an OR group
+    let expr = FilterExpression::or(vec![
+        FilterExpression::Condition(FilterCondition::numeric_range(
+            "price",
+            NumericRange::new(Some(1), Some(5))
+                .exclusive_min()
+                .exclusive_max(),
+        )),
+        FilterExpression::Condition(FilterCondition::boolean(
+            "free",
+            FilterOperator::Equal,
+            Some(true),
+        )),
+    ]);
+    assert_eq!(
+        expr.to_sql(false).unwrap(),
+        "((price > 1 AND price < 5) OR free = 1)"
+    );
+}
+
+#[test]
+fn test_is_empty_or_null() {
+    let name = ColumnDef::String("name");
+    let condition = name.to_filter_condition("IS EMPTY OR NULL", "").unwrap();
+    assert_eq!(
+        condition.to_sql(true).unwrap(),
+        "(name IS NULL OR name = '')"
+    );
+
+    let age = ColumnDef::UInt32("age");
+    let condition = age.to_filter_condition("is empty or null", "").unwrap();
+    assert_eq!(condition.to_sql(false).unwrap(), "(age IS NULL OR age = 0)");
+
+    let score = ColumnDef::Float64("score");
+    let condition = score.to_filter_condition("IS EMPTY OR NULL", "").unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "(score IS NULL OR score = 0)"
+    );
+
+    let created_at = ColumnDef::DateTime("created_at");
+    assert_eq!(
+        created_at
+            .to_filter_condition("IS EMPTY OR NULL", "")
+            .unwrap_err()
+            .to_string(),
+        "Operator IS EMPTY OR NULL is only supported on string and numeric columns"
+    );
+
+    // Stays one condition next to others
+    let expr = FilterExpression::and(vec![
+        FilterExpression::Condition(FilterCondition::string(
+            "name",
+            FilterOperator::IsEmptyOrNull,
+            None,
+        )),
+        FilterExpression::Condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(18),
+        )),
+    ]);
+    assert_eq!(
+        expr.to_sql(false).unwrap(),
+        "((name IS NULL OR name = '') AND age > 18)"
+    );
+}
+
+#[test]
+fn test_float_and_decimal_in_lists() {
+    let score = ColumnDef::Float64("score");
+    let condition = score.to_filter_condition("IN", "0.5, 1.25, 2").unwrap();
+    assert_eq!(
+        condition,
+        FilterCondition::in_values(
+            "score",
+            FilterOperator::In,
+            vec!["0.5".to_string(), "1.25".to_string(), "2".to_string()],
+            Some(ColumnTypeInfo::Numeric),
+        )
+    );
+    assert_eq!(condition.to_sql(true).unwrap(), "score IN (0.5, 1.25, 2)");
+
+    let ratio = ColumnDef::Float32("ratio");
+    let condition = ratio.to_filter_condition("NOT IN", "0.1,0.2").unwrap();
+    assert_eq!(condition.to_sql(false).unwrap(), "ratio NOT IN (0.1, 0.2)");
+
+    // Decimal values are written as given, without a round trip through f64
+    let price = ColumnDef::Decimal("price");
+    let condition = price.to_filter_condition("IN", "19.99, 0.10").unwrap();
+    assert_eq!(condition.to_sql(false).unwrap(), "price IN (19.99, 0.10)");
+
+    for (column, value, error) in [
+        (&score, "0.5, abc", "Invalid value for Float64: abc"),
+        (&ratio, "inf", "Invalid value for Float32: inf"),
+        (
+            &price,
+            "1.5, 2) OR (1",
+            "Invalid value for Decimal: 2) OR (1",
+        ),
+    ] {
+        assert_eq!(
+            column
+                .to_filter_condition("IN", value)
+                .unwrap_err()
+                .to_string(),
+            error
+        );
+    }
+
+    // A single value from the constructor
+    let condition = FilterCondition::float64("score", FilterOperator::In, Some(0.5));
+    assert_eq!(condition.to_sql(false).unwrap(), "score IN (0.5)");
+}
+
+#[test]
+fn test_uuid_values_are_validated() {
+    let id = ColumnDef::UUID("id");
+    let condition = id
+        .to_filter_condition("=", "550E8400-E29B-41D4-A716-446655440000")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "id = '550e8400-e29b-41d4-a716-446655440000'"
+    );
+
+    let condition = id
+        .to_filter_condition(
+            "IN",
+            "550e8400-e29b-41d4-a716-446655440000, 6BA7B810-9DAD-11D1-80B4-00C04FD430C8",
+        )
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "id IN ('550e8400-e29b-41d4-a716-446655440000', '6ba7b810-9dad-11d1-80b4-00c04fd430c8')"
+    );
+
+    for value in [
+        "not-a-uuid",
+        "550e8400e29b41d4a716446655440000",
+        "550e8400-e29b-41d4-a716-44665544000g",
+        "550e8400-e29b-41d4-a716-446655440000, nope",
+    ] {
+        let error = id.to_filter_condition("IN", value).unwrap_err();
+        let invalid = error.downcast_ref::<InvalidValue>().unwrap();
+        assert_eq!(invalid.column, "id");
+        assert_eq!(invalid.type_name, "UUID");
+        assert_eq!(
+            error.to_string(),
+            format!("Invalid value for UUID: {}", invalid.value)
+        );
+    }
+
+    let owners = ColumnDef::ArrayUUID("owners");
+    assert!(owners.to_filter_condition("ARRAY HAS", "nope").is_err());
+    let condition = owners
+        .to_filter_condition("ARRAY HAS", "550E8400-E29B-41D4-A716-446655440000")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "has(owners, toUUID('550e8400-e29b-41d4-a716-446655440000'))"
+    );
+}
+
+#[test]
+fn test_uuid_array_join_in() {
+    let condition = FilterCondition::ArrayJoinIn {
+        column: "id".to_string(),
+        operator: FilterOperator::In,
+        values: vec!["550e8400-e29b-41d4-a716-446655440000".to_string()],
+        column_type: Some(ColumnTypeInfo::UUID),
+    };
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "id IN (SELECT arrayJoin([toUUID('550e8400-e29b-41d4-a716-446655440000')]))"
+    );
+}
+
+#[test]
+fn test_ip_values() {
+    let ip = ColumnDef::IPv4("ip");
+    let condition = ip.to_filter_condition("=", "192.168.1.1").unwrap();
+    assert_eq!(
+        condition,
+        FilterCondition::ipv4("ip", FilterOperator::Equal, Some([192, 168, 1, 1].into()))
+    );
+    assert_eq!(
+        condition.to_sql(true).unwrap(),
+        "ip = toIPv4('192.168.1.1')"
+    );
+
+    let condition = ip.to_filter_condition(">=", "10.0.0.0").unwrap();
+    assert_eq!(condition.to_sql(false).unwrap(), "ip >= toIPv4('10.0.0.0')");
+
+    let condition = ip.to_filter_condition("IN", "10.0.0.1, 10.0.0.2").unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "ip IN (toIPv4('10.0.0.1'), toIPv4('10.0.0.2'))"
+    );
+
+    // IPv4 addresses in an IPv6 column are mapped
+    let ip6 = ColumnDef::IPv6("ip6");
+    let condition = ip6
+        .to_filter_condition("IN", "2001:DB8::1, 10.0.0.1")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "ip6 IN (toIPv6('2001:db8::1'), toIPv6('::ffff:10.0.0.1'))"
+    );
+
+    let (sql, params) =
+        FilterExpression::Condition(ip.to_filter_condition("!=", "127.0.0.1").unwrap())
+            .to_sql_with_params(false)
+            .unwrap();
+    assert_eq!(sql, "ip != {ip_0:IPv4}");
+    assert_eq!(params.get("ip_0"), Some("127.0.0.1"));
+
+    for (column, value, type_name) in [
+        (&ip, "256.0.0.1", "IPv4"),
+        (&ip, "2001:db8::1", "IPv4"),
+        (&ip6, "10.0.0.1, nope", "IPv6"),
+    ] {
+        let error = column.to_filter_condition("IN", value).unwrap_err();
+        let invalid = error.downcast_ref::<InvalidValue>().unwrap();
+        assert_eq!(invalid.type_name, type_name);
+    }
+    assert_eq!(
+        ip.to_filter_condition("LIKE", "10.%")
+            .unwrap_err()
+            .to_string(),
+        "Invalid value for IPv4: 10.%"
+    );
+    assert_eq!(
+        FilterCondition::ipv4("ip", FilterOperator::Like, Some([10, 0, 0, 1].into()))
+            .to_sql(false)
+            .unwrap_err()
+            .to_string(),
+        "Unsupported operator for IP type: LIKE"
+    );
+}
+
+#[test]
+fn test_contains_and_text_indexes() {
+    let contains = FilterCondition::string("title", FilterOperator::Contains, Some("50%_off"));
+    assert_eq!(
+        contains.to_sql(false).unwrap(),
+        r"title LIKE '%50\\%\\_off%'"
+    );
+
+    let mut columns = HashMap::new();
+    columns.insert("title", ColumnDef::String("title"));
+    columns.insert("body", ColumnDef::String("body"));
+    let columns = ColumnRegistry::new(columns)
+        .with_text_index("title", TextIndex::NgramBf)
+        .with_text_index("body", TextIndex::TokenBf);
+
+    let sql = |column: &str, operator: &str, value: &str, case_insensitive: bool| {
+        columns
+            .to_filter_condition(column, operator, value)
+            .unwrap()
+            .to_sql(case_insensitive)
+            .unwrap()
+    };
+    assert_eq!(
+        sql("title", "CONTAINS", "rust", false),
+        "position(title, 'rust') > 0"
+    );
+    assert_eq!(
+        sql("title", "CONTAINS", "rust", true),
+        "positionCaseInsensitive(title, 'rust') > 0"
+    );
+    assert_eq!(
+        sql("title", "STARTS WITH", "How", false),
+        "startsWith(title, 'How')"
+    );
+    assert_eq!(
+        sql("title", "ENDS WITH", "?", false),
+        "endsWith(title, '?')"
+    );
+    // No case-insensitive prefix function, so LIKE it is
+    assert_eq!(
+        sql("title", "STARTS WITH", "How", true),
+        "lower(title) LIKE lower('How%')"
+    );
+
+    assert_eq!(
+        sql("body", "CONTAINS", "timeout", false),
+        "hasToken(body, 'timeout')"
+    );
+    assert_eq!(
+        sql("body", "CONTAINS", "timeout", true),
+        "hasTokenCaseInsensitive(body, 'timeout')"
+    );
+    // Not a single token
+    assert_eq!(
+        sql("body", "CONTAINS", "read timeout", false),
+        "body LIKE '%read timeout%'"
+    );
+}
+
+#[test]
+fn test_prefix_and_suffix_match_literally() {
+    // LIKE metacharacters are escaped, so LIKE matches what startsWith() and
+    // endsWith() match on an indexed column
+    let plain: ColumnRegistry = HashMap::from([("title", ColumnDef::String("title"))]).into();
+    let indexed = plain.clone().with_text_index("title", TextIndex::NgramBf);
+    let sql = |columns: &ColumnRegistry, operator: &str, case_insensitive: bool| {
+        columns
+            .to_filter_condition("title", operator, "50%_off")
+            .unwrap()
+            .to_sql(case_insensitive)
+            .unwrap()
+    };
+
+    assert_eq!(
+        sql(&plain, "STARTS WITH", false),
+        r"title LIKE '50\\%\\_off%'"
+    );
+    assert_eq!(
+        sql(&indexed, "STARTS WITH", false),
+        "startsWith(title, '50%_off')"
+    );
+    assert_eq!(
+        sql(&plain, "ENDS WITH", false),
+        r"title LIKE '%50\\%\\_off'"
+    );
+    assert_eq!(
+        sql(&indexed, "ENDS WITH", false),
+        "endsWith(title, '50%_off')"
+    );
+    assert_eq!(
+        sql(&indexed, "STARTS WITH", true),
+        r"lower(title) LIKE lower('50\\%\\_off%')"
+    );
+}
+
+#[test]
+fn test_json_operator_round_trip() {
+    for operator in JsonOperator::ALL {
+        let name = operator.to_string();
+        assert_eq!(name.parse::<JsonOperator>().unwrap(), operator);
+        assert_eq!(
+            name.to_lowercase().parse::<JsonOperator>().unwrap(),
+            operator
+        );
+
+        let filter_operator = FilterOperator::from(operator);
+        assert_eq!(JsonOperator::try_from(&filter_operator).unwrap(), operator);
+        assert_eq!(parse_operator(&name).unwrap(), filter_operator);
+    }
+    assert_eq!(JsonOperator::NotIn.as_str(), "NOT IN");
+    assert_eq!(
+        "date_range".parse::<JsonOperator>().unwrap(),
+        JsonOperator::DateRange
+    );
+
+    // Unknown operators are errors rather than falling back to `=`
+    assert!("EQUALS".parse::<JsonOperator>().is_err());
+    assert!(parse_operator("").is_err());
+    assert!(parse_operator("NOT_IN").is_err());
+    assert!(JsonOperator::try_from(&FilterOperator::Custom("near")).is_err());
+}
@@ -0,0 +1,164 @@
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::sorting::SortedColumn;
+use clickhouse_filters::{
+    ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions, PaginationOptions,
+};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+fn columns() -> HashMap<&'static str, ColumnDef> {
+    let mut columns = HashMap::new();
+    columns.insert("name", ColumnDef::String("name"));
+    columns.insert("age", ColumnDef::UInt32("age"));
+    columns
+}
+
+// Equal filters must be built against the same registry
+fn registry() -> &'static ColumnRegistry {
+    static REGISTRY: OnceLock<ColumnRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(|| ColumnRegistry::new(columns()))
+}
+
+fn filters(name: &str, age: u32, page: i64) -> ClickHouseFilters {
+    sorted_filters(name, age, page, "asc")
+}
+
+fn sorted_filters(name: &str, age: u32, page: i64, order: &str) -> ClickHouseFilters {
+    let filtering = FilteringOptions::new(
+        vec![
+            FilterExpression::Condition(FilterCondition::string(
+                "name",
+                FilterOperator::Like,
+                Some(name),
+            )),
+            FilterExpression::Condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::GreaterThan,
+                Some(age),
+            )),
+        ],
+        registry(),
+    );
+
+    ClickHouseFilters::new(
+        Some(PaginationOptions::new(page, 10, 10, 1000)),
+        vec![SortedColumn::new("name", order)],
+        Some(filtering),
+        registry(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_fingerprint_is_deterministic() {
+    let a = filters("%John%", 25, 1).fingerprint();
+    let b = filters("%John%", 25, 1).fingerprint();
+    assert_eq!(a, b);
+    assert_eq!(a.to_string(), b.to_string());
+}
+
+#[test]
+fn test_shape_ignores_values() {
+    let a = filters("%John%", 25, 1).fingerprint();
+    let b = filters("%Jane%", 40, 3).fingerprint();
+
+    // Same structure, different literal values and page
+    assert_eq!(a.shape, b.shape);
+    assert_ne!(a.full, b.full);
+}
+
+#[test]
+fn test_shape_changes_with_structure() {
+    let base = filters("%John%", 25, 1);
+
+    // A different operator changes the shape
+    let other = FilterExpression::Condition(FilterCondition::uint32(
+        "age",
+        FilterOperator::LessThan,
+        Some(25),
+    ));
+    let gt = FilterExpression::Condition(FilterCondition::uint32(
+        "age",
+        FilterOperator::GreaterThan,
+        Some(25),
+    ));
+    assert_ne!(other.fingerprint().shape, gt.fingerprint().shape);
+
+    // Different sorting changes the shape
+    let resorted = sorted_filters("%John%", 25, 1, "desc");
+    assert_ne!(base.fingerprint().shape, resorted.fingerprint().shape);
+}
+
+#[test]
+fn test_fingerprint_is_stable() {
+    // Fingerprints are used as persistent cache keys, so they must not drift
+    let expr = FilterExpression::Condition(FilterCondition::uint32(
+        "age",
+        FilterOperator::Equal,
+        Some(30),
+    ));
+    assert_eq!(
+        expr.fingerprint().to_string(),
+        "3f1f26132e1ab8b8-fc07cc1888ab360a"
+    );
+}
+
+#[test]
+// The cached WHERE clause is interior mutability, but not part of Eq or Hash
+#[allow(clippy::mutable_key_type)]
+fn test_eq_and_hash() {
+    let mut cache = HashMap::new();
+    cache.insert(filters("%John%", 25, 1), "page 1");
+    assert_eq!(cache.get(&filters("%John%", 25, 1)), Some(&"page 1"));
+    assert_eq!(cache.get(&filters("%John%", 25, 2)), None);
+    assert_eq!(cache.get(&filters("%John%", 30, 1)), None);
+    assert_ne!(
+        sorted_filters("%John%", 25, 1, "asc"),
+        sorted_filters("%John%", 25, 1, "desc")
+    );
+    assert_eq!(
+        filters("%John%", 25, 1).filters().cloned(),
+        filters("%John%", 25, 1).filters().cloned()
+    );
+
+    // The registry changes the SQL, so filters built against another one differ
+    let john = filters("%John%", 25, 1);
+    let soft_deleting = registry().clone().with_soft_delete("age");
+    assert_ne!(john, john.clone().with_column_defs(soft_deleting).unwrap());
+    assert_ne!(
+        john,
+        john.clone()
+            .with_column_defs(ColumnRegistry::new(columns()))
+            .unwrap()
+    );
+    assert_eq!(john, john.clone().with_column_defs(registry()).unwrap());
+
+    // Zero compares equal to negative zero, so expressions holding them hash alike
+    let zero = |value: f64| {
+        FilterExpression::Condition(FilterCondition::float64(
+            "score",
+            FilterOperator::GreaterThan,
+            Some(value),
+        ))
+    };
+    let mut seen = std::collections::HashSet::new();
+    seen.insert(zero(0.0));
+    assert!(seen.contains(&zero(-0.0)));
+    assert!(!seen.contains(&zero(0.5)));
+}
+
+#[test]
+fn test_non_finite_floats_are_rejected() {
+    // NaN would make a condition unequal to itself and break map lookups
+    for column in [
+        ColumnDef::Float32("score"),
+        ColumnDef::Float64("score"),
+        ColumnDef::Decimal("score"),
+    ] {
+        for value in ["NaN", "nan", "inf", "-infinity"] {
+            assert!(column.to_filter_condition("=", value).is_err());
+        }
+        let condition = column.to_filter_condition(">", "2.5").unwrap();
+        assert_eq!(condition, condition.clone());
+    }
+}
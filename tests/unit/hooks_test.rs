@@ -0,0 +1,107 @@
+use clickhouse_filters::filtering::{
+    FilterBuilder, FilterCondition, FilterExpression, FilterOperator,
+};
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, FilteringOptions};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn columns() -> HashMap<&'static str, ColumnDef> {
+    let mut columns = HashMap::new();
+    columns.insert("name", ColumnDef::String("name"));
+    columns.insert("age", ColumnDef::UInt32("age"));
+    columns
+}
+
+#[test]
+fn test_on_condition_rewrites_conditions() {
+    // Rewrite every `name` condition into a case-sensitive exact match
+    let builder = FilterBuilder::new()
+        .add_condition(FilterCondition::string(
+            "name",
+            FilterOperator::Like,
+            Some("%John%"),
+        ))
+        .add_condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(25),
+        ))
+        .on_condition(|condition| {
+            if condition.column() == "name" {
+                return Ok(FilterExpression::Condition(FilterCondition::string(
+                    "name",
+                    FilterOperator::Equal,
+                    Some("John"),
+                )));
+            }
+            Ok(FilterExpression::Condition(condition))
+        });
+
+    assert_eq!(
+        builder.build().unwrap(),
+        " WHERE (name = 'John' AND age > 25)"
+    );
+
+    // The stored tree is untouched; hooks only apply at build time
+    assert_eq!(
+        builder.root.as_ref().unwrap().to_sql(false).unwrap(),
+        "(name LIKE '%John%' AND age > 25)"
+    );
+}
+
+#[test]
+fn test_on_condition_can_block_patterns() {
+    let builder = FilterBuilder::new()
+        .add_condition(FilterCondition::string(
+            "name",
+            FilterOperator::EndsWith,
+            Some("son"),
+        ))
+        .on_condition(|condition| match condition.operator() {
+            Some(FilterOperator::EndsWith) => Err(eyre::eyre!("Suffix matching is not allowed")),
+            _ => Ok(FilterExpression::Condition(condition)),
+        });
+
+    let err = builder.build().unwrap_err();
+    assert_eq!(err.to_string(), "Suffix matching is not allowed");
+}
+
+#[test]
+fn test_on_build_injects_tenant_predicate() {
+    let seen = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&seen);
+
+    let filtering = FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::LessThan,
+            Some(30),
+        ))],
+        columns(),
+    )
+    .on_condition(move |condition| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Ok(FilterExpression::Condition(condition))
+    })
+    .on_build(|root| {
+        Ok(FilterExpression::and(vec![
+            root,
+            FilterExpression::Condition(FilterCondition::uint32(
+                "tenant_id",
+                FilterOperator::Equal,
+                Some(42),
+            )),
+        ]))
+    });
+
+    let filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns()).unwrap();
+
+    assert_eq!(
+        filters.count_sql("db.users").unwrap(),
+        "SELECT COUNT(*) FROM db.users WHERE (age < 30 AND tenant_id = 42)"
+    );
+
+    // The condition hook saw the user condition but not the injected one
+    assert_eq!(seen.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,214 @@
+use clickhouse_filters::custom::quote_identifier;
+use clickhouse_filters::filtering::{FilterCondition, FilterOperator, JsonFilter, JsonOperator};
+use clickhouse_filters::testing::{
+    adversarial_values, assert_no_breakout, fixture_columns, json_filter_corpus, strip_literals,
+    INJECTION_MARKER,
+};
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions};
+use proptest::prelude::*;
+
+// The fixture plus the types it leaves out
+fn columns() -> ColumnRegistry {
+    let mut columns = fixture_columns().as_map().clone();
+    columns.extend([
+        ("code", ColumnDef::FixedString("code")),
+        ("day", ColumnDef::Date32("day")),
+        ("seen_at", ColumnDef::DateTime64Precision("seen_at", 3)),
+        ("level", ColumnDef::Enum16("level")),
+        ("ip6", ColumnDef::IPv6("ip6")),
+        ("big", ColumnDef::UInt128("big")),
+        ("weights", ColumnDef::ArrayFloat64("weights")),
+        ("roles", ColumnDef::ArrayEnum8("roles")),
+        ("owners", ColumnDef::ArrayUUID("owners")),
+        ("days", ColumnDef::ArrayDate("days")),
+        ("times", ColumnDef::ArrayDateTime("times")),
+        ("ips", ColumnDef::ArrayIPv4("ips")),
+        ("ip6s", ColumnDef::ArrayIPv6("ip6s")),
+    ]);
+    ColumnRegistry::new(columns)
+}
+
+// Render a JSON filter every way the crate can, checking each statement
+fn check(filter: JsonFilter, columns: &ColumnRegistry) {
+    let Ok(Some(mut filtering)) = FilteringOptions::from_json_filters(&[filter], columns) else {
+        return;
+    };
+    for case_insensitive in [true, false] {
+        filtering.case_insensitive = case_insensitive;
+        let filters =
+            ClickHouseFilters::new(None, vec![], Some(filtering.clone()), columns).unwrap();
+        if let Ok(sql) = filters.sql() {
+            assert_no_breakout(&sql);
+        }
+        if let Ok((sql, _params)) = filters.sql_with_params() {
+            assert_no_breakout(&sql);
+        }
+    }
+}
+
+#[test]
+fn test_adversarial_values_stay_in_literals() {
+    let columns = columns();
+    let values = adversarial_values(150, 2174);
+
+    for sample in json_filter_corpus(&columns) {
+        for value in &values {
+            // Replacing the value, and appending to it so paths and lists still parse
+            for v in [value.clone(), format!("{}{}", sample.v, value)] {
+                check(
+                    JsonFilter {
+                        n: sample.n.clone(),
+                        f: sample.f.clone(),
+                        v,
+                        c: None,
+                    },
+                    &columns,
+                );
+            }
+        }
+    }
+
+    // Map keys come from the filter's column name
+    for value in &values {
+        check(
+            JsonFilter {
+                n: format!("attrs['{}']", value),
+                f: "=".to_string(),
+                v: value.clone(),
+                c: None,
+            },
+            &columns,
+        );
+    }
+}
+
+// Adversarial text that always carries the marker
+fn adversarial() -> impl Strategy<Value = String> {
+    prop_oneof![
+        prop::sample::select(adversarial_values(64, 2174)),
+        ("\\PC{0,8}", "\\PC{0,8}")
+            .prop_map(|(before, after)| format!("{}{}{}", before, INJECTION_MARKER, after)),
+    ]
+}
+
+// Column names: registered ones, map keys, or registered ones with noise attached
+fn column_name() -> impl Strategy<Value = String> {
+    let names: Vec<String> = columns()
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    prop_oneof![
+        3 => prop::sample::select(names.clone()),
+        3 => adversarial().prop_map(|key| format!("attrs['{}']", key)),
+        1 => (prop::sample::select(names.clone()), adversarial())
+            .prop_map(|(name, noise)| format!("{}{}", name, noise)),
+        1 => (prop::sample::select(names), adversarial())
+            .prop_map(|(name, noise)| format!("{}.{}", name, noise)),
+        1 => adversarial(),
+    ]
+}
+
+// Operators: known ones in any case and padding, or with noise attached
+fn operator() -> impl Strategy<Value = String> {
+    let operators: Vec<&str> = JsonOperator::ALL.iter().map(|op| op.as_str()).collect();
+    prop_oneof![
+        4 => (prop::sample::select(operators.clone()), any::<bool>(), "[ \t]{0,2}").prop_map(
+            |(op, lower, padding)| {
+                let op = if lower { op.to_lowercase() } else { op.to_string() };
+                format!("{0}{1}{0}", padding, op)
+            }
+        ),
+        1 => (prop::sample::select(operators), adversarial())
+            .prop_map(|(op, noise)| format!("{}{}", op, noise)),
+        1 => adversarial(),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn test_fuzzed_names_and_operators_stay_in_literals(
+        n in column_name(),
+        f in operator(),
+        v in adversarial(),
+    ) {
+        check(JsonFilter { n, f, v, c: None }, &columns());
+    }
+}
+
+#[test]
+fn test_adversarial_values_are_deterministic() {
+    let values = adversarial_values(100, 7);
+    assert_eq!(values.len(), 100);
+    assert_eq!(values, adversarial_values(100, 7));
+    assert_ne!(values, adversarial_values(100, 8));
+    assert_eq!(values[0], format!("'{0}'", INJECTION_MARKER));
+    assert!(values.iter().all(|value| value.contains(INJECTION_MARKER)));
+}
+
+#[test]
+fn test_strip_literals() {
+    assert_eq!(
+        strip_literals(r"name = 'it''s' AND path = 'C:\\tmp\'' AND `my col` = 1").unwrap(),
+        r"name = '' AND path = '' AND `my col` = 1"
+    );
+    assert!(strip_literals("name = 'open").is_err());
+    assert!(strip_literals(r"name = 'open\'").is_err());
+}
+
+#[test]
+fn test_backslashes_are_escaped() {
+    let condition = FilterCondition::string("name", FilterOperator::Equal, Some(r"\' OR 1=1 --"));
+    assert_eq!(condition.to_sql(false).unwrap(), r"name = '\\'' OR 1=1 --'");
+
+    // LIKE escapes written by the caller reach the pattern unchanged
+    let condition = FilterCondition::string("name", FilterOperator::Like, Some(r"100\%"));
+    assert_eq!(condition.to_sql(false).unwrap(), r"name LIKE '100\\%'");
+}
+
+#[test]
+fn test_relative_dates_are_validated() {
+    let columns = columns();
+    for expr in [
+        "now() - INTERVAL 7 DAY",
+        "today() - 1",
+        "yesterday()",
+        "NOW()-interval 2 hour + INTERVAL 30 minute",
+    ] {
+        let condition = columns
+            .to_filter_condition("created_at", "RELATIVE", expr)
+            .unwrap();
+        assert_eq!(
+            condition.to_sql(false).unwrap(),
+            format!("created_at > {}", expr)
+        );
+    }
+
+    for expr in [
+        "now() OR 1 = 1",
+        "now() - 1; DROP TABLE users",
+        "now() - INTERVAL 7 DAYS",
+        "toDate('2024-01-01')",
+        "now(",
+        "",
+    ] {
+        assert_eq!(
+            columns
+                .to_filter_condition("created_at", "RELATIVE", expr)
+                .unwrap_err()
+                .to_string(),
+            format!("Invalid relative date expression: {}", expr)
+        );
+    }
+}
+
+#[test]
+fn test_quote_identifier() {
+    assert_eq!(quote_identifier("created_at"), "created_at");
+    assert_eq!(quote_identifier("_id2"), "_id2");
+    assert_eq!(quote_identifier("order id"), "`order id`");
+    assert_eq!(quote_identifier("2fa"), "`2fa`");
+    assert_eq!(quote_identifier(r"a`b\c"), r"`a\`b\\c`");
+}
@@ -1,4 +1,7 @@
-use clickhouse_filters::{filtering::JsonFilter, ColumnDef, FilteringOptions};
+use clickhouse_filters::filtering::{
+    FilterCondition, FilterExpression, FilterOperator, InvalidValue, JsonFilter, JsonNumberType,
+};
+use clickhouse_filters::{ColumnDef, ColumnRegistry, FilteringOptions};
 use std::collections::HashMap;
 
 #[test]
@@ -299,3 +302,293 @@ fn test_complex_json_filters() {
     assert!(sql.contains("90"));
     assert!(sql.contains("active") || sql.contains("1"));
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_json_filter_deserialize() {
+    // Set up column definitions
+    let mut columns = HashMap::new();
+    columns.insert("age", ColumnDef::UInt32("age"));
+
+    // Deserialize a JSON filter payload as sent by an API client
+    let payload = r#"[{"n": "age", "f": ">=", "v": "18", "c": null}]"#;
+    let json_filters: Vec<JsonFilter> = serde_json::from_str(payload).unwrap();
+
+    // Create filtering options from JSON
+    let filtering = FilteringOptions::from_json_filters(&json_filters, columns).unwrap();
+
+    // Verify the SQL output
+    assert_eq!(filtering.unwrap().to_sql().unwrap(), " WHERE age >= 18");
+}
+
+#[test]
+fn test_json_array_has() {
+    let condition = ColumnDef::JSON("payload")
+        .to_filter_condition("ARRAY HAS", "tags.rust")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "has(JSONExtract(payload, 'tags', 'Array(String)'), 'rust')"
+    );
+
+    // Without a path there is no array to look in
+    let condition = FilterCondition::json("payload", FilterOperator::ArrayHas, Some("rust"), None);
+    assert!(condition.to_sql(false).is_err());
+}
+
+#[test]
+fn test_json_array_of_documents() {
+    let column = ColumnDef::ArrayString("events");
+    let condition = column
+        .to_filter_condition("ARRAY JSON HAS", "type.click")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "arrayExists(x -> JSONExtractString(x, 'type') = 'click', events)"
+    );
+    assert_eq!(
+        condition.to_sql(true).unwrap(),
+        "arrayExists(x -> lower(JSONExtractString(x, 'type')) = lower('click'), events)"
+    );
+    assert_eq!(
+        condition,
+        FilterCondition::array_json("events", "type", "click")
+    );
+
+    // Everything after the first dot is the value
+    let condition = column
+        .to_filter_condition("ARRAY JSON HAS", "url.example.com/o'brien")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "arrayExists(x -> JSONExtractString(x, 'url') = 'example.com/o''brien', events)"
+    );
+
+    assert!(column
+        .to_filter_condition("ARRAY JSON HAS", "click")
+        .is_err());
+    assert!(ColumnDef::ArrayUInt32("ids")
+        .to_filter_condition("ARRAY JSON HAS", "type.click")
+        .is_err());
+}
+
+#[test]
+fn test_json_numeric_comparisons() {
+    let column = ColumnDef::JSON("metadata");
+
+    // Ordering comparisons on numeric values are inferred from the value
+    let condition = column.to_filter_condition(">=", "score.0.8").unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "JSONExtractFloat(metadata, 'score') >= 0.8"
+    );
+    let condition = column.to_filter_condition("<", "retries.3").unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "JSONExtractInt(metadata, 'retries') < 3"
+    );
+
+    // Equality keeps comparing the extracted string
+    let condition = column.to_filter_condition("=", "retries.3").unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "JSONExtractString(metadata, 'retries') = '3'"
+    );
+
+    // A type hint forces numeric comparison
+    let condition = column
+        .to_json_numeric_condition("retries", "=", "3", Some(JsonNumberType::Float))
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "JSONExtractFloat(metadata, 'retries') = 3"
+    );
+
+    let condition = FilterCondition::json_numeric(
+        "metadata",
+        "retries",
+        FilterOperator::GreaterThan,
+        "1.5",
+        JsonNumberType::Int,
+    );
+    assert!(condition.to_sql(false).is_err());
+    assert!(column
+        .to_json_numeric_condition("score", ">", "high", None)
+        .is_err());
+}
+
+#[test]
+fn test_json_numeric_declared_path_type() {
+    let columns = ColumnRegistry::from_iter([("metadata", ColumnDef::JSON("metadata"))])
+        .with_json_path_type("metadata", "version", JsonNumberType::Int);
+
+    let json_filters = vec![JsonFilter {
+        n: "metadata".to_string(),
+        f: "=".to_string(),
+        v: "version.2".to_string(),
+        c: None,
+    }];
+
+    let filtering = FilteringOptions::from_json_filters(&json_filters, columns)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE JSONExtractInt(metadata, 'version') = 2"
+    );
+}
+
+#[test]
+fn test_json_exists_null_and_empty() {
+    let column = ColumnDef::JSON("payload");
+
+    let exists = column.to_filter_condition("EXISTS", "email").unwrap();
+    assert_eq!(exists.to_sql(false).unwrap(), "JSONHas(payload, 'email')");
+
+    let null = column.to_filter_condition("IS NULL", "email").unwrap();
+    assert_eq!(
+        null.to_sql(false).unwrap(),
+        "(JSONHas(payload, 'email') AND JSONType(payload, 'email') = 'Null')"
+    );
+
+    let not_null = column.to_filter_condition("IS NOT NULL", "email").unwrap();
+    assert_eq!(
+        not_null.to_sql(false).unwrap(),
+        "JSONType(payload, 'email') != 'Null'"
+    );
+
+    let empty = column.to_filter_condition("IS EMPTY", "tags").unwrap();
+    assert_eq!(
+        empty.to_sql(false).unwrap(),
+        "((JSONType(payload, 'tags') = 'String' AND JSONExtractString(payload, 'tags') = '') \
+         OR (JSONType(payload, 'tags') = 'Array' AND JSONLength(payload, 'tags') = 0))"
+    );
+
+    // Path checks need a path and a JSON column
+    assert!(column
+        .to_filter_condition("EXISTS", "")
+        .unwrap()
+        .to_sql(false)
+        .is_err());
+    assert!(ColumnDef::String("name")
+        .to_filter_condition("EXISTS", "x")
+        .is_err());
+}
+
+#[test]
+fn test_from_equality_map() {
+    let mut columns = HashMap::new();
+    columns.insert("name", ColumnDef::String("name"));
+    columns.insert("age", ColumnDef::UInt32("age"));
+    columns.insert("active", ColumnDef::Boolean("active"));
+
+    let values = HashMap::from([("name", "O'Brien"), ("age", "42"), ("active", "true")]);
+    let filtering = FilteringOptions::from_equality_map(values, columns.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE (active = 1 AND age = 42 AND lower(name) = lower('O''Brien'))"
+    );
+
+    // Values are type-checked and columns must exist
+    let invalid = HashMap::from([("age", "forty")]);
+    let Err(err) = FilteringOptions::from_equality_map(invalid, columns.clone()) else {
+        panic!("expected an error");
+    };
+    assert_eq!(err.to_string(), "Invalid value for UInt32: forty");
+    let unknown = HashMap::from([("email", "a@b.c")]);
+    assert!(FilteringOptions::from_equality_map(unknown, columns.clone()).is_err());
+
+    assert!(FilteringOptions::from_equality_map(HashMap::new(), columns)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_json_bucket_equal() {
+    let mut columns = HashMap::new();
+    columns.insert("created_at", ColumnDef::DateTime("created_at"));
+    columns.insert("day", ColumnDef::Date("day"));
+    let columns = ColumnRegistry::new(columns);
+
+    let condition = columns
+        .to_filter_condition("created_at", "BUCKET_EQUAL", "5 minute|2024-01-01 10:05:00")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "toStartOfInterval(created_at, INTERVAL 5 MINUTE) = toDateTime('2024-01-01 10:05:00')"
+    );
+    assert_eq!(condition.kind(), "DateRange::Bucket");
+
+    let condition = columns
+        .to_filter_condition("day", "bucket_equal", "1 WEEK | 2024-01-01")
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "toStartOfInterval(day, INTERVAL 1 WEEK) = toDateTime('2024-01-01')"
+    );
+
+    let (sql, params) = FilterExpression::Condition(condition)
+        .to_sql_with_params(false)
+        .unwrap();
+    assert_eq!(
+        sql,
+        "toStartOfInterval(day, INTERVAL 1 WEEK) = {day_0:String}"
+    );
+    assert_eq!(params.get("day_0"), Some("2024-01-01"));
+
+    for value in [
+        "5 minutes|2024-01-01 10:05:00",
+        "0 minute|2024-01-01 10:05:00",
+        "-5 minute|2024-01-01 10:05:00",
+        "5 minute) OR (1|2024-01-01",
+        "5 minute",
+        "5 minute|",
+    ] {
+        assert_eq!(
+            columns
+                .to_filter_condition("created_at", "BUCKET_EQUAL", value)
+                .unwrap_err()
+                .to_string(),
+            format!("Invalid BUCKET_EQUAL value: {}", value)
+        );
+    }
+}
+
+#[test]
+fn test_json_boolean_values() {
+    let mut columns = HashMap::new();
+    columns.insert("active", ColumnDef::Boolean("active"));
+    let columns = ColumnRegistry::new(columns);
+
+    let sql = |operator: &str, value: &str| {
+        columns
+            .to_filter_condition("active", operator, value)
+            .unwrap()
+            .to_sql(false)
+            .unwrap()
+    };
+    assert_eq!(sql("=", "on"), "active = 1");
+    assert_eq!(sql("=", "OFF"), "active = 0");
+    assert_eq!(sql("!=", " 1.0 "), "active != 1");
+    assert_eq!(sql("IN", "0,1"), "active IN (0, 1)");
+    assert_eq!(sql("NOT IN", "yes, true"), "active NOT IN (1)");
+
+    let (sql, params) = FilterExpression::Condition(
+        columns
+            .to_filter_condition("active", "IN", "true,false")
+            .unwrap(),
+    )
+    .to_sql_with_params(false)
+    .unwrap();
+    assert_eq!(sql, "active IN ({active_0:Bool}, {active_1:Bool})");
+    assert_eq!(params.get("active_0"), Some("1"));
+
+    for value in ["2", "maybe", "0,2"] {
+        let error = columns
+            .to_filter_condition("active", "IN", value)
+            .unwrap_err();
+        assert!(error.downcast_ref::<InvalidValue>().is_some());
+    }
+}
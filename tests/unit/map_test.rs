@@ -0,0 +1,105 @@
+use clickhouse_filters::filtering::{FilterCondition, FilterOperator, JsonFilter, MapValueType};
+use clickhouse_filters::{ColumnDef, FilteringOptions};
+use std::collections::HashMap;
+
+#[test]
+fn test_map_value_typed_comparisons() {
+    let column = ColumnDef::Map("attrs");
+
+    let condition = column
+        .to_map_condition("retries", ">", "3", Some(MapValueType::Int64))
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "toInt64OrNull(attrs['retries']) > 3"
+    );
+
+    let condition = column
+        .to_map_condition("score", "<=", "0.5", Some(MapValueType::Float64))
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "toFloat64OrNull(attrs['score']) <= 0.5"
+    );
+
+    let condition = column
+        .to_map_condition("env", "=", "Prod", Some(MapValueType::String))
+        .unwrap();
+    assert_eq!(condition.to_sql(false).unwrap(), "attrs['env'] = 'Prod'");
+    assert_eq!(
+        condition.to_sql(true).unwrap(),
+        "lower(attrs['env']) = lower('Prod')"
+    );
+
+    // Without a type, ordering comparisons on numbers compare numerically
+    let condition = column.to_map_condition("retries", ">=", "2", None).unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "toInt64OrNull(attrs['retries']) >= 2"
+    );
+    let condition = column.to_map_condition("code", "=", "007", None).unwrap();
+    assert_eq!(condition.to_sql(false).unwrap(), "attrs['code'] = '007'");
+}
+
+#[test]
+fn test_map_value_null_checks_and_errors() {
+    let column = ColumnDef::Map("attrs");
+
+    let condition = column.to_map_condition("env", "IS NULL", "", None).unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "NOT mapContains(attrs, 'env')"
+    );
+
+    let condition = column
+        .to_map_condition("retries", "IS NOT NULL", "", Some(MapValueType::Int64))
+        .unwrap();
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "toInt64OrNull(attrs['retries']) IS NOT NULL"
+    );
+
+    let condition = FilterCondition::map_value(
+        "attrs",
+        "retries",
+        FilterOperator::GreaterThan,
+        Some("many"),
+        MapValueType::Int64,
+    );
+    assert!(condition.to_sql(false).is_err());
+
+    let condition = FilterCondition::map_value(
+        "attrs",
+        "retries",
+        FilterOperator::Like,
+        Some("1%"),
+        MapValueType::Int64,
+    );
+    assert!(condition.to_sql(false).is_err());
+
+    assert!(column.to_filter_condition("=", "x").is_err());
+    assert!(ColumnDef::String("name")
+        .to_map_condition("k", "=", "v", None)
+        .is_err());
+}
+
+#[test]
+fn test_map_value_from_json_filters() {
+    let mut columns = HashMap::new();
+    columns.insert("attrs", ColumnDef::Map("attrs"));
+
+    let json_filters = vec![JsonFilter {
+        n: "attrs['retries']".to_string(),
+        f: ">".to_string(),
+        v: "3".to_string(),
+        c: None,
+    }];
+
+    let filtering = FilteringOptions::from_json_filters(&json_filters, columns)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE toInt64OrNull(attrs['retries']) > 3"
+    );
+}
@@ -1,6 +1,40 @@
 //! Unit tests for clickhouse-filters
+pub mod array_test;
+pub mod ast_test;
+pub mod canonical_test;
 pub mod combined_test;
+pub mod cost_test;
+pub mod count_test;
+pub mod custom_test;
+pub mod describe_test;
+pub mod execution_test;
+pub mod external_test;
 pub mod filtering_test;
+pub mod fingerprint_test;
+pub mod hooks_test;
+pub mod injection_test;
 pub mod json_filter_test;
+pub mod map_test;
+pub mod mutation_test;
+pub mod normalize_test;
+pub mod optimize_test;
 pub mod pagination_test;
+pub mod params_test;
+pub mod policy_test;
+pub mod prepared_test;
+pub mod projection_test;
+pub mod registry_test;
+pub mod relative_test;
+pub mod request_test;
+pub mod result_test;
+pub mod schema_test;
+pub mod settings_test;
+pub mod soft_delete_test;
 pub mod sorting_test;
+pub mod statements_test;
+pub mod stats_test;
+pub mod table_engine_test;
+pub mod table_test;
+pub mod testing_test;
+pub mod typed_test;
+pub mod visit_test;
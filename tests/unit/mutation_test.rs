@@ -0,0 +1,184 @@
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::mutation::Assignment;
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions};
+
+fn columns() -> ColumnRegistry {
+    ColumnRegistry::from_iter([
+        ("email", ColumnDef::String("email")),
+        ("deleted_at", ColumnDef::DateTime("deleted_at")),
+    ])
+    .with_soft_delete("deleted_at")
+}
+
+fn email_filter(columns: &ColumnRegistry) -> FilteringOptions {
+    FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::string(
+            "email",
+            FilterOperator::Equal,
+            Some("o'brien@example.com"),
+        ))],
+        columns,
+    )
+}
+
+#[test]
+fn test_delete_sql() {
+    let columns = columns();
+    let filters = ClickHouseFilters::new(None, vec![], Some(email_filter(&columns)), &columns)
+        .unwrap()
+        .allow_mutations();
+
+    // Soft-deleted rows are erased too
+    assert_eq!(
+        filters.delete_sql("db", "users").unwrap(),
+        "ALTER TABLE db.users DELETE WHERE email = 'o''brien@example.com'"
+    );
+    assert_eq!(
+        filters.lightweight_delete_sql("db", "users").unwrap(),
+        "DELETE FROM db.users WHERE email = 'o''brien@example.com'"
+    );
+}
+
+#[test]
+fn test_delete_sql_guards() {
+    let columns = columns();
+
+    // Mutations must be confirmed
+    let filters =
+        ClickHouseFilters::new(None, vec![], Some(email_filter(&columns)), &columns).unwrap();
+    assert!(filters.delete_sql("db", "users").is_err());
+    assert!(filters.lightweight_delete_sql("db", "users").is_err());
+
+    // A mutation without a filter would touch every row
+    let filters = ClickHouseFilters::new(None, vec![], None, &columns)
+        .unwrap()
+        .allow_mutations();
+    assert!(filters.delete_sql("db", "users").is_err());
+
+    let empty = FilteringOptions::case_sensitive(vec![], &columns);
+    let filters = ClickHouseFilters::new(None, vec![], Some(empty), &columns)
+        .unwrap()
+        .allow_mutations();
+    assert!(filters.delete_sql("db", "users").is_err());
+}
+
+#[test]
+fn test_mutation_table_names_are_validated() {
+    let columns = columns();
+    let filters = ClickHouseFilters::new(None, vec![], Some(email_filter(&columns)), &columns)
+        .unwrap()
+        .allow_mutations();
+    let assignments = [Assignment::new("email", "new@example.com")];
+
+    for (schema, table) in [
+        ("db", "users DELETE WHERE 1 --"),
+        ("db; DROP TABLE users", "users"),
+        ("db", "users`"),
+        ("", "users"),
+    ] {
+        assert!(filters.delete_sql(schema, table).is_err());
+        assert!(filters.lightweight_delete_sql(schema, table).is_err());
+        assert!(filters.update_sql(schema, table, &assignments).is_err());
+    }
+}
+
+#[test]
+fn test_update_sql() {
+    let columns = ColumnRegistry::from_iter([
+        ("email", ColumnDef::String("email")),
+        ("age", ColumnDef::UInt32("age")),
+        ("verified", ColumnDef::Boolean("verified")),
+        ("tags", ColumnDef::ArrayString("tags")),
+        ("deleted_at", ColumnDef::DateTime("deleted_at")),
+    ]);
+    let filters = ClickHouseFilters::new(None, vec![], Some(email_filter(&columns)), &columns)
+        .unwrap()
+        .allow_mutations();
+
+    assert_eq!(
+        filters
+            .update_sql(
+                "db",
+                "users",
+                &[
+                    Assignment::new("email", "o'neil@example.com"),
+                    Assignment::new("age", "41"),
+                    Assignment::new("verified", "true"),
+                    Assignment::new("tags", "vip, beta"),
+                    Assignment::null("deleted_at"),
+                ],
+            )
+            .unwrap(),
+        "ALTER TABLE db.users UPDATE email = 'o''neil@example.com', age = 41, verified = true, \
+         tags = ['vip', 'beta'], deleted_at = NULL WHERE email = 'o''brien@example.com'"
+    );
+
+    let invalid = [
+        vec![],
+        vec![Assignment::new("age", "41; DROP TABLE users")],
+        vec![Assignment::new("verified", "maybe")],
+        vec![Assignment::new("nickname", "bob")],
+    ];
+    for assignments in invalid {
+        assert!(filters.update_sql("db", "users", &assignments).is_err());
+    }
+}
+
+#[test]
+fn test_update_sql_parses_numbers_by_column_type() {
+    let columns = ColumnRegistry::from_iter([
+        ("email", ColumnDef::String("email")),
+        ("age", ColumnDef::UInt32("age")),
+        ("level", ColumnDef::Int8("level")),
+        ("score", ColumnDef::Float64("score")),
+        ("balance", ColumnDef::Decimal("balance")),
+        ("big", ColumnDef::UInt256("big")),
+        ("delta", ColumnDef::Int128("delta")),
+    ]);
+    let filters = ClickHouseFilters::new(None, vec![], Some(email_filter(&columns)), &columns)
+        .unwrap()
+        .allow_mutations();
+
+    assert_eq!(
+        filters
+            .update_sql(
+                "db",
+                "users",
+                &[
+                    Assignment::new("age", "41"),
+                    Assignment::new("level", "-3"),
+                    Assignment::new("score", "1.5"),
+                    Assignment::new("balance", "10.25"),
+                    Assignment::new("big", "115792089237316195423570985008687907853269984665640"),
+                    Assignment::new("delta", "-170141183460469231731687303715884105728"),
+                ],
+            )
+            .unwrap(),
+        "ALTER TABLE db.users UPDATE age = 41, level = -3, score = 1.5, balance = 10.25, \
+         big = 115792089237316195423570985008687907853269984665640, \
+         delta = -170141183460469231731687303715884105728 \
+         WHERE email = 'o''brien@example.com'"
+    );
+
+    for (column, value) in [
+        ("age", "-1"),
+        ("age", "1.5"),
+        ("age", "4294967296"),
+        ("level", "200"),
+        ("score", "NaN"),
+        ("score", "inf"),
+        ("score", "infinity"),
+        ("balance", "-inf"),
+        ("big", "-1"),
+        ("big", "1e5"),
+        ("delta", "1.5"),
+    ] {
+        let assignments = [Assignment::new(column, value)];
+        assert!(
+            filters.update_sql("db", "users", &assignments).is_err(),
+            "{} = {}",
+            column,
+            value
+        );
+    }
+}
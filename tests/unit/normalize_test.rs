@@ -0,0 +1,104 @@
+use clickhouse_filters::filtering::{FilterCondition, FilterOperator, JsonFilter};
+use clickhouse_filters::normalize::ValueNormalization;
+use clickhouse_filters::{ColumnDef, FilteringOptions};
+use std::collections::HashMap;
+
+#[test]
+fn test_normalize_value() {
+    let value = " \tMary \n  Ann  ";
+    assert_eq!(ValueNormalization::new().normalize(value), value);
+    assert_eq!(
+        ValueNormalization::new().trim().normalize(value),
+        "Mary \n  Ann"
+    );
+    assert_eq!(
+        ValueNormalization::new()
+            .collapse_whitespace()
+            .normalize(value),
+        " Mary Ann "
+    );
+    assert_eq!(
+        ValueNormalization::new()
+            .trim()
+            .collapse_whitespace()
+            .normalize(value),
+        "Mary Ann"
+    );
+
+    // The custom function runs before trimming
+    let normalization = ValueNormalization::new()
+        .with(|v| v.replace('\u{a0}', " "))
+        .trim();
+    assert_eq!(normalization.normalize("Ann\u{a0}"), "Ann");
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn test_normalize_nfc() {
+    // "é" as "e" followed by a combining acute accent
+    let decomposed = "Rene\u{301} ";
+    let normalization = ValueNormalization::new().nfc().trim();
+    assert_eq!(normalization.normalize(decomposed), "Ren\u{e9}");
+
+    let condition = normalization.apply(FilterCondition::string(
+        "name",
+        FilterOperator::Equal,
+        Some(decomposed),
+    ));
+    assert_eq!(condition.to_sql(false).unwrap(), "name = 'Ren\u{e9}'");
+    assert_eq!(decomposed.chars().count(), 6);
+}
+
+#[test]
+fn test_normalize_conditions() {
+    let normalization = ValueNormalization::new().trim();
+
+    let condition = normalization.apply(FilterCondition::in_values(
+        "status",
+        FilterOperator::In,
+        vec![" open".to_string(), "closed ".to_string()],
+        None,
+    ));
+    assert_eq!(
+        condition.to_sql(false).unwrap(),
+        "status IN ('open', 'closed')"
+    );
+
+    let condition = normalization.apply(FilterCondition::uint32(
+        "age",
+        FilterOperator::Equal,
+        Some(30),
+    ));
+    assert_eq!(condition.to_sql(false).unwrap(), "age = 30");
+}
+
+#[test]
+fn test_filtering_options_normalize_values() {
+    let mut columns = HashMap::new();
+    columns.insert("name", ColumnDef::String("name"));
+    columns.insert("tags", ColumnDef::ArrayString("tags"));
+
+    let filters = [
+        JsonFilter {
+            n: "name".to_string(),
+            f: "=".to_string(),
+            v: "John  ".to_string(),
+            c: None,
+        },
+        JsonFilter {
+            n: "tags".to_string(),
+            f: "ARRAY HAS".to_string(),
+            v: " admin".to_string(),
+            c: None,
+        },
+    ];
+    let filtering = FilteringOptions::from_json_filters(&filters, columns)
+        .unwrap()
+        .unwrap()
+        .normalize_values(ValueNormalization::new().trim());
+
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE (lower(name) = lower('John') AND has(tags, 'admin'))"
+    );
+}
@@ -0,0 +1,184 @@
+use clickhouse_filters::filtering::{
+    ColumnTypeInfo, FilterCondition, FilterExpression, FilterOperator,
+};
+use clickhouse_filters::optimize::{InListStrategy, SelectivityHints};
+
+fn age(operator: FilterOperator, value: u32) -> FilterExpression {
+    FilterExpression::Condition(FilterCondition::uint32("age", operator, Some(value)))
+}
+
+fn name(value: &str) -> FilterExpression {
+    FilterExpression::Condition(FilterCondition::string(
+        "name",
+        FilterOperator::Equal,
+        Some(value),
+    ))
+}
+
+#[test]
+fn test_optimize_flattens_and_dedupes() {
+    let expr = FilterExpression::and(vec![
+        age(FilterOperator::GreaterThan, 18),
+        FilterExpression::and(vec![
+            age(FilterOperator::LessThan, 65),
+            FilterExpression::or(vec![]),
+            age(FilterOperator::GreaterThan, 18),
+        ]),
+        FilterExpression::or(vec![name("John")]),
+    ]);
+
+    assert_eq!(
+        expr.optimize().to_sql(false).unwrap(),
+        "(age > 18 AND age < 65 AND name = 'John')"
+    );
+}
+
+#[test]
+fn test_optimize_merges_equalities_into_in() {
+    let expr = FilterExpression::or(vec![
+        age(FilterOperator::Equal, 20),
+        name("John"),
+        age(FilterOperator::Equal, 30),
+        age(FilterOperator::GreaterThan, 90),
+        FilterExpression::or(vec![name("Jane"), age(FilterOperator::Equal, 20)]),
+    ]);
+
+    assert_eq!(
+        expr.optimize().to_sql(false).unwrap(),
+        "(age IN (20, 30) OR name IN ('John', 'Jane') OR age > 90)"
+    );
+    assert_eq!(
+        expr.optimize().to_sql(true).unwrap(),
+        "(age IN (20, 30) OR lower(name) IN (lower('John'), lower('Jane')) OR age > 90)"
+    );
+}
+
+#[test]
+fn test_optimize_keeps_and_equalities() {
+    // `age = 20 AND age = 30` is a contradiction, not an IN list
+    let expr = FilterExpression::and(vec![
+        age(FilterOperator::Equal, 20),
+        age(FilterOperator::Equal, 30),
+    ]);
+    assert_eq!(expr.optimize(), expr);
+
+    // A lone condition is returned unchanged
+    let single = age(FilterOperator::Equal, 20);
+    assert_eq!(single.optimize(), single);
+}
+
+#[test]
+fn test_order_by_selectivity() {
+    let like = FilterExpression::Condition(FilterCondition::string(
+        "email",
+        FilterOperator::Like,
+        Some("%@example.com"),
+    ));
+    let tenant = FilterExpression::Condition(FilterCondition::uint32(
+        "tenant_id",
+        FilterOperator::Equal,
+        Some(7),
+    ));
+    let either = FilterExpression::or(vec![name("John"), age(FilterOperator::LessThan, 18)]);
+    let expr = FilterExpression::and(vec![
+        like,
+        either,
+        age(FilterOperator::GreaterThan, 30),
+        name("Jane"),
+        tenant,
+    ]);
+
+    let hints = SelectivityHints::new().with_primary_key(&["tenant_id"]);
+    assert_eq!(
+        expr.order_by_selectivity(&hints).to_sql(false).unwrap(),
+        "(tenant_id = 7 AND name = 'Jane' AND age > 30 AND email LIKE '%@example.com' AND (name = 'John' OR age < 18))"
+    );
+}
+
+#[test]
+fn test_order_by_selectivity_cardinality_hints() {
+    let status = FilterExpression::Condition(FilterCondition::string(
+        "status",
+        FilterOperator::Equal,
+        Some("open"),
+    ));
+    let expr = FilterExpression::and(vec![status, name("Jane")]);
+
+    // Without hints ties keep their order
+    assert_eq!(expr.order_by_selectivity(&SelectivityHints::new()), expr);
+
+    let hints = SelectivityHints::new()
+        .with_cardinality("status", 4)
+        .with_cardinality("name", 1_000_000);
+    assert_eq!(
+        expr.order_by_selectivity(&hints).to_sql(false).unwrap(),
+        "(name = 'Jane' AND status = 'open')"
+    );
+}
+
+#[test]
+fn test_in_lists_are_deduplicated() {
+    let status = FilterCondition::string("status", FilterOperator::In, Some("a, b, a"));
+    assert_eq!(status.to_sql(false).unwrap(), "status IN ('a', 'b')");
+
+    let ids = FilterCondition::in_values(
+        "id",
+        FilterOperator::NotIn,
+        vec!["1".to_string(), "2".to_string(), "1".to_string()],
+        Some(ColumnTypeInfo::Numeric),
+    );
+    assert_eq!(ids.to_sql(false).unwrap(), "id NOT IN (1, 2)");
+}
+
+#[test]
+fn test_split_in_lists() {
+    let ids = |operator| {
+        FilterExpression::Condition(FilterCondition::in_values(
+            "id",
+            operator,
+            (1..=5).map(|i| i.to_string()).collect(),
+            Some(ColumnTypeInfo::Numeric),
+        ))
+    };
+
+    assert_eq!(
+        ids(FilterOperator::In)
+            .split_in_lists(4, InListStrategy::Chunks(2))
+            .to_sql(false)
+            .unwrap(),
+        "(id IN (1, 2) OR id IN (3, 4) OR id IN (5))"
+    );
+    assert_eq!(
+        ids(FilterOperator::NotIn)
+            .split_in_lists(4, InListStrategy::Chunks(3))
+            .to_sql(false)
+            .unwrap(),
+        "(id NOT IN (1, 2, 3) AND id NOT IN (4, 5))"
+    );
+    assert_eq!(
+        ids(FilterOperator::In)
+            .split_in_lists(4, InListStrategy::ArrayJoin)
+            .to_sql(false)
+            .unwrap(),
+        "id IN (SELECT arrayJoin([1, 2, 3, 4, 5]))"
+    );
+
+    // Lists at or under the threshold are untouched
+    let expr = ids(FilterOperator::In);
+    assert_eq!(expr.split_in_lists(5, InListStrategy::ArrayJoin), expr);
+
+    // Only distinct values count towards the threshold
+    let names = FilterExpression::Condition(FilterCondition::string(
+        "name",
+        FilterOperator::In,
+        Some("a,b,a,c,b"),
+    ));
+    assert_eq!(names.split_in_lists(3, InListStrategy::ArrayJoin), names);
+    assert_eq!(
+        names
+            .split_in_lists(2, InListStrategy::ArrayJoin)
+            .to_sql(true)
+            .unwrap(),
+        "lower(name) IN (SELECT arrayJoin([lower('a'), lower('b'), lower('c')]))"
+    );
+}
@@ -1,4 +1,13 @@
-use clickhouse_filters::pagination::{Paginate, Pagination};
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::pagination::{
+    Paginate, Pagination, PaginationSyntax, TimeWindowPagination,
+};
+use clickhouse_filters::sorting::{SortOrder, SortedColumn};
+use clickhouse_filters::{
+    column_registry, ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions,
+    PaginationOptions,
+};
+use std::collections::{BTreeSet, HashMap};
 
 #[test]
 fn test_pagination_new() {
@@ -52,7 +61,7 @@ fn test_paginate_new() {
     assert_eq!(paginate.pagination.total_pages, 100);
     assert_eq!(paginate.pagination.per_page, 10);
     assert_eq!(paginate.pagination.total_records, 1000);
-    assert_eq!(paginate.sql, "LIMIT 10 OFFSET 0");
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 0");
 }
 
 #[test]
@@ -60,12 +69,12 @@ fn test_paginate_with_invalid_per_page() {
     // per_page should be capped at per_page_limit
     let paginate = Paginate::new(1, 20, 10, 1000);
     assert_eq!(paginate.pagination.per_page, 10);
-    assert_eq!(paginate.sql, "LIMIT 10 OFFSET 0");
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 0");
 
     // per_page should be at least 1
     let paginate = Paginate::new(1, 0, 10, 1000);
     assert_eq!(paginate.pagination.per_page, 10);
-    assert_eq!(paginate.sql, "LIMIT 10 OFFSET 0");
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 0");
 }
 
 #[test]
@@ -73,12 +82,12 @@ fn test_paginate_with_invalid_current_page() {
     // current_page should be at least 1
     let paginate = Paginate::new(0, 10, 10, 1000);
     assert_eq!(paginate.pagination.current_page, 1);
-    assert_eq!(paginate.sql, "LIMIT 10 OFFSET 0");
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 0");
 
     // current_page should be capped at total_pages
     let paginate = Paginate::new(101, 10, 10, 1000);
     assert_eq!(paginate.pagination.current_page, 100);
-    assert_eq!(paginate.sql, "LIMIT 10 OFFSET 990");
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 990");
 }
 
 #[test]
@@ -86,24 +95,369 @@ fn test_paginate_with_zero_total_records() {
     let paginate = Paginate::new(1, 10, 10, 0);
     assert_eq!(paginate.pagination.total_pages, 0);
     assert_eq!(paginate.pagination.current_page, 1);
-    assert_eq!(paginate.sql, "LIMIT 10 OFFSET 0");
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 0");
 }
 
 #[test]
 fn test_paginate_offset_calculation() {
     // Page 1 should have offset 0
     let paginate = Paginate::new(1, 10, 10, 1000);
-    assert_eq!(paginate.sql, "LIMIT 10 OFFSET 0");
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 0");
 
     // Page 2 should have offset 10
     let paginate = Paginate::new(2, 10, 10, 1000);
-    assert_eq!(paginate.sql, "LIMIT 10 OFFSET 10");
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 10");
 
     // Page 10 should have offset 90
     let paginate = Paginate::new(10, 10, 10, 1000);
-    assert_eq!(paginate.sql, "LIMIT 10 OFFSET 90");
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 90");
 
     // Different page size: page 2 with 20 per page should have offset 20
     let paginate = Paginate::new(2, 20, 30, 1000);
-    assert_eq!(paginate.sql, "LIMIT 20 OFFSET 20");
+    assert_eq!(paginate.to_fragment(), "LIMIT 20 OFFSET 20");
+}
+
+#[test]
+fn test_paginate_recalculate() {
+    // Built before the count was known
+    let mut paginate = Paginate::new(3, 10, 10, 0);
+    assert_eq!(paginate.pagination.total_pages, 0);
+
+    paginate.recalculate(95);
+    assert_eq!(paginate.pagination.current_page, 3);
+    assert_eq!(paginate.pagination.total_pages, 10);
+    assert_eq!(paginate.pagination.next_page, 4);
+    assert_eq!(paginate.pagination.total_records, 95);
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 20");
+
+    // The page is clamped once there are fewer records
+    paginate.recalculate(15);
+    assert_eq!(paginate.pagination.current_page, 2);
+    assert_eq!(paginate.pagination.total_pages, 2);
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 10");
+}
+
+#[test]
+fn test_set_total_records() {
+    let mut columns = HashMap::new();
+    columns.insert("id", ColumnDef::UInt64("id"));
+
+    let mut filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(2, 20, 50, 0)),
+        vec![],
+        None,
+        columns.clone(),
+    )
+    .unwrap();
+    filters.set_total_records(45).unwrap();
+
+    let pagination = &filters.pagination.as_ref().unwrap().pagination;
+    assert_eq!(pagination.total_records, 45);
+    assert_eq!(pagination.total_pages, 3);
+    assert_eq!(pagination.current_page, 2);
+    assert_eq!(filters.sql().unwrap(), " LIMIT 20 OFFSET 20");
+
+    assert_eq!(
+        filters.set_total_records(-1).unwrap_err().to_string(),
+        "Invalid total_records: -1"
+    );
+
+    // Without pagination there is nothing to update
+    let mut filters = ClickHouseFilters::new(None, vec![], None, columns).unwrap();
+    filters.set_total_records(45).unwrap();
+    assert!(filters.pagination.is_none());
+}
+
+#[test]
+fn test_paginate_fragments() {
+    let paginate = Paginate::new(3, 25, 100, 1000);
+    assert_eq!(paginate.offset(), 50);
+    assert_eq!(paginate.to_sql(), " LIMIT 25 OFFSET 50");
+    assert_eq!(paginate.to_fragment(), "LIMIT 25 OFFSET 50");
+
+    let mut sql = String::from("SELECT * FROM users");
+    paginate.write_sql(&mut sql);
+    assert_eq!(sql, "SELECT * FROM users LIMIT 25 OFFSET 50");
+}
+
+#[test]
+fn test_paginate_syntax() {
+    let paginate = Paginate::new(3, 25, 100, 1000).with_syntax(PaginationSyntax::LimitComma);
+    assert_eq!(paginate.to_sql(), " LIMIT 50, 25");
+
+    let mut paginate = paginate.with_syntax(PaginationSyntax::OffsetFetch);
+    assert_eq!(
+        paginate.to_fragment(),
+        "OFFSET 50 ROWS FETCH NEXT 25 ROWS ONLY"
+    );
+
+    // Recalculating keeps the syntax
+    paginate.recalculate(60);
+    assert_eq!(
+        paginate.to_fragment(),
+        "OFFSET 50 ROWS FETCH NEXT 25 ROWS ONLY"
+    );
+
+    let mut columns = HashMap::new();
+    columns.insert("id", ColumnDef::UInt64("id"));
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(2, 10, 10, 100).with_syntax(PaginationSyntax::LimitComma)),
+        vec![],
+        None,
+        columns,
+    )
+    .unwrap();
+    assert_eq!(
+        filters.query_sql("db.users", &["id"]).unwrap(),
+        "SELECT id FROM db.users LIMIT 10, 10"
+    );
+}
+
+#[test]
+fn test_paginate_with_ties() {
+    let paginate = Paginate::new(2, 10, 10, 100).with_ties();
+    assert_eq!(paginate.to_fragment(), "LIMIT 10 OFFSET 10 WITH TIES");
+    assert_eq!(
+        paginate
+            .clone()
+            .with_syntax(PaginationSyntax::OffsetFetch)
+            .to_fragment(),
+        "OFFSET 10 ROWS FETCH FIRST 10 ROWS WITH TIES"
+    );
+    assert_eq!(
+        paginate
+            .with_syntax(PaginationSyntax::LimitComma)
+            .to_fragment(),
+        "LIMIT 10, 10 WITH TIES"
+    );
+
+    let mut columns = HashMap::new();
+    columns.insert("score", ColumnDef::UInt64("score"));
+    let pagination = PaginationOptions::new(1, 3, 10, 100)
+        .with_syntax(PaginationSyntax::OffsetFetch)
+        .with_ties();
+    let filters = ClickHouseFilters::new(
+        Some(pagination.clone()),
+        vec![SortedColumn::new("score", "desc")],
+        None,
+        columns.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        filters.query_sql("db.leaderboard", &["score"]).unwrap(),
+        "SELECT score FROM db.leaderboard ORDER BY score DESC \
+         OFFSET 0 ROWS FETCH FIRST 3 ROWS WITH TIES"
+    );
+
+    // Ties are only defined by an ORDER BY
+    assert!(ClickHouseFilters::new(Some(pagination), vec![], None, columns).is_err());
+}
+
+#[test]
+fn test_time_window_pagination() {
+    let columns: ColumnRegistry = column_registry! {
+        id: UInt64,
+        ts: DateTime64,
+        kind: String,
+    };
+    let filtering = FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::string(
+            "kind",
+            FilterOperator::Equal,
+            Some("click"),
+        ))],
+        &columns,
+    );
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(3, 10, 100, 0)),
+        vec![SortedColumn::new("kind", "asc")],
+        Some(filtering),
+        &columns,
+    )
+    .unwrap();
+
+    // The first window has no anchor; existing sorting and pagination are replaced
+    let window = TimeWindowPagination::new("ts", 25);
+    let first = filters.clone().with_time_window(&window).unwrap();
+    assert_eq!(
+        first.sql().unwrap(),
+        " WHERE kind = 'click' ORDER BY ts DESC LIMIT 25 OFFSET 0"
+    );
+
+    let window = window.next("2024-06-01 12:00:00.250");
+    let next = filters.clone().with_time_window(&window).unwrap();
+    assert_eq!(
+        next.sql().unwrap(),
+        " WHERE (kind = 'click' AND ts < toDateTime64('2024-06-01 12:00:00.250', 3)) ORDER BY ts DESC LIMIT 25 OFFSET 0"
+    );
+
+    // Paging forward, with the unique key breaking ties and the page size capped
+    let columns = columns.with_unique_key(&["id"]).with_per_page_limit(10);
+    let window = TimeWindowPagination::new("ts", 25)
+        .with_direction(SortOrder::Asc)
+        .with_anchor("2024-06-01 00:00:00");
+    let filters = ClickHouseFilters::new(None, vec![], None, &columns)
+        .unwrap()
+        .with_time_window(&window)
+        .unwrap();
+    assert_eq!(
+        filters.sql().unwrap(),
+        " WHERE ts > '2024-06-01 00:00:00' ORDER BY ts ASC, id ASC LIMIT 10 OFFSET 0"
+    );
+
+    let no_filters = || ClickHouseFilters::new(None, vec![], None, &columns).unwrap();
+    let err = no_filters()
+        .with_time_window(&TimeWindowPagination::new("ts", 10).with_anchor_key(&["7"]))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Time window anchor key requires an anchor");
+    let err = no_filters()
+        .with_time_window(&TimeWindowPagination::new("kind", 10))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Unsupported time window column type: kind");
+    let err = no_filters()
+        .with_time_window(&TimeWindowPagination::new("ts", 0))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Invalid time window size: 0");
+}
+
+#[test]
+fn test_time_window_anchor_key_breaks_ties() {
+    let columns: ColumnRegistry = column_registry! {
+        id: UInt64,
+        ts: DateTime,
+    };
+    let keyed = columns.clone().with_unique_key(&["id"]);
+    let no_filters =
+        |columns: &ColumnRegistry| ClickHouseFilters::new(None, vec![], None, columns).unwrap();
+
+    // Rows 3 and 4 share 10:02; the first page of two ends on row 4, so the
+    // next page must still return row 3
+    let window = TimeWindowPagination::new("ts", 2);
+    let first = no_filters(&keyed).with_time_window(&window).unwrap();
+    assert_eq!(
+        first.sql().unwrap(),
+        " ORDER BY ts DESC, id DESC LIMIT 2 OFFSET 0"
+    );
+    let next = no_filters(&keyed)
+        .with_time_window(&window.next_with_key("2024-06-01 10:02:00", &["4"]))
+        .unwrap();
+    assert_eq!(
+        next.sql().unwrap(),
+        " WHERE (ts, id) < ('2024-06-01 10:02:00', 4) ORDER BY ts DESC, id DESC LIMIT 2 OFFSET 0"
+    );
+    assert_eq!(
+        next.referenced_columns().unwrap(),
+        BTreeSet::from(["id".to_string(), "ts".to_string()])
+    );
+
+    let forward = window.clone().with_direction(SortOrder::Asc);
+    let next = no_filters(&keyed)
+        .with_time_window(&forward.next_with_key("2024-06-01 10:02:00", &["3"]))
+        .unwrap();
+    assert_eq!(
+        next.sql().unwrap(),
+        " WHERE (ts, id) > ('2024-06-01 10:02:00', 3) ORDER BY ts ASC, id ASC LIMIT 2 OFFSET 0"
+    );
+
+    // `next()` anchors by timestamp alone
+    let next = no_filters(&keyed)
+        .with_time_window(
+            &window
+                .next_with_key("2024-06-01 10:02:00", &["4"])
+                .next("2024-06-01 10:00:00"),
+        )
+        .unwrap();
+    assert_eq!(
+        next.sql().unwrap(),
+        " WHERE ts < '2024-06-01 10:00:00' ORDER BY ts DESC, id DESC LIMIT 2 OFFSET 0"
+    );
+
+    // Key values are checked against the key and its column types
+    let anchored = |key: &[&str]| window.next_with_key("2024-06-01 10:02:00", key);
+    let err = no_filters(&columns)
+        .with_time_window(&anchored(&["4"]))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Time window anchor key requires a unique key in the registry"
+    );
+    let err = no_filters(&keyed)
+        .with_time_window(&anchored(&["4", "5"]))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected 1 time window anchor key values, got 2"
+    );
+    assert!(no_filters(&keyed)
+        .with_time_window(&anchored(&["4) OR (1"]))
+        .is_err());
+    let escaped = no_filters(&keyed)
+        .with_time_window(&window.next_with_key("2024-06-01' OR '1", &["4"]))
+        .unwrap();
+    assert_eq!(
+        escaped.sql().unwrap(),
+        " WHERE (ts, id) < ('2024-06-01'' OR ''1', 4) ORDER BY ts DESC, id DESC LIMIT 2 OFFSET 0"
+    );
+}
+
+#[test]
+fn test_key_batch() {
+    let columns: ColumnRegistry = column_registry! {
+        name: String,
+        user_id: UUID,
+        age: UInt32,
+    };
+    let filtering = FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(18),
+        ))],
+        &columns,
+    );
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(3, 10, 100, 0)),
+        vec![SortedColumn::new("age", "desc")],
+        Some(filtering),
+        &columns,
+    )
+    .unwrap();
+
+    // The first batch has no lower bound; sorting and pagination are replaced
+    let first = filters.clone().with_key_batch("name", None, 2).unwrap();
+    assert_eq!(
+        first.sql().unwrap(),
+        " WHERE age > 18 ORDER BY name ASC LIMIT 2 OFFSET 0"
+    );
+
+    // Later batches compare string and UUID keys directly, escaping the value
+    let second = filters
+        .clone()
+        .with_key_batch("name", Some("O'Brien"), 2)
+        .unwrap();
+    assert_eq!(
+        second.sql().unwrap(),
+        " WHERE (age > 18 AND name > 'O''Brien') ORDER BY name ASC LIMIT 2 OFFSET 0"
+    );
+    let second = filters
+        .clone()
+        .with_key_batch("user_id", Some("550e8400-e29b-41d4-a716-446655440000"), 2)
+        .unwrap();
+    assert_eq!(
+        second.sql().unwrap(),
+        " WHERE (age > 18 AND user_id > '550e8400-e29b-41d4-a716-446655440000') \
+         ORDER BY user_id ASC LIMIT 2 OFFSET 0"
+    );
+
+    assert!(filters
+        .clone()
+        .with_key_batch("age", Some("1 OR 1 = 1"), 2)
+        .is_err());
+    assert_eq!(
+        filters
+            .with_key_batch("email", None, 2)
+            .unwrap_err()
+            .to_string(),
+        "Column not found: email"
+    );
 }
@@ -0,0 +1,252 @@
+use clickhouse_filters::filtering::{
+    ArrayElementType, ColumnTypeInfo, FilterBuilder, FilterCondition, FilterExpression,
+    FilterOperator,
+};
+use clickhouse_filters::params::{BindValue, QueryParams};
+use clickhouse_filters::sorting::SortedColumn;
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions};
+
+#[test]
+fn test_params_string_operators() {
+    let expr = FilterExpression::and(vec![
+        FilterExpression::Condition(FilterCondition::string(
+            "name",
+            FilterOperator::StartsWith,
+            Some("Jo"),
+        )),
+        FilterExpression::Condition(FilterCondition::string(
+            "status",
+            FilterOperator::In,
+            Some("open, paid"),
+        )),
+    ]);
+
+    let (sql, params) = expr.to_sql_with_params(true).unwrap();
+    assert_eq!(
+        sql,
+        "(lower(name) LIKE lower({name_0:String}) AND lower(status) IN (lower({status_1:String}), lower({status_2:String})))"
+    );
+    assert_eq!(params.get("name_0"), Some("Jo%"));
+    assert_eq!(params.get("status_1"), Some("open"));
+    assert_eq!(params.get("status_2"), Some("paid"));
+}
+
+#[test]
+fn test_params_typed_values() {
+    let expr = FilterExpression::and(vec![
+        FilterExpression::Condition(FilterCondition::int64(
+            "balance",
+            FilterOperator::LessThan,
+            Some(-5),
+        )),
+        FilterExpression::Condition(FilterCondition::date_time(
+            "created_at",
+            FilterOperator::GreaterThan,
+            Some("2024-01-01 00:00:00"),
+        )),
+        FilterExpression::Condition(FilterCondition::boolean(
+            "active",
+            FilterOperator::Equal,
+            Some(true),
+        )),
+        FilterExpression::Condition(FilterCondition::date_range(
+            "day",
+            "2024-01-01",
+            "2024-01-31",
+        )),
+        FilterExpression::Condition(FilterCondition::in_values(
+            "score",
+            FilterOperator::In,
+            vec!["1".to_string(), "2.5".to_string()],
+            Some(ColumnTypeInfo::Numeric),
+        )),
+        FilterExpression::Condition(FilterCondition::string(
+            "deleted_at",
+            FilterOperator::IsNull,
+            None,
+        )),
+    ]);
+
+    let (sql, params) = expr.to_sql_with_params(false).unwrap();
+    assert_eq!(
+        sql,
+        "(balance < {balance_0:Int64} AND created_at > {created_at_1:DateTime} AND active = {active_2:UInt8} \
+         AND day BETWEEN {day_3:String} AND {day_4:String} AND score IN ({score_5:Int64}, {score_6:Float64}) \
+         AND deleted_at IS NULL)"
+    );
+    assert_eq!(params.len(), 7);
+    assert_eq!(params.get("balance_0"), Some("-5"));
+    assert_eq!(params.get("active_2"), Some("1"));
+    assert_eq!(params.get("score_6"), Some("2.5"));
+}
+
+#[test]
+fn test_params_escaping_and_names() {
+    let builder = FilterBuilder::new()
+        .add_condition(FilterCondition::string(
+            "user.name",
+            FilterOperator::Equal,
+            Some("a\\b\tc' OR 1=1"),
+        ))
+        .add_condition(FilterCondition::array_has("tags", "x"));
+
+    let (sql, params) = builder.build_with_params().unwrap();
+    assert_eq!(
+        sql,
+        " WHERE (user.name = {user_name_0:String} AND has(tags, {tags_1:String}))"
+    );
+    assert_eq!(params.get("user_name_0"), Some("a\\\\b\\tc' OR 1=1"));
+    assert_eq!(
+        params.http_params(),
+        vec![
+            ("param_tags_1".to_string(), "x".to_string()),
+            (
+                "param_user_name_0".to_string(),
+                "a\\\\b\\tc' OR 1=1".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_query_sql_with_params() {
+    let columns = ColumnRegistry::from_iter([
+        ("name", ColumnDef::String("name")),
+        ("deleted_at", ColumnDef::DateTime("deleted_at")),
+    ])
+    .with_soft_delete("deleted_at");
+    let filtering = FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::string(
+            "name",
+            FilterOperator::Equal,
+            Some("John"),
+        ))],
+        &columns,
+    );
+    let filters = ClickHouseFilters::new(
+        None,
+        vec![SortedColumn::new("name", "asc")],
+        Some(filtering),
+        &columns,
+    )
+    .unwrap();
+
+    let (sql, params) = filters
+        .query_sql_with_params("db.users", &["name"])
+        .unwrap();
+    assert_eq!(
+        sql,
+        "SELECT name FROM db.users WHERE (name = {name_0:String} AND deleted_at IS NULL) ORDER BY name ASC"
+    );
+    assert_eq!(params.into_values().len(), 1);
+
+    // The inline rendering is unaffected
+    assert_eq!(
+        filters.sql().unwrap(),
+        " WHERE (name = 'John' AND deleted_at IS NULL) ORDER BY name ASC"
+    );
+}
+
+#[test]
+fn test_bind_values() {
+    let expr = FilterExpression::and(vec![
+        FilterExpression::Condition(FilterCondition::string(
+            "name",
+            FilterOperator::StartsWith,
+            Some("Jo"),
+        )),
+        FilterExpression::Condition(FilterCondition::int64(
+            "balance",
+            FilterOperator::LessThan,
+            Some(-5),
+        )),
+        FilterExpression::Condition(FilterCondition::float64(
+            "score",
+            FilterOperator::GreaterThanOrEqual,
+            Some(0.5),
+        )),
+        FilterExpression::Condition(FilterCondition::in_values(
+            "status",
+            FilterOperator::In,
+            vec!["open".to_string(), "new".to_string()],
+            Some(ColumnTypeInfo::String),
+        )),
+        FilterExpression::Condition(FilterCondition::boolean(
+            "active",
+            FilterOperator::Equal,
+            Some(true),
+        )),
+    ]);
+
+    let (sql, values) = expr.to_sql_with_bind_values(false).unwrap();
+    assert_eq!(
+        sql,
+        "(name LIKE ? AND balance < ? AND score >= ? AND status IN (?, ?) AND active = ?)"
+    );
+    assert_eq!(
+        values,
+        vec![
+            BindValue::String("Jo%".to_string()),
+            BindValue::Int(-5),
+            BindValue::Float(0.5),
+            BindValue::String("open".to_string()),
+            BindValue::String("new".to_string()),
+            BindValue::UInt(1),
+        ]
+    );
+
+    // Values are bound raw; the client does the escaping
+    let condition = FilterCondition::string("name", FilterOperator::Equal, Some(r"O'Brien\"));
+    let mut sql = String::new();
+    let mut params = QueryParams::positional();
+    condition
+        .write_sql_with_params(&mut sql, false, &mut params)
+        .unwrap();
+    assert_eq!(sql, "name = ?");
+    assert_eq!(params.len(), 1);
+    assert_eq!(
+        params.bind_values(),
+        [BindValue::String(r"O'Brien\".to_string())]
+    );
+    assert_eq!(params.get("name_0"), None);
+}
+
+#[test]
+fn test_bind_values_escape_inlined_question_marks() {
+    let columns = ColumnRegistry::from_iter([
+        ("name", ColumnDef::String("name")),
+        ("tags", ColumnDef::ArrayString("tags")),
+    ]);
+    let filtering = FilteringOptions::case_sensitive(
+        vec![
+            FilterExpression::Condition(FilterCondition::string(
+                "name",
+                FilterOperator::Equal,
+                Some("why?"),
+            )),
+            // Array element conditions are inlined
+            FilterExpression::Condition(FilterCondition::array_values(
+                "tags",
+                FilterOperator::ArrayAny,
+                vec!["a?".to_string()],
+                ArrayElementType::String,
+            )),
+        ],
+        &columns,
+    );
+    let filters = ClickHouseFilters::new(
+        None,
+        vec![SortedColumn::new("name", "asc")],
+        Some(filtering),
+        &columns,
+    )
+    .unwrap();
+
+    let (sql, values) = filters.query_sql_with_bind_values("db.posts", &[]).unwrap();
+    assert_eq!(
+        sql,
+        "SELECT * FROM db.posts WHERE (name = ? AND hasAny(tags, ['a??'])) ORDER BY name ASC"
+    );
+    assert_eq!(values, vec![BindValue::String("why?".to_string())]);
+    assert_eq!(filters.sql_with_bind_values().unwrap().1.len(), 1);
+}
@@ -0,0 +1,154 @@
+use clickhouse_filters::filtering::{
+    FilterCondition, FilterExpression, FilterOperator, JsonFilter,
+};
+use clickhouse_filters::policy::RowPolicies;
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, FilteringOptions};
+use std::collections::HashMap;
+
+struct User {
+    tenant_id: u32,
+    admin: bool,
+}
+
+fn columns() -> HashMap<&'static str, ColumnDef> {
+    let mut columns = HashMap::new();
+    columns.insert("status", ColumnDef::String("status"));
+    columns.insert("tenant_id", ColumnDef::UInt32("tenant_id"));
+    columns.insert("deleted", ColumnDef::UInt8("deleted"));
+    columns
+}
+
+fn policies() -> RowPolicies<User> {
+    RowPolicies::new()
+        .add("db.orders", |user: &User| {
+            if user.admin {
+                return Ok(None);
+            }
+            Ok(Some(FilterExpression::Condition(FilterCondition::uint32(
+                "tenant_id",
+                FilterOperator::Equal,
+                Some(user.tenant_id),
+            ))))
+        })
+        .add("db.orders", |_: &User| {
+            Ok(Some(FilterExpression::Condition(FilterCondition::uint8(
+                "deleted",
+                FilterOperator::Equal,
+                Some(0),
+            ))))
+        })
+}
+
+fn user(tenant_id: u32) -> User {
+    User {
+        tenant_id,
+        admin: false,
+    }
+}
+
+#[test]
+fn test_user_or_cannot_escape_policy() {
+    // A user trying to widen the result with `OR tenant_id = 2`
+    let json_filters = vec![
+        JsonFilter {
+            n: "status".to_string(),
+            f: "=".to_string(),
+            v: "open".to_string(),
+            c: Some("OR".to_string()),
+        },
+        JsonFilter {
+            n: "tenant_id".to_string(),
+            f: "=".to_string(),
+            v: "2".to_string(),
+            c: None,
+        },
+    ];
+    let filtering = FilteringOptions::from_json_filters(&json_filters, columns())
+        .unwrap()
+        .unwrap();
+
+    let mut filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns()).unwrap();
+    policies()
+        .apply("db.orders", &user(1), &mut filters)
+        .unwrap();
+
+    assert_eq!(
+        filters.count_sql("db.orders").unwrap(),
+        "SELECT COUNT(*) FROM db.orders WHERE ((lower(status) = lower('open') OR tenant_id = 2) AND (tenant_id = 1 AND deleted = 0))"
+    );
+}
+
+#[test]
+fn test_policy_applies_without_user_filters() {
+    let mut filters = ClickHouseFilters::new(None, vec![], None, columns()).unwrap();
+
+    // Rendering before the policy is applied must not leave a stale WHERE clause
+    assert_eq!(filters.sql().unwrap(), "");
+    policies()
+        .apply("db.orders", &user(3), &mut filters)
+        .unwrap();
+
+    assert_eq!(
+        filters.sql().unwrap(),
+        " WHERE (tenant_id = 3 AND deleted = 0)"
+    );
+}
+
+#[test]
+fn test_policy_context_and_unlisted_tables() {
+    let admin = User {
+        tenant_id: 0,
+        admin: true,
+    };
+
+    // The tenant policy opts out for admins; the soft-delete policy still applies
+    let predicate = policies().predicate("db.orders", &admin).unwrap().unwrap();
+    assert_eq!(predicate.to_sql(false).unwrap(), "deleted = 0");
+
+    // Tables without policies are unrestricted unless denied
+    assert!(policies()
+        .predicate("db.users", &user(1))
+        .unwrap()
+        .is_none());
+
+    let err = policies()
+        .deny_unlisted_tables()
+        .predicate("db.users", &user(1))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "No row policy defined for table: db.users");
+}
+
+#[test]
+fn test_policy_is_kept_when_pruning_filters() {
+    let json_filters = vec![JsonFilter {
+        n: "tenant_id".to_string(),
+        f: "=".to_string(),
+        v: "2".to_string(),
+        c: None,
+    }];
+    let filtering = FilteringOptions::from_json_filters(&json_filters, columns())
+        .unwrap()
+        .unwrap();
+    let mut filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns()).unwrap();
+    policies()
+        .apply("db.orders", &user(1), &mut filters)
+        .unwrap();
+
+    // Excluding the tenant facet's own filter leaves the tenant policy in place
+    assert_eq!(
+        filters
+            .distinct_values_sql("db.orders", "tenant_id", 20, true)
+            .unwrap(),
+        "SELECT DISTINCT tenant_id FROM db.orders WHERE (tenant_id = 1 AND deleted = 0) \
+         ORDER BY tenant_id LIMIT 20"
+    );
+
+    // Mutations are restricted by the policy too
+    assert_eq!(
+        filters
+            .allow_mutations()
+            .delete_sql("db", "orders")
+            .unwrap(),
+        "ALTER TABLE db.orders DELETE WHERE (tenant_id = 2 AND (tenant_id = 1 AND deleted = 0))"
+    );
+}
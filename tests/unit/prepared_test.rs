@@ -0,0 +1,94 @@
+use clickhouse_filters::filtering::JsonFilter;
+use clickhouse_filters::prepared::PreparedFilter;
+use clickhouse_filters::{column_registry, ColumnRegistry};
+use std::collections::HashMap;
+
+fn columns() -> ColumnRegistry {
+    column_registry! {
+        name: String,
+        status: String,
+        age: UInt32,
+        tags: ArrayString,
+    }
+}
+
+fn filter(n: &str, f: &str, v: &str, c: Option<&str>) -> JsonFilter {
+    JsonFilter {
+        n: n.to_string(),
+        f: f.to_string(),
+        v: v.to_string(),
+        c: c.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_prepared_filter_bind() {
+    let prepared = PreparedFilter::new(
+        &[
+            filter("name", "=", ":name", Some("AND")),
+            filter("age", ">", ":min_age", Some("AND")),
+            filter("status", "!=", "spam", Some("AND")),
+        ],
+        columns(),
+    )
+    .unwrap();
+    assert_eq!(prepared.placeholders(), ["name", "min_age"]);
+
+    // The same prepared filter serves many requests
+    let values = HashMap::from([("name", "O'Brien"), ("min_age", "30")]);
+    assert_eq!(
+        prepared.bind(&values).unwrap(),
+        " WHERE (lower(name) = lower('O''Brien') AND age > 30 AND lower(status) != lower('spam'))"
+    );
+    let values = HashMap::from([("name", "Mary"), ("min_age", "18")]);
+    assert_eq!(
+        prepared.bind(&values).unwrap(),
+        " WHERE (lower(name) = lower('Mary') AND age > 18 AND lower(status) != lower('spam'))"
+    );
+}
+
+#[test]
+fn test_prepared_filter_repeated_placeholder() {
+    let registry = columns().with_case_sensitive("status");
+    let prepared = PreparedFilter::new(
+        &[
+            filter("name", "=", ":term", Some("OR")),
+            filter("status", "=", ":term", Some("OR")),
+            filter("tags", "ARRAY HAS", ":tag", None),
+        ],
+        &registry,
+    )
+    .unwrap();
+    assert_eq!(prepared.placeholders(), ["term", "tag"]);
+
+    let values = HashMap::from([("term", "new"), ("tag", "vip")]);
+    assert_eq!(
+        prepared.bind(&values).unwrap(),
+        " WHERE (lower(name) = lower('new') OR status = 'new' OR has(tags, 'vip'))"
+    );
+}
+
+#[test]
+fn test_prepared_filter_errors() {
+    // Columns and operators are checked when preparing
+    assert!(PreparedFilter::new(&[filter("email", "=", ":email", None)], columns()).is_err());
+    assert!(PreparedFilter::new(&[filter("name", "ABOUT", ":name", None)], columns()).is_err());
+
+    let prepared =
+        PreparedFilter::case_sensitive(&[filter("age", ">=", ":age", None)], columns()).unwrap();
+    assert_eq!(
+        prepared.bind(&HashMap::from([("age", "21")])).unwrap(),
+        " WHERE age >= 21"
+    );
+
+    let err = prepared.bind(&HashMap::new()).unwrap_err();
+    assert_eq!(err.to_string(), "Missing value for placeholder: age");
+
+    let values = HashMap::from([("age", "21"), ("agee", "30")]);
+    let err = prepared.bind(&values).unwrap_err();
+    assert_eq!(err.to_string(), "Unknown placeholder: agee");
+
+    // Bound values are checked against the column type
+    let err = prepared.bind(&HashMap::from([("age", "old")])).unwrap_err();
+    assert_eq!(err.to_string(), "Invalid value for UInt32: old");
+}
@@ -0,0 +1,78 @@
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::projection::Projection;
+use clickhouse_filters::sorting::SortedColumn;
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, FilteringOptions, PaginationOptions};
+use std::collections::HashMap;
+
+fn filters() -> ClickHouseFilters {
+    let mut columns = HashMap::new();
+    columns.insert("name", ColumnDef::String("name"));
+    columns.insert("created_at", ColumnDef::DateTime("created_at"));
+    columns.insert("tags", ColumnDef::ArrayString("tags"));
+
+    let filtering = FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::string(
+            "name",
+            FilterOperator::StartsWith,
+            Some("J"),
+        ))],
+        columns.clone(),
+    );
+
+    ClickHouseFilters::new(
+        Some(PaginationOptions::new(1, 10, 10, 100)),
+        vec![SortedColumn::new("name", "asc")],
+        Some(filtering),
+        columns,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_query_sql_with_projections() {
+    let sql = filters()
+        .query_sql_with(
+            "db.users",
+            &[
+                "name".into(),
+                Projection::function("toDate", "created_at").alias("day"),
+                Projection::function("length", "tags").alias("tag_count"),
+            ],
+        )
+        .unwrap();
+
+    assert_eq!(
+        sql,
+        "SELECT name, toDate(created_at) AS day, length(tags) AS tag_count FROM db.users \
+         WHERE name LIKE 'J%' ORDER BY name ASC LIMIT 10 OFFSET 0"
+    );
+}
+
+#[test]
+fn test_query_sql_with_empty_projections_selects_all() {
+    let sql = filters().query_sql_with("db.users", &[]).unwrap();
+    assert!(sql.starts_with("SELECT * FROM db.users WHERE"));
+}
+
+#[test]
+fn test_query_sql_with_rejects_invalid_projections() {
+    let filters = filters();
+
+    let err = filters
+        .query_sql_with("db.users", &[Projection::column("password")])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Column not found: password");
+
+    let err = filters
+        .query_sql_with(
+            "db.users",
+            &[Projection::function("toDate", "created_at").alias("day; DROP TABLE users")],
+        )
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Invalid alias: day; DROP TABLE users");
+
+    let err = filters
+        .query_sql_with("db.users", &[Projection::function("sleep(3) +", "name")])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Invalid function name: sleep(3) +");
+}
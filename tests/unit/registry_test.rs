@@ -0,0 +1,273 @@
+use clickhouse_filters::filtering::{
+    FilterCondition, FilterExpression, FilterOperator, JsonFilter,
+};
+use clickhouse_filters::sorting::{SortedColumn, Sorting};
+use clickhouse_filters::{
+    column_registry, ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions,
+    PaginationOptions,
+};
+use std::collections::HashMap;
+
+#[test]
+fn test_registry_lookup() {
+    let mut columns = HashMap::new();
+    columns.insert("name", ColumnDef::String("name"));
+    columns.insert("age", ColumnDef::UInt32("age"));
+
+    let registry = ColumnRegistry::new(columns);
+
+    assert_eq!(registry.len(), 2);
+    assert!(!registry.is_empty());
+    assert!(registry.contains("name"));
+    assert!(!registry.contains("email"));
+    assert!(matches!(
+        registry.get("age"),
+        Some(ColumnDef::UInt32("age"))
+    ));
+    assert!(registry.get("email").is_none());
+    assert_eq!(registry.column_names(), vec!["age", "name"]);
+}
+
+#[test]
+fn test_registry_macro() {
+    let registry: ColumnRegistry = column_registry! {
+        id: UUID,
+        name: String,
+        created_at: DateTime,
+    };
+
+    assert_eq!(registry.column_names(), vec!["created_at", "id", "name"]);
+    assert!(matches!(
+        registry.get("created_at"),
+        Some(ColumnDef::DateTime("created_at"))
+    ));
+}
+
+#[test]
+fn test_registry_shared_across_filters() {
+    let registry: ColumnRegistry = column_registry! {
+        name: String,
+        age: UInt32,
+    };
+
+    // The same registry can back many requests without cloning the map
+    let filtering = FilteringOptions::new(
+        vec![FilterExpression::Condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(30),
+        ))],
+        &registry,
+    );
+    let filters = ClickHouseFilters::new(None, vec![], Some(filtering), &registry).unwrap();
+    assert_eq!(filters.sql().unwrap(), " WHERE age > 30");
+
+    let json_filters = vec![JsonFilter {
+        n: "name".to_string(),
+        f: "=".to_string(),
+        v: "John".to_string(),
+        c: None,
+    }];
+    let filtering = FilteringOptions::from_json_filters(&json_filters, &registry).unwrap();
+    assert_eq!(
+        filtering.unwrap().to_sql().unwrap(),
+        " WHERE lower(name) = lower('John')"
+    );
+}
+
+#[test]
+fn test_registry_rejects_unknown_json_column() {
+    let registry: ColumnRegistry = column_registry! { name: String };
+
+    let json_filters = vec![JsonFilter {
+        n: "email".to_string(),
+        f: "=".to_string(),
+        v: "john@example.com".to_string(),
+        c: None,
+    }];
+
+    let result = FilteringOptions::from_json_filters(&json_filters, registry);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_per_column_case_sensitivity() {
+    let columns: ColumnRegistry = column_registry! {
+        name: String,
+        code: String,
+        email: String,
+    };
+    let columns = columns
+        .with_case_sensitive("code")
+        .with_case_insensitive("email");
+
+    assert_eq!(columns.is_case_insensitive("code"), Some(false));
+    assert_eq!(columns.is_case_insensitive("email"), Some(true));
+    assert_eq!(columns.is_case_insensitive("name"), None);
+
+    let expressions = vec![
+        FilterExpression::Condition(FilterCondition::string(
+            "name",
+            FilterOperator::Equal,
+            Some("John"),
+        )),
+        FilterExpression::Condition(FilterCondition::string(
+            "code",
+            FilterOperator::Equal,
+            Some("AB-12"),
+        )),
+        FilterExpression::Condition(FilterCondition::string(
+            "email",
+            FilterOperator::Equal,
+            Some("John@Example.com"),
+        )),
+    ];
+
+    // The key column keeps its exact match under a case-insensitive filter
+    let filtering = FilteringOptions::new(expressions.clone(), &columns);
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE (lower(name) = lower('John') AND code = 'AB-12' AND lower(email) = lower('John@Example.com'))"
+    );
+
+    // And the case-insensitive column is lowered under a case-sensitive filter
+    let filtering = FilteringOptions::case_sensitive(expressions, &columns);
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE (name = 'John' AND code = 'AB-12' AND lower(email) = lower('John@Example.com'))"
+    );
+}
+
+#[test]
+fn test_low_cardinality_columns() {
+    let columns: ColumnRegistry = column_registry! {
+        name: String,
+        country: LowCardinality(String),
+    };
+    assert!(columns.get("country").unwrap().is_low_cardinality());
+    assert_eq!(columns.is_case_insensitive("country"), Some(false));
+    assert_eq!(columns.is_case_insensitive("name"), None);
+
+    // Case-insensitive filters compare LowCardinality columns exactly
+    let filtering = FilteringOptions::new(
+        vec![
+            FilterExpression::Condition(columns.to_filter_condition("name", "=", "John").unwrap()),
+            FilterExpression::Condition(
+                columns
+                    .to_filter_condition("country", "IN", "GB, FR")
+                    .unwrap(),
+            ),
+        ],
+        &columns,
+    );
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE (lower(name) = lower('John') AND country IN ('GB', 'FR'))"
+    );
+
+    // Unless declared case-insensitive
+    let columns = columns.with_case_insensitive("country");
+    let condition = columns.to_filter_condition("country", "=", "GB").unwrap();
+    let filtering = FilteringOptions::new(vec![FilterExpression::Condition(condition)], &columns);
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE lower(country) = lower('GB')"
+    );
+}
+
+#[test]
+fn test_unique_key_tiebreaker() {
+    let mut columns = HashMap::new();
+    columns.insert("tenant_id", ColumnDef::UInt32("tenant_id"));
+    columns.insert("id", ColumnDef::UInt64("id"));
+    columns.insert("created_at", ColumnDef::DateTime("created_at"));
+    let columns = ColumnRegistry::new(columns).with_unique_key(&["tenant_id", "id"]);
+
+    let filters = ClickHouseFilters::new(
+        None,
+        vec![
+            SortedColumn::new("created_at", "desc"),
+            SortedColumn::new("id", "desc"),
+        ],
+        None,
+        &columns,
+    )
+    .unwrap();
+    assert_eq!(
+        filters.sql().unwrap(),
+        " ORDER BY created_at DESC, id DESC, tenant_id ASC"
+    );
+
+    // Pagination alone also gets a stable order
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(1, 10, 10, 100)),
+        vec![],
+        None,
+        &columns,
+    )
+    .unwrap();
+    assert_eq!(
+        filters.sql().unwrap(),
+        " ORDER BY tenant_id ASC, id ASC LIMIT 10 OFFSET 0"
+    );
+
+    let key = columns.unique_key().unwrap();
+    let sorting = Sorting::new(vec![SortedColumn::new("id", "asc")]);
+    assert_eq!(
+        key.check_sorting(&sorting).unwrap_err().to_string(),
+        "Sorting does not include unique key column: tenant_id"
+    );
+    assert!(key.check_sorting(&sorting.with_tiebreaker(key)).is_ok());
+
+    let invalid = columns.clone().with_unique_key(&["uuid"]);
+    assert_eq!(
+        ClickHouseFilters::new(None, vec![], None, invalid)
+            .unwrap_err()
+            .to_string(),
+        "Column not found: uuid"
+    );
+}
+
+#[test]
+fn test_column_labels() {
+    let registry: ColumnRegistry = column_registry! {
+        created_at: Date,
+        age: UInt32,
+        score: UInt32,
+    };
+    let registry = registry
+        .with_label("created_at", "Signup date")
+        .with_description("created_at", "When the account was created")
+        .with_label("age", "Age");
+
+    assert_eq!(registry.label("created_at"), Some("Signup date"));
+    assert_eq!(
+        registry.description("created_at"),
+        Some("When the account was created")
+    );
+    assert_eq!(registry.label("score"), None);
+    assert_eq!(registry.display_name("score"), "score");
+
+    // Errors on labeled columns name the column by its label
+    let json_filters = vec![JsonFilter {
+        n: "age".to_string(),
+        f: ">".to_string(),
+        v: "old".to_string(),
+        c: None,
+    }];
+    let err = FilteringOptions::from_json_filters(&json_filters, &registry)
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "Age: Invalid value for UInt32: old");
+
+    let json_filters = vec![JsonFilter {
+        n: "score".to_string(),
+        f: ">".to_string(),
+        v: "high".to_string(),
+        c: None,
+    }];
+    let err = FilteringOptions::from_json_filters(&json_filters, &registry)
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "Invalid value for UInt32: high");
+}
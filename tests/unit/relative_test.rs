@@ -0,0 +1,84 @@
+use clickhouse_filters::filtering::{FilterCondition, FilterOperator, JsonFilter};
+use clickhouse_filters::relative::ReferenceTime;
+use clickhouse_filters::{ColumnDef, FilteringOptions};
+use std::collections::HashMap;
+
+#[test]
+fn test_reference_time_rewrite() {
+    let reference = ReferenceTime::new("2024-06-01 12:30:00").unwrap();
+    assert_eq!(reference.timestamp(), "2024-06-01 12:30:00");
+    assert_eq!(
+        reference.rewrite("now() - INTERVAL 7 DAY"),
+        "toDateTime('2024-06-01 12:30:00') - INTERVAL 7 DAY"
+    );
+    assert_eq!(
+        reference.rewrite("TODAY ( ) - 1"),
+        "toDate('2024-06-01') - 1"
+    );
+    assert_eq!(
+        reference.rewrite("yesterday() - 7"),
+        "(toDate('2024-06-01') - 1) - 7"
+    );
+    // Only calls without arguments, and not other names starting with the same letters
+    assert_eq!(reference.rewrite("now('UTC')"), "now('UTC')");
+    assert_eq!(reference.rewrite("knownow()"), "knownow()");
+
+    let reference = ReferenceTime::new("2024-06-01").unwrap();
+    assert_eq!(reference.timestamp(), "2024-06-01 00:00:00");
+    let reference = ReferenceTime::new("2024-06-01 12:30:00.250").unwrap();
+    assert_eq!(
+        reference.rewrite("now()"),
+        "toDateTime64('2024-06-01 12:30:00.250', 3)"
+    );
+
+    for invalid in [
+        "",
+        "yesterday",
+        "2024-6-1",
+        "2024-06-0112:30:00",
+        "2024-06-01 12:30",
+        "2024-06-01 12:30:00.x",
+    ] {
+        assert!(ReferenceTime::new(invalid).is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_filtering_reference_time() {
+    let mut columns = HashMap::new();
+    columns.insert("created_at", ColumnDef::DateTime("created_at"));
+    columns.insert("name", ColumnDef::String("name"));
+
+    let filters = vec![
+        JsonFilter {
+            n: "created_at".to_string(),
+            f: "RELATIVE".to_string(),
+            v: "today() - 30".to_string(),
+            c: None,
+        },
+        JsonFilter {
+            n: "name".to_string(),
+            f: "=".to_string(),
+            v: "now()".to_string(),
+            c: None,
+        },
+    ];
+    let filtering = FilteringOptions::from_json_filters(&filters, columns)
+        .unwrap()
+        .unwrap()
+        .reference_time(ReferenceTime::new("2024-06-01 12:30:00").unwrap());
+
+    // Only relative dates are rewritten, not values that happen to look like calls
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE (created_at > toDate('2024-06-01') - 30 AND lower(name) = lower('now()'))"
+    );
+
+    let condition = FilterCondition::string("name", FilterOperator::Equal, Some("x"));
+    assert_eq!(
+        ReferenceTime::new("2024-06-01")
+            .unwrap()
+            .apply(condition.clone()),
+        condition
+    );
+}
@@ -0,0 +1,134 @@
+use clickhouse_filters::filtering::JsonFilter;
+use clickhouse_filters::request::QueryRequest;
+use clickhouse_filters::sorting::JsonSort;
+use clickhouse_filters::{column_registry, ClickHouseFilters, ColumnRegistry};
+
+fn columns() -> ColumnRegistry {
+    let columns: ColumnRegistry = column_registry! {
+        name: String,
+        status: String,
+        created_at: DateTime,
+    };
+    columns.with_search_columns(&["name"])
+}
+
+#[test]
+fn test_from_request_json_body() {
+    let request: QueryRequest = serde_json::from_str(
+        r#"{
+            "filters": [{"n": "status", "f": "=", "v": "open", "c": null}],
+            "sort": [{"n": "-created_at"}],
+            "page": 3,
+            "per_page": 25
+        }"#,
+    )
+    .unwrap();
+
+    let filters = ClickHouseFilters::from_request(&request, columns()).unwrap();
+    assert_eq!(
+        filters.query_sql("db.orders", &[]).unwrap(),
+        "SELECT * FROM db.orders WHERE lower(status) = lower('open') \
+         ORDER BY created_at DESC LIMIT 25 OFFSET 50"
+    );
+}
+
+#[test]
+fn test_from_request_defaults() {
+    let request: QueryRequest = serde_json::from_str("{}").unwrap();
+    let filters = ClickHouseFilters::from_request(&request, columns()).unwrap();
+    assert_eq!(filters.sql().unwrap(), " LIMIT 10 OFFSET 0");
+
+    // per_page is capped by the registry
+    let request = QueryRequest {
+        per_page: Some(500),
+        ..Default::default()
+    };
+    let filters =
+        ClickHouseFilters::from_request(&request, columns().with_per_page_limit(50)).unwrap();
+    assert_eq!(filters.sql().unwrap(), " LIMIT 50 OFFSET 0");
+}
+
+#[test]
+fn test_from_request_search() {
+    let request = QueryRequest {
+        search: Some(" 100%_off ".to_string()),
+        ..Default::default()
+    };
+    let filters = ClickHouseFilters::from_request(&request, columns()).unwrap();
+    assert_eq!(
+        filters.sql().unwrap(),
+        r" WHERE lower(name) LIKE lower('%100\\%\\_off%') LIMIT 10 OFFSET 0"
+    );
+
+    // A blank search term is ignored
+    let request = QueryRequest {
+        search: Some("  ".to_string()),
+        ..Default::default()
+    };
+    let filters = ClickHouseFilters::from_request(&request, columns()).unwrap();
+    assert_eq!(filters.sql().unwrap(), " LIMIT 10 OFFSET 0");
+}
+
+#[test]
+fn test_from_request_validation() {
+    let request: QueryRequest = serde_json::from_str(r#"{"sort": [{"n": "password"}]}"#).unwrap();
+    let err = ClickHouseFilters::from_request(&request, columns()).unwrap_err();
+    assert_eq!(err.to_string(), "Column not found: password");
+
+    let request = QueryRequest {
+        search: Some("smith".to_string()),
+        ..Default::default()
+    };
+    let no_search: ColumnRegistry = column_registry! { name: String };
+    let err = ClickHouseFilters::from_request(&request, no_search).unwrap_err();
+    assert_eq!(err.to_string(), "No search columns declared");
+
+    let bad_search = columns().with_search_columns(&["created_at"]);
+    let err = ClickHouseFilters::from_request(&request, bad_search).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unsupported search column type: created_at"
+    );
+}
+
+#[test]
+fn test_from_request_versions() {
+    // Versioned bodies reject unknown keys at every level
+    let err = serde_json::from_str::<QueryRequest>(r#"{"version": 1, "pge": 2}"#).unwrap_err();
+    assert!(err.to_string().contains("unknown field `pge`"), "{}", err);
+    assert!(serde_json::from_str::<QueryRequest>(
+        r#"{"version": 1, "filters": [{"n": "status", "f": "=", "v": "open", "cc": "OR"}]}"#
+    )
+    .is_err());
+    assert!(serde_json::from_str::<QueryRequest>(
+        r#"{"version": 1, "sort": [{"n": "name", "dir": "asc"}]}"#
+    )
+    .is_err());
+
+    // Unversioned bodies are the old format, whose unknown keys are ignored
+    let request: QueryRequest = serde_json::from_str(
+        r#"{"pge": 2, "filters": [{"n": "status", "f": "=", "v": "open", "cc": "OR"}]}"#,
+    )
+    .unwrap();
+    assert_eq!(request.version, None);
+    let filters = ClickHouseFilters::from_request(&request, columns()).unwrap();
+    assert_eq!(
+        filters.sql().unwrap(),
+        " WHERE lower(status) = lower('open') LIMIT 10 OFFSET 0"
+    );
+
+    let request: QueryRequest =
+        serde_json::from_str(r#"{"version": 1, "sort": [{"n": "name", "d": "asc"}]}"#).unwrap();
+    assert!(ClickHouseFilters::from_request(&request, columns()).is_ok());
+
+    let request: QueryRequest = serde_json::from_str(r#"{"version": 2}"#).unwrap();
+    let err = ClickHouseFilters::from_request(&request, columns()).unwrap_err();
+    assert_eq!(err.to_string(), "Unsupported request format version: 2");
+
+    // Filters and sorts parsed on their own always reject unknown keys
+    assert!(serde_json::from_str::<JsonFilter>(
+        r#"{"n": "status", "f": "=", "v": "open", "connector": "OR"}"#
+    )
+    .is_err());
+    assert!(serde_json::from_str::<JsonSort>(r#"{"n": "name", "direction": "asc"}"#).is_err());
+}
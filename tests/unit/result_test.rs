@@ -0,0 +1,68 @@
+use clickhouse_filters::pagination::Paginate;
+use clickhouse_filters::result::{FacetCount, FacetCounts, QueryResult};
+
+#[test]
+fn test_query_result_envelope() {
+    let paginate = Paginate::new(2, 2, 10, 5);
+    let result = QueryResult::new(vec![3_u64, 4], paginate.pagination.clone());
+    assert_eq!(result.pagination.current_page, 2);
+    assert!(result.facets.is_none());
+
+    // Rows convert without losing the metadata
+    let result = result.map(|id| format!("user-{}", id));
+    assert_eq!(result.rows, vec!["user-3", "user-4"]);
+    assert_eq!(result.pagination, paginate.pagination);
+
+    let single = QueryResult::single_page(vec!["a", "b", "c"]);
+    assert_eq!(single.pagination.total_records, 3);
+    assert_eq!(single.pagination.total_pages, 1);
+    assert_eq!(single.pagination.next_page, 1);
+    let empty = QueryResult::<u64>::single_page(vec![]);
+    assert_eq!(empty.pagination.total_pages, 0);
+}
+
+#[test]
+fn test_query_result_serialization() {
+    let mut facets = FacetCounts::new();
+    facets.insert(
+        "status",
+        vec![FacetCount::new("open", 3), FacetCount::new("closed", 2)],
+    );
+    assert_eq!(facets.len(), 1);
+    assert_eq!(
+        facets.get("status").unwrap()[1],
+        FacetCount::new("closed", 2)
+    );
+    assert!(facets.get("kind").is_none());
+
+    let result = QueryResult::new(vec!["Jane"], Paginate::new(1, 1, 10, 1).pagination);
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "rows": ["Jane"],
+            "pagination": {
+                "current_page": 1,
+                "previous_page": 1,
+                "next_page": 1,
+                "total_pages": 1,
+                "per_page": 1,
+                "total_records": 1
+            }
+        })
+    );
+
+    let result = result.with_facets(facets);
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(
+        json["facets"],
+        serde_json::json!({
+            "status": [
+                {"value": "open", "count": 3},
+                {"value": "closed", "count": 2}
+            ]
+        })
+    );
+    let parsed: QueryResult<String> = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.map(|row| row.len()).rows, vec![4]);
+}
@@ -0,0 +1,150 @@
+use clickhouse_filters::schema::{schema_sql, SchemaSource};
+use clickhouse_filters::table::TableRef;
+use clickhouse_filters::{ColumnDef, ColumnRegistry};
+
+#[test]
+fn test_scalar_types() {
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("name", "String"),
+        Some(ColumnDef::String("name"))
+    ));
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("code", "FixedString(3)"),
+        Some(ColumnDef::FixedString("code"))
+    ));
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("age", "UInt32"),
+        Some(ColumnDef::UInt32("age"))
+    ));
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("active", "Bool"),
+        Some(ColumnDef::Boolean("active"))
+    ));
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("id", "UUID"),
+        Some(ColumnDef::UUID("id"))
+    ));
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("price", "Decimal(10, 2)"),
+        Some(ColumnDef::Decimal("price"))
+    ));
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("status", "Enum8('active' = 1, 'inactive' = 2)"),
+        Some(ColumnDef::Enum8("status"))
+    ));
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("attrs", "Map(String, String)"),
+        Some(ColumnDef::Map("attrs"))
+    ));
+}
+
+#[test]
+fn test_parameterised_date_types() {
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("created_at", "DateTime('UTC')"),
+        Some(ColumnDef::DateTime("created_at"))
+    ));
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("updated_at", "DateTime64(3, 'Europe/London')"),
+        Some(ColumnDef::DateTime64Precision("updated_at", 3))
+    ));
+}
+
+#[test]
+fn test_wrapped_types_are_unwrapped() {
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("email", "Nullable(String)"),
+        Some(ColumnDef::String("email"))
+    ));
+    let country =
+        ColumnDef::from_clickhouse_type("country", "LowCardinality(Nullable(String))").unwrap();
+    assert!(country.is_low_cardinality());
+    assert!(matches!(country.base(), ColumnDef::String("country")));
+    let city = ColumnDef::from_clickhouse_type("city", "Nullable(LowCardinality(String))").unwrap();
+    assert!(
+        matches!(city, ColumnDef::LowCardinality(inner) if matches!(*inner, ColumnDef::String("city")))
+    );
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("tags", "Array(LowCardinality(String))"),
+        Some(ColumnDef::ArrayString("tags"))
+    ));
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("scores", "Array(Nullable(Float64))"),
+        Some(ColumnDef::ArrayFloat64("scores"))
+    ));
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("ids", "Array(UUID)"),
+        Some(ColumnDef::ArrayUUID("ids"))
+    ));
+    assert!(matches!(
+        ColumnDef::from_clickhouse_type("hops", "Array(Nullable(IPv4))"),
+        Some(ColumnDef::ArrayIPv4("hops"))
+    ));
+}
+
+#[test]
+fn test_unsupported_types() {
+    assert!(ColumnDef::from_clickhouse_type("attrs", "Map(UInt64, String)").is_none());
+    assert!(ColumnDef::from_clickhouse_type("point", "Tuple(Float64, Float64)").is_none());
+    assert!(ColumnDef::from_clickhouse_type("nested", "Array(Array(String))").is_none());
+}
+
+#[test]
+fn test_schema_sql() {
+    assert_eq!(
+        schema_sql("db.users", SchemaSource::Describe).unwrap(),
+        "DESCRIBE TABLE db.users"
+    );
+    assert_eq!(
+        schema_sql(("db", "users"), SchemaSource::SystemColumns).unwrap(),
+        "SELECT name, type FROM system.columns WHERE database = 'db' AND table = 'users' ORDER BY position"
+    );
+
+    // Table functions read the schema from the same servers
+    let remote = TableRef::new("db", "users").remote("shard-02:9000");
+    assert_eq!(
+        schema_sql(&remote, SchemaSource::Describe).unwrap(),
+        "DESCRIBE TABLE remote('shard-02:9000', db, users)"
+    );
+    assert_eq!(
+        schema_sql(&remote, SchemaSource::SystemColumns).unwrap(),
+        "SELECT name, type FROM remote('shard-02:9000', system, columns) \
+         WHERE database = 'db' AND table = 'users' ORDER BY position"
+    );
+
+    assert!(schema_sql("db.users' OR 1", SchemaSource::SystemColumns).is_err());
+    assert!(schema_sql(
+        TableRef::new("db", "users").final_(),
+        SchemaSource::Describe
+    )
+    .is_err());
+}
+
+#[test]
+fn test_from_tab_separated() {
+    let output = "name\ttype\tdefault_type\n\
+                  id\tUInt64\t\t\t\t\t\n\
+                  email\tNullable(String)\t\t\t\t\t\n\
+                  order id\tDateTime64(3, 'UTC')\t\t\tOrder\\ttime\t\t\n\
+                  shape\tPolygon\t\t\t\t\t\n";
+    let columns = ColumnRegistry::from_tab_separated(output).unwrap();
+    assert_eq!(columns.len(), 3);
+    assert!(matches!(columns.get("id"), Some(ColumnDef::UInt64("id"))));
+    assert!(matches!(
+        columns.get("email"),
+        Some(ColumnDef::String("email"))
+    ));
+    assert!(matches!(
+        columns.get("`order id`"),
+        Some(ColumnDef::DateTime64Precision("`order id`", 3))
+    ));
+
+    let columns = ColumnRegistry::from_schema_rows([("tags", "Array(String)")]);
+    assert!(matches!(
+        columns.get("tags"),
+        Some(ColumnDef::ArrayString("tags"))
+    ));
+
+    assert!(ColumnRegistry::from_tab_separated("").is_err());
+    assert!(ColumnRegistry::from_tab_separated("id\n").is_err());
+}
@@ -0,0 +1,125 @@
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::sorting::SortedColumn;
+use clickhouse_filters::{
+    ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions, PaginationOptions,
+};
+
+fn columns() -> ColumnRegistry {
+    ColumnRegistry::from_iter([
+        ("name", ColumnDef::String("name")),
+        ("age", ColumnDef::UInt32("age")),
+    ])
+}
+
+#[test]
+fn test_read_limit_settings() {
+    let columns = columns();
+    let filtering = FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::uint32(
+            "age",
+            FilterOperator::GreaterThan,
+            Some(30),
+        ))],
+        &columns,
+    );
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(2, 50, 100, 1000)),
+        vec![],
+        Some(filtering),
+        &columns,
+    )
+    .unwrap()
+    .with_read_limit(100);
+
+    assert_eq!(
+        filters.query_sql("db.users", &[]).unwrap(),
+        "SELECT * FROM db.users WHERE age > 30 LIMIT 50 OFFSET 50 \
+         SETTINGS max_rows_to_read = 5000, max_result_rows = 50"
+    );
+    assert_eq!(
+        filters.count_sql("db.users").unwrap(),
+        "SELECT COUNT(*) FROM db.users WHERE age > 30 SETTINGS max_rows_to_read = 5000"
+    );
+
+    // The fragment is spliced into caller SQL, so it never carries settings
+    assert_eq!(filters.sql().unwrap(), " WHERE age > 30 LIMIT 50 OFFSET 50");
+}
+
+#[test]
+fn test_read_limit_with_ties() {
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(1, 10, 100, 1000).with_ties()),
+        vec![SortedColumn::new("age", "desc")],
+        None,
+        columns(),
+    )
+    .unwrap()
+    .with_read_limit(100);
+
+    // Ties past the page boundary must not trip max_result_rows
+    assert_eq!(
+        filters.query_sql("db.users", &[]).unwrap(),
+        "SELECT * FROM db.users ORDER BY age DESC LIMIT 10 OFFSET 0 WITH TIES \
+         SETTINGS max_rows_to_read = 1000"
+    );
+}
+
+#[test]
+fn test_read_limit_without_pagination() {
+    let filters = ClickHouseFilters::new(None, vec![], None, columns())
+        .unwrap()
+        .with_read_limit(100);
+    assert_eq!(
+        filters.query_sql("db.users", &[]).unwrap(),
+        "SELECT * FROM db.users"
+    );
+}
+
+#[test]
+fn test_query_tag() {
+    let filters = ClickHouseFilters::new(None, vec![], None, columns())
+        .unwrap()
+        .with_query_tag("req-7 user='bob' path=C:\\tmp");
+
+    assert_eq!(
+        filters.count_sql("db.users").unwrap(),
+        "SELECT COUNT(*) FROM db.users SETTINGS log_comment = 'req-7 user=\\'bob\\' path=C:\\\\tmp'"
+    );
+}
+
+#[test]
+fn test_request_settings() {
+    let columns = columns().with_allowed_settings(&["max_threads", "max_rows_to_read", "priority"]);
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(1, 10, 100, 1000)),
+        vec![],
+        None,
+        &columns,
+    )
+    .unwrap()
+    .with_read_limit(100)
+    .with_setting("max_threads", "4")
+    .unwrap()
+    .with_setting("max_threads", "16")
+    .unwrap()
+    .with_setting("max_rows_to_read", "5000")
+    .unwrap()
+    .with_setting("priority", "it's")
+    .unwrap();
+
+    assert_eq!(
+        filters.query_sql("db.users", &[]).unwrap(),
+        "SELECT * FROM db.users LIMIT 10 OFFSET 0 \
+         SETTINGS max_result_rows = 10, max_threads = 16, max_rows_to_read = 5000, priority = 'it\\'s'"
+    );
+    assert_eq!(
+        filters.count_sql("db.users").unwrap(),
+        "SELECT COUNT(*) FROM db.users \
+         SETTINGS max_threads = 16, max_rows_to_read = 5000, priority = 'it\\'s'"
+    );
+    // The fragment never carries settings
+    assert_eq!(filters.sql().unwrap(), " LIMIT 10 OFFSET 0");
+
+    let err = filters.with_setting("readonly", "0").unwrap_err();
+    assert_eq!(err.to_string(), "Setting not allowed: readonly");
+}
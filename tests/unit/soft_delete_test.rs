@@ -0,0 +1,77 @@
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions};
+use std::collections::HashMap;
+
+fn registry(soft_delete: &'static str) -> ColumnRegistry {
+    let mut columns = HashMap::new();
+    columns.insert("age", ColumnDef::UInt32("age"));
+    columns.insert("deleted_at", ColumnDef::DateTime("deleted_at"));
+    columns.insert("is_deleted", ColumnDef::UInt8("is_deleted"));
+    columns.insert("name", ColumnDef::String("name"));
+    ColumnRegistry::new(columns).with_soft_delete(soft_delete)
+}
+
+fn or_filter(columns: &ColumnRegistry) -> FilteringOptions {
+    FilteringOptions::case_sensitive(
+        vec![FilterExpression::or(vec![
+            FilterExpression::Condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::LessThan,
+                Some(18),
+            )),
+            FilterExpression::Condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::GreaterThan,
+                Some(65),
+            )),
+        ])],
+        columns,
+    )
+}
+
+#[test]
+fn test_soft_delete_excludes_deleted_rows() {
+    let columns = registry("deleted_at");
+    let filters =
+        ClickHouseFilters::new(None, vec![], Some(or_filter(&columns)), &columns).unwrap();
+
+    assert_eq!(
+        filters.count_sql("db.users").unwrap(),
+        "SELECT COUNT(*) FROM db.users WHERE ((age < 18 OR age > 65) AND deleted_at IS NULL)"
+    );
+    assert_eq!(
+        filters.query_sql("db.users", &["name"]).unwrap(),
+        "SELECT name FROM db.users WHERE ((age < 18 OR age > 65) AND deleted_at IS NULL)"
+    );
+
+    // Flag columns and queries without user filters
+    let columns = registry("is_deleted");
+    let filters = ClickHouseFilters::new(None, vec![], None, &columns).unwrap();
+    assert_eq!(filters.sql().unwrap(), " WHERE is_deleted = 0");
+}
+
+#[test]
+fn test_include_deleted() {
+    let columns = registry("deleted_at");
+    let filters = ClickHouseFilters::new(None, vec![], Some(or_filter(&columns)), &columns)
+        .unwrap()
+        .include_deleted();
+
+    assert_eq!(filters.sql().unwrap(), " WHERE (age < 18 OR age > 65)");
+
+    let filters = ClickHouseFilters::new(None, vec![], None, &columns)
+        .unwrap()
+        .include_deleted();
+    assert_eq!(filters.sql().unwrap(), "");
+}
+
+#[test]
+fn test_soft_delete_column_must_be_supported() {
+    let filters = ClickHouseFilters::new(None, vec![], None, registry("name")).unwrap();
+    let err = filters.sql().unwrap_err();
+    assert_eq!(err.to_string(), "Unsupported soft-delete column type: name");
+
+    let filters = ClickHouseFilters::new(None, vec![], None, registry("removed")).unwrap();
+    let err = filters.sql().unwrap_err();
+    assert_eq!(err.to_string(), "Column not found: removed");
+}
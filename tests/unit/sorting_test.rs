@@ -1,4 +1,6 @@
-use clickhouse_filters::sorting::{SortOrder, SortedColumn, Sorting};
+use clickhouse_filters::filtering::JsonNumberType;
+use clickhouse_filters::sorting::{JsonSort, SortOrder, SortTarget, SortedColumn, Sorting};
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, ColumnRegistry};
 
 #[test]
 fn test_sorting_with_multiple_columns() {
@@ -8,7 +10,7 @@ fn test_sorting_with_multiple_columns() {
     ]);
 
     assert_eq!(sorting.columns.len(), 2);
-    assert_eq!(sorting.sql, " ORDER BY age DESC, name ASC");
+    assert_eq!(sorting.to_sql(), " ORDER BY age DESC, name ASC");
 }
 
 #[test]
@@ -16,7 +18,7 @@ fn test_sorting_with_single_column() {
     let sorting = Sorting::new(vec![SortedColumn::new("name", "asc")]);
 
     assert_eq!(sorting.columns.len(), 1);
-    assert_eq!(sorting.sql, " ORDER BY name ASC");
+    assert_eq!(sorting.to_sql(), " ORDER BY name ASC");
 }
 
 #[test]
@@ -24,7 +26,7 @@ fn test_sorting_with_empty_columns() {
     let sorting = Sorting::new(vec![]);
 
     assert_eq!(sorting.columns.len(), 0);
-    assert_eq!(sorting.sql, "");
+    assert_eq!(sorting.to_sql(), "");
 }
 
 #[test]
@@ -48,7 +50,7 @@ fn test_sorting_with_duplicated_columns() {
     ]);
 
     assert_eq!(sorting.columns.len(), 2);
-    assert_eq!(sorting.sql, " ORDER BY age DESC, name ASC");
+    assert_eq!(sorting.to_sql(), " ORDER BY age DESC, name ASC");
 }
 
 #[test]
@@ -57,5 +59,228 @@ fn test_sorting_with_invalid_order() {
     let sorting = Sorting::new(vec![SortedColumn::new("name", "invalid")]);
 
     assert_eq!(sorting.columns.len(), 1);
-    assert_eq!(sorting.sql, " ORDER BY name ASC");
+    assert_eq!(sorting.to_sql(), " ORDER BY name ASC");
+}
+
+#[test]
+fn test_sorting_from_json_sorts() {
+    let columns = ColumnRegistry::from_iter([
+        ("name", ColumnDef::String("name")),
+        ("created_at", ColumnDef::DateTime("created_at")),
+        ("priority", ColumnDef::UInt8("priority")),
+    ]);
+    let sort = |n: &str, d: Option<&str>| JsonSort {
+        n: n.to_string(),
+        d: d.map(str::to_string),
+    };
+
+    let sorting = Sorting::from_json_sorts(
+        &[sort("-created_at", None), sort("name", Some("DESC"))],
+        &columns,
+    )
+    .unwrap();
+    assert_eq!(sorting.columns[0].order, SortOrder::Desc);
+    assert_eq!(sorting.to_sql(), " ORDER BY created_at DESC, name DESC");
+
+    let sorting = Sorting::from_json_sorts(&[sort("name", None)], &columns).unwrap();
+    assert_eq!(sorting.to_sql(), " ORDER BY name ASC");
+
+    // Keys stay in the requested order, also once passed to ClickHouseFilters,
+    // and only repeats of a column are dropped
+    let sorting = Sorting::from_json_sorts(
+        &[
+            sort("-priority", None),
+            sort("created_at", None),
+            sort("priority", None),
+        ],
+        &columns,
+    )
+    .unwrap();
+    assert_eq!(sorting.to_sql(), " ORDER BY priority DESC, created_at ASC");
+    let filters = ClickHouseFilters::new(None, sorting.columns, None, &columns).unwrap();
+    assert_eq!(
+        filters.sql().unwrap(),
+        " ORDER BY priority DESC, created_at ASC"
+    );
+
+    let err = Sorting::from_json_sorts(&[sort("-password", None)], &columns).unwrap_err();
+    assert_eq!(err.to_string(), "Column not found: password");
+
+    let err = Sorting::from_json_sorts(&[sort("name", Some("up"))], &columns).unwrap_err();
+    assert_eq!(err.to_string(), "Invalid sort direction: up");
+
+    let err = Sorting::from_json_sorts(&[sort("-name", Some("asc"))], &columns).unwrap_err();
+    assert_eq!(err.to_string(), "Conflicting sort direction for: name");
+}
+
+#[test]
+fn test_sorting_by_json_path() {
+    let sorting = Sorting::new(vec![
+        SortedColumn::json("data", "priority", "desc"),
+        SortedColumn::json("data", "score", "asc").with_number_type(JsonNumberType::Float),
+        SortedColumn::json("data", "priority", "asc"),
+        SortedColumn::new("data", "asc"),
+    ]);
+    assert_eq!(
+        sorting.to_sql(),
+        " ORDER BY JSONExtractFloat(data, 'score') ASC, \
+         JSONExtractString(data, 'priority') DESC, data ASC"
+    );
+    assert_eq!(sorting.referenced_columns().len(), 1);
+
+    let sorting = Sorting::new(vec![SortedColumn::json("data", "it's", "asc")]);
+    assert_eq!(
+        sorting.to_sql(),
+        " ORDER BY JSONExtractString(data, 'it''s') ASC"
+    );
+}
+
+#[test]
+fn test_sorting_json_sorts_by_path() {
+    let columns = ColumnRegistry::from_iter([
+        ("data", ColumnDef::JSON("data")),
+        ("name", ColumnDef::String("name")),
+    ])
+    .with_json_path_type("data", "priority", JsonNumberType::Int);
+    let sort = |n: &str| JsonSort {
+        n: n.to_string(),
+        d: None,
+    };
+
+    let sorting =
+        Sorting::from_json_sorts(&[sort("-data.priority"), sort("data.user.name")], &columns)
+            .unwrap();
+    assert_eq!(
+        sorting.to_sql(),
+        " ORDER BY JSONExtractInt(data, 'priority') DESC, \
+         JSONExtractString(data, 'user', 'name') ASC"
+    );
+
+    // Only JSON columns have paths
+    let err = Sorting::from_json_sorts(&[sort("name.first")], &columns).unwrap_err();
+    assert_eq!(err.to_string(), "Column not found: name.first");
+}
+
+#[test]
+fn test_sorting_by_array_length_and_element() {
+    let sorting = Sorting::in_order(vec![
+        SortedColumn::array_length("tags", "desc"),
+        SortedColumn::array_element("scores", 1, "asc"),
+        SortedColumn::array_element("scores", -1, "desc"),
+        SortedColumn::new("tags", "asc"),
+        SortedColumn::array_length("tags", "asc"),
+    ]);
+    assert_eq!(
+        sorting.to_sql(),
+        " ORDER BY length(tags) DESC, arrayElement(scores, 1) ASC, \
+         arrayElement(scores, -1) DESC, tags ASC"
+    );
+    assert_eq!(sorting.columns[0].target, SortTarget::ArrayLength);
+
+    // Array keys keep their order through ClickHouseFilters too
+    let columns = ColumnRegistry::from_iter([
+        ("tags", ColumnDef::ArrayString("tags")),
+        ("scores", ColumnDef::ArrayUInt32("scores")),
+    ]);
+    let filters = ClickHouseFilters::new(None, sorting.columns.clone(), None, &columns).unwrap();
+    assert_eq!(filters.sql().unwrap(), sorting.to_sql());
+    assert_eq!(
+        sorting.referenced_columns().into_iter().collect::<Vec<_>>(),
+        vec!["scores", "tags"]
+    );
+}
+
+#[test]
+fn test_sorted_column_strict_direction() {
+    let column = SortedColumn::try_new("name", "DESC").unwrap();
+    assert_eq!(column.order, SortOrder::Desc);
+
+    let err = SortedColumn::try_new("name", "dsc").unwrap_err();
+    assert_eq!(err.to_string(), "Invalid sort direction: dsc");
+    // The lenient constructor still falls back to ASC
+    assert_eq!(SortedColumn::new("name", "dsc").order, SortOrder::Asc);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_sorted_column_deserialize() {
+    let columns: Vec<SortedColumn> = serde_json::from_str(
+        r#"[
+            {"column": "name", "order": "desc"},
+            {"column": "tags", "order": "ASC", "target": "array_length"}
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        Sorting::new(columns).to_sql(),
+        " ORDER BY length(tags) ASC, name DESC"
+    );
+
+    let err =
+        serde_json::from_str::<SortedColumn>(r#"{"column": "name", "order": "dsc"}"#).unwrap_err();
+    assert!(err.to_string().contains("unknown variant `dsc`"));
+}
+
+#[test]
+fn test_sorting_renders_on_demand() {
+    let mut sorting = Sorting::new(vec![SortedColumn::new("name", "asc")]);
+    assert_eq!(sorting.to_fragment(), "ORDER BY name ASC");
+
+    sorting.columns.push(SortedColumn::new("age", "desc"));
+    assert_eq!(sorting.to_sql(), " ORDER BY name ASC, age DESC");
+
+    let mut sql = String::from("SELECT * FROM users");
+    sorting.write_sql(&mut sql);
+    assert_eq!(sql, "SELECT * FROM users ORDER BY name ASC, age DESC");
+
+    assert_eq!(Sorting::new(vec![]).to_fragment(), "");
+}
+
+#[test]
+fn test_sorting_by_case_ranks() {
+    let priority = SortedColumn::case("status", "asc")
+        .when("critical", 0)
+        .when("high", 1);
+    assert_eq!(
+        priority.expression(),
+        "CASE WHEN status = 'critical' THEN 0 WHEN status = 'high' THEN 1 ELSE 2 END"
+    );
+
+    // CASE keys keep their place among the other keys
+    let sorting = Sorting::in_order(vec![
+        SortedColumn::new("created_at", "desc"),
+        SortedColumn::case("owner", "desc")
+            .when("O'Brien", 5)
+            .otherwise(-1),
+    ]);
+    assert_eq!(
+        sorting.to_sql(),
+        " ORDER BY created_at DESC, CASE WHEN owner = 'O''Brien' THEN 5 ELSE -1 END DESC"
+    );
+
+    // Nothing ranks above i64::MAX, so that needs an explicit otherwise()
+    let columns = ColumnRegistry::from_iter([("status", ColumnDef::String("status"))]);
+    let highest = SortedColumn::case("status", "asc").when("critical", i64::MAX);
+    assert_eq!(
+        highest.validate().unwrap_err().to_string(),
+        format!("Sort rank out of range for status: {}", i64::MAX)
+    );
+    assert!(ClickHouseFilters::new(None, vec![highest.clone()], None, &columns).is_err());
+    assert_eq!(
+        highest.expression(),
+        format!(
+            "CASE WHEN status = 'critical' THEN {0} ELSE {0} END",
+            i64::MAX
+        )
+    );
+    let highest = highest.otherwise(0);
+    assert!(highest.validate().is_ok());
+    assert!(ClickHouseFilters::new(None, vec![highest], None, &columns).is_ok());
+
+    // Without ranks the column sorts by its value; other targets ignore ranks
+    assert_eq!(SortedColumn::case("status", "asc").expression(), "status");
+    assert_eq!(
+        SortedColumn::new("status", "asc").when("x", 0).target,
+        SortTarget::Column
+    );
 }
@@ -0,0 +1,123 @@
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::sorting::SortedColumn;
+use clickhouse_filters::{
+    ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions, PaginationOptions,
+};
+
+fn columns() -> ColumnRegistry {
+    ColumnRegistry::from_iter([
+        ("id", ColumnDef::UInt64("id")),
+        ("country", ColumnDef::String("country")),
+    ])
+}
+
+fn country_filter(columns: &ColumnRegistry) -> FilteringOptions {
+    FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::string(
+            "country",
+            FilterOperator::Equal,
+            Some("NZ"),
+        ))],
+        columns,
+    )
+}
+
+#[test]
+fn test_insert_select_sql() {
+    let columns = columns();
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(1, 100, 100, 1000)),
+        vec![SortedColumn::new("id", "asc")],
+        Some(country_filter(&columns)),
+        &columns,
+    )
+    .unwrap();
+
+    assert_eq!(
+        filters
+            .insert_select_sql("db.users", "extracts", "nz_users", &["id"])
+            .unwrap(),
+        "INSERT INTO extracts.nz_users (id) SELECT id FROM db.users \
+         WHERE country = 'NZ' ORDER BY id ASC LIMIT 100 OFFSET 0"
+    );
+
+    let filters =
+        ClickHouseFilters::new(None, vec![], Some(country_filter(&columns)), &columns).unwrap();
+    assert_eq!(
+        filters
+            .insert_select_sql("db.users", "extracts", "nz_users", &[])
+            .unwrap(),
+        "INSERT INTO extracts.nz_users SELECT * FROM db.users WHERE country = 'NZ'"
+    );
+    assert!(filters
+        .insert_select_sql("db.users", "extracts", "nz_users", &["email"])
+        .is_err());
+
+    // The target is validated like the source table
+    for (schema, table) in [
+        ("extracts", "nz_users SELECT 1 --"),
+        ("extracts; DROP TABLE t", "nz_users"),
+        ("", "nz_users"),
+    ] {
+        assert!(filters
+            .insert_select_sql("db.users", schema, table, &[])
+            .is_err());
+    }
+}
+
+#[test]
+fn test_create_view_sql() {
+    let columns = ColumnRegistry::from_iter([
+        ("id", ColumnDef::UInt64("id")),
+        ("country", ColumnDef::String("country")),
+        ("deleted_at", ColumnDef::DateTime("deleted_at")),
+    ])
+    .with_soft_delete("deleted_at");
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(1, 100, 100, 1000)),
+        vec![SortedColumn::new("id", "asc")],
+        Some(country_filter(&columns)),
+        &columns,
+    )
+    .unwrap();
+
+    // Sorting and pagination stay out of the view
+    assert_eq!(
+        filters
+            .create_view_sql("db.users", "segments", "nz_users", &["id", "country"])
+            .unwrap(),
+        "CREATE OR REPLACE VIEW segments.nz_users AS SELECT id, country FROM db.users \
+         WHERE (country = 'NZ' AND deleted_at IS NULL)"
+    );
+    assert!(filters
+        .create_view_sql("db.users", "segments", "nz_users", &["email"])
+        .is_err());
+
+    for (schema, name) in [
+        ("segments", "nz_users AS SELECT 1 --"),
+        ("segments.other", "nz_users"),
+        ("segments", ""),
+    ] {
+        assert!(filters
+            .create_view_sql("db.users", schema, name, &[])
+            .is_err());
+    }
+}
+
+#[test]
+fn test_verify_sql() {
+    let columns = columns();
+    let filters = ClickHouseFilters::new(
+        Some(PaginationOptions::new(2, 10, 100, 1000)),
+        vec![],
+        Some(country_filter(&columns)),
+        &columns,
+    )
+    .unwrap();
+
+    assert_eq!(
+        filters.verify_sql("db.users", &["id"]).unwrap(),
+        "EXPLAIN SELECT id FROM db.users WHERE country = 'NZ' LIMIT 10 OFFSET 10"
+    );
+    assert!(filters.verify_sql("users", &["id"]).is_err());
+}
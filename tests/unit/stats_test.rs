@@ -0,0 +1,133 @@
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions};
+use std::collections::HashMap;
+
+#[test]
+fn test_stats_sql() {
+    let mut columns = HashMap::new();
+    columns.insert("price", ColumnDef::Float64("price"));
+    columns.insert("category", ColumnDef::String("category"));
+    let filtering = FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::string(
+            "category",
+            FilterOperator::Equal,
+            Some("books"),
+        ))],
+        columns.clone(),
+    );
+    let filters = ClickHouseFilters::new(None, vec![], Some(filtering), columns).unwrap();
+
+    assert_eq!(
+        filters.stats_sql("shop.products", "price").unwrap(),
+        "SELECT min(price), max(price), avg(price), quantile(0.5)(price) \
+         FROM shop.products WHERE category = 'books'"
+    );
+    assert_eq!(
+        filters
+            .stats_sql("shop.products", "category")
+            .unwrap_err()
+            .to_string(),
+        "Statistics are only supported on numeric columns: category"
+    );
+    assert_eq!(
+        filters
+            .stats_sql("shop.products", "weight")
+            .unwrap_err()
+            .to_string(),
+        "Column not found: weight"
+    );
+}
+
+fn condition(column: &str, value: &str) -> FilterExpression {
+    FilterExpression::Condition(FilterCondition::string(
+        column,
+        FilterOperator::Equal,
+        Some(value),
+    ))
+}
+
+#[test]
+fn test_distinct_values_sql() {
+    let mut columns = HashMap::new();
+    columns.insert("country", ColumnDef::String("country"));
+    columns.insert("status", ColumnDef::String("status"));
+    columns.insert("is_deleted", ColumnDef::UInt8("is_deleted"));
+    let columns = ColumnRegistry::new(columns).with_soft_delete("is_deleted");
+    let filtering = FilteringOptions::case_sensitive(
+        vec![
+            condition("country", "NZ"),
+            FilterExpression::or(vec![
+                condition("status", "open"),
+                condition("status", "paid"),
+            ]),
+        ],
+        &columns,
+    );
+    let filters = ClickHouseFilters::new(None, vec![], Some(filtering), &columns).unwrap();
+
+    assert_eq!(
+        filters
+            .distinct_values_sql("db.orders", "status", 50, false)
+            .unwrap(),
+        "SELECT DISTINCT status FROM db.orders \
+         WHERE (country = 'NZ' AND (status = 'open' OR status = 'paid') AND is_deleted = 0) \
+         ORDER BY status LIMIT 50"
+    );
+    // The status selection is dropped, the soft-delete condition is kept
+    assert_eq!(
+        filters
+            .distinct_values_sql("db.orders", "status", 50, true)
+            .unwrap(),
+        "SELECT DISTINCT status FROM db.orders \
+         WHERE (country = 'NZ' AND is_deleted = 0) ORDER BY status LIMIT 50"
+    );
+    assert!(filters
+        .distinct_values_sql("db.orders", "city", 50, true)
+        .is_err());
+
+    // Dropping one side of an OR would narrow the values, so the whole OR goes
+    let filtering = FilteringOptions::case_sensitive(
+        vec![FilterExpression::or(vec![
+            condition("status", "open"),
+            condition("country", "NZ"),
+        ])],
+        &columns,
+    );
+    let filters = ClickHouseFilters::new(None, vec![], Some(filtering), &columns).unwrap();
+    assert_eq!(
+        filters
+            .distinct_values_sql("db.orders", "status", 50, true)
+            .unwrap(),
+        "SELECT DISTINCT status FROM db.orders WHERE is_deleted = 0 ORDER BY status LIMIT 50"
+    );
+}
+
+#[test]
+fn test_without_column() {
+    let expr = FilterExpression::and(vec![
+        condition("country", "NZ"),
+        FilterExpression::or(vec![condition("status", "open"), condition("city", "x")]),
+    ]);
+    assert_eq!(
+        expr.without_column("status")
+            .unwrap()
+            .to_sql(false)
+            .unwrap(),
+        "country = 'NZ'"
+    );
+    assert_eq!(condition("status", "open").without_column("status"), None);
+
+    // Groups left untouched are kept, and AND groups inside OR lose only the column
+    let expr = FilterExpression::or(vec![
+        FilterExpression::and(vec![condition("status", "open"), condition("city", "x")]),
+        condition("country", "NZ"),
+    ]);
+    assert_eq!(
+        expr.without_column("status")
+            .unwrap()
+            .to_sql(false)
+            .unwrap(),
+        "(city = 'x' OR country = 'NZ')"
+    );
+    assert_eq!(expr.without_column("region"), Some(expr.clone()));
+}
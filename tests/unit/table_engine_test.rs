@@ -0,0 +1,111 @@
+use clickhouse_filters::count::{CountExpression, Deduplication};
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions};
+
+fn collapsing_registry() -> ColumnRegistry {
+    ColumnRegistry::from_iter([
+        ("user_id", ColumnDef::UInt64("user_id")),
+        ("status", ColumnDef::String("status")),
+        ("sign", ColumnDef::Int8("sign")),
+    ])
+    .with_sign_column("sign")
+}
+
+fn status_filter(columns: &ColumnRegistry) -> FilteringOptions {
+    FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::string(
+            "status",
+            FilterOperator::Equal,
+            Some("active"),
+        ))],
+        columns,
+    )
+}
+
+#[test]
+fn test_collapsing_sign_column() {
+    let columns = collapsing_registry();
+    let filters =
+        ClickHouseFilters::new(None, vec![], Some(status_filter(&columns)), &columns).unwrap();
+
+    assert_eq!(
+        filters.query_sql("db.sessions", &["user_id"]).unwrap(),
+        "SELECT user_id FROM db.sessions WHERE (status = 'active' AND sign = 1)"
+    );
+    assert_eq!(
+        filters.count_sql("db.sessions").unwrap(),
+        "SELECT sum(sign) FROM db.sessions WHERE status = 'active'"
+    );
+
+    // Distinct counts keep the sign filter instead
+    assert_eq!(
+        filters
+            .count_sql_with("db.sessions", &CountExpression::uniq("user_id"))
+            .unwrap(),
+        "SELECT uniq(user_id) FROM db.sessions WHERE (status = 'active' AND sign = 1)"
+    );
+
+    let filters = ClickHouseFilters::new(None, vec![], None, &columns).unwrap();
+    assert_eq!(
+        filters.count_sql("db.sessions").unwrap(),
+        "SELECT sum(sign) FROM db.sessions"
+    );
+}
+
+#[test]
+fn test_collapsing_sign_column_must_be_int8() {
+    let columns =
+        ColumnRegistry::from_iter([("sign", ColumnDef::UInt8("sign"))]).with_sign_column("sign");
+    let filters = ClickHouseFilters::new(None, vec![], None, &columns).unwrap();
+    assert!(filters.sql().is_err());
+    assert!(filters.count_sql("db.sessions").is_err());
+}
+
+#[test]
+fn test_replacing_deduplicated_count() {
+    let columns = ColumnRegistry::from_iter([
+        ("id", ColumnDef::UInt64("id")),
+        ("version", ColumnDef::UInt32("version")),
+        ("status", ColumnDef::String("status")),
+    ]);
+
+    let final_columns = columns
+        .clone()
+        .with_deduplicated_count(Deduplication::Final);
+    let filters = ClickHouseFilters::new(
+        None,
+        vec![],
+        Some(status_filter(&final_columns)),
+        &final_columns,
+    )
+    .unwrap();
+    assert_eq!(
+        filters.count_sql("db.orders").unwrap(),
+        "SELECT COUNT(*) FROM db.orders FINAL WHERE status = 'active'"
+    );
+
+    let key_columns = columns
+        .clone()
+        .with_deduplicated_count(Deduplication::UniqueKey(vec!["id"]));
+    let filters = ClickHouseFilters::new(
+        None,
+        vec![],
+        Some(status_filter(&key_columns)),
+        &key_columns,
+    )
+    .unwrap();
+    assert_eq!(
+        filters.count_sql("db.orders").unwrap(),
+        "SELECT uniqExact(id) FROM db.orders WHERE status = 'active'"
+    );
+
+    // Row queries are unchanged
+    assert_eq!(
+        filters.query_sql("db.orders", &[]).unwrap(),
+        "SELECT * FROM db.orders WHERE status = 'active'"
+    );
+
+    let missing = columns.with_deduplicated_count(Deduplication::UniqueKey(vec!["order_id"]));
+    let filters = ClickHouseFilters::new(None, vec![], None, &missing).unwrap();
+    assert!(filters.count_sql("db.orders").is_err());
+}
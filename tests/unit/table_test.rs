@@ -0,0 +1,141 @@
+use clickhouse_filters::count::Deduplication;
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::table::{TableFunction, TableRef};
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions};
+
+fn filters(columns: &ColumnRegistry) -> ClickHouseFilters {
+    let filtering = FilteringOptions::case_sensitive(
+        vec![FilterExpression::Condition(FilterCondition::string(
+            "status",
+            FilterOperator::Equal,
+            Some("active"),
+        ))],
+        columns,
+    );
+    ClickHouseFilters::new(None, vec![], Some(filtering), columns).unwrap()
+}
+
+fn columns() -> ColumnRegistry {
+    ColumnRegistry::from_iter([("status", ColumnDef::String("status"))])
+}
+
+#[test]
+fn test_table_ref_conversions() {
+    assert_eq!(TableRef::from("db.users"), TableRef::new("db", "users"));
+    assert_eq!(
+        TableRef::from(("db", "users")),
+        TableRef::new("db", "users")
+    );
+
+    let filters = filters(&columns());
+    let expected = "SELECT * FROM db.users WHERE status = 'active'";
+    assert_eq!(filters.query_sql("db.users", &[]).unwrap(), expected);
+    assert_eq!(filters.query_sql(("db", "users"), &[]).unwrap(), expected);
+    assert_eq!(
+        filters
+            .query_sql(TableRef::new("db", "users"), &[])
+            .unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn test_table_ref_modifiers() {
+    let filters = filters(&columns());
+    let table = TableRef::new("db", "events")
+        .final_()
+        .sample("1/10 OFFSET 1/2");
+    assert_eq!(
+        filters.query_sql(&table, &["status"]).unwrap(),
+        "SELECT status FROM db.events FINAL SAMPLE 1/10 OFFSET 1/2 WHERE status = 'active'"
+    );
+    assert_eq!(
+        filters.count_sql(&table).unwrap(),
+        "SELECT COUNT(*) FROM db.events FINAL SAMPLE 1/10 OFFSET 1/2 WHERE status = 'active'"
+    );
+
+    // Count deduplication with FINAL doesn't repeat the modifier
+    let columns = columns().with_deduplicated_count(Deduplication::Final);
+    let filters = self::filters(&columns);
+    assert_eq!(
+        filters
+            .count_sql(TableRef::new("db", "events").final_())
+            .unwrap(),
+        "SELECT COUNT(*) FROM db.events FINAL WHERE status = 'active'"
+    );
+    assert_eq!(
+        filters.query_sql("db.events", &[]).unwrap(),
+        "SELECT * FROM db.events WHERE status = 'active'"
+    );
+}
+
+#[test]
+fn test_table_ref_clusters() {
+    let filters = filters(&columns());
+    assert_eq!(
+        filters
+            .count_sql(TableRef::new("db", "events").cluster_all_replicas("prod-eu.1"))
+            .unwrap(),
+        "SELECT COUNT(*) FROM clusterAllReplicas('prod-eu.1', db, events) WHERE status = 'active'"
+    );
+
+    let table = TableRef::new("db", "events").cluster("main");
+    assert_eq!(
+        table.function,
+        Some(TableFunction::Cluster("main".to_string()))
+    );
+    assert_eq!(
+        filters.query_sql(&table, &[]).unwrap(),
+        "SELECT * FROM cluster('main', db, events) WHERE status = 'active'"
+    );
+}
+
+#[test]
+fn test_table_ref_validation() {
+    let filters = filters(&columns());
+    for table in [
+        TableRef::from("users"),
+        TableRef::new("db", "users; DROP TABLE users"),
+        TableRef::new("db`", "users"),
+        TableRef::new("db", "users").cluster("main') --"),
+        TableRef::new("db", "users").cluster(""),
+        TableRef::new("db", "users").remote("host', 'default', 'secret"),
+        TableRef::new("db", "users").remote("host 9000"),
+        TableRef::new("db", "users").sample("0.1; SELECT 1"),
+        TableRef::new("db", "users").sample("1/"),
+        TableRef::new("db", "users").sample("0.1 OFFSET x"),
+    ] {
+        assert!(table.validate().is_err(), "{:?}", table);
+        assert!(filters.query_sql(&table, &[]).is_err());
+        assert!(filters.count_sql(&table).is_err());
+    }
+
+    assert!(TableRef::new("db", "users")
+        .sample("10000")
+        .validate()
+        .is_ok());
+    assert!(TableRef::new("db", "users")
+        .sample("0.25")
+        .validate()
+        .is_ok());
+}
+
+#[test]
+fn test_table_ref_remote() {
+    let filters = filters(&columns());
+    assert_eq!(
+        filters
+            .query_sql(
+                TableRef::new("db", "events").remote("shard-{01..03}.example.com:9000"),
+                &["status"]
+            )
+            .unwrap(),
+        "SELECT status FROM remote('shard-{01..03}.example.com:9000', db, events) WHERE status = 'active'"
+    );
+    assert_eq!(
+        filters
+            .count_sql(TableRef::new("db", "events").remote("[::1]:9000,10.0.0.2").final_())
+            .unwrap(),
+        "SELECT COUNT(*) FROM remote('[::1]:9000,10.0.0.2', db, events) FINAL WHERE status = 'active'"
+    );
+}
@@ -0,0 +1,71 @@
+use clickhouse_filters::testing::{
+    assert_sql_eq, fixture_columns, json_filter_corpus, normalize_sql,
+};
+use clickhouse_filters::{ColumnDef, ColumnRegistry, FilteringOptions};
+
+#[test]
+fn test_normalize_sql() {
+    assert_eq!(
+        normalize_sql("\n  SELECT  id,\n\tname\n  FROM db.users\n  WHERE ( age > 25 )\n"),
+        "SELECT id, name FROM db.users WHERE (age > 25)"
+    );
+    assert_eq!(normalize_sql("f( a , b )"), "f(a, b)");
+
+    // Quoted text is kept as is, including escaped quotes
+    assert_eq!(
+        normalize_sql("name  =  'a  \\'  b'   AND \"my  col\" = 1"),
+        "name = 'a  \\'  b' AND \"my  col\" = 1"
+    );
+}
+
+#[test]
+fn test_assert_sql_eq() {
+    assert_sql_eq(
+        " WHERE (age > 25 AND name = 'John')",
+        "
+        WHERE (
+            age > 25
+            AND name = 'John'
+        )
+        ",
+    );
+
+    let result = std::panic::catch_unwind(|| assert_sql_eq("name = 'a  b'", "name = 'a b'"));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_json_filter_corpus() {
+    let columns = fixture_columns();
+    let corpus = json_filter_corpus(&columns);
+
+    // Every column is covered, in name order, and every filter renders
+    let mut names: Vec<&str> = corpus.iter().map(|filter| filter.n.as_str()).collect();
+    names.dedup();
+    assert_eq!(names.len(), columns.len());
+    assert_eq!(names[0], "active");
+    assert!(names.contains(&"attrs['key']"));
+
+    for filter in &corpus {
+        let filtering = FilteringOptions::from_json_filters(std::slice::from_ref(filter), &columns)
+            .unwrap_or_else(|err| panic!("{} {} {:?}: {}", filter.n, filter.f, filter.v, err))
+            .unwrap();
+        assert!(filtering.to_sql().is_ok(), "{:?}", filter);
+    }
+
+    // Types outside the fixture are covered too
+    let columns = ColumnRegistry::from_iter([
+        ("code", ColumnDef::FixedString("code")),
+        ("ts", ColumnDef::DateTime64Precision("ts", 3)),
+        ("addrs", ColumnDef::ArrayIPv6("addrs")),
+    ]);
+    let corpus = json_filter_corpus(&columns);
+    assert!(corpus.iter().any(|filter| filter.n == "ts"));
+    for filter in &corpus {
+        assert!(
+            FilteringOptions::from_json_filters(std::slice::from_ref(filter), &columns).is_ok(),
+            "{:?}",
+            filter
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use clickhouse_filters::filtering::{FilterCondition, FilterExpression, FilterOperator};
+use clickhouse_filters::{ColumnDef, FilteringOptions};
+
+clickhouse_filters::table! {
+    mod users {
+        id: UInt64,
+        name: String,
+        age: UInt32,
+        score: Float64,
+        active: Boolean,
+        status: Enum8,
+        user_id: UUID,
+        birth_date: Date,
+        created_at: DateTime,
+        tags: ArrayString,
+        scores: ArrayUInt32,
+    }
+}
+
+use users::col;
+
+fn sql(expr: FilterExpression) -> String {
+    expr.to_sql(false).unwrap()
+}
+
+#[test]
+fn test_typed_numeric_columns() {
+    assert_eq!(sql(col::age.gt(25)), "age > 25");
+    assert_eq!(sql(col::age.lte(65)), "age <= 65");
+    assert_eq!(sql(col::id.eq(7)), "id = 7");
+    assert_eq!(sql(col::score.gte(0.5)), "score >= 0.5");
+    assert_eq!(sql(col::age.in_list([18, 21])), "age IN (18, 21)");
+    assert_eq!(sql(col::age.is_null()), "age IS NULL");
+}
+
+#[test]
+fn test_typed_string_columns() {
+    assert_eq!(sql(col::name.eq("O'Brien")), "name = 'O''Brien'");
+    assert_eq!(sql(col::name.like("%x%")), "name LIKE '%x%'");
+    assert_eq!(sql(col::name.starts_with("Jo")), "name LIKE 'Jo%'");
+    assert_eq!(
+        sql(col::status.not_in(["closed", "spam"])),
+        "status NOT IN ('closed', 'spam')"
+    );
+    assert_eq!(sql(col::active.eq(true)), "active = 1");
+    assert_eq!(sql(col::active.in_list([true, false])), "active IN (1, 0)");
+
+    // Matches the condition built by hand
+    assert_eq!(
+        col::name.like("%x%"),
+        FilterExpression::Condition(FilterCondition::string(
+            "name",
+            FilterOperator::Like,
+            Some("%x%")
+        ))
+    );
+}
+
+#[test]
+fn test_typed_temporal_and_uuid_columns() {
+    assert_eq!(
+        sql(col::birth_date.lt("2000-01-01")),
+        "birth_date < '2000-01-01'"
+    );
+    assert_eq!(
+        sql(col::created_at.between("2024-01-01 00:00:00", "2024-01-31 23:59:59")),
+        "created_at BETWEEN '2024-01-01 00:00:00' AND '2024-01-31 23:59:59'"
+    );
+    assert_eq!(
+        sql(col::created_at.on_date("2024-01-15")),
+        "toDate(created_at) = toDate('2024-01-15')"
+    );
+    assert_eq!(
+        sql(col::user_id.eq("123e4567-e89b-12d3-a456-426614174000")),
+        "user_id = '123e4567-e89b-12d3-a456-426614174000'"
+    );
+}
+
+#[test]
+fn test_typed_array_columns() {
+    assert_eq!(sql(col::tags.has("admin")), "has(tags, 'admin')");
+    assert_eq!(
+        sql(col::tags.has_any(["a", "b"])),
+        "hasAny(tags, ['a', 'b'])"
+    );
+    assert_eq!(sql(col::scores.has_all([1, 2])), "hasAll(scores, [1, 2])");
+    assert_eq!(
+        sql(col::tags.has_json("type", "click")),
+        "arrayExists(x -> JSONExtractString(x, 'type') = 'click', tags)"
+    );
+}
+
+#[test]
+fn test_typed_table_columns() {
+    assert_eq!(users::NAME, "users");
+    assert_eq!(col::age.name(), "age");
+    assert!(matches!(col::age.column_def(), ColumnDef::UInt32("age")));
+
+    let columns = users::columns();
+    assert_eq!(columns.len(), 11);
+    assert!(matches!(
+        columns.get("tags"),
+        Some(ColumnDef::ArrayString("tags"))
+    ));
+
+    let filtering = FilteringOptions::new(
+        vec![FilterExpression::and(vec![
+            col::age.gt(25),
+            col::tags.has("admin"),
+        ])],
+        columns,
+    );
+    assert_eq!(
+        filtering.to_sql().unwrap(),
+        " WHERE (age > 25 AND has(tags, 'admin'))"
+    );
+}
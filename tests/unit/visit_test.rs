@@ -0,0 +1,139 @@
+use clickhouse_filters::filtering::{
+    FilterCondition, FilterExpression, FilterOperator, LogicalOperator,
+};
+use clickhouse_filters::sorting::SortedColumn;
+use clickhouse_filters::visit::Visitor;
+use clickhouse_filters::{ClickHouseFilters, ColumnDef, ColumnRegistry, FilteringOptions};
+
+fn tree() -> FilterExpression {
+    FilterExpression::and(vec![
+        FilterExpression::Condition(FilterCondition::string(
+            "name",
+            FilterOperator::Like,
+            Some("%jo%"),
+        )),
+        FilterExpression::or(vec![
+            FilterExpression::Condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::LessThan,
+                Some(18),
+            )),
+            FilterExpression::Condition(FilterCondition::uint32(
+                "age",
+                FilterOperator::GreaterThan,
+                Some(65),
+            )),
+        ]),
+    ])
+}
+
+#[derive(Default)]
+struct Collect {
+    columns: Vec<String>,
+    groups: Vec<(LogicalOperator, usize)>,
+}
+
+impl Visitor for Collect {
+    fn visit_condition(&mut self, condition: &FilterCondition) {
+        self.columns.push(condition.column().to_string());
+    }
+
+    fn visit_group(&mut self, operator: LogicalOperator, expressions: &[FilterExpression]) {
+        self.groups.push((operator, expressions.len()));
+    }
+}
+
+#[test]
+fn test_walk_visits_in_tree_order() {
+    let mut visitor = Collect::default();
+    tree().walk(&mut visitor);
+
+    assert_eq!(visitor.columns, vec!["name", "age", "age"]);
+    assert_eq!(
+        visitor.groups,
+        vec![(LogicalOperator::And, 2), (LogicalOperator::Or, 2)]
+    );
+}
+
+#[test]
+fn test_map_and_try_map() {
+    // Conditions can be replaced by whole groups
+    let expanded = tree().map(|condition| match condition.column() {
+        "name" => FilterExpression::or(vec![
+            FilterExpression::Condition(FilterCondition::string(
+                "first_name",
+                FilterOperator::Like,
+                Some("%jo%"),
+            )),
+            FilterExpression::Condition(FilterCondition::string(
+                "last_name",
+                FilterOperator::Like,
+                Some("%jo%"),
+            )),
+        ]),
+        _ => FilterExpression::Condition(condition),
+    });
+    assert_eq!(
+        expanded.to_sql(false).unwrap(),
+        "((first_name LIKE '%jo%' OR last_name LIKE '%jo%') AND (age < 18 OR age > 65))"
+    );
+
+    let rejected = tree().try_map(|condition| match condition.operator() {
+        Some(FilterOperator::Like) => Err(eyre::eyre!("LIKE is not allowed")),
+        _ => Ok(FilterExpression::Condition(condition)),
+    });
+    assert!(rejected.is_err());
+}
+
+#[test]
+fn test_referenced_columns() {
+    let columns = ColumnRegistry::from_iter([
+        ("name", ColumnDef::String("name")),
+        ("age", ColumnDef::UInt32("age")),
+        ("created_at", ColumnDef::DateTime("created_at")),
+        ("tenant_id", ColumnDef::UInt32("tenant_id")),
+        ("deleted_at", ColumnDef::DateTime("deleted_at")),
+    ])
+    .with_soft_delete("deleted_at");
+
+    let filtering = FilteringOptions::case_sensitive(vec![tree()], &columns).on_build(|root| {
+        let tenant = FilterCondition::uint32("tenant_id", FilterOperator::Equal, Some(1));
+        Ok(FilterExpression::and(vec![
+            root,
+            FilterExpression::Condition(tenant),
+        ]))
+    });
+    assert_eq!(
+        filtering
+            .referenced_columns()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec!["age", "name"]
+    );
+
+    let filters = ClickHouseFilters::new(
+        None,
+        vec![SortedColumn::new("created_at", "desc")],
+        Some(filtering),
+        &columns,
+    )
+    .unwrap();
+    assert_eq!(
+        filters
+            .referenced_columns()
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec!["age", "created_at", "deleted_at", "name", "tenant_id"]
+    );
+    assert_eq!(
+        filters
+            .sorting
+            .as_ref()
+            .unwrap()
+            .referenced_columns()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec!["created_at"]
+    );
+}